@@ -0,0 +1,96 @@
+//! Compares `ChunkSection::into_iter`, which collects all blocks into a `Vec` first,
+//! against the streaming `ChunkSection::blocks` iterator.
+//!
+//! The fixture is a single synthetic 1.16-style section with a 12-entry palette
+//! (4 bits per block) of typical underground blocks: mostly stone and deepslate,
+//! sprinkled with dirt, gravel, stone variants, water and a few ores.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mca_analyzer::{ChunkFormat, ChunkSection, PaletteOptions, CHUNK_SIZE};
+use nbt::CompoundTag;
+
+const PALETTE: &[&str] = &[
+    "minecraft:air",
+    "minecraft:stone",
+    "minecraft:deepslate",
+    "minecraft:dirt",
+    "minecraft:gravel",
+    "minecraft:granite",
+    "minecraft:diorite",
+    "minecraft:andesite",
+    "minecraft:water",
+    "minecraft:coal_ore",
+    "minecraft:iron_ore",
+    "minecraft:diamond_ore",
+];
+
+fn section_iter(c: &mut Criterion) {
+    let section = fixture_section();
+    let mut group = c.benchmark_group("section_iter");
+
+    group.bench_function("into_iter (collecting)", |b| {
+        b.iter(|| {
+            section
+                .clone()
+                .into_iter()
+                .filter(|block| block.blockstate != "minecraft:air")
+                .count()
+        })
+    });
+
+    group.bench_function("blocks (streaming)", |b| {
+        b.iter(|| {
+            section
+                .blocks()
+                .filter(|block| block.blockstate != "minecraft:air")
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, section_iter);
+criterion_main!(benches);
+
+fn fixture_section() -> ChunkSection {
+    let mut nbt = CompoundTag::new();
+    nbt.insert_i8("Y", 0);
+
+    let palette = PALETTE
+        .iter()
+        .map(|name| {
+            let mut entry = CompoundTag::new();
+            entry.insert_str("Name", name);
+            entry
+        })
+        .collect();
+    nbt.insert_compound_tag_vec("Palette", palette);
+
+    // 4 bits per block, 16 blocks per long
+    let mut seed: u32 = 0x2545_f491;
+    let block_states = (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE / 16)
+        .map(|_| {
+            (0..16).fold(0i64, |long, index| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+
+                let id: i64 = match seed % 100 {
+                    0..=44 => 1,
+                    45..=79 => 2,
+                    80..=97 => 3 + (seed / 100 % 6) as i64,
+                    _ => 9 + (seed / 100 % 3) as i64,
+                };
+
+                long | (id << (index * 4))
+            })
+        })
+        .collect();
+    nbt.insert_i64_vec("BlockStates", block_states);
+
+    ChunkSection::from_nbt(&nbt, 0, 0, ChunkFormat::Padded, PaletteOptions::default())
+        .expect("fixture section is valid")
+}
@@ -0,0 +1,77 @@
+//! Compares decoding a chunk with `nbt::decode::read_compound_tag` against
+//! `selective_nbt::read_compound_tag_skipping`, which skips the entity lists.
+//!
+//! The fixture is a synthetic 1.16-style chunk with a single section and a large
+//! `Entities` list, like chunks with big mob farms or item piles.
+//!
+//! Run with `cargo bench`.
+
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mca_analyzer::{read_compound_tag_skipping, UNUSED_CHUNK_TAGS};
+use nbt::{decode::read_compound_tag, encode::write_compound_tag, CompoundTag};
+
+const ENTITY_COUNT: usize = 20_000;
+
+fn selective_nbt(c: &mut Criterion) {
+    let mut bytes = Vec::new();
+    write_compound_tag(&mut bytes, fixture_chunk()).unwrap();
+
+    let mut group = c.benchmark_group("selective_nbt");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+    group.bench_function("read_compound_tag", |b| {
+        b.iter(|| sections(&read_compound_tag(&mut Cursor::new(&bytes)).unwrap()))
+    });
+
+    group.bench_function("read_compound_tag_skipping", |b| {
+        b.iter(|| {
+            sections(
+                &read_compound_tag_skipping(&mut Cursor::new(&bytes), UNUSED_CHUNK_TAGS).unwrap(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, selective_nbt);
+criterion_main!(benches);
+
+fn sections(chunk: &CompoundTag) -> usize {
+    chunk
+        .get_compound_tag("Level")
+        .unwrap()
+        .get_compound_tag_vec("Sections")
+        .unwrap()
+        .len()
+}
+
+fn fixture_chunk() -> CompoundTag {
+    let mut section = CompoundTag::new();
+    section.insert_i8("Y", 0);
+    section.insert_i64_vec("BlockStates", vec![0; 256]);
+
+    let entities: Vec<CompoundTag> = (0..ENTITY_COUNT)
+        .map(|index| {
+            let mut entity = CompoundTag::new();
+            entity.insert_str("id", "minecraft:item");
+            entity.insert_i16("Health", 5);
+            entity.insert_i32_vec("UUID", vec![index as i32, 1, 2, 3]);
+            entity.insert_str("CustomName", "{\"text\":\"Some dropped item\"}");
+            entity
+        })
+        .collect();
+
+    let mut level = CompoundTag::new();
+    level.insert_i32("xPos", 0);
+    level.insert_i32("zPos", 0);
+    level.insert_compound_tag_vec("Sections", vec![section]);
+    level.insert_compound_tag_vec("Entities", entities);
+
+    let mut chunk = CompoundTag::new();
+    chunk.insert_i32("DataVersion", 2586);
+    chunk.insert_compound_tag("Level", level);
+    chunk
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// The six neighbors of a block, named by direction
+const NEIGHBORS: [(&str, (i64, i32, i64)); 6] = [
+    ("above", (0, 1, 0)),
+    ("below", (0, -1, 0)),
+    ("north", (0, 0, -1)),
+    ("south", (0, 0, 1)),
+    ("east", (1, 0, 0)),
+    ("west", (-1, 0, 0)),
+];
+
+/// Tallies which blocks sit next to a target block in each direction,
+/// e.g. to see what's usually found below grass
+pub struct AdjacencyAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    target: String,
+
+    /// (direction, neighbor blockstate) -> count
+    counts: HashMap<(&'static str, String), u32>,
+}
+
+impl<'a> AdjacencyAnalyzer<'a> {
+    pub fn new(path: &'a str, target: String, loader_options: LoaderOptions) -> Self {
+        AdjacencyAnalyzer::with_source(RegionFolderSource::new(path), target, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        target: String,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        AdjacencyAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            target,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for section in chunk {
+                for block in section.blocks() {
+                    if block.blockstate != self.target {
+                        continue;
+                    }
+
+                    let (x, y, z) = block.global_pos;
+
+                    // Neighbors in other chunks are loaded as needed,
+                    // those outside the world aren't counted
+                    for (direction, (dx, dy, dz)) in NEIGHBORS {
+                        if let Some(neighbor) =
+                            self.chunk_loader.get_blockstate_at(x + dx, y + dy, z + dz)
+                        {
+                            *self
+                                .counts
+                                .entry((direction, neighbor.to_string()))
+                                .or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints the neighbors of every direction, most common first
+    pub fn print_csv(&self, id_form: IdForm) {
+        let mut counts: Vec<(&(&str, String), &u32)> = self.counts.iter().collect();
+        counts.sort_by(|((a_direction, a_name), a), ((b_direction, b_name), b)| {
+            let direction_index =
+                |direction| NEIGHBORS.iter().position(|(name, _)| name == direction);
+            direction_index(a_direction)
+                .cmp(&direction_index(b_direction))
+                .then(b.cmp(a))
+                .then(a_name.cmp(b_name))
+        });
+
+        println!("Direction,Blockstate,Count");
+        for ((direction, blockstate), count) in counts {
+            println!("{},{},{:8}", direction, id_form.render(blockstate), count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn dirt_below_grass() {
+        let names = [
+            "minecraft:air",
+            "minecraft:dirt",
+            "minecraft:grass_block",
+            "minecraft:stone",
+        ];
+        // Grass at Y 6 on top of dirt, except for a single block of stone
+        let section = test_fixtures::section(0, &names, |x, y, z| match y {
+            6 => 2,
+            5 if (x, z) == (0, 0) => 3,
+            0..=5 => 1,
+            _ => 0,
+        });
+        let mut analyzer = AdjacencyAnalyzer::with_source(
+            test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]),
+            "minecraft:grass_block".to_string(),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        let count = |direction, blockstate: &str| {
+            analyzer
+                .counts
+                .get(&(direction, blockstate.to_string()))
+                .copied()
+        };
+        assert_eq!(count("below", "minecraft:dirt"), Some(255));
+        assert_eq!(count("below", "minecraft:stone"), Some(1));
+        assert_eq!(count("above", "minecraft:air"), Some(256));
+        // Grass at the edge of the chunk has no neighbor in the missing chunks next to it
+        assert_eq!(count("east", "minecraft:grass_block"), Some(15 * 16));
+    }
+}
@@ -1,4 +1,9 @@
-use std::ops::Range;
+use std::{fmt, ops::Range};
+
+use crate::chunk_section::{get_coords_from_array_pos, CHUNK_SIZE};
+
+/// Width of a region in chunks
+pub const REGION_SIZE: i32 = 32;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Area {
@@ -14,17 +19,79 @@ impl Area {
         }
     }
 
-    pub fn to_vis_coords(&self) -> Area {
+    /// Expands the area outward so that all of its edges lie on region boundaries.
+    /// Every region touched by the original area is then fully contained in the snapped area,
+    /// e.g. chunks `5..40` become `0..64`. Areas already aligned to regions are unchanged.
+    pub fn snap_to_regions(&self) -> Area {
+        let snap_down = |coord: i32| coord.div_euclid(REGION_SIZE) * REGION_SIZE;
+        let snap_up = |coord: i32| (coord + REGION_SIZE - 1).div_euclid(REGION_SIZE) * REGION_SIZE;
+
+        Area {
+            x_range: (snap_down(self.x_range.0), snap_up(self.x_range.1)),
+            z_range: (snap_down(self.z_range.0), snap_up(self.z_range.1)),
+        }
+    }
+
+    /// Coordinates of the regions the area touches, along the X and Z axis
+    pub fn region_ranges(&self) -> (Range<i32>, Range<i32>) {
+        let snapped = self.snap_to_regions();
+
+        (
+            snapped.x_range.0 / REGION_SIZE..snapped.x_range.1 / REGION_SIZE,
+            snapped.z_range.0 / REGION_SIZE..snapped.z_range.1 / REGION_SIZE,
+        )
+    }
+
+    /// Iterates over all blocks of the chunk section at the given chunk coordinates and section Y,
+    /// in the order they're stored in the section.
+    /// Yields each block's coordinates within the section and its global coordinates:
+    /// `((local_x, local_y, local_z), (global_x, global_y, global_z))`.
+    pub fn iter_blocks_in_chunk(
+        chunk_x: i32,
+        section_y: i8,
+        chunk_z: i32,
+    ) -> impl Iterator<Item = ((usize, usize, usize), (i64, i32, i64))> {
+        let chunk_start = (
+            chunk_x as i64 * CHUNK_SIZE as i64,
+            section_y as i32 * CHUNK_SIZE as i32,
+            chunk_z as i64 * CHUNK_SIZE as i64,
+        );
+
+        (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE).map(move |index| {
+            let (x, y, z) = get_coords_from_array_pos(index);
+
+            (
+                (x, y, z),
+                (
+                    chunk_start.0 + x as i64,
+                    chunk_start.1 + y as i32,
+                    chunk_start.2 + z as i64,
+                ),
+            )
+        })
+    }
+
+    pub fn to_vis_coords(self) -> Area {
         Area {
             x_range: (0, self.x_range.1 - self.x_range.0),
             z_range: (0, self.z_range.1 - self.z_range.0),
         }
     }
 
+    /// Chunk coordinates of the corner with the lowest X and Z
+    pub fn min_chunk(&self) -> (i32, i32) {
+        (self.x_range.0, self.z_range.0)
+    }
+
     pub fn get_positive_coords(&self, x: i32, z: i32) -> (u32, u32) {
+        ((x - self.x_range.0) as u32, (z - self.z_range.0) as u32)
+    }
+
+    /// Like `get_positive_coords`, but for block coordinates
+    pub fn get_positive_block_coords(&self, x: i64, z: i64) -> (u32, u32) {
         (
-            x as u32 - self.x_range.0 as u32,
-            z as u32 - self.z_range.0 as u32,
+            (x - self.x_range.0 as i64 * CHUNK_SIZE as i64) as u32,
+            (z - self.z_range.0 as i64 * CHUNK_SIZE as i64) as u32,
         )
     }
 
@@ -37,6 +104,14 @@ impl Area {
         let area = self.to_vis_coords();
         area.z_range.1 as u32
     }
+
+    pub fn block_width_x(&self) -> u32 {
+        self.chunk_width_x() * CHUNK_SIZE as u32
+    }
+
+    pub fn block_width_z(&self) -> u32 {
+        self.chunk_width_z() * CHUNK_SIZE as u32
+    }
 }
 
 impl IntoIterator for Area {
@@ -52,6 +127,53 @@ impl IntoIterator for Area {
     }
 }
 
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "chunks x {}..{}, z {}..{}",
+            self.x_range.0, self.x_range.1, self.z_range.0, self.z_range.1
+        )
+    }
+}
+
 fn rangeify_tuple((start, end): (i32, i32)) -> Range<i32> {
     start..end
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_to_regions() {
+        let snapped = Area::new(5, 40, 5, 40).snap_to_regions();
+        assert_eq!((snapped.x_range, snapped.z_range), ((0, 64), (0, 64)));
+        assert_eq!(snapped.region_ranges(), (0..2, 0..2));
+
+        // Negative chunks snap away from 0 as well, aligned areas stay as they are
+        let snapped = Area::new(-40, -5, -32, 32).snap_to_regions();
+        assert_eq!((snapped.x_range, snapped.z_range), ((-64, 0), (-32, 32)));
+    }
+
+    #[test]
+    fn iter_blocks_in_negative_chunk() {
+        let blocks: Vec<_> = Area::iter_blocks_in_chunk(-1, -1, -2).collect();
+        assert_eq!(blocks.len(), 4096);
+
+        // Stored X first, then Z, then Y
+        assert_eq!(blocks[0], ((0, 0, 0), (-16, -16, -32)));
+        assert_eq!(blocks[1], ((1, 0, 0), (-15, -16, -32)));
+        assert_eq!(blocks[16], ((0, 0, 1), (-16, -16, -31)));
+        assert_eq!(blocks[256], ((0, 1, 0), (-16, -15, -32)));
+        assert_eq!(blocks[4095], ((15, 15, 15), (-1, -1, -17)));
+
+        // Every block decomposes back into the chunk and section it came from
+        for ((local_x, local_y, local_z), (x, y, z)) in blocks {
+            let parts = crate::coords::decompose(x, y, z);
+            assert_eq!(parts.chunk, (-1, -2));
+            assert_eq!(parts.section_y, -1);
+            assert_eq!(parts.local, (local_x, local_y, local_z));
+        }
+    }
+}
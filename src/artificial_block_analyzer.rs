@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chunk::Chunk,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Blocks that are considered part of naturally generated terrain if no custom list is given
+pub const NATURAL_BLOCKS: &[&str] = &[
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "minecraft:bedrock",
+    "minecraft:stone",
+    "minecraft:granite",
+    "minecraft:diorite",
+    "minecraft:andesite",
+    "minecraft:deepslate",
+    "minecraft:tuff",
+    "minecraft:calcite",
+    "minecraft:dirt",
+    "minecraft:coarse_dirt",
+    "minecraft:grass_block",
+    "minecraft:podzol",
+    "minecraft:mycelium",
+    "minecraft:gravel",
+    "minecraft:sand",
+    "minecraft:red_sand",
+    "minecraft:sandstone",
+    "minecraft:red_sandstone",
+    "minecraft:clay",
+    "minecraft:snow",
+    "minecraft:snow_block",
+    "minecraft:ice",
+    "minecraft:packed_ice",
+    "minecraft:water",
+    "minecraft:lava",
+    "minecraft:obsidian",
+    "minecraft:grass",
+    "minecraft:tall_grass",
+    "minecraft:fern",
+    "minecraft:large_fern",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+    "minecraft:kelp",
+    "minecraft:kelp_plant",
+    "minecraft:oak_log",
+    "minecraft:oak_leaves",
+    "minecraft:birch_log",
+    "minecraft:birch_leaves",
+    "minecraft:spruce_log",
+    "minecraft:spruce_leaves",
+    "minecraft:jungle_log",
+    "minecraft:jungle_leaves",
+    "minecraft:acacia_log",
+    "minecraft:acacia_leaves",
+    "minecraft:dark_oak_log",
+    "minecraft:dark_oak_leaves",
+    "minecraft:coal_ore",
+    "minecraft:deepslate_coal_ore",
+    "minecraft:iron_ore",
+    "minecraft:deepslate_iron_ore",
+    "minecraft:copper_ore",
+    "minecraft:deepslate_copper_ore",
+    "minecraft:gold_ore",
+    "minecraft:deepslate_gold_ore",
+    "minecraft:redstone_ore",
+    "minecraft:deepslate_redstone_ore",
+    "minecraft:lapis_ore",
+    "minecraft:deepslate_lapis_ore",
+    "minecraft:emerald_ore",
+    "minecraft:deepslate_emerald_ore",
+    "minecraft:diamond_ore",
+    "minecraft:deepslate_diamond_ore",
+];
+
+type BlockPositions = Vec<((i64, i32, i64), String)>;
+
+pub struct ArtificialBlockAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    natural_blocks: HashSet<String>,
+
+    /// blockstate -> count, only for blocks that aren't natural
+    artificial_blocks: HashMap<String, u32>,
+
+    /// Locations of every artificial block, only recorded if requested
+    positions: Option<BlockPositions>,
+}
+
+impl<'a> ArtificialBlockAnalyzer<'a> {
+    pub fn new(
+        path: &'a str,
+        loader_options: LoaderOptions,
+        natural_blocks: HashSet<String>,
+        record_positions: bool,
+    ) -> Self {
+        ArtificialBlockAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            loader_options,
+            natural_blocks,
+            record_positions,
+        )
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        loader_options: LoaderOptions,
+        natural_blocks: HashSet<String>,
+        record_positions: bool,
+    ) -> Self {
+        ArtificialBlockAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            natural_blocks,
+            artificial_blocks: HashMap::new(),
+            positions: if record_positions {
+                Some(Vec::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            self.analyze_chunk(chunk);
+        }
+    }
+
+    fn analyze_chunk(&mut self, chunk: Chunk) {
+        for section in chunk {
+            for block in section {
+                if self.natural_blocks.contains(&block.blockstate) {
+                    continue;
+                }
+
+                *self
+                    .artificial_blocks
+                    .entry(block.blockstate.clone())
+                    .or_insert(0) += 1;
+
+                if let Some(positions) = &mut self.positions {
+                    positions.push((block.global_pos, block.blockstate));
+                }
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut artificial_blocks: Vec<(&String, &u32)> = self.artificial_blocks.iter().collect();
+        artificial_blocks.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("Artificial block,Count");
+        for (blockstate, count) in artificial_blocks {
+            println!("{},{:8}", blockstate, count);
+        }
+
+        if let Some(positions) = &self.positions {
+            println!();
+
+            println!("X,Y,Z,Artificial block");
+            for ((x, y, z), blockstate) in positions {
+                println!("{},{},{},{}", x, y, z, blockstate);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn wool_and_torches_on_natural_terrain() {
+        // Stone and dirt with grass on top, and a wool block and two torches placed on the grass
+        let section = test_fixtures::section(
+            0,
+            &[
+                "minecraft:stone",
+                "minecraft:dirt",
+                "minecraft:grass_block",
+                "minecraft:air",
+                "minecraft:white_wool",
+                "minecraft:torch",
+            ],
+            |x, y, z| match (x, y, z) {
+                (2, 12, 3) => 4,
+                (5, 12, 5) | (6, 12, 5) => 5,
+                (_, 0..=8, _) => 0,
+                (_, 9..=10, _) => 1,
+                (_, 11, _) => 2,
+                _ => 3,
+            },
+        );
+        let natural_blocks = NATURAL_BLOCKS.iter().map(|name| name.to_string()).collect();
+        let mut analyzer = ArtificialBlockAnalyzer::with_source(
+            test_fixtures::source(vec![test_fixtures::chunk(1, 0, vec![section])]),
+            LoaderOptions::default(),
+            natural_blocks,
+            true,
+        );
+        analyzer.analyze(vec![(1, 0)]);
+
+        assert_eq!(
+            analyzer.artificial_blocks,
+            HashMap::from([
+                ("minecraft:white_wool".to_string(), 1),
+                ("minecraft:torch".to_string(), 2),
+            ])
+        );
+
+        let mut positions = analyzer.positions.unwrap();
+        positions.sort();
+        assert_eq!(
+            positions,
+            vec![
+                ((18, 12, 3), "minecraft:white_wool".to_string()),
+                ((21, 12, 5), "minecraft:torch".to_string()),
+                ((22, 12, 5), "minecraft:torch".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use anvil_region::error::ChunkReadError;
+use nbt::CompoundTag;
+
+use crate::{
+    chunk::ChunkFormat,
+    chunk_loader::{read_chunk_nbt_skipping, UNUSED_CHUNK_TAGS},
+    chunk_section::ChunkSection,
+    packed_longs,
+};
+
+/// Biomes are stored in cells of 4x4x4 blocks, 64 per section
+const BIOME_CELL_SIZE: i32 = 4;
+const BIOME_CELLS_PER_SECTION: usize = 64;
+
+/// Cross-tabulates biomes by height, e.g. to see at which depths cave biomes generate.
+/// Only chunks saved since 1.18 are counted, older chunks store biomes as numeric ids without height.
+pub struct BiomeHeightAnalyzer<'a> {
+    region_folder: &'a str,
+
+    /// (Y of the cell's lowest layer, biome) -> number of biome cells
+    counts: HashMap<(i32, String), u32>,
+}
+
+impl<'a> BiomeHeightAnalyzer<'a> {
+    pub fn new(region_folder: &'a str) -> Self {
+        BiomeHeightAnalyzer {
+            region_folder,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let nbt = match read_chunk_nbt_skipping(
+                self.region_folder,
+                chunk_x,
+                chunk_z,
+                UNUSED_CHUNK_TAGS,
+            ) {
+                Ok(nbt) => nbt,
+                Err(ChunkReadError::ChunkNotFound { .. }) => continue,
+                Err(err) => {
+                    eprintln!("Could not read chunk ({},{}): {:?}", chunk_x, chunk_z, err);
+                    continue;
+                }
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let data_version = nbt.get_i32("DataVersion").unwrap_or(0);
+            if ChunkFormat::from_data_version(data_version) != ChunkFormat::Flattened {
+                continue;
+            }
+
+            for section in nbt.get_compound_tag_vec("sections").unwrap_or_default() {
+                self.count_section(section);
+            }
+        }
+    }
+
+    fn count_section(&mut self, section: &CompoundTag) {
+        let (y, biomes) = match (
+            ChunkSection::y_from_nbt(section),
+            section.get_compound_tag("biomes"),
+        ) {
+            (Some(y), Ok(biomes)) => (y, biomes),
+            _ => return,
+        };
+
+        let palette: Vec<&str> = match biomes.get_str_vec("palette") {
+            Ok(palette) if !palette.is_empty() => palette,
+            _ => return,
+        };
+
+        // `data` is left out if the whole section is a single biome
+        let indices = match biomes.get_i64_vec("data") {
+            Ok(data) => {
+                let bits = usize::BITS - (palette.len() - 1).leading_zeros();
+                packed_longs::unpack(data, bits.max(1), BIOME_CELLS_PER_SECTION, false)
+            }
+            Err(_) => vec![0; BIOME_CELLS_PER_SECTION],
+        };
+
+        // Cells are ordered by Y, then Z, then X
+        for (cell, index) in indices.into_iter().enumerate() {
+            let biome = match palette.get(index as usize) {
+                Some(biome) => biome,
+                None => continue,
+            };
+
+            let cell_y = y as i32 * 16 + (cell / 16) as i32 * BIOME_CELL_SIZE;
+            *self.counts.entry((cell_y, biome.to_string())).or_insert(0) += 1;
+        }
+    }
+
+    /// Prints one row per layer of biome cells and biome, from the top down.
+    /// Each cell covers 4 layers starting at the given Y.
+    pub fn print_csv(&self) {
+        let mut counts: Vec<(&(i32, String), &u32)> = self.counts.iter().collect();
+        counts.sort_by(|((a_y, a_biome), a), ((b_y, b_biome), b)| {
+            b_y.cmp(a_y).then(b.cmp(a)).then(a_biome.cmp(b_biome))
+        });
+
+        println!("Y,Biome,Count");
+        for ((y, biome), count) in counts {
+            println!("{:5},{},{:8}", y, biome, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, TempDir};
+
+    #[test]
+    fn cave_biome_below_surface_biome() {
+        // Lush caves deep down, dripstone caves below the plains at the surface
+        let cave = test_fixtures::with_biomes(
+            test_fixtures::uniform_section(-1, "minecraft:stone"),
+            &["minecraft:lush_caves"],
+            |_, _, _| 0,
+        );
+        let surface = test_fixtures::with_biomes(
+            test_fixtures::uniform_section(4, "minecraft:stone"),
+            &["minecraft:dripstone_caves", "minecraft:plains"],
+            |_, y, _| (y >= 2) as usize,
+        );
+        let chunk = test_fixtures::chunk(0, 0, vec![cave, surface]);
+
+        let dir = TempDir::new();
+        test_fixtures::write_region_file(
+            &dir.path().join("r.0.0.mca"),
+            vec![((0, 0), 2, test_fixtures::zlib(chunk))],
+        );
+
+        let mut analyzer = BiomeHeightAnalyzer::new(dir.as_str());
+        analyzer.analyze(vec![(0, 0), (1, 0)]);
+
+        let count = |y: i32, biome: &str| analyzer.counts.get(&(y, biome.to_string())).copied();
+        for y in [-16, -12, -8, -4] {
+            assert_eq!(count(y, "minecraft:lush_caves"), Some(16));
+        }
+        for y in [64, 68] {
+            assert_eq!(count(y, "minecraft:dripstone_caves"), Some(16));
+        }
+        for y in [72, 76] {
+            assert_eq!(count(y, "minecraft:plains"), Some(16));
+        }
+        assert_eq!(analyzer.counts.len(), 8);
+    }
+}
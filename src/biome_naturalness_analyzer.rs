@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use anvil_region::error::ChunkReadError;
+use nbt::CompoundTag;
+
+use crate::{
+    chunk::ChunkFormat,
+    chunk_loader::{read_chunk_nbt_skipping, UNUSED_CHUNK_TAGS},
+    chunk_section::{section_biomes, ChunkSection},
+    packed_longs,
+    palette::PaletteOptions,
+};
+
+/// Blocks expected at the surface of each biome, i.e. the top and filler blocks of vanilla terrain.
+/// Biomes not listed here (oceans, rivers, caves, other dimensions) aren't scored.
+pub const EXPECTED_SURFACES: &[(&str, &[&str])] = &[
+    ("minecraft:plains", &["minecraft:grass_block"]),
+    ("minecraft:sunflower_plains", &["minecraft:grass_block"]),
+    ("minecraft:meadow", &["minecraft:grass_block"]),
+    ("minecraft:cherry_grove", &["minecraft:grass_block"]),
+    ("minecraft:forest", &["minecraft:grass_block"]),
+    ("minecraft:flower_forest", &["minecraft:grass_block"]),
+    ("minecraft:birch_forest", &["minecraft:grass_block"]),
+    (
+        "minecraft:old_growth_birch_forest",
+        &["minecraft:grass_block"],
+    ),
+    ("minecraft:dark_forest", &["minecraft:grass_block"]),
+    ("minecraft:taiga", &["minecraft:grass_block"]),
+    ("minecraft:savanna", &["minecraft:grass_block"]),
+    ("minecraft:savanna_plateau", &["minecraft:grass_block"]),
+    ("minecraft:jungle", &["minecraft:grass_block"]),
+    ("minecraft:sparse_jungle", &["minecraft:grass_block"]),
+    (
+        "minecraft:bamboo_jungle",
+        &["minecraft:grass_block", "minecraft:podzol"],
+    ),
+    ("minecraft:swamp", &["minecraft:grass_block"]),
+    (
+        "minecraft:mangrove_swamp",
+        &["minecraft:mud", "minecraft:grass_block"],
+    ),
+    (
+        "minecraft:old_growth_pine_taiga",
+        &[
+            "minecraft:grass_block",
+            "minecraft:podzol",
+            "minecraft:coarse_dirt",
+        ],
+    ),
+    (
+        "minecraft:old_growth_spruce_taiga",
+        &[
+            "minecraft:grass_block",
+            "minecraft:podzol",
+            "minecraft:coarse_dirt",
+        ],
+    ),
+    (
+        "minecraft:snowy_plains",
+        &["minecraft:grass_block", "minecraft:snow_block"],
+    ),
+    (
+        "minecraft:snowy_taiga",
+        &["minecraft:grass_block", "minecraft:snow_block"],
+    ),
+    (
+        "minecraft:grove",
+        &["minecraft:snow_block", "minecraft:powder_snow"],
+    ),
+    ("minecraft:mushroom_fields", &["minecraft:mycelium"]),
+    (
+        "minecraft:desert",
+        &["minecraft:sand", "minecraft:sandstone"],
+    ),
+    ("minecraft:beach", &["minecraft:sand"]),
+    ("minecraft:snowy_beach", &["minecraft:sand"]),
+    ("minecraft:stony_shore", &["minecraft:stone"]),
+    (
+        "minecraft:badlands",
+        &[
+            "minecraft:red_sand",
+            "minecraft:terracotta",
+            "minecraft:white_terracotta",
+            "minecraft:orange_terracotta",
+            "minecraft:yellow_terracotta",
+            "minecraft:brown_terracotta",
+            "minecraft:red_terracotta",
+            "minecraft:light_gray_terracotta",
+        ],
+    ),
+    (
+        "minecraft:eroded_badlands",
+        &[
+            "minecraft:red_sand",
+            "minecraft:terracotta",
+            "minecraft:white_terracotta",
+            "minecraft:orange_terracotta",
+            "minecraft:yellow_terracotta",
+            "minecraft:brown_terracotta",
+            "minecraft:red_terracotta",
+            "minecraft:light_gray_terracotta",
+        ],
+    ),
+    (
+        "minecraft:wooded_badlands",
+        &[
+            "minecraft:grass_block",
+            "minecraft:coarse_dirt",
+            "minecraft:red_sand",
+            "minecraft:terracotta",
+        ],
+    ),
+];
+
+/// Lowest Y of the overworld since 1.18, which heightmaps are relative to
+const MIN_Y: i32 = -64;
+
+/// Heightmaps store one 9-bit value per column, enough for the 384 layers of the overworld
+const HEIGHTMAP_BITS: u32 = 9;
+
+/// Per biome, how many surface blocks differ from what the biome generates,
+/// e.g. grass in a desert, as a measure of how much the terrain was changed.
+/// The surface of a column is its highest block that blocks motion, so plants, snow layers
+/// and water are looked through, as well as trees. Only chunks saved since 1.18 are counted.
+pub struct BiomeNaturalnessAnalyzer<'a> {
+    region_folder: &'a str,
+
+    /// Biome -> (surface columns, columns with an unexpected surface block)
+    counts: HashMap<String, (u32, u32)>,
+}
+
+impl<'a> BiomeNaturalnessAnalyzer<'a> {
+    pub fn new(region_folder: &'a str) -> Self {
+        BiomeNaturalnessAnalyzer {
+            region_folder,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let nbt = match read_chunk_nbt_skipping(
+                self.region_folder,
+                chunk_x,
+                chunk_z,
+                UNUSED_CHUNK_TAGS,
+            ) {
+                Ok(nbt) => nbt,
+                Err(ChunkReadError::ChunkNotFound { .. }) => continue,
+                Err(err) => {
+                    eprintln!("Could not read chunk ({},{}): {:?}", chunk_x, chunk_z, err);
+                    continue;
+                }
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let data_version = nbt.get_i32("DataVersion").unwrap_or(0);
+            if ChunkFormat::from_data_version(data_version) != ChunkFormat::Flattened {
+                continue;
+            }
+
+            self.count_chunk(&nbt, chunk_x, chunk_z);
+        }
+    }
+
+    fn count_chunk(&mut self, nbt: &CompoundTag, chunk_x: i32, chunk_z: i32) {
+        // Chunks that aren't fully generated don't have this heightmap yet
+        let heights = match nbt
+            .get_compound_tag("Heightmaps")
+            .and_then(|heightmaps| heightmaps.get_i64_vec("OCEAN_FLOOR"))
+        {
+            Ok(data) => packed_longs::unpack(data, HEIGHTMAP_BITS, 256, false),
+            Err(_) => return,
+        };
+
+        let mut sections = HashMap::new();
+        for section_nbt in nbt.get_compound_tag_vec("sections").unwrap_or_default() {
+            let section = ChunkSection::from_nbt(
+                section_nbt,
+                chunk_x,
+                chunk_z,
+                ChunkFormat::Flattened,
+                PaletteOptions::default(),
+            );
+            if let Some(section) = section {
+                sections.insert(section.pos.1, (section, section_biomes(section_nbt)));
+            }
+        }
+
+        // Columns are ordered by Z, then X
+        for (column, height) in heights.into_iter().enumerate() {
+            // A height of 0 means the column is empty
+            if height == 0 {
+                continue;
+            }
+
+            let (x, z) = (column % 16, column / 16);
+            let block_at = |y: i32| {
+                sections
+                    .get(&(y.div_euclid(16) as i8))
+                    .and_then(|(section, _)| section.get_block_at(x, y.rem_euclid(16) as usize, z))
+            };
+
+            // Trees block motion too, but the ground below them is what the biome generated
+            let mut y = MIN_Y + height as i32 - 1;
+            while block_at(y).is_some_and(is_tree) {
+                y -= 1;
+            }
+            let surface = match block_at(y) {
+                Some(surface) => surface,
+                None => continue,
+            };
+
+            let cell = (y.rem_euclid(16) as usize / 4) * 16 + (z / 4) * 4 + x / 4;
+            let biome = match sections
+                .get(&(y.div_euclid(16) as i8))
+                .and_then(|(_, biomes)| biomes.get(cell))
+            {
+                Some(biome) => biome,
+                None => continue,
+            };
+
+            let expected = match EXPECTED_SURFACES
+                .iter()
+                .find(|(expected_biome, _)| expected_biome == biome)
+            {
+                Some((_, expected)) => expected,
+                None => continue,
+            };
+
+            let (columns, deviations) = self.counts.entry(biome.clone()).or_insert((0, 0));
+            *columns += 1;
+            if !expected.contains(&surface) {
+                *deviations += 1;
+            }
+        }
+    }
+
+    /// Prints the number of scored columns and deviations per biome, most changed biomes first
+    pub fn print_csv(&self) {
+        let mut counts: Vec<(&String, &(u32, u32))> = self.counts.iter().collect();
+        counts.sort_by(|(a_biome, (_, a)), (b_biome, (_, b))| b.cmp(a).then(a_biome.cmp(b_biome)));
+
+        println!("Biome,Columns,Deviations");
+        for (biome, (columns, deviations)) in counts {
+            println!("{},{:8},{:8}", biome, columns, deviations);
+        }
+    }
+}
+
+fn is_tree(blockstate: &str) -> bool {
+    blockstate.ends_with("_leaves") || blockstate.ends_with("_log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn grass_in_desert() {
+        let names = [
+            "minecraft:air",
+            "minecraft:sand",
+            "minecraft:grass_block",
+            "minecraft:oak_log",
+        ];
+        // Sand up to Y 67 with a 4x4 patch of grass on top and a log at the far corner
+        let section = test_fixtures::section(4, &names, |x, y, z| match (x, y, z) {
+            (0..=3, 3, 0..=3) => 2,
+            (15, 4, 15) => 3,
+            (_, 0..=3, _) => 1,
+            _ => 0,
+        });
+        let section = test_fixtures::with_biomes(section, &["minecraft:desert"], |_, _, _| 0);
+
+        // Heights are stored relative to the bottom of the world, one above the highest block
+        let heights: Vec<u32> = (0..256)
+            .map(|column| if column == 255 { 133 } else { 132 })
+            .collect();
+        let mut heightmaps = CompoundTag::new();
+        heightmaps.insert_i64_vec("OCEAN_FLOOR", test_fixtures::pack(&heights, HEIGHTMAP_BITS));
+        let mut chunk = test_fixtures::chunk(0, 0, vec![section]);
+        chunk.insert_compound_tag("Heightmaps", heightmaps);
+
+        let mut analyzer = BiomeNaturalnessAnalyzer::new("");
+        analyzer.count_chunk(&chunk, 0, 0);
+
+        assert_eq!(analyzer.counts.len(), 1);
+        assert_eq!(analyzer.counts["minecraft:desert"], (256, 16));
+    }
+}
@@ -0,0 +1,36 @@
+use nbt::CompoundTag;
+
+/// A block that stores extra data, e.g. a chest with its items or a sign with its text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEntity {
+    /// Type of the block entity, e.g. `minecraft:chest`.
+    /// Before 1.11 these were names like `Chest` without namespace.
+    pub id: String,
+    pub pos: (i32, i32, i32),
+}
+
+/// Reads the block entities of a chunk from its `TileEntities` (or, since 1.18, `block_entities`) tag.
+/// Entries without an id or position are left out.
+pub fn parse_block_entities(level: &CompoundTag) -> Vec<BlockEntity> {
+    let block_entities = match level
+        .get_compound_tag_vec("block_entities")
+        .or_else(|_| level.get_compound_tag_vec("TileEntities"))
+    {
+        Ok(block_entities) => block_entities,
+        Err(_) => return Vec::new(),
+    };
+
+    block_entities
+        .into_iter()
+        .filter_map(|block_entity| {
+            Some(BlockEntity {
+                id: block_entity.get_str("id").ok()?.to_string(),
+                pos: (
+                    block_entity.get_i32("x").ok()?,
+                    block_entity.get_i32("y").ok()?,
+                    block_entity.get_i32("z").ok()?,
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{
+    block_entities::BlockEntity,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// How a block entity differs between the baseline and the compared world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockEntityChange {
+    Added(BlockEntity),
+    Removed(BlockEntity),
+    /// A block entity was removed and one of the same type added elsewhere in the same chunk
+    Moved {
+        id: String,
+        from: (i32, i32, i32),
+        to: (i32, i32, i32),
+    },
+}
+
+/// Compares the block entities of two worlds by position and type, e.g. to find the chests that
+/// were added or removed since a backup. A block entity replaced by one of another type counts as
+/// removed and added. Changes to the contents of a block entity aren't detected.
+pub struct BlockEntityDiffAnalyzer<'a> {
+    /// The earlier world, e.g. a backup
+    baseline_loader: ChunkLoader<'a>,
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Changes in the order the chunks were scanned
+    changes: Vec<BlockEntityChange>,
+}
+
+impl<'a> BlockEntityDiffAnalyzer<'a> {
+    pub fn new(baseline_path: &'a str, path: &'a str, loader_options: LoaderOptions) -> Self {
+        BlockEntityDiffAnalyzer::with_sources(
+            RegionFolderSource::new(baseline_path),
+            RegionFolderSource::new(path),
+            loader_options,
+        )
+    }
+
+    /// Compares chunks from any sources, e.g. `MemoryChunkSource`s in tests
+    pub fn with_sources<B: ChunkSource + 'a, S: ChunkSource + 'a>(
+        baseline_source: B,
+        source: S,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        // Block entities are among the unused tags, and blocks aren't needed
+        let loader_options = LoaderOptions {
+            skip_unused_tags: false,
+            ..loader_options
+        };
+
+        BlockEntityDiffAnalyzer {
+            baseline_loader: ChunkLoader::with_source(baseline_source, Some(0..0), loader_options),
+            chunk_loader: ChunkLoader::with_source(source, Some(0..0), loader_options),
+            changes: Vec::new(),
+        }
+    }
+
+    /// A chunk missing from one of the worlds counts as a chunk without block entities there
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let baseline = self
+                .baseline_loader
+                .get_or_load(chunk_x, chunk_z)
+                .map(|chunk| chunk.block_entities.clone());
+            let current = self
+                .chunk_loader
+                .get_or_load(chunk_x, chunk_z)
+                .map(|chunk| chunk.block_entities.clone());
+
+            if baseline.is_none() && current.is_none() {
+                continue;
+            }
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            self.changes.extend(diff_block_entities(
+                baseline.unwrap_or_default(),
+                current.unwrap_or_default(),
+            ));
+        }
+    }
+
+    pub fn print_csv(&self) {
+        self.write_csv(&mut io::stdout().lock()).unwrap();
+    }
+
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "Change,Block entity,X,Y,Z,Previous X,Previous Y,Previous Z"
+        )?;
+
+        let (mut added, mut removed, mut moved) = (0, 0, 0);
+        for change in &self.changes {
+            match change {
+                BlockEntityChange::Added(block_entity) => {
+                    let (x, y, z) = block_entity.pos;
+                    writeln!(out, "added,{},{},{},{},,,", block_entity.id, x, y, z)?;
+                    added += 1;
+                }
+                BlockEntityChange::Removed(block_entity) => {
+                    let (x, y, z) = block_entity.pos;
+                    writeln!(out, "removed,{},,,,{},{},{}", block_entity.id, x, y, z)?;
+                    removed += 1;
+                }
+                BlockEntityChange::Moved { id, from, to } => {
+                    writeln!(
+                        out,
+                        "moved,{},{},{},{},{},{},{}",
+                        id, to.0, to.1, to.2, from.0, from.1, from.2
+                    )?;
+                    moved += 1;
+                }
+            }
+        }
+
+        eprintln!(
+            "{} block entities added, {} removed, {} moved",
+            added, removed, moved
+        );
+        Ok(())
+    }
+}
+
+/// Changes between the block entities of a chunk in the baseline and the compared world,
+/// ordered by position
+fn diff_block_entities(
+    baseline: Vec<BlockEntity>,
+    current: Vec<BlockEntity>,
+) -> Vec<BlockEntityChange> {
+    let baseline_ids: HashMap<(i32, i32, i32), &str> = baseline
+        .iter()
+        .map(|block_entity| (block_entity.pos, block_entity.id.as_str()))
+        .collect();
+    let current_ids: HashMap<(i32, i32, i32), &str> = current
+        .iter()
+        .map(|block_entity| (block_entity.pos, block_entity.id.as_str()))
+        .collect();
+
+    let mut removed: Vec<&BlockEntity> = baseline
+        .iter()
+        .filter(|block_entity| {
+            current_ids.get(&block_entity.pos) != Some(&block_entity.id.as_str())
+        })
+        .collect();
+    let mut added: Vec<&BlockEntity> = current
+        .iter()
+        .filter(|block_entity| {
+            baseline_ids.get(&block_entity.pos) != Some(&block_entity.id.as_str())
+        })
+        .collect();
+    removed.sort_by_key(|block_entity| block_entity.pos);
+    added.sort_by_key(|block_entity| block_entity.pos);
+
+    let mut changes = Vec::new();
+    for old in removed {
+        match added.iter().position(|new| new.id == old.id) {
+            Some(index) => {
+                let new = added.remove(index);
+                changes.push(BlockEntityChange::Moved {
+                    id: old.id.clone(),
+                    from: old.pos,
+                    to: new.pos,
+                });
+            }
+            None => changes.push(BlockEntityChange::Removed(old.clone())),
+        }
+    }
+    changes.extend(
+        added
+            .into_iter()
+            .map(|new| BlockEntityChange::Added(new.clone())),
+    );
+
+    changes.sort_by_key(|change| match change {
+        BlockEntityChange::Added(block_entity) | BlockEntityChange::Removed(block_entity) => {
+            block_entity.pos
+        }
+        BlockEntityChange::Moved { to, .. } => *to,
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures::{self, MemoryChunkSource};
+
+    /// A chunk without blocks, with chests at the given positions
+    fn world(chests: &[(i32, i32, i32)]) -> MemoryChunkSource {
+        let block_entities = chests
+            .iter()
+            .map(|&(x, y, z)| {
+                let mut chest = CompoundTag::new();
+                chest.insert_str("id", "minecraft:chest");
+                chest.insert_i32("x", x);
+                chest.insert_i32("y", y);
+                chest.insert_i32("z", z);
+                chest
+            })
+            .collect();
+
+        let mut chunk = test_fixtures::chunk(0, 0, Vec::new());
+        chunk.insert_compound_tag_vec("block_entities", block_entities);
+        test_fixtures::source(vec![chunk])
+    }
+
+    #[test]
+    fn added_chest() {
+        let baseline = world(&[(1, 64, 1)]);
+        let current = world(&[(1, 64, 1), (5, 70, 3)]);
+
+        let mut analyzer =
+            BlockEntityDiffAnalyzer::with_sources(baseline, current, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0)]);
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Change,Block entity,X,Y,Z,Previous X,Previous Y,Previous Z\n\
+             added,minecraft:chest,5,70,3,,,\n"
+        );
+    }
+}
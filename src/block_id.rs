@@ -0,0 +1,64 @@
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// How block identifiers are rendered in output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdForm {
+    /// Always with namespace, e.g. `minecraft:diamond_ore`
+    #[default]
+    Full,
+    /// Without any namespace, e.g. `diamond_ore`
+    Short,
+    /// Only keep namespaces other than `minecraft`, e.g. `diamond_ore` but `create:zinc_ore`
+    Namespaced,
+}
+
+impl IdForm {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(IdForm::Full),
+            "short" => Some(IdForm::Short),
+            "namespaced" => Some(IdForm::Namespaced),
+            _ => None,
+        }
+    }
+
+    pub fn render(self, id: &str) -> String {
+        let (namespace, name) = split_namespace(id);
+
+        match self {
+            IdForm::Full => format!("{}:{}", namespace, name),
+            IdForm::Short => name.to_string(),
+            IdForm::Namespaced if namespace == DEFAULT_NAMESPACE => name.to_string(),
+            IdForm::Namespaced => format!("{}:{}", namespace, name),
+        }
+    }
+}
+
+/// Splits an identifier into namespace and name, e.g. `create:zinc_ore` into `create` and `zinc_ore`.
+/// Identifiers without namespace are in the `minecraft` namespace.
+pub fn split_namespace(id: &str) -> (&str, &str) {
+    id.split_once(':').unwrap_or((DEFAULT_NAMESPACE, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        assert_eq!(IdForm::Short.render("minecraft:diamond_ore"), "diamond_ore");
+        assert_eq!(
+            IdForm::Full.render("minecraft:diamond_ore"),
+            "minecraft:diamond_ore"
+        );
+        assert_eq!(IdForm::Full.render("diamond_ore"), "minecraft:diamond_ore");
+        assert_eq!(
+            IdForm::Namespaced.render("minecraft:diamond_ore"),
+            "diamond_ore"
+        );
+        assert_eq!(
+            IdForm::Namespaced.render("create:zinc_ore"),
+            "create:zinc_ore"
+        );
+    }
+}
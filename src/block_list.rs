@@ -0,0 +1,28 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Parses a list of blockstates, one per line.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_block_list(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+pub fn read_block_list(path: &Path) -> io::Result<HashSet<String>> {
+    Ok(parse_block_list(&fs::read_to_string(path)?))
+}
+
+/// Reads a block list from a stream such as stdin, in the same format as `parse_block_list`
+pub fn read_block_list_from<R: Read>(mut reader: R) -> io::Result<HashSet<String>> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+    Ok(parse_block_list(&contents))
+}
@@ -0,0 +1,40 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use crate::ore_defs::parse_named_list;
+
+/// Tag name (e.g. `#minecraft:logs`) -> blockstates belonging to that tag
+pub type BlockTags = BTreeMap<String, Vec<String>>;
+
+/// Parses block tags in the same format as ore definitions, one tag per line.
+/// Tag names need to be quoted if they start with `#`, otherwise the line is a comment:
+///
+/// ```toml
+/// "#logs" = ["minecraft:oak_log", "minecraft:spruce_log"]
+/// leaves = ["minecraft:oak_leaves", "minecraft:spruce_leaves"]
+/// ```
+///
+/// Names without a leading `#` get one, so both of the above are reported as tags.
+pub fn parse_block_tags(contents: &str) -> Result<BlockTags, String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, blocks) = parse_named_list(line)
+                .ok_or_else(|| format!("Invalid tag definition '{}'", line))?;
+
+            let name = if name.starts_with('#') {
+                name
+            } else {
+                format!("#{}", name)
+            };
+
+            Ok((name, blocks))
+        })
+        .collect()
+}
+
+pub fn read_block_tags(path: &Path) -> io::Result<BlockTags> {
+    parse_block_tags(&fs::read_to_string(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
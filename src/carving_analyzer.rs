@@ -0,0 +1,224 @@
+use std::path::Path;
+
+use anvil_region::error::ChunkReadError;
+use image::{ImageBuffer, Rgb, RgbImage};
+use nbt::CompoundTag;
+
+use crate::{
+    area::Area,
+    chunk::ChunkFormat,
+    chunk_loader::{read_chunk_nbt_skipping, UNUSED_CHUNK_TAGS},
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+};
+
+const MISSING_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Kinds of carvers that have their own mask
+const CARVING_STEPS: &[&str] = &["AIR", "LIQUID"];
+
+/// Fraction of every chunk that cave and ravine carvers marked as carved, from the carving masks
+/// that worldgen stores while a chunk is generating. The masks are dropped once a chunk is fully
+/// generated, so only chunks at the edge of the explored world usually still have them.
+pub struct CarvingAnalyzer<'a> {
+    region_folder: &'a str,
+
+    area: Area,
+
+    /// Chunk coordinates and carved fraction, for chunks that have carving masks
+    fractions: Vec<((i32, i32), f64)>,
+
+    /// Number of chunks without carving masks
+    chunks_without_masks: u32,
+}
+
+impl<'a> CarvingAnalyzer<'a> {
+    pub fn new(region_folder: &'a str, area: Area) -> Self {
+        CarvingAnalyzer {
+            region_folder,
+            area,
+            fractions: Vec::new(),
+            chunks_without_masks: 0,
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let nbt = match read_chunk_nbt_skipping(
+                self.region_folder,
+                chunk_x,
+                chunk_z,
+                UNUSED_CHUNK_TAGS,
+            ) {
+                Ok(nbt) => nbt,
+                Err(ChunkReadError::ChunkNotFound { .. }) => continue,
+                Err(err) => {
+                    eprintln!("Could not read chunk ({},{}): {:?}", chunk_x, chunk_z, err);
+                    continue;
+                }
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            match carved_fraction(&nbt) {
+                Some(fraction) => self.fractions.push(((chunk_x, chunk_z), fraction)),
+                None => self.chunks_without_masks += 1,
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        if self.chunks_without_masks > 0 {
+            eprintln!(
+                "{} chunks have no carving masks, most likely because they're fully generated",
+                self.chunks_without_masks
+            );
+        }
+
+        println!("X,Z,Carved fraction");
+        for ((x, z), fraction) in &self.fractions {
+            println!("{},{},{:.4}", x, z, fraction);
+        }
+    }
+
+    /// Saves a heatmap with one pixel per chunk, white for fully carved chunks.
+    /// Chunks without carving masks are black.
+    /// With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, fractions) = (&self.area, self.fractions.as_slice());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, fractions, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, fractions, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+/// Fraction of the bits set in any of the chunk's carving masks, or `None` if it has none.
+/// Masks are byte arrays before 1.17 and long arrays since, with one bit per block either way.
+pub fn carved_fraction(nbt: &CompoundTag) -> Option<f64> {
+    let data_version = nbt.get_i32("DataVersion").unwrap_or(0);
+    let masks = match ChunkFormat::from_data_version(data_version) {
+        ChunkFormat::Flattened => nbt.get_compound_tag("carving_masks").ok()?,
+        _ => nbt
+            .get_compound_tag("Level")
+            .and_then(|level| level.get_compound_tag("CarvingMasks"))
+            .ok()?,
+    };
+
+    let mut carved: Vec<u8> = Vec::new();
+    for step in CARVING_STEPS {
+        let bytes: Vec<u8> = if let Ok(longs) = masks.get_i64_vec(step) {
+            longs.iter().flat_map(|long| long.to_le_bytes()).collect()
+        } else if let Ok(bytes) = masks.get_i8_vec(step) {
+            bytes.iter().map(|&byte| byte as u8).collect()
+        } else {
+            continue;
+        };
+
+        if carved.len() < bytes.len() {
+            carved.resize(bytes.len(), 0);
+        }
+        for (carved, byte) in carved.iter_mut().zip(bytes) {
+            *carved |= byte;
+        }
+    }
+
+    if carved.is_empty() {
+        return None;
+    }
+
+    let carved_bits: u32 = carved.iter().map(|byte| byte.count_ones()).sum();
+    Some(carved_bits as f64 / (carved.len() * 8) as f64)
+}
+
+/// Renders the part of the heatmap with its top left corner at `(x, y)`
+fn render(
+    area: &Area,
+    fractions: &[((i32, i32), f64)],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, MISSING_COLOR);
+    let map_height = area.chunk_width_z();
+
+    for &((chunk_x, chunk_z), fraction) in fractions {
+        let (pixel_x, pixel_z) = area.get_positive_coords(chunk_x, chunk_z);
+        if pixel_z >= map_height {
+            continue;
+        }
+
+        let pixel_y = map_height - pixel_z - 1;
+        if (x..x + width).contains(&pixel_x) && (y..y + height).contains(&pixel_y) {
+            let brightness = (fraction * u8::MAX as f64).round() as u8;
+            img.put_pixel(
+                pixel_x - x,
+                pixel_y - y,
+                Rgb([brightness, brightness, brightness]),
+            );
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn carved_fraction_of_masks() {
+        // Air and liquid carvers overlap in the first long, so half of the chunk is carved
+        let mut masks = CompoundTag::new();
+        masks.insert_i64_vec("AIR", vec![-1, 0, 0, 0]);
+        masks.insert_i64_vec("LIQUID", vec![-1, -1, 0, 0]);
+        let mut chunk = test_fixtures::chunk(0, 0, Vec::new());
+        chunk.insert_compound_tag("carving_masks", masks);
+
+        assert_eq!(carved_fraction(&chunk), Some(0.5));
+    }
+
+    #[test]
+    fn carved_fraction_of_byte_masks() {
+        let mut masks = CompoundTag::new();
+        masks.insert_i8_vec("AIR", vec![-1, 0, 0, 0]);
+        let mut level = CompoundTag::new();
+        level.insert_compound_tag("CarvingMasks", masks);
+        let mut chunk = CompoundTag::new();
+        chunk.insert_i32("DataVersion", 2230);
+        chunk.insert_compound_tag("Level", level);
+
+        assert_eq!(carved_fraction(&chunk), Some(0.25));
+    }
+
+    #[test]
+    fn no_carving_masks() {
+        let chunk = test_fixtures::chunk(0, 0, Vec::new());
+
+        assert_eq!(carved_fraction(&chunk), None);
+    }
+}
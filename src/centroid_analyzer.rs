@@ -0,0 +1,157 @@
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Where a blockstate is concentrated, in block coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centroid {
+    pub count: u64,
+    /// Average position of all blocks
+    pub center: (f64, f64, f64),
+    /// Root mean square distance of the blocks from the center
+    pub spread: f64,
+}
+
+/// Summarizes the spatial distribution of a blockstate in a few numbers, e.g. to see where most
+/// of the gold in an area is: the average position of all its blocks and how far they spread
+/// around it
+pub struct CentroidAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    target: String,
+
+    count: u64,
+    /// Sum of the X, Y and Z coordinates of all blocks found
+    sum: (f64, f64, f64),
+    /// Sum of the squared X, Y and Z coordinates, for the spread
+    sum_of_squares: (f64, f64, f64),
+}
+
+impl<'a> CentroidAnalyzer<'a> {
+    pub fn new(path: &'a str, target: String, loader_options: LoaderOptions) -> Self {
+        CentroidAnalyzer::with_source(RegionFolderSource::new(path), target, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        target: String,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        CentroidAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            target,
+            count: 0,
+            sum: (0.0, 0.0, 0.0),
+            sum_of_squares: (0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let target = self.target.as_str();
+            let positions = chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .filter(|section| section.contains_blockstate(target))
+                .flat_map(|section| section.blocks())
+                .filter(|block| block.blockstate == target)
+                .map(|block| block.global_pos);
+
+            for (x, y, z) in positions {
+                let (x, y, z) = (x as f64, y as f64, z as f64);
+                self.count += 1;
+                self.sum.0 += x;
+                self.sum.1 += y;
+                self.sum.2 += z;
+                self.sum_of_squares.0 += x * x;
+                self.sum_of_squares.1 += y * y;
+                self.sum_of_squares.2 += z * z;
+            }
+        }
+    }
+
+    /// Center and spread of all target blocks found so far, `None` if there were none
+    pub fn centroid(&self) -> Option<Centroid> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let count = self.count as f64;
+        let center = (self.sum.0 / count, self.sum.1 / count, self.sum.2 / count);
+        let (squares, center_squares) = (
+            self.sum_of_squares.0 + self.sum_of_squares.1 + self.sum_of_squares.2,
+            center.0 * center.0 + center.1 * center.1 + center.2 * center.2,
+        );
+        let variance = squares / count - center_squares;
+
+        Some(Centroid {
+            count: self.count,
+            center,
+            // Rounding can make the variance slightly negative if all blocks are in one place
+            spread: variance.max(0.0).sqrt(),
+        })
+    }
+
+    pub fn print_csv(&self) {
+        println!("Blockstate,Count,Center X,Center Y,Center Z,Spread");
+        match self.centroid() {
+            Some(centroid) => println!(
+                "{},{},{:.2},{:.2},{:.2},{:.2}",
+                self.target,
+                centroid.count,
+                centroid.center.0,
+                centroid.center.1,
+                centroid.center.2,
+                centroid.spread
+            ),
+            None => eprintln!("Found no {}", self.target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn cube_of_gold() {
+        // Stone with a 3x3x3 cube of gold ore from (4, 4, 4) to (6, 6, 6)
+        let cube = 4..=6;
+        let section =
+            test_fixtures::section(0, &["minecraft:stone", "minecraft:gold_ore"], |x, y, z| {
+                (cube.contains(&x) && cube.contains(&y) && cube.contains(&z)) as usize
+            });
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+
+        let mut analyzer = CentroidAnalyzer::with_source(
+            source,
+            "minecraft:gold_ore".to_string(),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        let centroid = analyzer.centroid().unwrap();
+        assert_eq!(centroid.count, 27);
+        assert_eq!(centroid.center, (5.0, 5.0, 5.0));
+        // Each axis contributes a variance of 2/3
+        assert!((centroid.spread - 2f64.sqrt()).abs() < 1e-9);
+    }
+}
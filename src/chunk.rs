@@ -1,51 +1,395 @@
-use std::{collections::HashMap, ops::Range};
+use std::{
+    collections::HashMap,
+    ops::{Range, RangeInclusive},
+};
 
 use nbt::CompoundTag;
 
-use crate::chunk_section::{ChunkSection, CHUNK_SIZE};
+use crate::{
+    block_entities::{parse_block_entities, BlockEntity},
+    chunk_section::{section_biomes, ChunkSection, CHUNK_SIZE},
+    mcregion, packed_longs,
+    palette::{is_air, PaletteOptions},
+    structures::{parse_structure_references, parse_structure_starts, StructureStart},
+};
 
 #[derive(Debug, Clone)]
 pub struct Chunk {
     sections: HashMap<i8, ChunkSection>,
     pub x: i32,
     pub z: i32,
+    pub data_version: i32,
+
+    /// Game tick at which this chunk was last saved while loaded, 0 if unknown
+    pub last_update: i64,
+
+    /// Total game ticks players have spent near this chunk, 0 if unknown
+    pub inhabited_time: i64,
+
+    /// Block or fluid id of every pending scheduled tick (e.g. falling sand, flowing water)
+    pub scheduled_ticks: Vec<String>,
+
+    /// Structures starting in this chunk
+    pub structure_starts: Vec<StructureStart>,
+
+    /// Structures reaching into this chunk, with the chunk they start in
+    pub structure_references: Vec<(String, (i32, i32))>,
+
+    /// Blocks with extra data, e.g. chests. Left empty if the loader skips unused tags.
+    pub block_entities: Vec<BlockEntity>,
+
+    /// Generation stage as stored, `None` for chunks from before 1.13 which don't have one
+    status: Option<String>,
+
+    /// Biome -> number of 4x4x4 cells with that biome, over the sections in the loader's Y range.
+    /// Only filled for chunks since 1.18, which store biomes per section.
+    pub biome_cells: HashMap<String, u32>,
+
+    /// Section Y -> biome of each of its cells, see `section_biomes`
+    biomes: HashMap<i8, Vec<String>>,
+
+    /// Y of sections that are stored but couldn't be decoded, and are missing from this chunk
+    pub skipped_sections: Vec<i8>,
+
+    /// `WORLD_SURFACE` heightmap as stored, relative to `min_y`, for chunks since 1.13 that have one
+    world_surface: Option<Vec<u32>>,
+}
+
+/// Heightmaps store one 9-bit value per column, enough for the 384 layers of the overworld
+const HEIGHTMAP_BITS: u32 = 9;
+
+/// FNV-1a parameters, used for fingerprints that need to be stable across runs and builds
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// First data version (20w17a, 1.16) where block states no longer straddle two longs
+const PADDED_BLOCK_STATES_DATA_VERSION: i32 = 2529;
+
+/// First data version (21w43a, 1.18) without the `Level` tag
+const FLATTENED_DATA_VERSION: i32 = 2844;
+
+/// How a chunk is laid out in NBT, which depends on the version it was saved in.
+/// Worlds can contain chunks saved in different versions, so this is determined per chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkFormat {
+    /// Before 1.16, block states may continue in the next long
+    Straddling,
+    /// 1.16 and 1.17, block states never straddle two longs
+    Padded,
+    /// Since 1.18, everything is at the top level and sections store their blocks in `block_states`
+    Flattened,
+}
+
+impl ChunkFormat {
+    pub fn from_data_version(data_version: i32) -> Self {
+        if data_version >= FLATTENED_DATA_VERSION {
+            ChunkFormat::Flattened
+        } else if data_version >= PADDED_BLOCK_STATES_DATA_VERSION {
+            ChunkFormat::Padded
+        } else {
+            ChunkFormat::Straddling
+        }
+    }
+}
+
+/// Stages that mean a chunk is completely generated: `full` since 1.14,
+/// `fullchunk` and `postprocessed` (the last two stages) in 1.13
+const FULL_STATUSES: &[&str] = &["full", "fullchunk", "postprocessed"];
+
+/// Strips the `minecraft:` namespace that newer versions store statuses with
+pub fn normalize_status(status: &str) -> &str {
+    status.strip_prefix("minecraft:").unwrap_or(status)
 }
 
+/// First data version (1.18 experimental snapshot 1) where the world starts at Y=-64
+pub(crate) const NEGATIVE_Y_DATA_VERSION: i32 = 2825;
+
 impl Chunk {
-    pub fn from_nbt(nbt: &CompoundTag, sections: &Option<Range<i8>>) -> Self {
-        let level = nbt.get_compound_tag("Level").expect("Level doesn't exist");
+    pub fn from_nbt(
+        nbt: &CompoundTag,
+        sections: &Option<Range<i8>>,
+        palette_options: PaletteOptions,
+    ) -> Self {
+        // Chunks saved before 1.9 don't have a `DataVersion` tag
+        let data_version = nbt.get_i32("DataVersion").unwrap_or(0);
+        let format = ChunkFormat::from_data_version(data_version);
+
+        let (level, sections_tag) = match format {
+            ChunkFormat::Flattened => (nbt, "sections"),
+            _ => (
+                nbt.get_compound_tag("Level").expect("Level doesn't exist"),
+                "Sections",
+            ),
+        };
+
+        let last_update = level.get_i64("LastUpdate").unwrap_or(0);
+        let inhabited_time = level.get_i64("InhabitedTime").unwrap_or(0);
+
+        // Heightmaps straddle longs like block states do in the same versions
+        let world_surface = level
+            .get_compound_tag("Heightmaps")
+            .and_then(|heightmaps| heightmaps.get_i64_vec("WORLD_SURFACE"))
+            .ok()
+            .map(|data| {
+                let cross_long = format == ChunkFormat::Straddling;
+                packed_longs::unpack(data, HEIGHTMAP_BITS, CHUNK_SIZE * CHUNK_SIZE, cross_long)
+            });
+        let status = level.get_str("Status").ok().map(String::from);
+
+        let tick_lists: &[&str] = match format {
+            ChunkFormat::Flattened => &["block_ticks", "fluid_ticks"],
+            _ => &["TileTicks", "LiquidTicks"],
+        };
+
+        // Chunks without pending ticks leave the lists out entirely
+        let scheduled_ticks = tick_lists
+            .iter()
+            .flat_map(|list| level.get_compound_tag_vec(list).unwrap_or_default())
+            .filter_map(|tick| tick.get_str("i").ok())
+            .map(String::from)
+            .collect();
+
+        let (structure_starts, structure_references) = match level
+            .get_compound_tag("Structures")
+            .or_else(|_| level.get_compound_tag("structures"))
+        {
+            Ok(structures) => (
+                parse_structure_starts(structures),
+                parse_structure_references(structures),
+            ),
+            Err(_) => (Vec::new(), Vec::new()),
+        };
+
+        let block_entities = parse_block_entities(level);
 
         let x = level.get_i32("xPos").expect("xPos couldn't be parsed");
         let z = level.get_i32("zPos").expect("zPos couldn't be parsed");
 
-        let sections = level
-            .get_compound_tag_vec("Sections")
-            .expect("Sections couldn't be parsed")
-            .into_iter()
-            .filter_map(|section_nbt| {
-                let section = ChunkSection::from_nbt(section_nbt, x, z)?;
-
-                let y = section.pos.1;
+        let mut skipped_sections = Vec::new();
+        let mut biome_cells = HashMap::new();
+        let mut biomes = HashMap::new();
+        let sections = if mcregion::is_mcregion_chunk(level) {
+            mcregion::sections_from_level(level, x, z)
+                .into_iter()
+                .filter(|section| {
+                    sections
+                        .as_ref()
+                        .is_none_or(|y_range| y_range.contains(&section.pos.1))
+                })
+                .map(|section| (section.pos.1, section))
+                .collect()
+        } else {
+            level
+                .get_compound_tag_vec(sections_tag)
+                .expect("Sections couldn't be parsed")
+                .into_iter()
+                // Check the Y index first so that sections outside the range are never decoded
+                .filter(
+                    |section_nbt| match (sections, ChunkSection::y_from_nbt(section_nbt)) {
+                        (Some(y_range), Some(y)) => y_range.contains(&y),
+                        _ => true,
+                    },
+                )
+                .filter_map(|section_nbt| {
+                    if format == ChunkFormat::Flattened {
+                        let cells = section_biomes(section_nbt);
+                        for biome in &cells {
+                            *biome_cells.entry(biome.clone()).or_insert(0) += 1;
+                        }
+                        if let Some(y) =
+                            ChunkSection::y_from_nbt(section_nbt).filter(|_| !cells.is_empty())
+                        {
+                            biomes.insert(y, cells);
+                        }
+                    }
 
-                if let Some(y_range) = sections {
-                    if y_range.contains(&y) {
-                        Some((y, section))
-                    } else {
-                        None
+                    let section =
+                        ChunkSection::from_nbt(section_nbt, x, z, format, palette_options);
+                    if section.is_none() {
+                        skipped_sections.extend(ChunkSection::y_from_nbt(section_nbt));
                     }
-                } else {
-                    Some((y, section))
-                }
+
+                    let section = section?;
+                    Some((section.pos.1, section))
+                })
+                .collect()
+        };
+
+        Chunk {
+            sections,
+            x,
+            z,
+            data_version,
+            last_update,
+            inhabited_time,
+            scheduled_ticks,
+            structure_starts,
+            structure_references,
+            block_entities,
+            status,
+            biome_cells,
+            biomes,
+            skipped_sections,
+            world_surface,
+        }
+    }
+
+    /// Y of the highest non-air block of every column, ordered by Z, then X, and `min_y - 1` for empty columns.
+    /// Taken from the `WORLD_SURFACE` heightmap if the chunk has one, otherwise found by scanning
+    /// the decoded sections.
+    pub fn surface_heights(&self) -> Vec<i32> {
+        // Heightmaps store the height of the lowest air block above the surface
+        if let Some(world_surface) = &self.world_surface {
+            return world_surface
+                .iter()
+                .map(|&height| self.min_y() + height as i32 - 1)
+                .collect();
+        }
+
+        let section_ys = self.section_indices();
+
+        (0..CHUNK_SIZE * CHUNK_SIZE)
+            .map(|column| {
+                let (x, z) = (column % CHUNK_SIZE, column / CHUNK_SIZE);
+
+                section_ys
+                    .iter()
+                    .rev()
+                    .filter_map(|&section_y| Some((section_y, self.get_section(section_y)?)))
+                    .find_map(|(section_y, section)| {
+                        (0..CHUNK_SIZE).rev().find_map(|y| {
+                            let blockstate = section.get_block_at(x, y, z)?;
+                            if is_air(blockstate) {
+                                return None;
+                            }
+                            Some(section_y as i32 * CHUNK_SIZE as i32 + y as i32)
+                        })
+                    })
+                    .unwrap_or_else(|| self.min_y() - 1)
             })
-            .collect();
+            .collect()
+    }
+
+    /// Puts a section of air in place of every section that couldn't be decoded
+    pub fn fill_skipped_sections_with_air(&mut self) {
+        for y in self.skipped_sections.drain(..) {
+            self.sections.insert(
+                y,
+                ChunkSection::uniform((self.x, y, self.z), "minecraft:air"),
+            );
+        }
+    }
 
-        Chunk { sections, x, z }
+    /// Generation stage without namespace, e.g. `features` or `full`
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref().map(normalize_status)
+    }
+
+    /// Whether this chunk went through all generation stages.
+    /// Chunks saved before statuses existed are only saved once generated, so they count as complete.
+    pub fn is_fully_generated(&self) -> bool {
+        self.status()
+            .is_none_or(|status| FULL_STATUSES.contains(&status))
     }
 
     pub fn get_section(&self, y: i8) -> Option<&ChunkSection> {
         self.sections.get(&y)
     }
 
+    /// Biome of the 4x4x4 cell containing a block, given by its X and Z within the chunk and its Y.
+    /// `None` for chunks from before 1.18, which don't store biomes per section.
+    pub fn biome_at(&self, x: usize, y: i32, z: usize) -> Option<&str> {
+        let section_y = y.div_euclid(CHUNK_SIZE as i32) as i8;
+        let y = y.rem_euclid(CHUNK_SIZE as i32) as usize;
+
+        self.biomes
+            .get(&section_y)?
+            .get((y / 4) * 16 + (z / 4) * 4 + x / 4)
+            .map(String::as_str)
+    }
+
+    /// Y indices of all sections present in this chunk, sorted from bottom to top
+    pub fn section_indices(&self) -> Vec<i8> {
+        let mut indices: Vec<i8> = self.sections.keys().copied().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Number of sections that contain at least one block that isn't air
+    pub fn non_air_section_count(&self) -> usize {
+        self.sections
+            .values()
+            .filter(|section| !section.is_uniform_air())
+            .count()
+    }
+
+    /// Section Ys vanilla stores blocks in for the version this chunk was saved in.
+    /// Data packs can make worlds since 1.18 higher, which this doesn't account for.
+    pub fn section_y_bounds(&self) -> RangeInclusive<i8> {
+        if self.data_version >= NEGATIVE_Y_DATA_VERSION {
+            -4..=19
+        } else {
+            0..=15
+        }
+    }
+
+    /// Removes sections outside of `section_y_bounds`, e.g. from modded or hand-edited chunks,
+    /// which would otherwise add blocks at impossible heights. Returns the Ys of the removed sections.
+    pub fn remove_out_of_range_sections(&mut self) -> Vec<i8> {
+        let bounds = self.section_y_bounds();
+        let mut removed: Vec<i8> = self
+            .sections
+            .keys()
+            .copied()
+            .filter(|y| !bounds.contains(y))
+            .collect();
+        removed.sort_unstable();
+
+        for y in &removed {
+            self.sections.remove(y);
+        }
+
+        // Sections just above and below the world only hold light and are never decoded,
+        // so they aren't reported, but mustn't be filled with air either
+        self.skipped_sections.retain(|y| bounds.contains(y));
+
+        removed
+    }
+
+    /// Lowest Y coordinate of the world this chunk was saved in
+    pub fn min_y(&self) -> i32 {
+        if self.data_version >= NEGATIVE_Y_DATA_VERSION {
+            -64
+        } else {
+            0
+        }
+    }
+
+    /// Hash of the multiset of blockstates in this chunk, ignoring their positions.
+    /// Chunks made up of the same blocks in the same amounts share a fingerprint.
+    pub fn composition_fingerprint(&self) -> u64 {
+        let mut counts: HashMap<String, u32> = HashMap::new();
+
+        for section in self.sections.values() {
+            for block in section.blocks() {
+                *counts.entry(block.blockstate).or_insert(0) += 1;
+            }
+        }
+
+        let mut counts: Vec<(String, u32)> = counts.into_iter().collect();
+        counts.sort_unstable();
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for (blockstate, count) in counts {
+            for byte in blockstate.bytes().chain([0]).chain(count.to_le_bytes()) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        hash
+    }
+
     pub fn get_global_pos(&self) -> (i64, i64) {
         (
             self.x as i64 * CHUNK_SIZE as i64,
@@ -61,7 +405,7 @@ impl IntoIterator for Chunk {
 
     fn into_iter(self) -> Self::IntoIter {
         let mut iter_list: Vec<(i8, ChunkSection)> = self.sections.into_iter().collect();
-        iter_list.sort_by(|a, b| a.0.cmp(&b.0));
+        iter_list.sort_by_key(|(y, _)| *y);
 
         let section_list: Vec<ChunkSection> =
             iter_list.into_iter().map(|(_, section)| section).collect();
@@ -69,3 +413,102 @@ impl IntoIterator for Chunk {
         section_list.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn decode(nbt: &CompoundTag) -> Chunk {
+        Chunk::from_nbt(nbt, &None, PaletteOptions::default())
+    }
+
+    #[test]
+    fn composition_fingerprint() {
+        let palette = &["minecraft:stone", "minecraft:dirt"];
+        let chunk = |x, block: fn(usize, usize, usize) -> usize| {
+            decode(&test_fixtures::chunk(
+                x,
+                0,
+                vec![test_fixtures::section(0, palette, block)],
+            ))
+        };
+
+        // Half stone and half dirt, once with stone below and once above
+        let stone_below = chunk(0, |_, y, _| (y >= 8) as usize);
+        let stone_above = chunk(1, |_, y, _| (y < 8) as usize);
+        // Three quarters stone
+        let mostly_stone = chunk(2, |_, y, _| (y >= 12) as usize);
+
+        assert_eq!(
+            stone_below.composition_fingerprint(),
+            stone_above.composition_fingerprint()
+        );
+        assert_ne!(
+            stone_below.composition_fingerprint(),
+            mostly_stone.composition_fingerprint()
+        );
+    }
+
+    #[test]
+    fn last_update() {
+        let mut nbt = test_fixtures::chunk(0, 0, Vec::new());
+        nbt.insert_i64("LastUpdate", 1_234_567);
+        assert_eq!(decode(&nbt).last_update, 1_234_567);
+
+        // Stored within `Level` before 1.18
+        let mut legacy = test_fixtures::legacy_chunk(0, 0, 1976, Vec::new());
+        let mut level = legacy.get_compound_tag("Level").unwrap().clone();
+        level.insert_i64("LastUpdate", 89_000);
+        legacy.insert_compound_tag("Level", level);
+        assert_eq!(decode(&legacy).last_update, 89_000);
+
+        // Unknown if it isn't stored at all
+        assert_eq!(
+            decode(&test_fixtures::chunk(0, 0, Vec::new())).last_update,
+            0
+        );
+    }
+
+    #[test]
+    fn surface_only_sections() {
+        let nbt = test_fixtures::chunk(
+            0,
+            0,
+            [-4, -1, 0, 3]
+                .iter()
+                .map(|&y| test_fixtures::uniform_section(y, "minecraft:stone"))
+                .collect(),
+        );
+
+        let all = decode(&nbt);
+        assert_eq!(all.section_indices(), vec![-4, -1, 0, 3]);
+
+        let surface = Chunk::from_nbt(&nbt, &Some(0..4), PaletteOptions::default());
+        assert_eq!(surface.section_indices(), vec![0, 3]);
+        let lowest = surface
+            .into_iter()
+            .flatten()
+            .map(|block| block.global_pos.1)
+            .min();
+        assert_eq!(lowest, Some(0));
+    }
+
+    #[test]
+    fn status_with_and_without_namespace() {
+        assert_eq!(normalize_status("minecraft:full"), "full");
+        assert_eq!(normalize_status("full"), "full");
+
+        let with_status = |status: &str| {
+            let mut nbt = test_fixtures::chunk(0, 0, Vec::new());
+            nbt.insert_str("Status", status);
+            decode(&nbt)
+        };
+        assert!(with_status("full").is_fully_generated());
+        assert!(with_status("minecraft:full").is_fully_generated());
+        assert_eq!(with_status("minecraft:features").status(), Some("features"));
+        assert!(!with_status("minecraft:features").is_fully_generated());
+        // Chunks from before statuses existed
+        assert!(decode(&test_fixtures::chunk(0, 0, Vec::new())).is_fully_generated());
+    }
+}
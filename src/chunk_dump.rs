@@ -0,0 +1,117 @@
+use std::io::{self, Write};
+
+use crate::{chunk::Chunk, chunk_section::CHUNK_SIZE, palette::UNKNOWN_BLOCK};
+
+/// Writes the decoded blocks of a chunk as text, for checking what a section was decoded to.
+///
+/// Blockstates are numbered in the order they first appear and listed in a legend up front.
+/// Below it, every Y layer from bottom to top is a 16x16 grid of those numbers,
+/// with X increasing to the right and Z increasing downwards.
+pub fn write_chunk_dump<W: Write>(chunk: &Chunk, out: &mut W) -> io::Result<()> {
+    let sections: Vec<_> = chunk.clone().into_iter().collect();
+
+    let mut legend: Vec<&str> = Vec::new();
+    let mut layers = Vec::new();
+
+    for section in &sections {
+        for y in 0..CHUNK_SIZE {
+            let mut grid = [[0; CHUNK_SIZE]; CHUNK_SIZE];
+
+            for (z, row) in grid.iter_mut().enumerate() {
+                for (x, cell) in row.iter_mut().enumerate() {
+                    let blockstate = section.get_block_at(x, y, z).unwrap_or(UNKNOWN_BLOCK);
+
+                    *cell = match legend.iter().position(|&entry| entry == blockstate) {
+                        Some(index) => index,
+                        None => {
+                            legend.push(blockstate);
+                            legend.len() - 1
+                        }
+                    };
+                }
+            }
+
+            let global_y = section.pos.1 as i32 * CHUNK_SIZE as i32 + y as i32;
+            layers.push((global_y, grid));
+        }
+    }
+
+    let width = (legend.len().max(1) - 1).to_string().len();
+
+    writeln!(out, "Chunk ({},{})", chunk.x, chunk.z)?;
+    writeln!(out)?;
+    for (index, blockstate) in legend.iter().enumerate() {
+        writeln!(out, "{:>width$} {}", index, blockstate, width = width)?;
+    }
+
+    for (y, grid) in layers {
+        writeln!(out)?;
+        writeln!(out, "Y={}", y)?;
+
+        for row in grid.iter() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|index| format!("{:>width$}", index, width = width))
+                .collect();
+            writeln!(out, "{}", cells.join(" "))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{palette::PaletteOptions, test_fixtures};
+
+    #[test]
+    fn blocks_at_known_positions() {
+        // Stone with a block of gold ore at X 2, Y -11 and Z 7 within the chunk
+        let section =
+            test_fixtures::section(-1, &["minecraft:stone", "minecraft:gold_ore"], |x, y, z| {
+                ((x, y, z) == (2, 5, 7)) as usize
+            });
+        let nbt = test_fixtures::chunk(3, -2, vec![section]);
+        let chunk = Chunk::from_nbt(&nbt, &None, PaletteOptions::default());
+
+        let mut dump = Vec::new();
+        write_chunk_dump(&chunk, &mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(
+            lines[..4],
+            [
+                "Chunk (3,-2)",
+                "",
+                "0 minecraft:stone",
+                "1 minecraft:gold_ore"
+            ]
+        );
+
+        // A grid of 16 rows follows every layer's label, from the bottom up
+        let layer = |y: i32| {
+            let label = format!("Y={}", y);
+            let start = lines.iter().position(|line| *line == label).unwrap() + 1;
+            &lines[start..start + 16]
+        };
+        assert_eq!(
+            lines.iter().filter(|line| line.starts_with("Y=")).count(),
+            16
+        );
+        assert!(
+            lines.iter().position(|line| *line == "Y=-16")
+                < lines.iter().position(|line| *line == "Y=-1")
+        );
+
+        let gold_layer = layer(-11);
+        assert_eq!(gold_layer[7], "0 0 1 0 0 0 0 0 0 0 0 0 0 0 0 0");
+        assert!(gold_layer
+            .iter()
+            .enumerate()
+            .filter(|(z, _)| *z != 7)
+            .all(|(_, row)| !row.contains('1')));
+        assert!(layer(-10).iter().all(|row| !row.contains('1')));
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{self, BufRead};
+
+use crate::area::Area;
+
+/// An explicit set of chunks to analyze, e.g. as produced by another tool
+#[derive(Debug, Clone)]
+pub struct ChunkList {
+    chunks: Vec<(i32, i32)>,
+}
+
+impl ChunkList {
+    /// Reads a list of chunk coordinates, one `chunk_x chunk_z` pair per line
+    /// (separated by whitespace or a comma). Blank lines and lines starting with `#` are ignored.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut chunks = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let chunk = parse_chunk(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("'{}' is not a valid chunk", line),
+                )
+            })?;
+
+            chunks.push(chunk);
+        }
+
+        Ok(ChunkList { chunks })
+    }
+
+    /// Smallest area containing all listed chunks
+    pub fn bounding_area(&self) -> Area {
+        let min_x = self.chunks.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = self.chunks.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+        let min_z = self.chunks.iter().map(|&(_, z)| z).min().unwrap_or(0);
+        let max_z = self.chunks.iter().map(|&(_, z)| z + 1).max().unwrap_or(0);
+
+        Area::new(min_x, max_x, min_z, max_z)
+    }
+}
+
+impl IntoIterator for ChunkList {
+    type Item = (i32, i32);
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_iter()
+    }
+}
+
+fn parse_chunk(line: &str) -> Option<(i32, i32)> {
+    let mut coords = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(str::parse);
+
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(x)), Some(Ok(z)), None) => Some((x, z)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunk_loader::LoaderOptions,
+        composition_analyzer::{CompositionAnalyzer, CompositionOptions},
+        test_fixtures,
+    };
+
+    #[test]
+    fn only_listed_chunks() {
+        // As piped into `--chunks-from-stdin`
+        let stdin = "3 -1\n\n# from a chunk-change detector\n-2,5\n";
+        let chunks = ChunkList::from_reader(stdin.as_bytes()).unwrap();
+        let area = chunks.bounding_area();
+        assert_eq!(area.chunk_width_x(), 6);
+        assert_eq!(area.chunk_width_z(), 7);
+
+        let chunk =
+            |x, z, name| test_fixtures::chunk(x, z, vec![test_fixtures::uniform_section(0, name)]);
+        let source = test_fixtures::source(vec![
+            chunk(3, -1, "minecraft:stone"),
+            chunk(-2, 5, "minecraft:dirt"),
+            chunk(0, 0, "minecraft:sand"),
+        ]);
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(chunks);
+
+        assert_eq!(
+            analyzer.totals(),
+            vec![
+                ("minecraft:dirt".to_string(), 4096),
+                ("minecraft:stone".to_string(), 4096),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_line() {
+        let err = ChunkList::from_reader("1 2\n1 2 3\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,31 +1,213 @@
 use std::{
-    collections::{hash_map::Entry, HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
     ops::Range,
+    path::{Path, PathBuf},
 };
 
 use anvil_region::{
+    error::ChunkReadError,
     position::{RegionChunkPosition, RegionPosition},
     provider::{FolderRegionProvider, RegionProvider},
 };
+use byteorder::{BigEndian, ReadBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use nbt::CompoundTag;
 
-use crate::{chunk::Chunk, chunk_section::CHUNK_SIZE};
+use crate::{
+    area::REGION_SIZE,
+    chunk::Chunk,
+    chunk_section::ChunkSection,
+    chunk_source::{ChunkSource, RegionFolderSource, RetryingChunkSource},
+    coords,
+    mcregion::MCREGION_EXTENSION,
+    palette::{PaletteOptions, Properties},
+    selective_nbt::read_compound_tag_skipping,
+};
+
+/// Number of chunks a `ChunkLoader` keeps in memory
+pub const MAX_LOADED_CHUNKS: usize = 32;
+
+/// Set in a chunk's compression type if it was too big for the region file
+/// and is stored in an external `c.X.Z.mcc` file instead
+const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
+const GZIP_COMPRESSION_TYPE: u8 = 1;
+const ZLIB_COMPRESSION_TYPE: u8 = 2;
+const UNCOMPRESSED_COMPRESSION_TYPE: u8 = 3;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// First byte of zlib streams using deflate with a 32 KiB window, which is what every encoder writes
+const ZLIB_MAGIC: u8 = 0x78;
+/// First four bytes of every Zstandard frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+const REGION_SECTOR_LENGTH: u64 = 4096;
+
+/// Location table followed by the timestamp table, one entry per chunk each
+const REGION_HEADER_LENGTH: u64 = 2 * REGION_SECTOR_LENGTH;
+
+/// Largest chunk that can be stored inside a region file, bigger ones are external
+const MAX_CHUNK_SECTORS: u64 = 255;
+
+/// Tags that only hold entities and structure references, which block analyses don't need
+pub const UNUSED_CHUNK_TAGS: &[&str] = &[
+    "Entities",
+    "TileEntities",
+    "block_entities",
+    "Structures",
+    "structures",
+];
+
+/// Options controlling how chunks are read and parsed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoaderOptions {
+    pub palette: PaletteOptions,
+
+    /// Log and skip region files that can't be read (e.g. truncated or corrupt ones)
+    /// instead of aborting. All chunks of a skipped region are treated as missing.
+    pub skip_corrupt_regions: bool,
+
+    /// Skip `UNUSED_CHUNK_TAGS` while decoding chunks instead of loading them into memory,
+    /// for analyses that only look at blocks
+    pub skip_unused_tags: bool,
+
+    /// Treat chunks that aren't fully generated (e.g. at the edge of explored terrain) as missing
+    pub full_only: bool,
+
+    /// How sections that couldn't be decoded contribute to the results
+    pub skipped_sections: SkippedSectionPolicy,
+
+    /// What to do with sections outside of the heights the chunk's version allows
+    pub out_of_range_sections: OutOfRangeSectionPolicy,
+
+    /// How often to retry reading a chunk that failed, e.g. from a network mount,
+    /// see `RetryingChunkSource`
+    pub io_retries: u32,
+}
+
+/// What to do with sections that are stored in a chunk but couldn't be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SkippedSectionPolicy {
+    /// Leave them out, as if they weren't stored at all, when only the blocks present matter
+    #[default]
+    Ignore,
+    /// Count them as 4096 blocks of air, e.g. when measuring how full the world is
+    Count,
+}
+
+impl SkippedSectionPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ignore" => Some(SkippedSectionPolicy::Ignore),
+            "count" => Some(SkippedSectionPolicy::Count),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with sections whose Y is outside of the heights a chunk's version allows,
+/// see `Chunk::section_y_bounds`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRangeSectionPolicy {
+    /// Log and leave them out
+    #[default]
+    Drop,
+    /// Log and skip the whole chunk, like chunks of a region skipped as corrupt
+    Error,
+    /// Count them anyway, e.g. for worlds made higher by a data pack
+    Keep,
+}
+
+impl OutOfRangeSectionPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drop" => Some(OutOfRangeSectionPolicy::Drop),
+            "error" => Some(OutOfRangeSectionPolicy::Error),
+            "keep" => Some(OutOfRangeSectionPolicy::Keep),
+            _ => None,
+        }
+    }
+}
+
+/// Statistics about the chunk cache of a `ChunkLoader`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Requests that were answered by an already loaded chunk
+    pub hits: u64,
+    /// Requests that needed the chunk to be read from its region file
+    pub misses: u64,
+    /// Chunks currently held in the cache
+    pub loaded_chunks: usize,
+}
 
-const MAX_LOADED_CHUNKS: usize = 32;
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} chunks loaded",
+            self.hits, self.misses, self.loaded_chunks
+        )
+    }
+}
 
 pub struct ChunkLoader<'a> {
     loaded_chunks: HashMap<(i32, i32), Chunk>,
     recently_loaded_chunks: VecDeque<(i32, i32)>,
-    region_provider: FolderRegionProvider<'a>,
+    source: Box<dyn ChunkSource + 'a>,
     y_range: Option<Range<i8>>,
+    options: LoaderOptions,
+    corrupt_regions: HashSet<(i32, i32)>,
+    /// Regions that were checked before reading their first chunk
+    checked_regions: HashSet<(i32, i32)>,
+    /// Chunks that don't exist or weren't kept, so that they aren't read again for every block
+    absent_chunks: HashSet<(i32, i32)>,
+    cache_hits: u64,
+    cache_misses: u64,
 }
 
 impl<'a> ChunkLoader<'a> {
-    pub fn new(region_folder: &'a str, y_range: Option<Range<i8>>) -> Self {
+    pub fn new(region_folder: &'a str, y_range: Option<Range<i8>>, options: LoaderOptions) -> Self {
+        ChunkLoader::with_source(RegionFolderSource::new(region_folder), y_range, options)
+    }
+
+    /// Loads chunks from somewhere other than a region folder, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        y_range: Option<Range<i8>>,
+        options: LoaderOptions,
+    ) -> Self {
+        let source: Box<dyn ChunkSource + 'a> = if options.io_retries > 0 {
+            Box::new(RetryingChunkSource::new(source, options.io_retries))
+        } else {
+            Box::new(source)
+        };
+
         ChunkLoader {
             loaded_chunks: HashMap::new(),
             recently_loaded_chunks: VecDeque::new(),
-            region_provider: FolderRegionProvider::new(region_folder),
+            source,
             y_range,
+            options,
+            corrupt_regions: HashSet::new(),
+            checked_regions: HashSet::new(),
+            absent_chunks: HashSet::new(),
+            cache_hits: 0,
+            cache_misses: 0,
+        }
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.loaded_chunks.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits,
+            misses: self.cache_misses,
+            loaded_chunks: self.loaded_chunk_count(),
         }
     }
 
@@ -41,49 +223,705 @@ impl<'a> ChunkLoader<'a> {
         self.recently_loaded_chunks.push_back(coordinate);
     }
 
+    /// Unloads the least recently loaded chunks until there's room for another one
     fn unload_chunks(&mut self) {
-        if self.recently_loaded_chunks.len() >= MAX_LOADED_CHUNKS {
-            for _ in 0..(self.recently_loaded_chunks.len() - MAX_LOADED_CHUNKS) {
-                if let Some(least_recently_loaded_chunk) = self.recently_loaded_chunks.pop_front() {
-                    self.loaded_chunks.remove(&least_recently_loaded_chunk);
-                }
+        while self.recently_loaded_chunks.len() >= MAX_LOADED_CHUNKS {
+            if let Some(least_recently_loaded_chunk) = self.recently_loaded_chunks.pop_front() {
+                self.loaded_chunks.remove(&least_recently_loaded_chunk);
             }
         }
     }
 
-    pub fn get_or_load(&mut self, chunk_x: i32, chunk_z: i32) -> &Chunk {
-        self.load_chunk((chunk_x, chunk_z));
-        self.unload_chunks();
+    /// Checks a region with its source the first time one of its chunks is read
+    fn check_region(&mut self, region_x: i32, region_z: i32) -> Result<(), ChunkReadError> {
+        if self.checked_regions.insert((region_x, region_z)) {
+            self.source.check_region(region_x, region_z)
+        } else {
+            Ok(())
+        }
+    }
 
-        match self.loaded_chunks.entry((chunk_x, chunk_z)) {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => {
-                let chunk_pos = RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
+    /// Returns `None` if the chunk doesn't exist, was skipped, or its region has been skipped because
+    /// it couldn't be read
+    pub fn get_or_load(&mut self, chunk_x: i32, chunk_z: i32) -> Option<&Chunk> {
+        let region_pos = RegionPosition::from_chunk_position(chunk_x, chunk_z);
+        if self.corrupt_regions.contains(&(region_pos.x, region_pos.z)) {
+            return None;
+        }
 
-                let mut region = self
-                    .region_provider
-                    .get_region(RegionPosition::from_chunk_position(chunk_x, chunk_z))
-                    .expect("Could not load chunk file");
+        let coordinate = (chunk_x, chunk_z);
+        if self.absent_chunks.contains(&coordinate) {
+            self.cache_hits += 1;
+            return None;
+        }
 
-                let chunk_nbt = region.read_chunk(chunk_pos).expect("could not read chunk");
-                let chunk = Chunk::from_nbt(&chunk_nbt, &self.y_range);
+        if self.loaded_chunks.contains_key(&coordinate) {
+            self.cache_hits += 1;
+            self.load_chunk(coordinate);
+            return self.loaded_chunks.get(&coordinate);
+        }
+
+        self.cache_misses += 1;
+
+        let skip = if self.options.skip_unused_tags {
+            UNUSED_CHUNK_TAGS
+        } else {
+            &[]
+        };
+        let chunk_nbt = self
+            .check_region(region_pos.x, region_pos.z)
+            .and_then(|()| self.source.read_chunk_nbt(chunk_x, chunk_z, skip));
+
+        let chunk_nbt = match chunk_nbt {
+            Ok(chunk_nbt) => chunk_nbt,
+            // Chunks that were never generated are simply absent
+            Err(ChunkReadError::ChunkNotFound { .. }) => {
+                self.absent_chunks.insert(coordinate);
+                return None;
+            }
+            Err(err) if self.options.skip_corrupt_regions => {
+                eprintln!(
+                    "Skipping region r.{}.{}.mca, could not read chunk ({},{}): {:?}",
+                    region_pos.x, region_pos.z, chunk_x, chunk_z, err
+                );
+                self.corrupt_regions.insert((region_pos.x, region_pos.z));
+                return None;
+            }
+            Err(err) => panic!("could not read chunk: {:?}", err),
+        };
+        let mut chunk = Chunk::from_nbt(&chunk_nbt, &self.y_range, self.options.palette);
 
-                entry.insert(chunk)
+        if self.options.out_of_range_sections != OutOfRangeSectionPolicy::Keep {
+            let bounds = chunk.section_y_bounds();
+            let removed = chunk.remove_out_of_range_sections();
+            if !removed.is_empty() {
+                if self.options.out_of_range_sections == OutOfRangeSectionPolicy::Error {
+                    eprintln!(
+                        "Skipping chunk ({},{}), it has sections at Y {:?}, outside of {} to {}",
+                        chunk_x,
+                        chunk_z,
+                        removed,
+                        bounds.start(),
+                        bounds.end()
+                    );
+                    self.absent_chunks.insert(coordinate);
+                    return None;
+                }
+
+                eprintln!(
+                    "Warning: dropped sections at Y {:?} of chunk ({},{}), outside of {} to {}",
+                    removed,
+                    chunk_x,
+                    chunk_z,
+                    bounds.start(),
+                    bounds.end()
+                );
             }
         }
+
+        if self.options.skipped_sections == SkippedSectionPolicy::Count {
+            chunk.fill_skipped_sections_with_air();
+        }
+
+        if self.options.full_only && !chunk.is_fully_generated() {
+            self.absent_chunks.insert(coordinate);
+            return None;
+        }
+
+        self.unload_chunks();
+        self.load_chunk(coordinate);
+        Some(self.loaded_chunks.entry(coordinate).or_insert(chunk))
     }
 
     pub fn get_blockstate_at(&mut self, x: i64, y: i32, z: i64) -> Option<&str> {
-        let (chunk_x, chunk_z) = (x as i32 / CHUNK_SIZE as i32, z as i32 / CHUNK_SIZE as i32);
-        let chunk = self.get_or_load(chunk_x, chunk_z);
+        let (section, (local_x, local_y, local_z)) = self.get_section_at(x, y, z)?;
+        section.get_block_at(local_x, local_y, local_z)
+    }
+
+    pub fn get_properties_at(&mut self, x: i64, y: i32, z: i64) -> Option<&Properties> {
+        let (section, (local_x, local_y, local_z)) = self.get_section_at(x, y, z)?;
+        section.get_properties_at(local_x, local_y, local_z)
+    }
+
+    /// Returns the section containing the given block, along with the block's coordinates within that section
+    fn get_section_at(
+        &mut self,
+        x: i64,
+        y: i32,
+        z: i64,
+    ) -> Option<(&ChunkSection, (usize, usize, usize))> {
+        let parts = coords::decompose(x, y, z);
 
-        let section_index = y as i8 / CHUNK_SIZE as i8;
-        let section = chunk.get_section(section_index)?;
+        let chunk = self.get_or_load(parts.chunk.0, parts.chunk.1)?;
+        let section = chunk.get_section(parts.section_y)?;
+
+        Some((section, parts.local))
+    }
+}
+
+pub(crate) fn read_chunk_nbt(
+    region_provider: &FolderRegionProvider,
+    region_folder: &str,
+    chunk_x: i32,
+    chunk_z: i32,
+) -> Result<CompoundTag, ChunkReadError> {
+    let chunk_pos = RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
+
+    // `anvil_region` only reads `.mca` files
+    let region_pos = RegionPosition::from_chunk_position(chunk_x, chunk_z);
+    let path = region_file_path(region_folder, region_pos.x, region_pos.z);
+    if is_mcregion_file(&path) {
+        return read_chunk_nbt_skipping(region_folder, chunk_x, chunk_z, &[]);
+    }
+
+    let mut region =
+        region_provider.get_region(RegionPosition::from_chunk_position(chunk_x, chunk_z))?;
+
+    match region.read_chunk(chunk_pos) {
+        // `anvil_region` doesn't know about external chunks and reports them as an unknown compression type
+        Err(ChunkReadError::UnsupportedCompressionScheme { compression_scheme })
+            if compression_scheme & EXTERNAL_CHUNK_FLAG != 0 =>
+        {
+            read_external_chunk_nbt(
+                region_folder,
+                chunk_x,
+                chunk_z,
+                compression_scheme & !EXTERNAL_CHUNK_FLAG,
+                &[],
+            )
+        }
+        // Other compression types are detected from the data itself
+        Err(ChunkReadError::UnsupportedCompressionScheme { .. }) => {
+            read_chunk_nbt_skipping(region_folder, chunk_x, chunk_z, &[])
+        }
+        result => result,
+    }
+}
+
+/// Like `read_chunk_nbt`, but reads the region file itself instead of going through `anvil_region`,
+/// so that tags with the given names can be skipped while decoding
+pub(crate) fn read_chunk_nbt_skipping(
+    region_folder: &str,
+    chunk_x: i32,
+    chunk_z: i32,
+    skip: &[&str],
+) -> Result<CompoundTag, ChunkReadError> {
+    let position = RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
+    let region_pos = RegionPosition::from_chunk_position(chunk_x, chunk_z);
+    let path = region_file_path(region_folder, region_pos.x, region_pos.z);
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(ChunkReadError::ChunkNotFound { position })
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if file.metadata()?.len() < REGION_HEADER_LENGTH {
+        return Err(ChunkReadError::ChunkNotFound { position });
+    }
+
+    let header_index =
+        chunk_x.rem_euclid(REGION_SIZE) + chunk_z.rem_euclid(REGION_SIZE) * REGION_SIZE;
+    file.seek(SeekFrom::Start(header_index as u64 * 4))?;
+
+    let location = file.read_u32::<BigEndian>()?;
+    let (start_sector, sectors) = ((location >> 8) as u64, (location & 0xff) as u64);
+
+    if location == 0 {
+        return Err(ChunkReadError::ChunkNotFound { position });
+    }
+
+    file.seek(SeekFrom::Start(start_sector * REGION_SECTOR_LENGTH))?;
+
+    let length = file.read_u32::<BigEndian>()?;
+    let maximum_length = (sectors.min(MAX_CHUNK_SECTORS) * REGION_SECTOR_LENGTH) as u32;
+
+    if length > maximum_length {
+        return Err(ChunkReadError::LengthExceedsMaximum {
+            length,
+            maximum_length,
+        });
+    }
+
+    let compression_scheme = file.read_u8()?;
+
+    if compression_scheme & EXTERNAL_CHUNK_FLAG != 0 {
+        return read_external_chunk_nbt(
+            region_folder,
+            chunk_x,
+            chunk_z,
+            compression_scheme & !EXTERNAL_CHUNK_FLAG,
+            skip,
+        );
+    }
 
-        section.get_block_at(
-            x as usize % CHUNK_SIZE,
-            y as usize % CHUNK_SIZE,
-            z as usize % CHUNK_SIZE,
+    let mut reader = BufReader::new(file.take(length.saturating_sub(1) as u64));
+    decode_chunk_nbt(&mut reader, compression_scheme, skip)
+}
+
+/// Path of a region's file, `r.X.Z.mca` unless the region only exists as an McRegion `r.X.Z.mcr`.
+/// Both use the same layout, only the chunks inside differ (see `mcregion`).
+pub(crate) fn region_file_path(region_folder: &str, region_x: i32, region_z: i32) -> PathBuf {
+    let folder = Path::new(region_folder);
+    let anvil_path = folder.join(format!("r.{}.{}.mca", region_x, region_z));
+
+    if !anvil_path.exists() {
+        let mcregion_path = folder.join(format!(
+            "r.{}.{}.{}",
+            region_x, region_z, MCREGION_EXTENSION
+        ));
+        if mcregion_path.exists() {
+            return mcregion_path;
+        }
+    }
+
+    anvil_path
+}
+
+/// Fails if a chunk of the region file lies (partly) past its end, e.g. because the file was
+/// truncated. `anvil_region` panics when loading such a file instead of returning an error.
+pub(crate) fn check_region_length(path: &Path) -> io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let length = file.metadata()?.len();
+    if length < REGION_HEADER_LENGTH {
+        return Ok(());
+    }
+
+    let sectors_in_file = length.div_ceil(REGION_SECTOR_LENGTH);
+    let mut locations = BufReader::new(file.take(REGION_SECTOR_LENGTH));
+    for _ in 0..REGION_SIZE * REGION_SIZE {
+        let location = locations.read_u32::<BigEndian>()?;
+        let end_sector = (location >> 8) as u64 + (location & 0xff) as u64;
+
+        if location != 0 && end_sector > sectors_in_file {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} is truncated", path.display()),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_mcregion_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == MCREGION_EXTENSION)
+}
+
+/// Which chunks of a region are stored, from its location table alone without reading any chunk data.
+/// Indexed by `x + z * REGION_SIZE` with coordinates relative to the region,
+/// all `false` if the region file doesn't exist.
+pub fn read_present_chunks(
+    region_folder: &str,
+    region_x: i32,
+    region_z: i32,
+) -> io::Result<Vec<bool>> {
+    let chunk_count = (REGION_SIZE * REGION_SIZE) as usize;
+    let path = region_file_path(region_folder, region_x, region_z);
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![false; chunk_count]),
+        Err(err) => return Err(err),
+    };
+
+    if file.metadata()?.len() < REGION_HEADER_LENGTH {
+        return Ok(vec![false; chunk_count]);
+    }
+
+    let mut locations = vec![0; chunk_count];
+    file.read_u32_into::<BigEndian>(&mut locations)?;
+
+    Ok(locations
+        .into_iter()
+        .map(|location| location != 0)
+        .collect())
+}
+
+fn read_external_chunk_nbt(
+    region_folder: &str,
+    chunk_x: i32,
+    chunk_z: i32,
+    compression_scheme: u8,
+    skip: &[&str],
+) -> Result<CompoundTag, ChunkReadError> {
+    let path = Path::new(region_folder).join(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+    let mut reader = BufReader::new(File::open(path)?);
+
+    decode_chunk_nbt(&mut reader, compression_scheme, skip)
+}
+
+fn decode_chunk_nbt<R: Read>(
+    reader: &mut R,
+    compression_scheme: u8,
+    skip: &[&str],
+) -> Result<CompoundTag, ChunkReadError> {
+    match compression_scheme {
+        GZIP_COMPRESSION_TYPE => Ok(read_compound_tag_skipping(
+            &mut GzDecoder::new(reader),
+            skip,
+        )?),
+        ZLIB_COMPRESSION_TYPE => Ok(read_compound_tag_skipping(
+            &mut ZlibDecoder::new(reader),
+            skip,
+        )?),
+        UNCOMPRESSED_COMPRESSION_TYPE => Ok(read_compound_tag_skipping(reader, skip)?),
+        // Compression types that aren't vanilla differ between tools (e.g. 4 is zstd in some forks),
+        // so go by the magic bytes at the start of the data instead
+        _ => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+
+            if data.starts_with(&ZSTD_MAGIC) {
+                decode_zstd_chunk_nbt(&data, compression_scheme, skip)
+            } else if data.starts_with(&GZIP_MAGIC) {
+                decode_chunk_nbt(&mut data.as_slice(), GZIP_COMPRESSION_TYPE, skip)
+            } else if data.first() == Some(&ZLIB_MAGIC) {
+                decode_chunk_nbt(&mut data.as_slice(), ZLIB_COMPRESSION_TYPE, skip)
+            } else {
+                Err(ChunkReadError::UnsupportedCompressionScheme { compression_scheme })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decode_zstd_chunk_nbt(
+    data: &[u8],
+    _compression_scheme: u8,
+    skip: &[&str],
+) -> Result<CompoundTag, ChunkReadError> {
+    let mut decoder = zstd::Decoder::new(data)?;
+    Ok(read_compound_tag_skipping(&mut decoder, skip)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decode_zstd_chunk_nbt(
+    _data: &[u8],
+    compression_scheme: u8,
+    _skip: &[&str],
+) -> Result<CompoundTag, ChunkReadError> {
+    eprintln!(
+        "Reading zstd-compressed chunks needs mca-analyzer to be built with `--features zstd`"
+    );
+    Err(ChunkReadError::UnsupportedCompressionScheme { compression_scheme })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::test_fixtures::{self, MemoryChunkSource, TempDir};
+
+    /// A chunk of stone at section Y 0 and, impossibly high, at section Y 30
+    fn too_high_chunk() -> impl ChunkSource {
+        let sections = vec![
+            test_fixtures::uniform_section(0, "minecraft:stone"),
+            test_fixtures::uniform_section(30, "minecraft:stone"),
+        ];
+        test_fixtures::source(vec![test_fixtures::chunk(0, 0, sections)])
+    }
+
+    fn load_with(policy: OutOfRangeSectionPolicy) -> Option<Vec<i8>> {
+        let options = LoaderOptions {
+            out_of_range_sections: policy,
+            ..LoaderOptions::default()
+        };
+        let mut chunk_loader = ChunkLoader::with_source(too_high_chunk(), None, options);
+        chunk_loader
+            .get_or_load(0, 0)
+            .map(|chunk| chunk.section_indices())
+    }
+
+    #[test]
+    fn out_of_range_sections_are_dropped_by_default() {
+        assert_eq!(load_with(OutOfRangeSectionPolicy::default()), Some(vec![0]));
+    }
+
+    #[test]
+    fn out_of_range_sections_are_kept() {
+        assert_eq!(load_with(OutOfRangeSectionPolicy::Keep), Some(vec![0, 30]));
+    }
+
+    #[test]
+    fn out_of_range_sections_skip_the_chunk() {
+        assert_eq!(load_with(OutOfRangeSectionPolicy::Error), None);
+    }
+
+    #[test]
+    fn external_chunk() {
+        // Chunk (1, 2) is only flagged in the region file, its data is in `c.1.2.mcc`
+        let folder = TempDir::new();
+        let stored = test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:dirt")],
+        );
+        let external = test_fixtures::chunk(
+            1,
+            2,
+            vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+        );
+        test_fixtures::write_region_file(
+            &folder.path().join("r.0.0.mca"),
+            vec![
+                ((0, 0), ZLIB_COMPRESSION_TYPE, test_fixtures::zlib(stored)),
+                (
+                    (1, 2),
+                    EXTERNAL_CHUNK_FLAG | ZLIB_COMPRESSION_TYPE,
+                    Vec::new(),
+                ),
+            ],
+        );
+        std::fs::write(
+            folder.path().join("c.1.2.mcc"),
+            test_fixtures::zlib(external),
         )
+        .unwrap();
+
+        let source = RegionFolderSource::new(folder.as_str());
+        for skip in [&[][..], UNUSED_CHUNK_TAGS] {
+            let nbt = source.read_chunk_nbt(1, 2, skip).unwrap();
+            assert_eq!(nbt.get_i32("zPos").unwrap(), 2);
+        }
+
+        let mut chunk_loader = ChunkLoader::with_source(source, None, LoaderOptions::default());
+        let chunk = chunk_loader.get_or_load(1, 2).unwrap();
+        assert_eq!(
+            chunk.get_section(0).unwrap().get_block_at(0, 0, 0),
+            Some("minecraft:stone")
+        );
+        assert!(chunk_loader.get_or_load(0, 0).is_some());
+    }
+
+    #[test]
+    fn skip_truncated_region() {
+        // r.0.0.mca is fine, r.1.0.mca was cut off in the middle of its first chunk
+        let folder = TempDir::new();
+        let chunk = |x, z| {
+            let nbt = test_fixtures::chunk(
+                x,
+                z,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            );
+            ((x, z), ZLIB_COMPRESSION_TYPE, test_fixtures::zlib(nbt))
+        };
+        test_fixtures::write_region_file(&folder.path().join("r.0.0.mca"), vec![chunk(0, 0)]);
+        let truncated = folder.path().join("r.1.0.mca");
+        test_fixtures::write_region_file(&truncated, vec![chunk(32, 0), chunk(33, 0)]);
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&truncated)
+            .unwrap();
+        file.set_len(2 * 4096 + 10).unwrap();
+
+        // Both when reading through `anvil_region` and when skipping tags
+        for skip_unused_tags in [false, true] {
+            let options = LoaderOptions {
+                skip_corrupt_regions: true,
+                skip_unused_tags,
+                ..LoaderOptions::default()
+            };
+            let mut chunk_loader = ChunkLoader::new(folder.as_str(), None, options);
+
+            assert!(chunk_loader.get_or_load(32, 0).is_none());
+            assert_eq!(chunk_loader.corrupt_regions, HashSet::from([(1, 0)]));
+            // The rest of the region is skipped without reading it again
+            assert!(chunk_loader.get_or_load(33, 0).is_none());
+            assert!(chunk_loader.get_or_load(0, 0).is_some());
+        }
+    }
+
+    #[test]
+    fn regions_are_checked_once() {
+        /// Counts how often regions are checked
+        struct CheckCountingSource {
+            source: MemoryChunkSource,
+            checks: Rc<Cell<u32>>,
+        }
+
+        impl ChunkSource for CheckCountingSource {
+            fn read_chunk_nbt(
+                &self,
+                chunk_x: i32,
+                chunk_z: i32,
+                skip: &[&str],
+            ) -> Result<CompoundTag, ChunkReadError> {
+                self.source.read_chunk_nbt(chunk_x, chunk_z, skip)
+            }
+
+            fn check_region(&self, _: i32, _: i32) -> Result<(), ChunkReadError> {
+                self.checks.set(self.checks.get() + 1);
+                Ok(())
+            }
+        }
+
+        let chunks = [(0, 0), (1, 0), (32, 0)]
+            .iter()
+            .map(|&(x, z)| {
+                test_fixtures::chunk(
+                    x,
+                    z,
+                    vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+                )
+            })
+            .collect();
+        let checks = Rc::new(Cell::new(0));
+        let source = CheckCountingSource {
+            source: test_fixtures::source(chunks),
+            checks: checks.clone(),
+        };
+        let mut chunk_loader = ChunkLoader::with_source(source, None, LoaderOptions::default());
+
+        // Two chunks of region (0, 0), one of region (1, 0) and a missing one of region (0, 0)
+        for (x, z) in [(0, 0), (1, 0), (32, 0), (2, 0)] {
+            chunk_loader.get_or_load(x, z);
+        }
+        assert_eq!(checks.get(), 2);
+    }
+
+    #[test]
+    fn cache_stats() {
+        let chunks = (0..40)
+            .map(|x| {
+                test_fixtures::chunk(
+                    x,
+                    0,
+                    vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+                )
+            })
+            .collect();
+        let mut chunk_loader = ChunkLoader::with_source(
+            test_fixtures::source(chunks),
+            None,
+            LoaderOptions::default(),
+        );
+
+        for (x, z) in [(0, 0), (1, 0), (0, 0), (1, 0), (2, 0)] {
+            chunk_loader.get_or_load(x, z);
+        }
+        assert_eq!(
+            chunk_loader.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 3,
+                loaded_chunks: 3,
+            }
+        );
+
+        // Loading more chunks than fit evicts the least recently used ones, starting with (0, 0)
+        for x in 3..40 {
+            chunk_loader.get_or_load(x, 0);
+        }
+        assert_eq!(chunk_loader.loaded_chunk_count(), MAX_LOADED_CHUNKS);
+        chunk_loader.get_or_load(0, 0);
+        assert_eq!(
+            chunk_loader.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 41,
+                loaded_chunks: MAX_LOADED_CHUNKS,
+            }
+        );
+    }
+
+    #[test]
+    fn absent_chunks_are_read_once() {
+        let options = LoaderOptions {
+            out_of_range_sections: OutOfRangeSectionPolicy::Error,
+            ..LoaderOptions::default()
+        };
+        let mut chunk_loader = ChunkLoader::with_source(too_high_chunk(), None, options);
+
+        // (0, 0) is skipped for its out of range section, (1, 0) doesn't exist
+        for _ in 0..3 {
+            assert!(chunk_loader.get_blockstate_at(0, 0, 0).is_none());
+            assert!(chunk_loader.get_blockstate_at(16, 0, 0).is_none());
+        }
+        assert_eq!(
+            chunk_loader.stats(),
+            CacheStats {
+                hits: 4,
+                misses: 2,
+                loaded_chunks: 0,
+            }
+        );
+        assert!(chunk_loader.recently_loaded_chunks.is_empty());
+    }
+
+    #[test]
+    fn partial_chunks_are_read_once() {
+        let mut partial = test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+        );
+        partial.insert_str("Status", "minecraft:features");
+        let options = LoaderOptions {
+            full_only: true,
+            ..LoaderOptions::default()
+        };
+        let mut chunk_loader =
+            ChunkLoader::with_source(test_fixtures::source(vec![partial]), None, options);
+
+        for _ in 0..3 {
+            assert!(chunk_loader.get_blockstate_at(0, 0, 0).is_none());
+        }
+        assert_eq!(
+            chunk_loader.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                loaded_chunks: 0,
+            }
+        );
+        assert!(chunk_loader.recently_loaded_chunks.is_empty());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_chunk_parses_like_zlib() {
+        // Compression type 4 isn't vanilla, the data is recognized by its magic bytes
+        const ZSTD_COMPRESSION_TYPE: u8 = 4;
+
+        let chunk = || {
+            let section = test_fixtures::section(
+                0,
+                &["minecraft:stone", "minecraft:dirt", "minecraft:diamond_ore"],
+                |x, y, z| (x + y * 3 + z * 7) % 3,
+            );
+            test_fixtures::chunk(0, 0, vec![section])
+        };
+        let blocks = |compression_scheme, data| {
+            let folder = TempDir::new();
+            test_fixtures::write_region_file(
+                &folder.path().join("r.0.0.mca"),
+                vec![((0, 0), compression_scheme, data)],
+            );
+
+            let source = RegionFolderSource::new(folder.as_str());
+            let mut chunk_loader = ChunkLoader::with_source(source, None, LoaderOptions::default());
+            let chunk = chunk_loader.get_or_load(0, 0).unwrap();
+            chunk
+                .clone()
+                .into_iter()
+                .flatten()
+                .map(|block| (block.global_pos, block.blockstate))
+                .collect::<Vec<_>>()
+        };
+
+        let zlib = blocks(ZLIB_COMPRESSION_TYPE, test_fixtures::zlib(chunk()));
+        let zstd = blocks(ZSTD_COMPRESSION_TYPE, test_fixtures::zstd(chunk()));
+        assert_eq!(zlib.len(), 4096);
+        assert_eq!(zstd, zlib);
     }
 }
@@ -1,12 +1,21 @@
-use std::io::Cursor;
+use std::convert::TryFrom;
 
-use bitstream_io::{BitRead, BitReader, LittleEndian};
 use nbt::CompoundTag;
 
-use crate::palette::Palette;
+use crate::{
+    area::Area,
+    chunk::ChunkFormat,
+    packed_longs,
+    palette::{is_air, Palette, PaletteOptions, Properties, UNKNOWN_BLOCK},
+};
 
 pub const CHUNK_SIZE: usize = 16;
 
+const WATER: &str = "minecraft:water";
+
+/// Biomes are stored in cells of 4x4x4 blocks since 1.18
+const BIOME_CELLS_PER_SECTION: usize = 64;
+
 #[derive(Debug, Clone, Copy)]
 struct BlocksArray {
     pub contents: [usize; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
@@ -31,18 +40,81 @@ pub struct ChunkSection {
 }
 
 impl ChunkSection {
-    pub fn from_nbt(nbt: &CompoundTag, x: i32, z: i32) -> Option<Self> {
-        // `Palette` nbt tag is implicitly empty if it doesn't exist
-        let palette_nbt = nbt.get_compound_tag_vec("Palette").unwrap_or_default();
-        let palette = Palette::from_nbt(palette_nbt);
+    /// Reads only the Y index of a section, without decoding its blocks
+    pub fn y_from_nbt(nbt: &CompoundTag) -> Option<i8> {
+        section_y(nbt)
+    }
 
-        let blocks = if let Ok(block_state_array) = nbt.get_i64_vec("BlockStates") {
-            get_blocks_in_chunk(block_state_array, &palette)
+    /// Decodes a section stored in the given format, `None` if its blocks can't be read
+    pub fn from_nbt(
+        nbt: &CompoundTag,
+        x: i32,
+        z: i32,
+        format: ChunkFormat,
+        palette_options: PaletteOptions,
+    ) -> Option<Self> {
+        let y = section_y(nbt)?;
+
+        let (palette, blocks) = if format == ChunkFormat::Flattened {
+            let block_states = match nbt.get_compound_tag("block_states") {
+                Ok(block_states) => block_states,
+                // The section exists but only stores biomes, so it's entirely air rather than missing
+                Err(_) if nbt.get_compound_tag("biomes").is_ok() => {
+                    return Some(Self::uniform((x, y, z), "minecraft:air"));
+                }
+                Err(_) => return None,
+            };
+
+            // Palettes are always complete in this format, there's no implicit air
+            let palette_nbt = block_states
+                .get_compound_tag_vec("palette")
+                .unwrap_or_default();
+            let palette = Palette::from_nbt(
+                palette_nbt,
+                PaletteOptions {
+                    implicit_air: false,
+                    ..palette_options
+                },
+            );
+            let bits = palette_options.bit_width(&palette);
+
+            // `data` is left out if the palette only has a single entry, making every block
+            // that entry. Some tools write an empty array instead, which means the same.
+            let blocks = match block_states.get_i64_vec("data") {
+                Ok(data) if data.is_empty() && palette.len() == 1 => EMPTY_BLOCKS_ARRAY,
+                Ok(data) => get_blocks_in_chunk(data, bits, (x, y, z), false),
+                Err(_) if palette.len() == 1 => EMPTY_BLOCKS_ARRAY,
+                Err(_) => return None,
+            };
+
+            (palette, blocks)
         } else {
-            return None;
+            // `Palette` nbt tag is implicitly empty if it doesn't exist
+            let palette_nbt = nbt.get_compound_tag_vec("Palette").unwrap_or_default();
+            let palette = Palette::from_nbt(palette_nbt, palette_options);
+
+            let cross_long = format == ChunkFormat::Straddling;
+            let blocks = get_blocks_in_chunk(
+                nbt.get_i64_vec("BlockStates").ok()?,
+                palette_options.bit_width(&palette),
+                (x, y, z),
+                cross_long,
+            );
+
+            (palette, blocks)
         };
 
-        let y = nbt.get_i8("Y").ok()?;
+        let invalid_count = blocks
+            .contents
+            .iter()
+            .filter(|&&id| id >= palette.len())
+            .count();
+        if invalid_count > 0 {
+            eprintln!(
+                "Warning: section ({},{},{}) has {} blocks with a palette index out of range, counting them as {}",
+                x, y, z, invalid_count, UNKNOWN_BLOCK
+            );
+        }
 
         Some(Self {
             blocks,
@@ -51,6 +123,35 @@ impl ChunkSection {
         })
     }
 
+    /// Builds a section made up entirely of a single blockstate
+    pub fn uniform(pos: (i32, i8, i32), blockstate: &str) -> Self {
+        Self {
+            blocks: EMPTY_BLOCKS_ARRAY,
+            pos,
+            palette: Palette::from_states(vec![blockstate.to_string()]),
+        }
+    }
+
+    /// Builds a section from already decoded data, e.g. for synthetic worlds.
+    /// `indices` are palette indices in storage order (see `get_coords_from_array_pos`)
+    /// and must contain exactly one entry per block.
+    pub fn from_blocks(pos: (i32, i8, i32), palette: Palette, indices: &[usize]) -> Self {
+        let mut blocks = EMPTY_BLOCKS_ARRAY;
+
+        assert_eq!(
+            indices.len(),
+            blocks.contents.len(),
+            "A section needs exactly one palette index per block"
+        );
+        blocks.contents.copy_from_slice(indices);
+
+        Self {
+            blocks,
+            pos,
+            palette,
+        }
+    }
+
     pub fn get_block_at(&self, x: usize, y: usize, z: usize) -> Option<&str> {
         assert!(x < CHUNK_SIZE);
         assert!(y < CHUNK_SIZE);
@@ -59,52 +160,167 @@ impl ChunkSection {
         let block_id = self.blocks.get(x, y, z);
         self.palette.get_state(block_id)
     }
-}
 
-fn get_blocks_in_chunk(block_state_array: &[i64], chunk_section_palette: &Palette) -> BlocksArray {
-    let mut result = EMPTY_BLOCKS_ARRAY;
+    pub fn get_properties_at(&self, x: usize, y: usize, z: usize) -> Option<&Properties> {
+        assert!(x < CHUNK_SIZE);
+        assert!(y < CHUNK_SIZE);
+        assert!(z < CHUNK_SIZE);
 
-    let chunk_section_ids = get_block_ids_in_chunk(block_state_array, &chunk_section_palette);
+        let block_id = self.blocks.get(x, y, z);
+        self.palette.get_properties(block_id)
+    }
 
-    for (index, chunk_section_id) in chunk_section_ids.into_iter().enumerate() {
-        if index >= CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE {
-            break;
+    /// Whether any block in this section has the given blockstate.
+    /// Sections whose palette doesn't include it are ruled out without looking at their blocks.
+    pub fn contains_blockstate(&self, blockstate: &str) -> bool {
+        let ids: Vec<usize> = (0..self.palette.len())
+            .filter(|&id| self.palette.get_state(id) == Some(blockstate))
+            .collect();
+
+        !ids.is_empty() && self.blocks.contents.iter().any(|id| ids.contains(id))
+    }
+
+    /// Number of entries in this section's palette
+    pub fn palette_len(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Palette indices that no block of this section refers to, e.g. left over from edits.
+    /// They still widen the bits stored per block once the palette outgrows a power of two.
+    pub fn unused_palette_entries(&self) -> Vec<usize> {
+        let mut used = vec![false; self.palette.len()];
+        for &id in self.blocks.contents.iter() {
+            if let Some(used) = used.get_mut(id) {
+                *used = true;
+            }
         }
 
-        result.contents[index] = chunk_section_id;
+        used.into_iter()
+            .enumerate()
+            .filter(|&(_, used)| !used)
+            .map(|(id, _)| id)
+            .collect()
     }
 
-    result
+    /// Number of blocks in this section that aren't some kind of air
+    pub fn non_air_count(&self) -> usize {
+        self.blocks
+            .contents
+            .iter()
+            .filter(|&&id| {
+                self.palette
+                    .get_state(id)
+                    .is_some_and(|state| !is_air(state))
+            })
+            .count()
+    }
+
+    /// Whether every block in this section is some kind of air
+    pub fn is_uniform_air(&self) -> bool {
+        self.blocks
+            .contents
+            .iter()
+            .all(|&id| self.palette.get_state(id).is_none_or(is_air))
+    }
 }
 
-fn get_block_ids_in_chunk(block_state_array: &[i64], palette: &Palette) -> Vec<usize> {
-    let width = palette.get_elem_bit_size();
-    let mut result = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+/// Blocks that couldn't be decoded are left as palette index 0, but a warning is logged
+/// since a `BlockStates` array of the wrong length usually means the chunk was truncated
+fn get_blocks_in_chunk(
+    block_state_array: &[i64],
+    bit_width: u32,
+    pos: (i32, i8, i32),
+    cross_long: bool,
+) -> BlocksArray {
+    let mut result = EMPTY_BLOCKS_ARRAY;
 
-    for &val in block_state_array {
-        parse_blockstate_val(width, val).into_iter().for_each(|id| {
-            result.push(id);
-        });
+    let block_count = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+    if let Some(warning) = length_warning(block_state_array.len(), bit_width, pos, cross_long) {
+        eprintln!("{}", warning);
     }
 
-    result
-}
+    let chunk_section_ids =
+        packed_longs::unpack(block_state_array, bit_width, block_count, cross_long);
 
-fn parse_blockstate_val(width: u32, val: i64) -> Vec<usize> {
-    let bytes = val.to_le_bytes();
-    let cursor = Cursor::new(bytes);
-    let mut reader = BitReader::endian(cursor, LittleEndian);
+    for (index, chunk_section_id) in chunk_section_ids.into_iter().enumerate() {
+        result.contents[index] = chunk_section_id as usize;
+    }
 
-    let mut vec = Vec::with_capacity((64 / width) as usize);
+    result
+}
 
-    while let Ok(value) = reader.read::<u64>(width) {
-        vec.push(value as usize);
+/// Warning about a `BlockStates` array that doesn't hold exactly one section of blocks
+fn length_warning(
+    len: usize,
+    bit_width: u32,
+    pos: (i32, i8, i32),
+    cross_long: bool,
+) -> Option<String> {
+    let block_count = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+    let bits = bit_width as usize;
+    let expected_len = if cross_long {
+        (block_count * bits).div_ceil(64)
+    } else {
+        block_count.div_ceil(64 / bits)
+    };
+
+    if len == expected_len {
+        return None;
     }
 
-    vec
+    let decodable = if cross_long {
+        len * 64 / bits
+    } else {
+        len * (64 / bits)
+    };
+
+    Some(format!(
+        "Warning: section ({},{},{}) has {} BlockStates entries instead of {}, decoded {} of {} blocks",
+        pos.0,
+        pos.1,
+        pos.2,
+        len,
+        expected_len,
+        decodable.min(block_count),
+        block_count,
+    ))
+}
+
+/// Biome of each of the 64 cells of a section, ordered by Y, then Z, then X.
+/// Empty if the section doesn't store biomes.
+pub fn section_biomes(section: &CompoundTag) -> Vec<String> {
+    let biomes = match section.get_compound_tag("biomes") {
+        Ok(biomes) => biomes,
+        Err(_) => return Vec::new(),
+    };
+
+    let palette: Vec<&str> = match biomes.get_str_vec("palette") {
+        Ok(palette) if !palette.is_empty() => palette,
+        _ => return Vec::new(),
+    };
+
+    // `data` is left out if the whole section is a single biome
+    let indices = match biomes.get_i64_vec("data") {
+        Ok(data) => {
+            let bits = usize::BITS - (palette.len() - 1).leading_zeros();
+            packed_longs::unpack(data, bits.max(1), BIOME_CELLS_PER_SECTION, false)
+        }
+        Err(_) => vec![0; BIOME_CELLS_PER_SECTION],
+    };
+
+    indices
+        .into_iter()
+        .map(|index| {
+            palette
+                .get(index as usize)
+                .copied()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect()
 }
 
-fn get_coords_from_array_pos(index: usize) -> (usize, usize, usize) {
+pub(crate) fn get_coords_from_array_pos(index: usize) -> (usize, usize, usize) {
     let x = index % CHUNK_SIZE;
     let z = (index / CHUNK_SIZE) % CHUNK_SIZE;
     let y = index / (CHUNK_SIZE * CHUNK_SIZE);
@@ -118,42 +334,304 @@ pub struct ChunkSectionBlock {
     pub blockstate: String,
 }
 
+impl ChunkSection {
+    /// Iterates over all blocks in this section without collecting them first.
+    /// Blocks with a palette index that's out of range are reported as `UNKNOWN_BLOCK`,
+    /// the warning about them is logged once when the section is read.
+    pub fn blocks(&self) -> impl Iterator<Item = ChunkSectionBlock> + '_ {
+        let (x, y, z) = self.pos;
+
+        self.blocks
+            .contents
+            .iter()
+            .zip(Area::iter_blocks_in_chunk(x, y, z))
+            .map(move |(id, (chunk_pos, global_pos))| ChunkSectionBlock {
+                chunk_pos,
+                global_pos,
+                blockstate: self
+                    .palette
+                    .get_state(*id)
+                    .unwrap_or(UNKNOWN_BLOCK)
+                    .to_string(),
+            })
+    }
+
+    /// A block of water at the position of every waterlogged block, i.e. every block whose
+    /// `waterlogged` property is `true`. The water isn't part of the blockstate's name,
+    /// so counting these as well gives the actual amount of water.
+    pub fn waterlogged_blocks(&self) -> impl Iterator<Item = ChunkSectionBlock> + '_ {
+        let (x, y, z) = self.pos;
+
+        let waterlogged: Vec<bool> = (0..self.palette.len())
+            .map(|id| {
+                self.palette
+                    .get_properties(id)
+                    .and_then(|properties| properties.get("waterlogged"))
+                    .is_some_and(|value| value == "true")
+            })
+            .collect();
+
+        self.blocks
+            .contents
+            .iter()
+            .zip(Area::iter_blocks_in_chunk(x, y, z))
+            .filter(move |(id, _)| waterlogged.get(**id).copied().unwrap_or(false))
+            .map(|(_, (chunk_pos, global_pos))| ChunkSectionBlock {
+                chunk_pos,
+                global_pos,
+                blockstate: WATER.to_string(),
+            })
+    }
+}
+
 impl IntoIterator for ChunkSection {
     type Item = ChunkSectionBlock;
 
     type IntoIter = std::vec::IntoIter<ChunkSectionBlock>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let chunk_start = (
-            self.pos.0 as i64 * CHUNK_SIZE as i64,
-            self.pos.1 as i32 * CHUNK_SIZE as i32,
-            self.pos.2 as i64 * CHUNK_SIZE as i64,
-        );
-        let palette = self.palette;
+        let list: Vec<ChunkSectionBlock> = self.blocks().collect();
 
-        let list: Vec<ChunkSectionBlock> = self
-            .blocks
-            .contents
+        list.into_iter()
+    }
+}
+
+fn section_y(nbt: &CompoundTag) -> Option<i8> {
+    // Some converters store `Y` as an int instead of a byte
+    match nbt.get_i8("Y") {
+        Ok(y) => Some(y),
+        Err(_) => i8::try_from(nbt.get_i32("Y").ok()?).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn decode(nbt: &CompoundTag) -> ChunkSection {
+        ChunkSection::from_nbt(nbt, 0, 0, ChunkFormat::Flattened, PaletteOptions::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn single_block_section_without_data() {
+        let section = decode(&test_fixtures::uniform_section(-4, "minecraft:bedrock"));
+        let bedrock = section
+            .blocks()
+            .filter(|block| block.blockstate == "minecraft:bedrock")
+            .count();
+        assert_eq!(bedrock, 4096);
+    }
+
+    #[test]
+    fn unused_palette_entries() {
+        // Stone with a single block of dirt, and gold that was mined out again
+        let palette = Palette::from_states(vec![
+            "minecraft:stone".to_string(),
+            "minecraft:dirt".to_string(),
+            "minecraft:gold_ore".to_string(),
+        ]);
+        let mut indices = vec![0; 4096];
+        indices[100] = 1;
+
+        let section = ChunkSection::from_blocks((0, 0, 0), palette, &indices);
+        assert_eq!(section.unused_palette_entries(), vec![2]);
+    }
+
+    #[test]
+    fn waterlogged_blocks() {
+        // An oak fence under water next to one on land, in a section of air
+        let palette = vec![
+            test_fixtures::palette_entry("minecraft:air", &[]),
+            test_fixtures::palette_entry("minecraft:oak_fence", &[("waterlogged", "true")]),
+            test_fixtures::palette_entry("minecraft:oak_fence", &[("waterlogged", "false")]),
+        ];
+        let nbt = test_fixtures::section_with_palette(0, palette, |x, y, z| match (x, y, z) {
+            (0, 0, 0) => 1,
+            (1, 0, 0) => 2,
+            _ => 0,
+        });
+
+        let section = decode(&nbt);
+        let water: Vec<_> = section.waterlogged_blocks().collect();
+        assert_eq!(water.len(), 1);
+        assert_eq!(water[0].blockstate, "minecraft:water");
+        assert_eq!(water[0].chunk_pos, (0, 0, 0));
+    }
+
+    #[test]
+    fn y_stored_as_int() {
+        let mut nbt = test_fixtures::section(
+            -2,
+            &["minecraft:stone", "minecraft:deepslate"],
+            |_, y, _| (y < 8) as usize,
+        );
+        nbt.insert_i32("Y", -2);
+        assert_eq!(ChunkSection::y_from_nbt(&nbt), Some(-2));
+
+        let section = decode(&nbt);
+        assert_eq!(section.pos, (0, -2, 0));
+        let deepslate: Vec<_> = section
+            .blocks()
+            .filter(|block| block.blockstate == "minecraft:deepslate")
+            .collect();
+        assert_eq!(deepslate.len(), 2048);
+        assert!(deepslate
             .iter()
-            .enumerate()
-            .map(|(index, id)| {
-                let chunk_pos = get_coords_from_array_pos(index);
-
-                ChunkSectionBlock {
-                    chunk_pos,
-                    global_pos: (
-                        chunk_start.0 + chunk_pos.0 as i64,
-                        chunk_start.1 + chunk_pos.1 as i32,
-                        chunk_start.2 + chunk_pos.2 as i64,
-                    ),
-                    blockstate: palette
-                        .get_state(*id)
-                        .expect("Blockstate is not in palette")
-                        .to_string(),
-                }
+            .all(|block| (-32..-24).contains(&block.global_pos.1)));
+
+        // Out of range for a section index
+        nbt.insert_i32("Y", 200);
+        assert_eq!(ChunkSection::y_from_nbt(&nbt), None);
+    }
+
+    #[test]
+    fn short_block_states() {
+        // Dirt with stone in the lowest 8 layers, but cut off after the first 4 layers
+        let mut nbt =
+            test_fixtures::section(3, &["minecraft:dirt", "minecraft:stone"], |_, y, _| {
+                (y < 8) as usize
+            });
+        let mut block_states = nbt.get_compound_tag("block_states").unwrap().clone();
+        let data = block_states.get_i64_vec("data").unwrap();
+        assert_eq!(data.len(), 256);
+        block_states.insert_i64_vec("data", data[..64].to_vec());
+        nbt.insert_compound_tag("block_states", block_states);
+
+        assert_eq!(length_warning(256, 4, (1, 3, 2), false), None);
+        assert_eq!(
+            length_warning(64, 4, (1, 3, 2), false).unwrap(),
+            "Warning: section (1,3,2) has 64 BlockStates entries instead of 256, decoded 1024 of 4096 blocks"
+        );
+
+        // Still parses, with the missing blocks left as the first palette entry
+        let section = decode(&nbt);
+        let stone = section
+            .blocks()
+            .filter(|block| block.blockstate == "minecraft:stone")
+            .count();
+        assert_eq!(stone, 1024);
+        assert_eq!(section.get_block_at(0, 4, 0), Some("minecraft:dirt"));
+    }
+
+    #[test]
+    fn indices_beyond_palette() {
+        // Two palette entries, but indices up to 15 as if decoded with the wrong bit width
+        let palette = Palette::from_states(vec![
+            "minecraft:stone".to_string(),
+            "minecraft:dirt".to_string(),
+        ]);
+        let indices: Vec<usize> = (0..4096).map(|index| index % 16).collect();
+        let section = ChunkSection::from_blocks((0, 0, 0), palette, &indices);
+
+        let unknown = section
+            .blocks()
+            .filter(|block| block.blockstate == UNKNOWN_BLOCK)
+            .count();
+        assert_eq!(unknown, 4096 / 16 * 14);
+        assert_eq!(section.into_iter().count(), 4096);
+
+        let section = ChunkSection::from_blocks(
+            (0, 0, 0),
+            Palette::from_states(vec!["minecraft:stone".to_string()]),
+            &indices,
+        );
+        assert_eq!(section.get_block_at(0, 0, 0), Some("minecraft:stone"));
+        assert_eq!(section.get_block_at(5, 0, 0), None);
+    }
+
+    #[test]
+    fn biomes_without_block_states() {
+        let mut nbt = CompoundTag::new();
+        nbt.insert_i8("Y", 2);
+
+        // Without biomes either, there's nothing to decode
+        assert!(ChunkSection::from_nbt(
+            &nbt,
+            0,
+            0,
+            ChunkFormat::Flattened,
+            PaletteOptions::default()
+        )
+        .is_none());
+
+        let nbt = test_fixtures::with_biomes(nbt, &["minecraft:plains"], |_, _, _| 0);
+        let section = decode(&nbt);
+        assert_eq!(section.blocks().count(), 4096);
+        assert!(section
+            .blocks()
+            .all(|block| block.blockstate == "minecraft:air"));
+        assert_eq!(section.blocks().next().unwrap().global_pos.1, 32);
+    }
+
+    #[test]
+    fn forced_bit_width() {
+        // Stone with one block of dirt, stored with 4 bits per block
+        let nbt = test_fixtures::section(0, &["minecraft:stone", "minecraft:dirt"], |x, y, z| {
+            ((x, y, z) == (1, 0, 0)) as usize
+        });
+        let decode_with = |forced_bit_width| {
+            let options = PaletteOptions {
+                forced_bit_width,
+                ..PaletteOptions::default()
+            };
+            ChunkSection::from_nbt(&nbt, 0, 0, ChunkFormat::Flattened, options).unwrap()
+        };
+
+        let section = decode_with(None);
+        assert_eq!(section.get_block_at(0, 0, 0), Some("minecraft:stone"));
+        assert_eq!(section.get_block_at(1, 0, 0), Some("minecraft:dirt"));
+
+        // Read with 8 bits, the dirt's index 1 and the stone's 0 before it become index 16,
+        // beyond the palette. Blocks past the end of the too short data are all index 0.
+        let section = decode_with(Some(8));
+        assert_eq!(section.get_block_at(0, 0, 0), None);
+        assert_eq!(section.get_block_at(1, 0, 0), Some("minecraft:stone"));
+        let count = |blockstate: &str| {
+            section
+                .blocks()
+                .filter(|block| block.blockstate == blockstate)
+                .count()
+        };
+        assert_eq!(count(UNKNOWN_BLOCK), 1);
+        assert_eq!(count("minecraft:dirt"), 0);
+        assert_eq!(count("minecraft:stone"), 4095);
+    }
+
+    #[test]
+    fn from_blocks_round_trip() {
+        // Stone at the bottom layer, glass along the X 0 edge and an index the palette lacks
+        // at the very top
+        let palette = Palette::from_states(vec![
+            "minecraft:air".to_string(),
+            "minecraft:stone".to_string(),
+            "minecraft:glass".to_string(),
+        ]);
+        let indices: Vec<usize> = (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE)
+            .map(|index| match get_coords_from_array_pos(index) {
+                (15, 15, 15) => 7,
+                (_, 0, _) => 1,
+                (0, _, _) => 2,
+                _ => 0,
             })
             .collect();
+        let section = ChunkSection::from_blocks((-1, 2, 3), palette, &indices);
 
-        list.into_iter()
+        let blocks: Vec<ChunkSectionBlock> = section.blocks().collect();
+        assert_eq!(blocks.len(), 4096);
+
+        let count = |name: &str| blocks.iter().filter(|b| b.blockstate == name).count();
+        assert_eq!(count("minecraft:stone"), 256);
+        assert_eq!(count("minecraft:glass"), 15 * 16);
+        assert_eq!(count(UNKNOWN_BLOCK), 1);
+
+        let glass = blocks
+            .iter()
+            .find(|block| block.chunk_pos == (0, 5, 9))
+            .unwrap();
+        assert_eq!(glass.blockstate, "minecraft:glass");
+        assert_eq!(glass.global_pos, (-16, 37, 57));
+        assert_eq!(section.get_block_at(4, 0, 4), Some("minecraft:stone"));
     }
 }
@@ -0,0 +1,189 @@
+use std::{thread, time::Duration};
+
+use anvil_region::{error::ChunkReadError, provider::FolderRegionProvider};
+use nbt::CompoundTag;
+
+use crate::chunk_loader::{
+    check_region_length, read_chunk_nbt, read_chunk_nbt_skipping, region_file_path,
+};
+
+/// Where a `ChunkLoader` reads the NBT of chunks from
+pub trait ChunkSource {
+    /// Reads a chunk, leaving out tags with one of the names in `skip` if the source supports it.
+    /// Chunks that don't exist are reported as `ChunkReadError::ChunkNotFound`.
+    fn read_chunk_nbt(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        skip: &[&str],
+    ) -> Result<CompoundTag, ChunkReadError>;
+
+    /// Checks a region once before any of its chunks are read, e.g. that its file isn't truncated.
+    /// A failed check is handled like a chunk of the region that couldn't be read.
+    fn check_region(&self, _region_x: i32, _region_z: i32) -> Result<(), ChunkReadError> {
+        Ok(())
+    }
+}
+
+impl<S: ChunkSource + ?Sized> ChunkSource for Box<S> {
+    fn read_chunk_nbt(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        skip: &[&str],
+    ) -> Result<CompoundTag, ChunkReadError> {
+        (**self).read_chunk_nbt(chunk_x, chunk_z, skip)
+    }
+
+    fn check_region(&self, region_x: i32, region_z: i32) -> Result<(), ChunkReadError> {
+        (**self).check_region(region_x, region_z)
+    }
+}
+
+/// Chunks stored in the `.mca` files of a region folder
+pub struct RegionFolderSource<'a> {
+    region_provider: FolderRegionProvider<'a>,
+    region_folder: &'a str,
+}
+
+impl<'a> RegionFolderSource<'a> {
+    pub fn new(region_folder: &'a str) -> Self {
+        RegionFolderSource {
+            region_provider: FolderRegionProvider::new(region_folder),
+            region_folder,
+        }
+    }
+}
+
+impl ChunkSource for RegionFolderSource<'_> {
+    fn read_chunk_nbt(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        skip: &[&str],
+    ) -> Result<CompoundTag, ChunkReadError> {
+        if skip.is_empty() {
+            read_chunk_nbt(&self.region_provider, self.region_folder, chunk_x, chunk_z)
+        } else {
+            read_chunk_nbt_skipping(self.region_folder, chunk_x, chunk_z, skip)
+        }
+    }
+
+    fn check_region(&self, region_x: i32, region_z: i32) -> Result<(), ChunkReadError> {
+        check_region_length(&region_file_path(self.region_folder, region_x, region_z))?;
+        Ok(())
+    }
+}
+
+/// Wait before the first retry of a failed read, doubled for every further retry
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Retries reads that fail, e.g. because of a flaky network mount, up to a number of times with
+/// growing pauses in between. Every error except a missing chunk is retried, since a read cut short
+/// can also surface as a chunk that doesn't decode.
+pub struct RetryingChunkSource<S: ChunkSource> {
+    source: S,
+    retries: u32,
+    /// Wait before the first retry, `DEFAULT_RETRY_BACKOFF` outside of tests
+    backoff: Duration,
+}
+
+impl<S: ChunkSource> RetryingChunkSource<S> {
+    pub fn new(source: S, retries: u32) -> Self {
+        RetryingChunkSource {
+            source,
+            retries,
+            backoff: DEFAULT_RETRY_BACKOFF,
+        }
+    }
+}
+
+impl<S: ChunkSource> ChunkSource for RetryingChunkSource<S> {
+    fn read_chunk_nbt(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        skip: &[&str],
+    ) -> Result<CompoundTag, ChunkReadError> {
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+
+        loop {
+            match self.source.read_chunk_nbt(chunk_x, chunk_z, skip) {
+                Err(err)
+                    if attempt < self.retries
+                        && !matches!(err, ChunkReadError::ChunkNotFound { .. }) =>
+                {
+                    attempt += 1;
+                    eprintln!(
+                        "Could not read chunk ({},{}), retrying ({}/{}): {:?}",
+                        chunk_x, chunk_z, attempt, self.retries, err
+                    );
+
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn check_region(&self, region_x: i32, region_z: i32) -> Result<(), ChunkReadError> {
+        self.source.check_region(region_x, region_z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use super::*;
+
+    /// Fails a number of times before the chunk can be read
+    struct FlakySource {
+        failures: Cell<u32>,
+    }
+
+    impl ChunkSource for FlakySource {
+        fn read_chunk_nbt(
+            &self,
+            _: i32,
+            _: i32,
+            _: &[&str],
+        ) -> Result<CompoundTag, ChunkReadError> {
+            if self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                return Err(ChunkReadError::UnsupportedCompressionScheme {
+                    compression_scheme: 0,
+                });
+            }
+            Ok(CompoundTag::new())
+        }
+    }
+
+    #[test]
+    fn retries_until_read() {
+        let flaky = FlakySource {
+            failures: Cell::new(2),
+        };
+        let source = RetryingChunkSource {
+            source: flaky,
+            retries: 3,
+            backoff: Duration::ZERO,
+        };
+        assert!(source.read_chunk_nbt(0, 0, &[]).is_ok());
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let flaky = FlakySource {
+            failures: Cell::new(4),
+        };
+        let source = RetryingChunkSource {
+            source: flaky,
+            retries: 3,
+            backoff: Duration::ZERO,
+        };
+        assert!(source.read_chunk_nbt(0, 0, &[]).is_err());
+    }
+}
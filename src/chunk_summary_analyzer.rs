@@ -0,0 +1,179 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+};
+
+use crate::{
+    chunk::Chunk,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::{ChunkSource, RegionFolderSource},
+    palette::is_air,
+};
+
+/// One row of the summary table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSummary {
+    pub x: i32,
+    pub z: i32,
+    /// Biome covering the most cells, ties going to the first name alphabetically.
+    /// `None` for chunks from before 1.18, which don't store biomes per section.
+    pub dominant_biome: Option<String>,
+    /// Mean Y of the highest non-air block over all 256 columns, see `Chunk::surface_heights`
+    pub mean_surface_height: f64,
+    pub non_air_sections: usize,
+    /// Distinct blockstates other than air
+    pub distinct_blocks: usize,
+}
+
+impl ChunkSummary {
+    fn from_chunk(chunk: &Chunk) -> Self {
+        let dominant_biome = chunk
+            .biome_cells
+            .iter()
+            .max_by(|(a, a_cells), (b, b_cells)| a_cells.cmp(b_cells).then(b.cmp(a)))
+            .map(|(biome, _)| biome.clone());
+
+        let heights = chunk.surface_heights();
+        let mean_surface_height =
+            heights.iter().map(|&height| height as f64).sum::<f64>() / heights.len() as f64;
+
+        let mut blockstates = HashSet::new();
+        for y in chunk.section_indices() {
+            let section = match chunk.get_section(y) {
+                Some(section) => section,
+                None => continue,
+            };
+
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        if let Some(blockstate) = section.get_block_at(x, y, z) {
+                            blockstates.insert(blockstate);
+                        }
+                    }
+                }
+            }
+        }
+        let distinct_blocks = blockstates
+            .into_iter()
+            .filter(|blockstate| !is_air(blockstate))
+            .count();
+
+        ChunkSummary {
+            x: chunk.x,
+            z: chunk.z,
+            dominant_biome,
+            mean_surface_height,
+            non_air_sections: chunk.non_air_section_count(),
+            distinct_blocks,
+        }
+    }
+}
+
+/// Summarizes every chunk in one wide row, e.g. as a feature table for dashboards:
+/// its dominant biome, mean surface height, number of non-air sections and number of distinct blocks
+pub struct ChunkSummaryAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Summaries in the order the chunks were scanned
+    rows: Vec<ChunkSummary>,
+}
+
+impl<'a> ChunkSummaryAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        ChunkSummaryAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Summarizes chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        ChunkSummaryAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let summary = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => ChunkSummary::from_chunk(chunk),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            self.rows.push(summary);
+        }
+    }
+
+    pub fn print_csv(&self) {
+        self.write_csv(&mut io::stdout().lock()).unwrap();
+    }
+
+    /// Writes the summary of every scanned chunk, in the order they were scanned
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "Chunk X,Chunk Z,Dominant biome,Mean surface height,Non-air sections,Distinct blocks"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                out,
+                "{},{},{},{:.2},{},{}",
+                row.x,
+                row.z,
+                row.dominant_biome.as_deref().unwrap_or(""),
+                row.mean_surface_height,
+                row.non_air_sections,
+                row.distinct_blocks
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn summary_row() {
+        // One section of stone at Y 0 to 15, whose lowest 16 of the 64 biome cells are river
+        let section = test_fixtures::with_biomes(
+            test_fixtures::uniform_section(0, "minecraft:stone"),
+            &["minecraft:plains", "minecraft:river"],
+            |_, y, _| (y == 0) as usize,
+        );
+
+        // Seven 9-bit heights per long, each 80 above the bottom of the world at Y -64
+        let long = (0..7).fold(0i64, |long, i| long | 80 << (9 * i));
+        let mut heightmaps = CompoundTag::new();
+        heightmaps.insert_i64_vec("WORLD_SURFACE", vec![long; 37]);
+
+        let mut chunk = test_fixtures::chunk(0, 0, vec![section]);
+        chunk.insert_compound_tag("Heightmaps", heightmaps);
+
+        let mut analyzer = ChunkSummaryAnalyzer::with_source(
+            test_fixtures::source(vec![chunk]),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap().lines().nth(1),
+            Some("0,0,minecraft:plains,15.00,1,1")
+        );
+    }
+}
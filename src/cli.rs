@@ -0,0 +1,2334 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+
+use crate::{
+    adjacency_analyzer::AdjacencyAnalyzer,
+    area::{Area, REGION_SIZE},
+    artificial_block_analyzer::ArtificialBlockAnalyzer,
+    biome_height_analyzer::BiomeHeightAnalyzer,
+    biome_naturalness_analyzer::BiomeNaturalnessAnalyzer,
+    block_entity_diff_analyzer::BlockEntityDiffAnalyzer,
+    block_id::IdForm,
+    block_list, block_tags,
+    carving_analyzer::CarvingAnalyzer,
+    centroid_analyzer::CentroidAnalyzer,
+    chunk_dump::write_chunk_dump,
+    chunk_list::ChunkList,
+    chunk_loader::{
+        ChunkLoader, LoaderOptions, OutOfRangeSectionPolicy, SkippedSectionPolicy,
+        MAX_LOADED_CHUNKS,
+    },
+    chunk_source::RegionFolderSource,
+    chunk_summary_analyzer::ChunkSummaryAnalyzer,
+    co_occurrence_analyzer::{CoOccurrenceAnalyzer, CoOccurrenceBlocks, DEFAULT_CO_OCCURRENCE_TOP},
+    composition_analyzer::{
+        analyze_dimensions, data_version_spread_warning, ColumnOrder, CompositionAnalyzer,
+        CompositionOptions, CountMetric, DimensionTotals, Normalization, YFilter, OTHER_BLOCKSTATE,
+        SLICE_COMPARISON_TOP,
+    },
+    composition_results::{CompositionResults, SIDECAR_EXTENSION},
+    coords,
+    core_sample::{core_sample, write_core_samples},
+    data_version_analyzer::DataVersionAnalyzer,
+    diamond_vein_analyzer::{DiamondVeinAnalyzer, DEFAULT_MAX_VEIN_SIZE, MAX_FORTUNE},
+    dimension::Dimension,
+    distance_band_analyzer::DistanceBandAnalyzer,
+    diversity_analyzer::DiversityAnalyzer,
+    duplicate_chunk_analyzer::DuplicateChunkAnalyzer,
+    entity_category_analyzer::EntityCategoryAnalyzer,
+    event_log::{Event, EventLog, EventLogIter},
+    exposure_analyzer::ExposureAnalyzer,
+    fingerprint_analyzer::FingerprintAnalyzer,
+    flat_world_analyzer::{FlatRecipe, FlatWorldAnalyzer},
+    floating_block_analyzer::FloatingBlockAnalyzer,
+    floor_analyzer::FloorAnalyzer,
+    generated_chunk_analyzer::ChunkPresenceMapAnalyzer,
+    image_output::ImageFormat,
+    interrupt,
+    last_update_analyzer::LastUpdateAnalyzer,
+    layer_slice_analyzer::LayerSliceAnalyzer,
+    light_source_analyzer::LightSourceAnalyzer,
+    ore_defs::resolve_ore_group,
+    output_dir::OutputDir,
+    overview_analyzer::{ChunkMetric, OverviewAnalyzer, DEFAULT_OVERVIEW_METRICS},
+    palette::PaletteOptions,
+    palette_efficiency_analyzer::PaletteEfficiencyAnalyzer,
+    poi_analyzer::PoiAnalyzer,
+    polygon_area::PolygonArea,
+    presence_analyzer::PresenceAnalyzer,
+    presets::Preset,
+    progress::ProgressIter,
+    redstone_analyzer::{RedstoneAnalyzer, REDSTONE_COMPONENTS},
+    region_archive::{self, RegionArchive},
+    region_list::RegionList,
+    region_matrix_analyzer::RegionMatrixAnalyzer,
+    run_config::RunConfig,
+    scan_size::{estimated_duration, ScanSize},
+    scheduled_tick_analyzer::ScheduledTickAnalyzer,
+    section_density_analyzer::SectionDensityAnalyzer,
+    section_index_analyzer::SectionIndexAnalyzer,
+    structure_block_analyzer::StructureBlockAnalyzer,
+    structure_composition_analyzer::StructureCompositionAnalyzer,
+    technical_block_analyzer::TechnicalBlockAnalyzer,
+    world_path,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+/// Parses the command line arguments and runs the chosen analysis
+pub fn run() {
+    let matches = App::new("mca-analyzer")
+        .version("0.1.0")
+        .about("Analyze Minecraft's .mca (and old .mcr) region files")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Merge the region sidecars written with --sidecars into one composition report")
+                .arg(
+                    Arg::with_name("folder")
+                        .help("The folder containing the sidecar files")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Print results saved with --save-results again, without scanning the world")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The results file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Print the layers as CSV, or the results as JSON like a sidecar")
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("id-form")
+                        .long("id-form")
+                        .value_name("FORM")
+                        .help("How block names are printed: with namespace, without, or only with non-minecraft namespaces")
+                        .possible_values(&["full", "short", "namespaced"])
+                        .default_value("full")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("blocks-file")
+                        .long("blocks-file")
+                        .value_name("FILE")
+                        .help("Only report the blockstates listed in this file, one per line")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("y-range")
+                        .long("y-range")
+                        .value_names(&["MIN", "MAX"])
+                        .help("Only report the layers in this inclusive range")
+                        .number_of_values(2)
+                        .allow_hyphen_values(true)
+                        .takes_value(true),
+                ),
+        )
+        .arg(
+            Arg::with_name("folder")
+                .help("The region folder to be analyzed, or a .tar.gz of it (the world folder with --all-dimensions)")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("An optional output file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output-dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .help("A folder for all generated files, created if missing")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("mode")
+                .short("m")
+                .long("mode")
+                .value_name("MODE")
+                .help("The analysis to run (defaults to 'veins' if an output file is given, 'composition' otherwise)")
+                .possible_values(&[
+                    "composition",
+                    "veins",
+                    "floor",
+                    "artificial",
+                    "sections",
+                    "poi",
+                    "slices",
+                    "fingerprints",
+                    "flat",
+                    "last-update",
+                    "ticks",
+                    "distance",
+                    "structures",
+                    "biome-height",
+                    "presence",
+                    "exposure",
+                    "adjacency",
+                    "light",
+                    "naturalness",
+                    "diversity",
+                    "redstone",
+                    "technical",
+                    "generated",
+                    "density",
+                    "carving",
+                    "floating",
+                    "cooccurrence",
+                    "versions",
+                    "overview",
+                    "duplicates",
+                    "palettes",
+                    "block-entity-diff",
+                    "summary",
+                    "entities",
+                    "centroid",
+                    "structure-instances",
+                ])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("preset")
+                .long("preset")
+                .value_name("PRESET")
+                .help("Count and locate a ready-made set of blocks instead of running a mode, e.g. deep-dark for sculk and ancient cities")
+                .possible_values(&["deep-dark"])
+                .takes_value(true)
+                .conflicts_with("mode"),
+        )
+        .arg(
+            Arg::with_name("all-dimensions")
+                .long("all-dimensions")
+                .help("Count blocks in the overworld, nether and end of a world folder and report them per dimension"),
+        )
+        .arg(
+            Arg::with_name("regions-file")
+                .long("regions-file")
+                .value_name("FILE")
+                .help("Only analyze the regions listed in this file (as r.X.Z.mca or X,Z), one per line")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("chunks-from-stdin")
+                .long("chunks-from-stdin")
+                .help("Only analyze the chunks read from stdin, one `chunk_x chunk_z` pair per line")
+                .conflicts_with("regions-file"),
+        )
+        .arg(
+            Arg::with_name("blocks-from-stdin")
+                .long("blocks-from-stdin")
+                .help("Only count the blockstates read from stdin, one per line (composition mode)")
+                .conflicts_with("chunks-from-stdin"),
+        )
+        .arg(
+            Arg::with_name("polygon-file")
+                .long("polygon-file")
+                .value_name("FILE")
+                .help("Only analyze the chunks around the polygon whose corners are listed in this file (as X,Z block coordinates), one per line, and only count blocks inside it (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["regions-file", "chunks-from-stdin"]),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .long("yes")
+                .short("y")
+                .help("Don't ask for confirmation before very large scans"),
+        )
+        .arg(
+            Arg::with_name("ore")
+                .long("ore")
+                .value_name("NAME")
+                .help("The ore group to find veins of or check for exposure (veins and exposure mode)")
+                .default_value("diamond")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ore-defs")
+                .long("ore-defs")
+                .value_name("FILE")
+                .help("Custom ore groups, one `name = [\"namespace:ore\", ...]` per line")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("connect-property")
+                .long("connect-property")
+                .value_name("PROPERTY")
+                .help("Only connect vein blocks with the same value for this blockstate property (veins mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-vein-size")
+                .long("max-vein-size")
+                .value_name("BLOCKS")
+                .help("Ignore veins with at least this many blocks (veins mode) [default: 16]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fortune")
+                .long("fortune")
+                .value_name("LEVEL")
+                .help("Also estimate the items the ores drop when mined with this Fortune level, 0 to 3 (veins mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("image-format")
+                .long("image-format")
+                .value_name("FORMAT")
+                .help("The format of generated images")
+                .possible_values(&["png", "svg"])
+                .default_value("png")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tile-size")
+                .long("tile-size")
+                .value_name("PIXELS")
+                .help("Split images into square tiles of this size, e.g. for huge areas (veins, slices, presence, diversity, redstone, generated, carving and overview mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("grid")
+                .long("grid")
+                .value_name("CHUNKS")
+                .help("Draw gridlines labeled with chunk coordinates every this many chunks onto generated images (veins, presence, diversity, redstone, generated, carving and overview mode)")
+                .takes_value(true)
+                .conflicts_with("tile-size"),
+        )
+        .arg(
+            Arg::with_name("id-form")
+                .long("id-form")
+                .value_name("FORM")
+                .help("How block names are printed: with namespace, without, or only with non-minecraft namespaces")
+                .possible_values(&["full", "short", "namespaced"])
+                .default_value("full")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("legend")
+                .long("legend")
+                .help("Add a color legend below generated images"),
+        )
+        .arg(
+            Arg::with_name("block")
+                .long("block")
+                .value_name("BLOCKSTATE")
+                .help("The blockstate to draw or look for (slices, presence, adjacency and centroid mode)")
+                .default_value("minecraft:diamond_ore")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("exists")
+                .long("exists")
+                .value_name("BLOCKSTATE")
+                .help("Only check whether the blockstate occurs: stop at the first one and print its position, exit with 1 if there is none")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dump-chunk")
+                .long("dump-chunk")
+                .value_names(&["X", "Z", "FILE"])
+                .help("Only write the decoded blocks of the chunk at these chunk coordinates to a file, as one grid per Y layer")
+                .number_of_values(3)
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("core")
+                .long("core")
+                .value_names(&["X", "Z"])
+                .help("Only print the blockstate at every Y of the block column at these block coordinates, from top to bottom; can be given several times for a column each")
+                .number_of_values(2)
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("region-matrix")
+                .long("region-matrix")
+                .value_name("BLOCKSTATE")
+                .help("Only count the blockstate per region and print a grid with a row per region Z and a column per region X, e.g. for spreadsheet heatmaps")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("y-range")
+                .long("y-range")
+                .value_names(&["MIN", "MAX"])
+                .help("The inclusive range of Y levels to analyze")
+                .number_of_values(2)
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("below-y")
+                .long("below-y")
+                .value_name("Y")
+                .help("Only count blocks below this Y, combined with --above-y and --y-range if given (composition mode)")
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("above-y")
+                .long("above-y")
+                .value_name("Y")
+                .help("Only count blocks above this Y, combined with --below-y and --y-range if given (composition mode)")
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("center")
+                .long("center")
+                .value_names(&["X", "Z"])
+                .help("The block coordinates distances are measured from (distance mode)")
+                .number_of_values(2)
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("band-width")
+                .long("band-width")
+                .value_name("BLOCKS")
+                .help("The width of each distance band (distance mode)")
+                .default_value("100")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("natural-blocks")
+                .long("natural-blocks")
+                .value_name("FILE")
+                .help("A file listing the blockstates considered natural, one per line, instead of those of the scanned dimension (artificial mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("redstone-blocks")
+                .long("redstone-blocks")
+                .value_name("FILE")
+                .help("A file listing the blockstates counted as redstone components, one per line (redstone mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cooccurrence-top")
+                .long("cooccurrence-top")
+                .value_name("N")
+                .help("Only report pairs among the N blockstates found in the most chunks, 20 by default (cooccurrence mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cooccurrence-blocks")
+                .long("cooccurrence-blocks")
+                .value_name("FILE")
+                .help("Only report pairs among the blockstates listed in this file, one per line (cooccurrence mode)")
+                .takes_value(true)
+                .conflicts_with("cooccurrence-top"),
+        )
+        .arg(
+            Arg::with_name("overview-metrics")
+                .long("overview-metrics")
+                .value_name("R,G,B")
+                .help("The metrics shown in the red, green and blue channel, each of ores, diversity, inhabited, last-update, sections or a blockstate such as minecraft:diamond_ore (overview mode)")
+                .default_value(DEFAULT_OVERVIEW_METRICS)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("coordinates")
+                .long("coordinates")
+                .help("Also list the coordinates of every reported block"),
+        )
+        .arg(
+            Arg::with_name("per-chunk")
+                .long("per-chunk")
+                .help("Also list the results for every single chunk"),
+        )
+        .arg(
+            Arg::with_name("normalize-names")
+                .long("normalize-names")
+                .help("Trim and lowercase blockstate names before counting them (composition mode)"),
+        )
+        .arg(
+            Arg::with_name("layer-deltas")
+                .long("layer-deltas")
+                .help("Print each layer's counts relative to the layer below it (composition mode)"),
+        )
+        .arg(
+            Arg::with_name("sort-columns")
+                .long("sort-columns")
+                .value_name("ORDER")
+                .help("Order blockstate columns by count or group them by namespace (composition mode)")
+                .possible_values(&["count", "namespace"])
+                .default_value("count")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("metric")
+                .long("metric")
+                .value_name("METRIC")
+                .help("Count every block, or only the columns each blockstate occurs in, in the totals (composition mode)")
+                .possible_values(&["blocks", "columns"])
+                .default_value("blocks")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("normalize")
+                .long("normalize")
+                .value_name("NORMALIZATION")
+                .help("Print the totals as they are, or as averages per scanned chunk to compare areas of different sizes (composition mode)")
+                .possible_values(&["none", "per-chunk"])
+                .default_value("none")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Print aggregate CSV, one line of JSON per chunk while scanning, or collapsed stacks for flamegraph tools (composition mode)")
+                .possible_values(&["csv", "ndjson", "flamegraph"])
+                .default_value("csv")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tags")
+                .long("tags")
+                .value_name("FILE")
+                .help("Also count blocks per tag, one `\"#tag\" = [\"namespace:block\", ...]` per line (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("profile-image")
+                .long("profile-image")
+                .value_name("FILE")
+                .help("Also save a chart of each layer's composition (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("append")
+                .long("append")
+                .value_name("FILE")
+                .help("Append the results as a new run to a CSV of earlier runs instead of printing them (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("split-layers")
+                .long("split-layers")
+                .value_name("DIR")
+                .help("Write each layer's counts to its own layer_<y>.csv in this folder instead of printing them (composition mode)")
+                .takes_value(true)
+                .conflicts_with("append"),
+        )
+        .arg(
+            Arg::with_name("sqlite")
+                .long("sqlite")
+                .value_name("FILE")
+                .help("Save the results to a new SQLite database instead of printing them. Needs the sqlite feature, which is on by default (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["append", "split-layers"]),
+        )
+        .arg(
+            Arg::with_name("parquet")
+                .long("parquet")
+                .value_name("FILE")
+                .help("Save every chunk's counts per layer to a new Parquet file instead of printing them, as rows of chunk_x, chunk_z, y, blockstate and count. Needs the parquet feature (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["append", "split-layers", "sqlite"]),
+        )
+        .arg(
+            Arg::with_name("sidecars")
+                .long("sidecars")
+                .value_name("DIR")
+                .help("Save each region's counts as r.X.Z.json in this folder instead of printing them, to be combined with the merge subcommand (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["append", "split-layers", "sqlite", "parquet", "all-dimensions"]),
+        )
+        .arg(
+            Arg::with_name("save-results")
+                .long("save-results")
+                .value_name("FILE")
+                .help("Also save the counts in a compact binary file, to be printed again with the report subcommand (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["sidecars", "all-dimensions"]),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .value_name("FILE")
+                .help("Keep the counts gathered so far in this file while scanning, in the format of --save-results, so that they survive a crash or a killed scan (composition mode)")
+                .takes_value(true)
+                .conflicts_with_all(&["sidecars", "all-dimensions"]),
+        )
+        .arg(
+            Arg::with_name("checkpoint-every")
+                .long("checkpoint-every")
+                .value_name("CHUNKS")
+                .help("How many chunks to scan between writes of the --checkpoint file")
+                .default_value("100")
+                .requires("checkpoint")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("underground-only")
+                .long("underground-only")
+                .help("Only count blocks below the WORLD_SURFACE heightmap of their column, leaving out surface builds and foliage (composition mode)"),
+        )
+        .arg(
+            Arg::with_name("in-biome")
+                .long("in-biome")
+                .value_name("BIOME")
+                .help("Only count blocks in this biome, e.g. desert, going by 4x4x4 cells; only works for worlds since 1.18 (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("compact-palette")
+                .long("compact-palette")
+                .help("Print how large the block data would be if every palette were trimmed to the blockstates it uses, as optimizing the world does (palettes mode)"),
+        )
+        .arg(
+            Arg::with_name("count-waterlogged-water")
+                .long("count-waterlogged-water")
+                .help("Also count a block of water for every waterlogged block, e.g. for the actual amount of water (composition mode)"),
+        )
+        .arg(
+            Arg::with_name("compare-slices")
+                .long("compare-slices")
+                .value_names(&["Y1", "Y2"])
+                .help("Print the most common blockstates of two layers side by side instead of all layers (composition mode)")
+                .number_of_values(2)
+                .allow_hyphen_values(true)
+                .takes_value(true)
+                .conflicts_with_all(&["append", "split-layers", "sqlite", "parquet"]),
+        )
+        .arg(
+            Arg::with_name("y-bounds")
+                .long("y-bounds")
+                .help("Print the lowest and highest Y and the total of every blockstate instead of all layers (composition mode)")
+                .conflicts_with_all(&["append", "split-layers", "sqlite", "parquet", "compare-slices"]),
+        )
+        .arg(
+            Arg::with_name("mining-time")
+                .long("mining-time")
+                .help("Print how many ticks it takes to mine every blockstate with diamond tools and the total time to dig out the area instead of all layers (composition mode)")
+                .conflicts_with_all(&["append", "split-layers", "sqlite", "parquet", "compare-slices", "y-bounds"]),
+        )
+        .arg(
+            Arg::with_name("volume-percent")
+                .long("volume-percent")
+                .help("Print every blockstate's share of the scanned box, all chunks times the height of their world limited by --y-range, --below-y, --above-y and --y-step, instead of all layers (composition mode)")
+                .conflicts_with_all(&["append", "split-layers", "sqlite", "parquet", "compare-slices", "y-bounds", "mining-time"]),
+        )
+        .arg(
+            Arg::with_name("max-distinct")
+                .long("max-distinct")
+                .value_name("N")
+                .help("Count blockstates beyond the first N distinct ones as 'other' to limit memory (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("simulate-replace")
+                .long("simulate-replace")
+                .value_name("FROM=TO")
+                .help("Also report the totals after replacing a blockstate, can be repeated (composition mode)")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("only-layers-with")
+                .long("only-layers-with")
+                .value_name("BLOCKSTATE")
+                .help("Only print the layers containing this blockstate (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("band-height")
+                .long("band-height")
+                .value_name("N")
+                .help("Merge the layers into bands of N Y levels, summing their counts (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("human-numbers")
+                .long("human-numbers")
+                .help("Group the digits of counts with thousands separators (composition CSV)"),
+        )
+        .arg(
+            Arg::with_name("y-step")
+                .long("y-step")
+                .value_name("N")
+                .help("Only count every Nth layer, totals then only cover those layers (composition mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("baseline")
+                .long("baseline")
+                .value_name("FOLDER")
+                .help("Region folder of an earlier copy of the world, e.g. a backup, to compare with (block-entity-diff mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("detect-flat")
+                .long("detect-flat")
+                .help("Report whether the world appears to be superflat, with a confidence score and the detected layer recipe (same as --mode flat)")
+                .conflicts_with_all(&["mode", "preset"]),
+        )
+        .arg(
+            Arg::with_name("verify-flat")
+                .long("verify-flat")
+                .value_name("RECIPE")
+                .help("Check every column against this superflat recipe, e.g. bedrock,2*dirt,grass_block, and list the layers that deviate from it (flat mode)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tick-bucket")
+                .long("tick-bucket")
+                .value_name("TICKS")
+                .help("Width of a histogram bucket in game ticks (last-update mode)")
+                .default_value("24000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("slow-chunk-threshold")
+                .long("slow-chunk-threshold")
+                .value_name("MS")
+                .help("Log every chunk that takes longer than this many milliseconds to process, and the slowest one at the end")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("print-config")
+                .long("print-config")
+                .help("Print the resolved settings to stderr before analyzing, and how the chunk cache did afterwards"),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .help("Periodically print how many chunks are done and an estimated time remaining"),
+        )
+        .arg(
+            Arg::with_name("event-log")
+                .long("event-log")
+                .value_name("FILE")
+                .help("Write the scan's progress to this file as one JSON object per line, e.g. for tools supervising the scan")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("full-only")
+                .long("full-only")
+                .help("Skip chunks that aren't fully generated yet"),
+        )
+        .arg(
+            Arg::with_name("skip-corrupt-regions")
+                .long("skip-corrupt-regions")
+                .help("Log and skip region files that can't be read instead of aborting"),
+        )
+        .arg(
+            Arg::with_name("io-retries")
+                .long("io-retries")
+                .value_name("N")
+                .help("Retry reading a chunk this many times with growing pauses before giving up, e.g. for worlds on a network mount")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("no-implicit-air")
+                .long("no-implicit-air")
+                .help("Don't insert minecraft:air as palette index 0 (for non-vanilla data)"),
+        )
+        .arg(
+            Arg::with_name("implicit-air-policy")
+                .long("implicit-air-policy")
+                .value_name("POLICY")
+                .help("Whether sections that can't be decoded are left out or counted as 4096 blocks of air")
+                .possible_values(&["ignore", "count"])
+                .default_value("ignore")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("out-of-range-sections")
+                .long("out-of-range-sections")
+                .value_name("POLICY")
+                .help("Whether sections outside of the heights of the chunk's version are dropped with a warning, skip the whole chunk or are counted anyway (e.g. for data pack worlds)")
+                .possible_values(&["drop", "error", "keep"])
+                .default_value("drop")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("force-bit-width")
+                .long("force-bit-width")
+                .value_name("BITS")
+                .help("Debugging: decode every section with this many bits per block instead of the width implied by its palette. A wrong width shows up as garbled blocks or minecraft:unknown")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    // Output printed without a line break yet is still buffered when a panic ends the process,
+    // so write it out before the panic is reported
+    let default_panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = io::stdout().flush();
+        default_panic_hook(info);
+    }));
+
+    if let Some(merge_matches) = matches.subcommand_matches("merge") {
+        let folder = Path::new(merge_matches.value_of("folder").unwrap());
+
+        match CompositionResults::merge_folder(folder) {
+            Ok((results, sidecar_count)) => {
+                eprintln!("Merged {} sidecars", sidecar_count);
+                if let Some(warning) = data_version_spread_warning(&results.data_versions) {
+                    eprintln!("Warning: {}", warning);
+                }
+                results.print_csv(IdForm::default());
+            }
+            Err(err) => {
+                eprintln!("Could not merge '{}': {}", folder.display(), err);
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
+    if let Some(report_matches) = matches.subcommand_matches("report") {
+        let file = Path::new(report_matches.value_of("file").unwrap());
+
+        let mut results = match CompositionResults::read_binary(file) {
+            Ok(results) => results,
+            Err(err) => {
+                eprintln!("Could not read '{}': {}", file.display(), err);
+                process::exit(1);
+            }
+        };
+
+        if let Some(blocks_file) = report_matches.value_of("blocks-file") {
+            match block_list::read_block_list(Path::new(blocks_file)) {
+                Ok(blocks) => results.retain_blocks(&blocks),
+                Err(err) => {
+                    eprintln!("Could not read '{}': {}", blocks_file, err);
+                    process::exit(1);
+                }
+            }
+        }
+
+        if let Some(values) = report_matches.values_of("y-range") {
+            let values: Result<Vec<i32>, _> = values.map(str::parse).collect();
+            match values.as_deref() {
+                Ok(&[min, max]) if min <= max => results.retain_layers(&(min..=max)),
+                _ => {
+                    eprintln!("--y-range expects two integers MIN MAX with MIN <= MAX");
+                    process::exit(1);
+                }
+            }
+        }
+
+        match report_matches.value_of("format").unwrap() {
+            "json" => println!("{}", results.to_json()),
+            _ => results.print_csv(
+                IdForm::from_name(report_matches.value_of("id-form").unwrap()).unwrap_or_default(),
+            ),
+        }
+
+        return;
+    }
+
+    let archive = match matches.value_of("folder").map(Path::new) {
+        Some(path) if region_archive::is_archive(path) => match RegionArchive::extract(path) {
+            Ok(archive) => Some(archive),
+            Err(err) => {
+                eprintln!("Could not read '{}': {}", path.display(), err);
+                return;
+            }
+        },
+        _ => None,
+    };
+
+    // Owned, so that the archive can be dropped (removing its extracted files) before exiting early
+    let input_path = if let Some(archive) = &archive {
+        archive.path().to_path_buf()
+    } else if let Some(folder) = matches.value_of("folder") {
+        let path = Path::new(folder);
+        if !path.is_dir() {
+            eprintln!("'{}' is not a folder!", folder);
+            return;
+        }
+        path.to_path_buf()
+    } else {
+        eprintln!("No input folder has been specified.");
+        return;
+    };
+    let input_path = input_path.as_path();
+
+    let output_path = matches.value_of("output").map(Path::new);
+
+    let output_dir = match matches.value_of("output-dir").map(Path::new) {
+        Some(dir) => match OutputDir::create(dir) {
+            Ok(output_dir) => Some(output_dir),
+            Err(err) => {
+                eprintln!("Could not create '{}': {}", dir.display(), err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let mode = if matches.is_present("preset") {
+        "preset"
+    } else if matches.is_present("detect-flat") {
+        "flat"
+    } else {
+        matches
+            .value_of("mode")
+            .unwrap_or(if output_path.is_some() || output_dir.is_some() {
+                "veins"
+            } else {
+                "composition"
+            })
+    };
+
+    let polygon = match matches.value_of("polygon-file") {
+        Some(file) => match PolygonArea::from_file(Path::new(file)) {
+            Ok(polygon) => Some(polygon),
+            Err(err) => {
+                eprintln!("Could not read '{}': {}", file, err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let only_blocks = if matches.is_present("blocks-from-stdin") {
+        match block_list::read_block_list_from(io::stdin().lock()) {
+            Ok(blocks) => Some(blocks),
+            Err(err) => {
+                eprintln!("Could not read blockstates from stdin: {}", err);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let (area, chunks): (Area, Vec<(i32, i32)>) =
+        if let Some(file) = matches.value_of("regions-file") {
+            match RegionList::from_file(Path::new(file)) {
+                Ok(regions) => (regions.bounding_area(), regions.into_iter().collect()),
+                Err(err) => {
+                    eprintln!("Could not read '{}': {}", file, err);
+                    return;
+                }
+            }
+        } else if matches.is_present("chunks-from-stdin") {
+            match ChunkList::from_reader(io::stdin().lock()) {
+                Ok(chunks) => (chunks.bounding_area(), chunks.into_iter().collect()),
+                Err(err) => {
+                    eprintln!("Could not read chunks from stdin: {}", err);
+                    return;
+                }
+            }
+        } else if let Some(polygon) = &polygon {
+            (
+                polygon.bounding_area(),
+                polygon.clone().into_iter().collect(),
+            )
+        } else {
+            let area = Area::new(0, 256, 0, 256);
+            (area, area.into_iter().collect())
+        };
+
+    eprintln!(
+        "Scanning {} chunks, roughly {} minutes",
+        chunks.len(),
+        estimated_duration(chunks.len()).as_secs() / 60
+    );
+
+    match ScanSize::of(chunks.len()) {
+        ScanSize::Empty => {
+            eprintln!("The area to analyze is empty.");
+            drop(archive);
+            process::exit(1);
+        }
+        ScanSize::Large if !matches.is_present("yes") => {
+            // Stdin is already used up by the chunk or block list in that case
+            if matches.is_present("chunks-from-stdin") || matches.is_present("blocks-from-stdin") {
+                eprintln!("This is a very large scan, pass --yes to run it anyway.");
+                drop(archive);
+                process::exit(1);
+            }
+
+            eprint!("This is a very large scan. Continue? [y/N] ");
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).is_err()
+                || !answer.trim().eq_ignore_ascii_case("y")
+            {
+                drop(archive);
+                process::exit(1);
+            }
+        }
+        _ => {}
+    }
+
+    let image_format =
+        ImageFormat::from_name(matches.value_of("image-format").unwrap()).unwrap_or_default();
+
+    let tile_size = match matches.value_of("tile-size").map(str::parse::<u32>) {
+        Some(Ok(tile_size)) if tile_size > 0 => Some(tile_size),
+        Some(_) => {
+            eprintln!("--tile-size expects a positive integer");
+            return;
+        }
+        None => None,
+    };
+
+    let grid = match matches.value_of("grid").map(str::parse::<u32>) {
+        Some(Ok(spacing)) if spacing > 0 => Some(spacing),
+        Some(_) => {
+            eprintln!("--grid expects a positive integer");
+            return;
+        }
+        None => None,
+    };
+
+    let id_form = IdForm::from_name(matches.value_of("id-form").unwrap()).unwrap_or_default();
+
+    let forced_bit_width = match matches.value_of("force-bit-width").map(str::parse::<u32>) {
+        Some(Ok(bits)) if (1..=32).contains(&bits) => Some(bits),
+        Some(_) => {
+            eprintln!("--force-bit-width expects an integer from 1 to 32");
+            return;
+        }
+        None => None,
+    };
+
+    let io_retries = match matches.value_of("io-retries").map(str::parse::<u32>) {
+        Some(Ok(retries)) => retries,
+        Some(Err(_)) => {
+            eprintln!("--io-retries expects a non-negative integer");
+            return;
+        }
+        None => 0,
+    };
+
+    let loader_options = LoaderOptions {
+        palette: PaletteOptions {
+            implicit_air: !matches.is_present("no-implicit-air"),
+            forced_bit_width,
+        },
+        skip_corrupt_regions: matches.is_present("skip-corrupt-regions"),
+        skip_unused_tags: false,
+        full_only: matches.is_present("full-only"),
+        skipped_sections: SkippedSectionPolicy::from_name(
+            matches.value_of("implicit-air-policy").unwrap(),
+        )
+        .unwrap_or_default(),
+        out_of_range_sections: OutOfRangeSectionPolicy::from_name(
+            matches.value_of("out-of-range-sections").unwrap(),
+        )
+        .unwrap_or_default(),
+        io_retries,
+    };
+
+    let y_range = match matches.values_of("y-range") {
+        Some(values) => {
+            let values: Result<Vec<i32>, _> = values.map(str::parse).collect();
+            match values.as_deref() {
+                Ok(&[min, max]) if min <= max => Some(min..=max),
+                _ => {
+                    eprintln!("--y-range expects two integers MIN MAX with MIN <= MAX");
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
+
+    let mut y_bounds = [None, None];
+    for (bound, name) in y_bounds.iter_mut().zip(["below-y", "above-y"]) {
+        *bound = match matches.value_of(name).map(str::parse::<i32>) {
+            Some(Ok(y)) => Some(y),
+            Some(Err(_)) => {
+                eprintln!("--{} expects an integer", name);
+                return;
+            }
+            None => None,
+        };
+    }
+    let y_filter = YFilter {
+        range: y_range.clone(),
+        below: y_bounds[0],
+        above: y_bounds[1],
+    };
+    if y_filter.is_empty() {
+        eprintln!("--y-range, --below-y and --above-y don't leave any Y levels to count");
+        return;
+    }
+
+    let y_step = match matches.value_of("y-step").map(str::parse::<i32>) {
+        Some(Ok(step)) if step > 0 => Some(step),
+        Some(_) => {
+            eprintln!("--y-step expects a positive integer");
+            return;
+        }
+        None => None,
+    };
+
+    let slow_chunk_threshold = match matches
+        .value_of("slow-chunk-threshold")
+        .map(str::parse::<u64>)
+    {
+        Some(Ok(millis)) => Some(Duration::from_millis(millis)),
+        Some(Err(_)) => {
+            eprintln!("--slow-chunk-threshold expects a number of milliseconds");
+            return;
+        }
+        None => None,
+    };
+
+    // A second handle on the same file, for errors reported after the scan took the first one
+    let (event_log, mut error_log) = match matches.value_of("event-log") {
+        Some(path) => match fs::File::create(path).and_then(|file| Ok((file.try_clone()?, file))) {
+            Ok((file, error_file)) => (
+                Some(EventLog::new(io::LineWriter::new(file))),
+                Some(EventLog::new(io::LineWriter::new(error_file))),
+            ),
+            Err(err) => {
+                eprintln!("Could not create '{}': {}", path, err);
+                return;
+            }
+        },
+        None => (None, None),
+    };
+
+    if matches.is_present("print-config") {
+        let config = RunConfig {
+            mode: mode.to_string(),
+            input_folder: input_path
+                .canonicalize()
+                .unwrap_or_else(|_| input_path.to_path_buf()),
+            output: output_dir
+                .as_ref()
+                .map(|output_dir| output_dir.path())
+                .or(output_path)
+                .map(Path::to_path_buf),
+            area,
+            chunk_count: chunks.len(),
+            y_range: y_range.clone(),
+            y_step,
+            loader_options,
+            cache_size: MAX_LOADED_CHUNKS,
+            image_format,
+            tile_size,
+            id_form,
+        };
+        eprintln!("{}", config);
+    }
+
+    if matches.is_present("all-dimensions") {
+        let options = match composition_options(
+            &matches,
+            input_path,
+            id_form,
+            y_step,
+            y_filter,
+            only_blocks,
+            polygon,
+        ) {
+            Some(options) => options,
+            None => return,
+        };
+
+        let dimensions = analyze_dimensions(input_path, loader_options, &options, || {
+            ProgressIter::new(
+                chunks.clone().into_iter(),
+                chunks.len(),
+                matches.is_present("progress"),
+            )
+            .with_slow_chunk_threshold(slow_chunk_threshold)
+        });
+        if dimensions.is_empty() {
+            eprintln!("'{}' doesn't contain any dimensions", input_path.display());
+            return;
+        }
+
+        let mut printed_header = false;
+        for DimensionTotals {
+            dimension, totals, ..
+        } in &dimensions
+        {
+            for (blockstate, count) in totals {
+                if !printed_header {
+                    println!("Dimension,Blockstate,Count");
+                    printed_header = true;
+                }
+                println!("{},{},{:8}", dimension, id_form.render(blockstate), count);
+            }
+        }
+
+        if !printed_header {
+            eprintln!("No blocks found in any dimension");
+        }
+
+        let data_versions = dimensions
+            .iter()
+            .filter_map(|dimension| dimension.data_versions.as_ref());
+        if let Some(warning) = data_version_spread_warning(data_versions) {
+            eprintln!("Warning: {}", warning);
+        }
+
+        return;
+    }
+
+    if let Some(folder) = matches.value_of("sidecars") {
+        let folder = Path::new(folder);
+        if let Err(err) = fs::create_dir_all(folder) {
+            eprintln!("Could not create '{}': {}", folder.display(), err);
+            return;
+        }
+
+        let mut regions: BTreeMap<(i32, i32), Vec<(i32, i32)>> = BTreeMap::new();
+        for (chunk_x, chunk_z) in chunks {
+            let region = (
+                chunk_x.div_euclid(REGION_SIZE),
+                chunk_z.div_euclid(REGION_SIZE),
+            );
+            regions.entry(region).or_default().push((chunk_x, chunk_z));
+        }
+
+        let options = match composition_options(
+            &matches,
+            input_path,
+            id_form,
+            y_step,
+            y_filter,
+            only_blocks,
+            polygon,
+        ) {
+            Some(options) => options,
+            None => return,
+        };
+
+        for ((region_x, region_z), region_chunks) in regions {
+            eprintln!("Analyzing region r.{}.{}.mca", region_x, region_z);
+
+            let mut composition_analyzer = CompositionAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                options.clone(),
+            );
+
+            let chunk_count = region_chunks.len();
+            composition_analyzer.analyze(
+                ProgressIter::new(
+                    region_chunks.into_iter(),
+                    chunk_count,
+                    matches.is_present("progress"),
+                )
+                .with_slow_chunk_threshold(slow_chunk_threshold),
+            );
+
+            let path = folder.join(format!("r.{}.{}.{}", region_x, region_z, SIDECAR_EXTENSION));
+            if let Err(err) = composition_analyzer.results().write_json(&path) {
+                eprintln!("Could not write '{}': {}", path.display(), err);
+                return;
+            }
+        }
+
+        return;
+    }
+
+    if let Some(mut values) = matches.values_of("dump-chunk") {
+        let (chunk_x, chunk_z, path) = match (
+            values.next().unwrap().parse::<i32>(),
+            values.next().unwrap().parse::<i32>(),
+            values.next().unwrap(),
+        ) {
+            (Ok(chunk_x), Ok(chunk_z), path) => (chunk_x, chunk_z, path),
+            _ => {
+                eprintln!("--dump-chunk expects two integer chunk coordinates and a file");
+                return;
+            }
+        };
+
+        let mut chunk_loader = ChunkLoader::new(
+            input_path.as_os_str().to_str().unwrap(),
+            None,
+            loader_options,
+        );
+        let chunk = match chunk_loader.get_or_load(chunk_x, chunk_z) {
+            Some(chunk) => chunk,
+            None => {
+                eprintln!("Could not load chunk ({},{})", chunk_x, chunk_z);
+                return;
+            }
+        };
+
+        let result = fs::File::create(path)
+            .map(io::BufWriter::new)
+            .and_then(|mut file| write_chunk_dump(chunk, &mut file).and_then(|_| file.flush()));
+        match result {
+            Ok(()) => eprintln!("Wrote chunk ({},{}) to '{}'", chunk_x, chunk_z, path),
+            Err(err) => eprintln!("Could not write '{}': {}", path, err),
+        }
+
+        return;
+    }
+
+    if let Some(values) = matches.values_of("core") {
+        let values: Vec<&str> = values.collect();
+        let mut columns = Vec::new();
+        for pair in values.chunks(2) {
+            match (pair[0].parse::<i64>(), pair[1].parse::<i64>()) {
+                (Ok(x), Ok(z)) => columns.push((x, z)),
+                _ => {
+                    eprintln!("--core expects two integer block coordinates");
+                    return;
+                }
+            }
+        }
+
+        let dimension = Dimension::of_region_folder(input_path);
+        let mut chunk_loader = ChunkLoader::new(
+            input_path.as_os_str().to_str().unwrap(),
+            None,
+            loader_options,
+        );
+
+        let mut samples = Vec::new();
+        for (x, z) in columns {
+            let parts = coords::decompose(x, 0, z);
+            eprintln!(
+                "Column ({},{}) is in chunk ({},{}), ({},{}) within r.{}.{}.mca",
+                x,
+                z,
+                parts.chunk.0,
+                parts.chunk.1,
+                parts.region_chunk.x,
+                parts.region_chunk.z,
+                parts.region.x,
+                parts.region.z
+            );
+
+            let sample = core_sample(&mut chunk_loader, x, z, y_range.clone(), dimension);
+            if sample.is_empty() {
+                eprintln!("Could not load the column at ({},{})", x, z);
+            }
+            samples.push(((x, z), sample));
+        }
+        write_core_samples(&mut io::stdout().lock(), &samples).unwrap();
+
+        return;
+    }
+
+    if let Some(target) = matches.value_of("exists") {
+        let mut presence_analyzer = PresenceAnalyzer::new(
+            input_path.as_os_str().to_str().unwrap(),
+            area,
+            target.to_string(),
+            loader_options,
+        );
+
+        match presence_analyzer.find_first(chunks) {
+            Some((x, y, z)) => {
+                println!("{},{},{}", x, y, z);
+                return;
+            }
+            None => {
+                eprintln!("No {} found", target);
+                drop(archive);
+                process::exit(1);
+            }
+        }
+    }
+
+    let chunk_count = chunks.len();
+    let chunks = EventLogIter::new(
+        ProgressIter::new(
+            chunks.into_iter(),
+            chunk_count,
+            matches.is_present("progress"),
+        )
+        .with_slow_chunk_threshold(slow_chunk_threshold),
+        chunk_count,
+        event_log,
+    );
+
+    if let Some(target) = matches.value_of("region-matrix") {
+        let mut region_matrix_analyzer = RegionMatrixAnalyzer::new(
+            input_path.as_os_str().to_str().unwrap(),
+            area,
+            target.to_string(),
+            loader_options,
+        );
+
+        region_matrix_analyzer.analyze(chunks);
+
+        region_matrix_analyzer.print_csv();
+        return;
+    }
+
+    match mode {
+        "veins" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                output_dir.file(&format!("veins.{}", image_format.extension()))
+            } else if let Some(path) = output_path {
+                path.to_path_buf()
+            } else {
+                eprintln!("The 'veins' mode requires an output file or folder.");
+                return;
+            };
+
+            let ores = match resolve_ore_group(
+                matches.value_of("ore-defs").map(Path::new),
+                matches.value_of("ore").unwrap(),
+            ) {
+                Ok(ores) => ores,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                }
+            };
+
+            let max_vein_size = match matches.value_of("max-vein-size").map(str::parse::<usize>) {
+                Some(Ok(size)) if size > 0 => size,
+                Some(_) => {
+                    eprintln!("--max-vein-size expects a positive integer");
+                    return;
+                }
+                None => DEFAULT_MAX_VEIN_SIZE,
+            };
+
+            let fortune = match matches.value_of("fortune").map(str::parse::<u8>) {
+                Some(Ok(fortune)) if fortune <= MAX_FORTUNE => Some(fortune),
+                Some(_) => {
+                    eprintln!("--fortune expects a level from 0 to {}", MAX_FORTUNE);
+                    return;
+                }
+                None => None,
+            };
+
+            let mut builder = DiamondVeinAnalyzer::builder(
+                RegionFolderSource::new(input_path.as_os_str().to_str().unwrap()),
+                area,
+            )
+            .ores(ores)
+            .max_vein_size(max_vein_size)
+            .loader_options(loader_options);
+            if let Some(property) = matches.value_of("connect-property") {
+                builder = builder.connect_property(property.to_string());
+            }
+            if let Some(y_range) = y_range.clone() {
+                builder = builder.y_range(y_range);
+            }
+            if let Some(fortune) = fortune {
+                builder = builder.fortune(fortune);
+            }
+            let mut diamond_vein_analyzer = builder.build();
+
+            diamond_vein_analyzer.analyze(chunks);
+            if let Some(output_dir) = &output_dir {
+                if let Err(err) = diamond_vein_analyzer.save_to_dir(
+                    output_dir,
+                    id_form,
+                    matches.is_present("legend"),
+                    image_format,
+                    tile_size,
+                    grid,
+                ) {
+                    eprintln!(
+                        "Could not write to '{}': {}",
+                        output_dir.path().display(),
+                        err
+                    );
+                }
+                return;
+            }
+
+            diamond_vein_analyzer.print_csv(id_form);
+            diamond_vein_analyzer.print_img(
+                img_path.as_os_str().to_str().unwrap(),
+                matches.is_present("legend"),
+                image_format,
+                tile_size,
+                grid,
+            );
+        }
+        "light" => {
+            let mut light_source_analyzer =
+                LightSourceAnalyzer::new(input_path.as_os_str().to_str().unwrap(), loader_options);
+
+            light_source_analyzer.analyze(chunks);
+
+            light_source_analyzer.print_csv(id_form);
+        }
+        "adjacency" => {
+            let mut adjacency_analyzer = AdjacencyAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                matches.value_of("block").unwrap().to_string(),
+                loader_options,
+            );
+
+            adjacency_analyzer.analyze(chunks);
+
+            adjacency_analyzer.print_csv(id_form);
+        }
+        "exposure" => {
+            let ores = match resolve_ore_group(
+                matches.value_of("ore-defs").map(Path::new),
+                matches.value_of("ore").unwrap(),
+            ) {
+                Ok(ores) => ores,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                }
+            };
+
+            let mut exposure_analyzer = ExposureAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                ores,
+                loader_options,
+            );
+
+            exposure_analyzer.analyze(chunks);
+
+            exposure_analyzer.print_csv(id_form);
+        }
+        "floor" => {
+            let mut floor_analyzer =
+                FloorAnalyzer::new(input_path.as_os_str().to_str().unwrap(), loader_options);
+
+            floor_analyzer.analyze(chunks);
+
+            floor_analyzer.print_csv();
+        }
+        "artificial" => {
+            let natural_blocks = if let Some(file) = matches.value_of("natural-blocks") {
+                match block_list::read_block_list(Path::new(file)) {
+                    Ok(natural_blocks) => natural_blocks,
+                    Err(err) => {
+                        eprintln!("Could not read '{}': {}", file, err);
+                        return;
+                    }
+                }
+            } else {
+                Dimension::of_region_folder(input_path)
+                    .natural_blocks()
+                    .iter()
+                    .map(|block| block.to_string())
+                    .collect()
+            };
+
+            let mut artificial_block_analyzer = ArtificialBlockAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                natural_blocks,
+                matches.is_present("coordinates"),
+            );
+
+            artificial_block_analyzer.analyze(chunks);
+
+            artificial_block_analyzer.print_csv();
+        }
+        "technical" => {
+            let mut technical_block_analyzer = TechnicalBlockAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            technical_block_analyzer.analyze(chunks);
+
+            technical_block_analyzer.print_csv(id_form);
+        }
+        "preset" => {
+            let preset = Preset::from_name(matches.value_of("preset").unwrap()).unwrap();
+
+            let mut preset_analyzer = TechnicalBlockAnalyzer::for_preset(
+                RegionFolderSource::new(input_path.as_os_str().to_str().unwrap()),
+                preset,
+                loader_options,
+            );
+
+            preset_analyzer.analyze(chunks);
+
+            preset_analyzer.print_csv(id_form);
+        }
+        "floating" => {
+            let mut floating_block_analyzer = FloatingBlockAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            floating_block_analyzer.analyze(chunks);
+
+            floating_block_analyzer.print_csv(id_form);
+        }
+        "cooccurrence" => {
+            let blocks = if let Some(file) = matches.value_of("cooccurrence-blocks") {
+                match block_list::read_block_list(Path::new(file)) {
+                    Ok(blocks) => CoOccurrenceBlocks::Listed(blocks),
+                    Err(err) => {
+                        eprintln!("Could not read '{}': {}", file, err);
+                        return;
+                    }
+                }
+            } else {
+                match matches
+                    .value_of("cooccurrence-top")
+                    .map(str::parse::<usize>)
+                {
+                    Some(Ok(top)) if top > 0 => CoOccurrenceBlocks::Top(top),
+                    Some(_) => {
+                        eprintln!("--cooccurrence-top expects a positive integer");
+                        return;
+                    }
+                    None => CoOccurrenceBlocks::Top(DEFAULT_CO_OCCURRENCE_TOP),
+                }
+            };
+
+            let mut co_occurrence_analyzer = CoOccurrenceAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                blocks,
+                loader_options,
+            );
+
+            co_occurrence_analyzer.analyze(chunks);
+
+            co_occurrence_analyzer.print_csv(id_form);
+        }
+        "sections" => {
+            let mut section_index_analyzer = SectionIndexAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                matches.is_present("per-chunk"),
+            );
+
+            section_index_analyzer.analyze(chunks);
+
+            section_index_analyzer.print_csv();
+        }
+        "density" => {
+            let mut section_density_analyzer = SectionDensityAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            section_density_analyzer.analyze(chunks);
+
+            section_density_analyzer.print_csv();
+        }
+        "fingerprints" => {
+            let mut fingerprint_analyzer =
+                FingerprintAnalyzer::new(input_path.as_os_str().to_str().unwrap(), loader_options);
+
+            fingerprint_analyzer.analyze(chunks);
+
+            fingerprint_analyzer.print_csv();
+        }
+        "duplicates" => {
+            let mut duplicate_chunk_analyzer = DuplicateChunkAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            duplicate_chunk_analyzer.analyze(chunks);
+
+            duplicate_chunk_analyzer.print_csv();
+        }
+        "block-entity-diff" => {
+            let baseline = match matches.value_of("baseline") {
+                Some(baseline) if Path::new(baseline).is_dir() => baseline,
+                Some(baseline) => {
+                    eprintln!("'{}' is not a folder!", baseline);
+                    return;
+                }
+                None => {
+                    eprintln!("The 'block-entity-diff' mode requires a --baseline region folder.");
+                    return;
+                }
+            };
+
+            let mut block_entity_diff_analyzer = BlockEntityDiffAnalyzer::new(
+                baseline,
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            block_entity_diff_analyzer.analyze(chunks);
+
+            block_entity_diff_analyzer.print_csv();
+        }
+        "summary" => {
+            let mut chunk_summary_analyzer =
+                ChunkSummaryAnalyzer::new(input_path.as_os_str().to_str().unwrap(), loader_options);
+
+            chunk_summary_analyzer.analyze(chunks);
+
+            chunk_summary_analyzer.print_csv();
+        }
+        "palettes" => {
+            let mut palette_efficiency_analyzer = PaletteEfficiencyAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            palette_efficiency_analyzer.analyze(chunks);
+
+            if matches.is_present("compact-palette") {
+                palette_efficiency_analyzer.print_compaction();
+            } else {
+                palette_efficiency_analyzer.print_csv();
+            }
+        }
+        "flat" => {
+            let recipe = match matches.value_of("verify-flat") {
+                Some(recipe) => match FlatRecipe::parse(recipe) {
+                    Some(recipe) => Some(recipe),
+                    None => {
+                        eprintln!(
+                            "--verify-flat expects layers from the bottom up like bedrock,2*dirt,grass_block"
+                        );
+                        return;
+                    }
+                },
+                None => None,
+            };
+            let verify = recipe.is_some();
+
+            let mut flat_world_analyzer = FlatWorldAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                recipe,
+                loader_options,
+            );
+
+            flat_world_analyzer.analyze(chunks);
+
+            if verify {
+                flat_world_analyzer.print_verification();
+                if !flat_world_analyzer.matches_recipe() {
+                    drop(archive);
+                    process::exit(1);
+                }
+            } else {
+                flat_world_analyzer.print_csv();
+            }
+        }
+        "last-update" => {
+            let bucket_size = match matches.value_of("tick-bucket").unwrap().parse::<i64>() {
+                Ok(size) if size > 0 => size,
+                _ => {
+                    eprintln!("--tick-bucket expects a positive integer");
+                    return;
+                }
+            };
+
+            let mut last_update_analyzer = LastUpdateAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                bucket_size,
+            );
+
+            last_update_analyzer.analyze(chunks);
+
+            last_update_analyzer.print_csv();
+        }
+        "versions" => {
+            let mut data_version_analyzer =
+                DataVersionAnalyzer::new(input_path.as_os_str().to_str().unwrap(), loader_options);
+
+            data_version_analyzer.analyze(chunks);
+
+            data_version_analyzer.print_csv();
+        }
+        "ticks" => {
+            let mut scheduled_tick_analyzer = ScheduledTickAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            scheduled_tick_analyzer.analyze(chunks);
+
+            scheduled_tick_analyzer.print_csv();
+        }
+        "distance" => {
+            let center = match matches.values_of("center") {
+                Some(values) => {
+                    let values: Result<Vec<i64>, _> = values.map(str::parse).collect();
+                    match values.as_deref() {
+                        Ok(&[x, z]) => (x, z),
+                        _ => {
+                            eprintln!("--center expects two integers X Z");
+                            return;
+                        }
+                    }
+                }
+                None => (0, 0),
+            };
+
+            let band_width = match matches.value_of("band-width").unwrap().parse::<u32>() {
+                Ok(width) if width > 0 => width,
+                _ => {
+                    eprintln!("--band-width expects a positive integer");
+                    return;
+                }
+            };
+
+            let mut distance_band_analyzer = DistanceBandAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                center,
+                band_width,
+            );
+
+            distance_band_analyzer.analyze(chunks);
+
+            distance_band_analyzer.print_csv();
+        }
+        "structures" => {
+            let mut structure_block_analyzer = StructureBlockAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            structure_block_analyzer.analyze(chunks);
+
+            structure_block_analyzer.print_csv();
+        }
+        "structure-instances" => {
+            let mut structure_composition_analyzer = StructureCompositionAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+            );
+
+            structure_composition_analyzer.analyze(chunks);
+
+            structure_composition_analyzer.print_csv();
+        }
+        "biome-height" => {
+            let mut biome_height_analyzer =
+                BiomeHeightAnalyzer::new(input_path.as_os_str().to_str().unwrap());
+
+            biome_height_analyzer.analyze(chunks);
+
+            biome_height_analyzer.print_csv();
+        }
+        "naturalness" => {
+            let mut biome_naturalness_analyzer =
+                BiomeNaturalnessAnalyzer::new(input_path.as_os_str().to_str().unwrap());
+
+            biome_naturalness_analyzer.analyze(chunks);
+
+            biome_naturalness_analyzer.print_csv();
+        }
+        "poi" => {
+            let poi_folder = world_path::sibling_folder(input_path, "poi");
+            if !poi_folder.is_dir() {
+                eprintln!("Could not find a poi folder at '{}'", poi_folder.display());
+                return;
+            }
+
+            let poi_folder = poi_folder.to_string_lossy();
+            let mut poi_analyzer = PoiAnalyzer::new(&poi_folder);
+
+            poi_analyzer.analyze(chunks);
+
+            poi_analyzer.print_csv();
+        }
+        "centroid" => {
+            let mut centroid_analyzer = CentroidAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                matches.value_of("block").unwrap().to_string(),
+                loader_options,
+            );
+
+            centroid_analyzer.analyze(chunks);
+
+            centroid_analyzer.print_csv();
+        }
+        "entities" => {
+            // Worlds before 1.17 don't have an entities folder and store entities in the chunks
+            let entities_folder = world_path::sibling_folder(input_path, "entities");
+            let entities_folder = if entities_folder.is_dir() {
+                entities_folder.to_string_lossy().into_owned()
+            } else {
+                input_path.to_string_lossy().into_owned()
+            };
+
+            let mut entity_category_analyzer = EntityCategoryAnalyzer::new(&entities_folder);
+
+            entity_category_analyzer.analyze(chunks);
+
+            entity_category_analyzer.print_csv();
+        }
+        "presence" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                Some(output_dir.file(&format!("presence.{}", image_format.extension())))
+            } else {
+                output_path.map(Path::to_path_buf)
+            };
+
+            let mut presence_analyzer = PresenceAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                area,
+                matches.value_of("block").unwrap().to_string(),
+                loader_options,
+            );
+
+            presence_analyzer.analyze(chunks);
+
+            if let Some(img_path) = img_path {
+                presence_analyzer.print_img(
+                    img_path.to_str().unwrap(),
+                    image_format,
+                    tile_size,
+                    grid,
+                );
+            }
+            presence_analyzer.print_csv();
+        }
+        "diversity" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                Some(output_dir.file(&format!("diversity.{}", image_format.extension())))
+            } else {
+                output_path.map(Path::to_path_buf)
+            };
+
+            let mut diversity_analyzer = DiversityAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                area,
+                loader_options,
+            );
+
+            diversity_analyzer.analyze(chunks);
+
+            if let Some(img_path) = img_path {
+                diversity_analyzer.print_img(
+                    img_path.to_str().unwrap(),
+                    image_format,
+                    tile_size,
+                    grid,
+                );
+            }
+            diversity_analyzer.print_csv();
+        }
+        "overview" => {
+            let channels =
+                match ChunkMetric::parse_channels(matches.value_of("overview-metrics").unwrap()) {
+                    Some(channels) => channels,
+                    None => {
+                        eprintln!("--overview-metrics expects three metrics separated by commas");
+                        return;
+                    }
+                };
+
+            let img_path = match (&output_dir, output_path) {
+                (Some(output_dir), _) => {
+                    output_dir.file(&format!("world.{}", image_format.extension()))
+                }
+                (None, Some(output_path)) => output_path.to_path_buf(),
+                (None, None) => PathBuf::from(format!("world.{}", image_format.extension())),
+            };
+
+            let mut overview_analyzer = OverviewAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                area,
+                channels,
+                loader_options,
+            );
+
+            overview_analyzer.analyze(chunks);
+
+            overview_analyzer.print_img(img_path.to_str().unwrap(), image_format, tile_size, grid);
+            overview_analyzer.print_csv();
+        }
+        "redstone" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                Some(output_dir.file(&format!("redstone.{}", image_format.extension())))
+            } else {
+                output_path.map(Path::to_path_buf)
+            };
+
+            let components = if let Some(file) = matches.value_of("redstone-blocks") {
+                match block_list::read_block_list(Path::new(file)) {
+                    Ok(components) => components,
+                    Err(err) => {
+                        eprintln!("Could not read '{}': {}", file, err);
+                        return;
+                    }
+                }
+            } else {
+                REDSTONE_COMPONENTS
+                    .iter()
+                    .map(|block| block.to_string())
+                    .collect()
+            };
+
+            let mut redstone_analyzer = RedstoneAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                area,
+                components,
+                loader_options,
+            );
+
+            redstone_analyzer.analyze(chunks);
+
+            if let Some(img_path) = img_path {
+                redstone_analyzer.print_img(
+                    img_path.to_str().unwrap(),
+                    image_format,
+                    tile_size,
+                    grid,
+                );
+            }
+            redstone_analyzer.print_csv(id_form);
+        }
+        "generated" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                Some(output_dir.file(&format!("generated.{}", image_format.extension())))
+            } else {
+                output_path.map(Path::to_path_buf)
+            };
+
+            let mut chunk_presence_map_analyzer =
+                ChunkPresenceMapAnalyzer::new(input_path.as_os_str().to_str().unwrap(), area);
+
+            chunk_presence_map_analyzer.analyze(chunks);
+
+            if let Some(img_path) = img_path {
+                chunk_presence_map_analyzer.print_img(
+                    img_path.to_str().unwrap(),
+                    image_format,
+                    tile_size,
+                    grid,
+                );
+            }
+            chunk_presence_map_analyzer.print_csv();
+        }
+        "carving" => {
+            let img_path = if let Some(output_dir) = &output_dir {
+                Some(output_dir.file(&format!("carving.{}", image_format.extension())))
+            } else {
+                output_path.map(Path::to_path_buf)
+            };
+
+            let mut carving_analyzer =
+                CarvingAnalyzer::new(input_path.as_os_str().to_str().unwrap(), area);
+
+            carving_analyzer.analyze(chunks);
+
+            if let Some(img_path) = img_path {
+                carving_analyzer.print_img(
+                    img_path.to_str().unwrap(),
+                    image_format,
+                    tile_size,
+                    grid,
+                );
+            }
+            carving_analyzer.print_csv();
+        }
+        "slices" => {
+            let folder = if let Some(output_dir) = &output_dir {
+                output_dir.path()
+            } else if let Some(path) = output_path {
+                path
+            } else {
+                eprintln!("The 'slices' mode requires an output folder.");
+                return;
+            };
+
+            if let Err(err) = fs::create_dir_all(folder) {
+                eprintln!("Could not create '{}': {}", folder.display(), err);
+                return;
+            }
+
+            let mut layer_slice_analyzer = LayerSliceAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                area,
+                matches.value_of("block").unwrap().to_string(),
+                y_range.unwrap_or(0..=15),
+                loader_options,
+            );
+
+            layer_slice_analyzer.analyze(chunks);
+            layer_slice_analyzer.print_imgs(folder, image_format, tile_size);
+        }
+        _ => {
+            let ndjson = matches.value_of("format") == Some("ndjson");
+
+            if matches.is_present("sqlite") && !cfg!(feature = "sqlite") {
+                eprintln!("--sqlite needs mca-analyzer to be built with `--features sqlite`");
+                drop(archive);
+                process::exit(1);
+            }
+
+            if matches.is_present("parquet") && !cfg!(feature = "parquet") {
+                eprintln!("--parquet needs mca-analyzer to be built with `--features parquet`");
+                drop(archive);
+                process::exit(1);
+            }
+
+            if matches.is_present("mining-time") && matches.value_of("metric") == Some("columns") {
+                eprintln!("--mining-time needs every block to be counted, not --metric columns");
+                return;
+            }
+
+            let compare_slices = match matches.values_of("compare-slices") {
+                Some(values) => {
+                    let values: Result<Vec<i32>, _> = values.map(str::parse).collect();
+                    match values.as_deref() {
+                        Ok(&[y1, y2]) => Some((y1, y2)),
+                        _ => {
+                            eprintln!("--compare-slices expects two integers Y1 Y2");
+                            return;
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            let options = match composition_options(
+                &matches,
+                input_path,
+                id_form,
+                y_step,
+                y_filter,
+                only_blocks,
+                polygon,
+            ) {
+                Some(options) => options,
+                None => return,
+            };
+
+            let checkpoint_every = match matches.value_of("checkpoint-every").unwrap().parse() {
+                Ok(every) if every > 0 => every,
+                _ => {
+                    eprintln!("--checkpoint-every expects a positive integer");
+                    return;
+                }
+            };
+
+            let mut composition_analyzer = CompositionAnalyzer::new(
+                input_path.as_os_str().to_str().unwrap(),
+                loader_options,
+                options,
+            );
+            if let Some(path) = matches.value_of("checkpoint") {
+                composition_analyzer =
+                    composition_analyzer.with_checkpoint(PathBuf::from(path), checkpoint_every);
+            }
+
+            // Ctrl-C stops the scan after the current chunk, and the partial results are still printed
+            if !interrupt::install_handler() {
+                eprintln!(
+                    "Could not handle Ctrl-C, it will stop the scan without printing results"
+                );
+            }
+
+            composition_analyzer.analyze(chunks);
+
+            if matches.is_present("print-config") {
+                eprintln!("chunk cache: {}", composition_analyzer.cache_stats());
+            }
+
+            let complete = composition_analyzer.aborted_at().is_none();
+            if let (Some((chunk_x, chunk_z)), Some(error_log)) =
+                (composition_analyzer.aborted_at(), &mut error_log)
+            {
+                error_log.emit(&Event::Error {
+                    message: format!("Scan interrupted at chunk ({},{})", chunk_x, chunk_z),
+                });
+            }
+
+            let dropped = composition_analyzer.dropped_blockstate_count();
+            if dropped > 0 {
+                eprintln!(
+                    "Counted {} distinct blockstates beyond --max-distinct as '{}'",
+                    dropped, OTHER_BLOCKSTATE
+                );
+            }
+
+            if let Some(path) = matches.value_of("profile-image") {
+                composition_analyzer.print_profile_img(path, image_format);
+            }
+
+            if let Some(path) = matches.value_of("save-results") {
+                match composition_analyzer.results().write_binary(Path::new(path)) {
+                    Ok(()) => eprintln!("Saved results to '{}'", path),
+                    Err(err) => {
+                        eprintln!("Could not write '{}': {}", path, err);
+                        drop(archive);
+                        process::exit(1);
+                    }
+                }
+            }
+
+            if let Some(path) = matches.value_of("append") {
+                match composition_analyzer.append_csv(Path::new(path)) {
+                    Ok(run) => eprintln!("Appended run {} to '{}'", run, path),
+                    Err(err) => {
+                        eprintln!("Could not append to '{}': {}", path, err);
+                        drop(archive);
+                        process::exit(1);
+                    }
+                }
+            } else if let Some(folder) = matches.value_of("split-layers") {
+                match composition_analyzer.write_split_layers(Path::new(folder)) {
+                    Ok(files) => eprintln!("Wrote {} layer files to '{}'", files, folder),
+                    Err(err) => {
+                        eprintln!("Could not write layers to '{}': {}", folder, err);
+                        drop(archive);
+                        process::exit(1);
+                    }
+                }
+            } else if let Some(path) = matches.value_of("sqlite") {
+                #[cfg(feature = "sqlite")]
+                let result = composition_analyzer.write_sqlite(Path::new(path));
+                #[cfg(not(feature = "sqlite"))]
+                let result = Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "built without the sqlite feature",
+                ));
+
+                match result {
+                    Ok(()) => eprintln!("Saved results to '{}'", path),
+                    Err(err) => {
+                        eprintln!("Could not write '{}': {}", path, err);
+                        drop(archive);
+                        process::exit(1);
+                    }
+                }
+            } else if let Some(path) = matches.value_of("parquet") {
+                #[cfg(feature = "parquet")]
+                let result = composition_analyzer.write_parquet(Path::new(path));
+                #[cfg(not(feature = "parquet"))]
+                let result = Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "built without the parquet feature",
+                ));
+
+                match result {
+                    Ok(()) => eprintln!("Saved results to '{}'", path),
+                    Err(err) => {
+                        eprintln!("Could not write '{}': {}", path, err);
+                        drop(archive);
+                        process::exit(1);
+                    }
+                }
+            } else if let Some(slices) = compare_slices {
+                composition_analyzer.print_slice_comparison(slices, SLICE_COMPARISON_TOP);
+            } else if matches.is_present("y-bounds") {
+                composition_analyzer.print_y_bounds();
+            } else if matches.is_present("mining-time") {
+                composition_analyzer.print_mining_time();
+            } else if matches.is_present("volume-percent") {
+                composition_analyzer.print_volume_percentages();
+            } else if matches.value_of("format") == Some("flamegraph") {
+                composition_analyzer.print_flamegraph();
+            } else if !ndjson {
+                composition_analyzer.print_csv();
+            }
+
+            if !complete {
+                drop(archive);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Options for counting blocks in composition mode, shared by scans of one and of all dimensions.
+/// Prints an error and returns `None` if an argument is invalid.
+fn composition_options(
+    matches: &ArgMatches,
+    region_folder: &Path,
+    id_form: IdForm,
+    y_step: Option<i32>,
+    y_filter: YFilter,
+    only_blocks: Option<HashSet<String>>,
+    polygon: Option<PolygonArea>,
+) -> Option<CompositionOptions> {
+    let tags = match matches.value_of("tags") {
+        Some(file) => match block_tags::read_block_tags(Path::new(file)) {
+            Ok(tags) => Some(tags),
+            Err(err) => {
+                eprintln!("Could not read '{}': {}", file, err);
+                return None;
+            }
+        },
+        None => None,
+    };
+
+    let max_distinct = match matches.value_of("max-distinct").map(str::parse::<usize>) {
+        Some(Ok(max_distinct)) if max_distinct > 0 => Some(max_distinct),
+        Some(_) => {
+            eprintln!("--max-distinct expects a positive integer");
+            return None;
+        }
+        None => None,
+    };
+
+    let mut replacements = Vec::new();
+    for replacement in matches.values_of("simulate-replace").into_iter().flatten() {
+        match replacement.split_once('=') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+                replacements.push((from.to_string(), to.to_string()))
+            }
+            _ => {
+                eprintln!("--simulate-replace expects FROM=TO, got '{}'", replacement);
+                return None;
+            }
+        }
+    }
+
+    let band_height = match matches.value_of("band-height").map(str::parse::<i32>) {
+        Some(Ok(height)) if height > 0 => Some(height),
+        Some(_) => {
+            eprintln!("--band-height expects a positive integer");
+            return None;
+        }
+        None => None,
+    };
+
+    Some(CompositionOptions {
+        normalize_names: matches.is_present("normalize-names"),
+        layer_deltas: matches.is_present("layer-deltas"),
+        id_form,
+        y_step,
+        y_filter,
+        column_order: ColumnOrder::from_name(matches.value_of("sort-columns").unwrap())
+            .unwrap_or_default(),
+        dimension: Dimension::of_region_folder(region_folder),
+        metric: CountMetric::from_name(matches.value_of("metric").unwrap()).unwrap_or_default(),
+        normalization: Normalization::from_name(matches.value_of("normalize").unwrap())
+            .unwrap_or_default(),
+        ndjson: matches.value_of("format") == Some("ndjson"),
+        human_numbers: matches.is_present("human-numbers"),
+        tags,
+        max_distinct,
+        replacements,
+        only_layers_with: matches.value_of("only-layers-with").map(String::from),
+        band_height,
+        underground_only: matches.is_present("underground-only"),
+        in_biome: matches
+            .value_of("in-biome")
+            .map(|biome| IdForm::Full.render(biome)),
+        count_waterlogged_water: matches.is_present("count-waterlogged-water"),
+        remap: None,
+        only_blocks,
+        polygon,
+        chunk_rows: matches.is_present("parquet"),
+    })
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Number of blockstates whose pairs are reported, unless configured otherwise
+pub const DEFAULT_CO_OCCURRENCE_TOP: usize = 20;
+
+/// Which blockstates pairs are reported for, since all pairs of all blockstates would be huge
+#[derive(Debug, Clone)]
+pub enum CoOccurrenceBlocks {
+    /// The blockstates occurring in the most chunks
+    Top(usize),
+    /// Exactly these blockstates
+    Listed(HashSet<String>),
+}
+
+/// Counts for each pair of blockstates how many chunks contain both, e.g. for clustering chunks
+/// by the blocks they're made of
+pub struct CoOccurrenceAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    blocks: CoOccurrenceBlocks,
+
+    /// Blockstate names, indexed by the ids used in `chunk_blocks`
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+
+    /// Distinct blockstate ids of every scanned chunk, sorted
+    chunk_blocks: Vec<Vec<u32>>,
+}
+
+impl<'a> CoOccurrenceAnalyzer<'a> {
+    pub fn new(path: &'a str, blocks: CoOccurrenceBlocks, loader_options: LoaderOptions) -> Self {
+        CoOccurrenceAnalyzer::with_source(RegionFolderSource::new(path), blocks, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        blocks: CoOccurrenceBlocks,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        CoOccurrenceAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            blocks,
+            names: Vec::new(),
+            ids: HashMap::new(),
+            chunk_blocks: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let mut distinct: HashSet<u32> = HashSet::new();
+            for block in chunk.into_iter().flatten() {
+                let id = match self.ids.get(&block.blockstate) {
+                    Some(&id) => id,
+                    None => {
+                        let id = self.names.len() as u32;
+                        self.ids.insert(block.blockstate.clone(), id);
+                        self.names.push(block.blockstate);
+                        id
+                    }
+                };
+                distinct.insert(id);
+            }
+
+            let mut distinct: Vec<u32> = distinct.into_iter().collect();
+            distinct.sort_unstable();
+            self.chunk_blocks.push(distinct);
+        }
+    }
+
+    /// Ids of the blockstates to report pairs for
+    fn selected_ids(&self) -> HashSet<u32> {
+        match &self.blocks {
+            CoOccurrenceBlocks::Listed(blocks) => blocks
+                .iter()
+                .filter_map(|block| self.ids.get(block).copied())
+                .collect(),
+            CoOccurrenceBlocks::Top(top) => {
+                let mut chunk_counts = vec![0u32; self.names.len()];
+                for &id in self.chunk_blocks.iter().flatten() {
+                    chunk_counts[id as usize] += 1;
+                }
+
+                let mut ids: Vec<u32> = (0..self.names.len() as u32).collect();
+                ids.sort_by(|&a, &b| {
+                    chunk_counts[b as usize]
+                        .cmp(&chunk_counts[a as usize])
+                        .then(self.names[a as usize].cmp(&self.names[b as usize]))
+                });
+                ids.truncate(*top);
+                ids.into_iter().collect()
+            }
+        }
+    }
+
+    /// Number of chunks containing both blockstates of each pair, as
+    /// `(block_a, block_b, chunks)` with `block_a < block_b`, most frequent pairs first.
+    /// Pairs that never occur together are left out.
+    pub fn pairs(&self) -> Vec<(&str, &str, u32)> {
+        let selected = self.selected_ids();
+
+        let mut pair_counts: HashMap<(u32, u32), u32> = HashMap::new();
+        for blocks in &self.chunk_blocks {
+            let blocks: Vec<u32> = blocks
+                .iter()
+                .copied()
+                .filter(|id| selected.contains(id))
+                .collect();
+
+            for (index, &a) in blocks.iter().enumerate() {
+                for &b in &blocks[index + 1..] {
+                    *pair_counts.entry((a, b)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut pairs: Vec<(&str, &str, u32)> = pair_counts
+            .into_iter()
+            .map(|((a, b), count)| {
+                let (a, b) = (
+                    self.names[a as usize].as_str(),
+                    self.names[b as usize].as_str(),
+                );
+                if a < b {
+                    (a, b, count)
+                } else {
+                    (b, a, count)
+                }
+            })
+            .collect();
+        pairs.sort_by(|(a1, b1, a), (a2, b2, b)| b.cmp(a).then((a1, b1).cmp(&(a2, b2))));
+        pairs
+    }
+
+    pub fn print_csv(&self, id_form: IdForm) {
+        println!("Block A,Block B,Chunks with both");
+        for (a, b, count) in self.pairs() {
+            println!("{},{},{}", id_form.render(a), id_form.render(b), count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    /// Stone everywhere, diamond ore in chunks 0 and 1, and lava in chunks 1 and 2
+    fn analyze(blocks: CoOccurrenceBlocks) -> CoOccurrenceAnalyzer<'static> {
+        let chunk = |x: i32, names: &[&str]| {
+            let section = test_fixtures::section(0, names, |x, _, _| x % names.len());
+            test_fixtures::chunk(x, 0, vec![section])
+        };
+        let source = test_fixtures::source(vec![
+            chunk(0, &["minecraft:stone", "minecraft:diamond_ore"]),
+            chunk(
+                1,
+                &["minecraft:stone", "minecraft:diamond_ore", "minecraft:lava"],
+            ),
+            chunk(2, &["minecraft:stone", "minecraft:lava"]),
+        ]);
+
+        let mut analyzer =
+            CoOccurrenceAnalyzer::with_source(source, blocks, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+        analyzer
+    }
+
+    #[test]
+    fn listed_pairs() {
+        let listed = ["minecraft:stone", "minecraft:diamond_ore", "minecraft:lava"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let analyzer = analyze(CoOccurrenceBlocks::Listed(listed));
+
+        assert_eq!(
+            analyzer.pairs(),
+            vec![
+                ("minecraft:diamond_ore", "minecraft:stone", 2),
+                ("minecraft:lava", "minecraft:stone", 2),
+                ("minecraft:diamond_ore", "minecraft:lava", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_pairs() {
+        // Stone is in every chunk, diamond ore wins the tie with lava by name
+        let analyzer = analyze(CoOccurrenceBlocks::Top(2));
+
+        assert_eq!(
+            analyzer.pairs(),
+            vec![("minecraft:diamond_ore", "minecraft:stone", 2)]
+        );
+    }
+}
@@ -1,92 +1,2478 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, BufWriter, Write},
+    mem,
+    ops::RangeInclusive,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
 
 use crate::{
-    area::Area,
-    chunk_loader::ChunkLoader,
-    chunk_section::{ChunkSection, ChunkSectionBlock},
-    layers::Layers,
+    block_id::{split_namespace, IdForm, DEFAULT_NAMESPACE},
+    block_tags::BlockTags,
+    chunk_loader::{CacheStats, ChunkLoader, LoaderOptions},
+    chunk_section::{ChunkSection, ChunkSectionBlock, CHUNK_SIZE},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    composition_results::CompositionResults,
+    csv_append::{append_run, CsvRows},
+    dimension::Dimension,
+    flamegraph,
+    image_output::{save_image, ImageFormat},
+    interrupt,
+    layers::{Layer, Layers},
+    mining_time::{format_duration, MiningEstimate},
+    ndjson,
+    number_format::human_number,
+    polygon_area::PolygonArea,
+    profile_chart::{render_profile, OTHER_COLOR},
+    world_path,
+};
+
+#[cfg(feature = "sqlite")]
+use rusqlite::{params, Connection};
+
+#[cfg(feature = "parquet")]
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type},
+    errors::ParquetError,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
 };
 
+/// Options controlling how blocks are counted
+#[derive(Debug, Clone, Default)]
+pub struct CompositionOptions {
+    /// Trim and lowercase blockstate names before counting, so that e.g. `minecraft:Stone `
+    /// and `minecraft:stone` are counted together.
+    /// Off by default since vanilla names are already normalized and this hides what's actually stored.
+    pub normalize_names: bool,
+
+    /// Print each layer's counts as the difference to the layer below it instead of absolute counts.
+    /// The lowest layer has no predecessor and is printed as all zeros.
+    pub layer_deltas: bool,
+
+    /// How blockstate names are rendered in the header
+    pub id_form: IdForm,
+
+    /// Only count blocks on every Nth layer (those with Y divisible by N), for a coarse profile of large areas.
+    /// Totals then only cover the sampled layers.
+    pub y_step: Option<i32>,
+
+    /// Only count blocks within these Y limits. Totals then only cover those layers.
+    pub y_filter: YFilter,
+
+    pub column_order: ColumnOrder,
+
+    /// Dimension the chunks are from, which decides the height of the world for `scanned_volume`
+    pub dimension: Dimension,
+
+    /// Whether totals count blocks or the columns a blockstate occurs in
+    pub metric: CountMetric,
+
+    /// Whether the totals row shows the totals themselves or their average per scanned chunk
+    pub normalization: Normalization,
+
+    /// Print every chunk's counts as a line of JSON as soon as it's scanned
+    pub ndjson: bool,
+
+    /// Group the digits of counts in the CSV with thousands separators, for reading rather than parsing
+    pub human_numbers: bool,
+
+    /// Also print counts aggregated per block tag. Blocks in several tags count towards each of them.
+    pub tags: Option<BlockTags>,
+
+    /// Once this many distinct blockstates have been seen, count any further new ones as `OTHER_BLOCKSTATE`,
+    /// so that worlds with huge numbers of modded blockstates don't exhaust memory
+    pub max_distinct: Option<usize>,
+
+    /// Also print how the totals would change if blockstates were replaced, as `(from, to)` pairs
+    /// applied in order. Nothing in the world is modified.
+    pub replacements: Vec<(String, String)>,
+
+    /// Only print the layers containing this blockstate. The totals then only cover those layers.
+    pub only_layers_with: Option<String>,
+
+    /// Print the layers merged into bands of this many Y levels, see `Layers::bands`
+    pub band_height: Option<i32>,
+
+    /// Only count blocks below the highest block of their column, leaving out surface builds and foliage.
+    /// See `Chunk::surface_heights` for how the surface is found.
+    pub underground_only: bool,
+
+    /// Renames blockstates before they're counted, after `normalize_names`, e.g. to count blocks
+    /// that were split or merged between versions under their current names
+    pub remap: Option<BlockRemap>,
+
+    /// Only count these blockstates, as named after `normalize_names` and `remap`.
+    /// Totals and layers then have a column for each of them that occurs.
+    pub only_blocks: Option<HashSet<String>>,
+
+    /// Also count a block of water for every waterlogged block, whose water isn't part of its name,
+    /// see `ChunkSection::waterlogged_blocks`
+    pub count_waterlogged_water: bool,
+
+    /// Only count blocks whose column lies inside this polygon
+    pub polygon: Option<PolygonArea>,
+
+    /// Only count blocks in this biome, e.g. `minecraft:desert`, going by the biome of their 4x4x4 cell.
+    /// Chunks from before 1.18 don't store biomes per section, so nothing is counted in them.
+    pub in_biome: Option<String>,
+
+    /// Keep every chunk's counts per layer for long-format output such as `write_parquet`.
+    /// This keeps a row per chunk, layer and blockstate in memory.
+    pub chunk_rows: bool,
+}
+
+impl CompositionOptions {
+    /// Whether a layer is printed, see `only_layers_with`
+    fn shows_layer(&self, layer: &Layer) -> bool {
+        match &self.only_layers_with {
+            Some(target) => layer.get_count(target) > 0,
+            None => true,
+        }
+    }
+}
+
+/// Function that a blockstate is passed through before it's counted, see `CompositionOptions::remap`
+/// It also gets the block's global position, so one old blockstate can be split into several new ones.
+#[derive(Clone)]
+pub struct BlockRemap(Arc<RemapFn>);
+
+type RemapFn = dyn Fn(&str, (i64, i32, i64)) -> String + Send + Sync;
+
+impl BlockRemap {
+    pub fn new<F: Fn(&str, (i64, i32, i64)) -> String + Send + Sync + 'static>(remap: F) -> Self {
+        BlockRemap(Arc::new(remap))
+    }
+
+    pub fn apply(&self, blockstate: &str, global_pos: (i64, i32, i64)) -> String {
+        (self.0)(blockstate, global_pos)
+    }
+}
+
+impl fmt::Debug for BlockRemap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BlockRemap")
+    }
+}
+
+/// Name of the bucket that blockstates beyond `max_distinct` are counted in
+pub const OTHER_BLOCKSTATE: &str = "other";
+
+/// Number of blockstates shown per layer when comparing two layers
+pub const SLICE_COMPARISON_TOP: usize = 10;
+
+/// Limits on the Y of counted blocks, each of them optional. All limits that are set apply at
+/// once, so combining them counts the intersection, and limits that exclude each other count nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YFilter {
+    /// Inclusive range of Y levels
+    pub range: Option<RangeInclusive<i32>>,
+    /// Only Y levels strictly below this one
+    pub below: Option<i32>,
+    /// Only Y levels strictly above this one
+    pub above: Option<i32>,
+}
+
+impl YFilter {
+    pub fn contains(&self, y: i32) -> bool {
+        self.range.as_ref().is_none_or(|range| range.contains(&y))
+            && self.below.is_none_or(|below| y < below)
+            && self.above.is_none_or(|above| y > above)
+    }
+
+    /// Whether no Y level can pass the filter
+    pub fn is_empty(&self) -> bool {
+        let min = self.range.as_ref().map_or(i32::MIN, |range| *range.start());
+        let min = self
+            .above
+            .map_or(min, |above| min.max(above.saturating_add(1)));
+        let max = self.range.as_ref().map_or(i32::MAX, |range| *range.end());
+        let max = self
+            .below
+            .map_or(max, |below| max.min(below.saturating_sub(1)));
+        min > max
+    }
+}
+
+/// What the totals of each blockstate count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CountMetric {
+    /// Every single block
+    #[default]
+    Blocks,
+    /// Every `(x, z)` column the blockstate occurs in at least once, e.g. to see how much of the
+    /// surface is sand. Layers are unaffected since every column has a single block per layer.
+    Columns,
+}
+
+impl CountMetric {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blocks" => Some(CountMetric::Blocks),
+            "columns" => Some(CountMetric::Columns),
+            _ => None,
+        }
+    }
+}
+
+/// What the totals in the CSV are relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// Absolute totals
+    #[default]
+    None,
+    /// Totals divided by the number of chunks scanned, so that scans of differently sized areas
+    /// can be compared directly
+    PerChunk,
+}
+
+impl Normalization {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Normalization::None),
+            "per-chunk" => Some(Normalization::PerChunk),
+            _ => None,
+        }
+    }
+}
+
+/// Order of the blockstate columns in the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnOrder {
+    /// Most common blockstates first
+    #[default]
+    Count,
+    /// Grouped by namespace (`minecraft` first), then alphabetically by name within each namespace
+    Namespace,
+}
+
+impl ColumnOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(ColumnOrder::Count),
+            "namespace" => Some(ColumnOrder::Namespace),
+            _ => None,
+        }
+    }
+
+    /// Sorts blockstates with their totals into this order
+    pub fn sort(self, columns: &mut [(String, u64)]) {
+        match self {
+            ColumnOrder::Count => {
+                columns.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)))
+            }
+            ColumnOrder::Namespace => columns.sort_by_key(|(blockstate, _)| {
+                let (namespace, name) = split_namespace(blockstate);
+                (
+                    namespace != DEFAULT_NAMESPACE,
+                    namespace.to_string(),
+                    name.to_string(),
+                )
+            }),
+        }
+    }
+}
+
 pub struct CompositionAnalyzer<'a> {
-    blockstate_map: HashMap<String, u32>,
+    /// Counts are 64 bit since large scans easily contain more than `u32::MAX` blocks of air
+    blockstate_map: HashMap<String, u64>,
     layers: Layers,
     chunk_loader: ChunkLoader<'a>,
+    options: CompositionOptions,
+
+    /// Chunk at which the scan was aborted by a panic or an interrupt, if any
+    aborted_at: Option<(i32, i32)>,
+
+    /// Lowest and highest `DataVersion` of the scanned chunks
+    data_versions: Option<RangeInclusive<i32>>,
+
+    /// Number of chunks whose blocks were counted
+    chunk_count: u64,
+
+    /// File the results are written to while scanning, and after how many chunks each time
+    checkpoint: Option<(PathBuf, u64)>,
+
+    /// Stops the scan like an interrupt when set, in addition to Ctrl-C
+    interrupt_flag: Option<Arc<AtomicBool>>,
+
+    /// Number of blocks of the scanned chunks within the world's height that pass `y_filter`
+    /// and `y_step`, whether or not they were counted
+    scanned_volume: u64,
+
+    /// Hashes of the blockstates counted as `OTHER_BLOCKSTATE` because of `max_distinct`.
+    /// Only hashes are kept since keeping the names would defeat the point of the cap.
+    dropped_blockstates: HashSet<u64>,
+
+    /// Counts per chunk, layer and blockstate, only kept with `CompositionOptions::chunk_rows`
+    chunk_rows: Vec<ChunkRow>,
+
+    /// Columns of the current chunk in which each blockstate was already counted,
+    /// as `(x, z, blockstate)`, only used with `CountMetric::Columns`
+    seen_columns: HashSet<(usize, usize, String)>,
+}
+
+/// Count of one blockstate in one layer of a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkRow {
+    pub chunk: (i32, i32),
+    pub y: i32,
+    pub blockstate: String,
+    pub count: u32,
+}
+
+/// Data versions further apart than this (roughly one major release) likely rename or remove blocks
+pub const MAX_DATA_VERSION_SPREAD: i32 = 100;
+
+/// Warning for results merged from scans of different versions, whose totals may count the same
+/// block under several names, or `None` if all scans are close enough in version
+pub fn data_version_spread_warning<'r, I>(ranges: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'r RangeInclusive<i32>>,
+{
+    let (min, max) = ranges
+        .into_iter()
+        .fold(None, |spread, range| match spread {
+            None => Some((*range.start(), *range.end())),
+            Some((min, max)) => Some((min.min(*range.start()), max.max(*range.end()))),
+        })?;
+
+    if max - min <= MAX_DATA_VERSION_SPREAD {
+        return None;
+    }
+
+    Some(format!(
+        "Merged inputs span data versions {} to {}, blocks renamed or removed in between \
+         may be counted under different names",
+        min, max
+    ))
+}
+
+/// Results of scanning one dimension, see `analyze_dimensions`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimensionTotals {
+    pub dimension: Dimension,
+    pub totals: Vec<(String, u64)>,
+    pub data_versions: Option<RangeInclusive<i32>>,
+}
+
+/// Scans the same chunks in every dimension of a world folder, counting them with the same
+/// options as a scan of a single region folder. Dimensions without a region folder are left out.
+/// `chunks` is called once per dimension, e.g. to show the progress of each separately.
+pub fn analyze_dimensions<I, F>(
+    world_folder: &Path,
+    loader_options: LoaderOptions,
+    options: &CompositionOptions,
+    mut chunks: F,
+) -> Vec<DimensionTotals>
+where
+    I: IntoIterator<Item = (i32, i32)>,
+    F: FnMut() -> I,
+{
+    world_path::dimension_region_folders(world_folder)
+        .into_iter()
+        .map(|(dimension, region_folder)| {
+            eprintln!("Analyzing dimension {}", dimension);
+
+            let region_folder = region_folder.to_string_lossy();
+            let options = CompositionOptions {
+                dimension,
+                ..options.clone()
+            };
+            let mut composition_analyzer =
+                CompositionAnalyzer::new(&region_folder, loader_options, options);
+            composition_analyzer.analyze(chunks());
+
+            DimensionTotals {
+                dimension,
+                totals: composition_analyzer.totals(),
+                data_versions: composition_analyzer.data_versions(),
+            }
+        })
+        .collect()
 }
 
 impl<'a> CompositionAnalyzer<'a> {
-    pub fn new(path: &'a str) -> Self {
+    pub fn new(path: &'a str, loader_options: LoaderOptions, options: CompositionOptions) -> Self {
+        CompositionAnalyzer::with_source(RegionFolderSource::new(path), loader_options, options)
+    }
+
+    /// Counts blocks of chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        loader_options: LoaderOptions,
+        options: CompositionOptions,
+    ) -> Self {
         CompositionAnalyzer {
             blockstate_map: HashMap::new(),
             layers: Layers::new(),
-            chunk_loader: ChunkLoader::new(path, None),
+            // Only blocks are counted, so entities don't need to be kept in memory
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            options,
+            aborted_at: None,
+            data_versions: None,
+            chunk_count: 0,
+            checkpoint: None,
+            interrupt_flag: None,
+            scanned_volume: 0,
+            dropped_blockstates: HashSet::new(),
+            chunk_rows: Vec::new(),
+            seen_columns: HashSet::new(),
         }
     }
 
-    pub fn analyze(&mut self, area: Area) {
-        for (chunk_x, chunk_z) in area {
-            let chunk = self.chunk_loader.get_or_load(chunk_x, chunk_z).clone();
+    /// Keeps the results (see `results`) written to `path` during scans: every `every` chunks,
+    /// when the scan ends or stops early, and when a panic unwinds through the analyzer.
+    /// What was counted up to then survives a crash or a killed process this way.
+    pub fn with_checkpoint(mut self, path: PathBuf, every: u64) -> Self {
+        self.checkpoint = Some((path, every.max(1)));
+        self
+    }
+
+    /// Stops scans before the next chunk once `flag` is set, e.g. from another thread or a UI,
+    /// just like an interrupt requested with Ctrl-C (see `interrupt`)
+    pub fn with_interrupt_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.interrupt_flag = Some(flag);
+        self
+    }
+
+    fn is_interrupted(&self) -> bool {
+        interrupt::is_requested()
+            || self
+                .interrupt_flag
+                .as_ref()
+                .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// Replaces the checkpoint file in one step, so that it's never left half written
+    fn write_checkpoint(&self) {
+        if let Some((path, _)) = &self.checkpoint {
+            let partial_path = path.with_extension("partial");
+            let result = self
+                .results()
+                .write_binary(&partial_path)
+                .and_then(|_| fs::rename(&partial_path, path));
+
+            if let Err(err) = result {
+                eprintln!("Could not write checkpoint '{}': {}", path.display(), err);
+            }
+        }
+    }
+
+    /// If reading a chunk panics or an interrupt is requested (see `interrupt` and
+    /// `with_interrupt_flag`), the scan stops
+    /// there so that the results gathered so far can still be printed.
+    /// Use `aborted_at` to check whether the scan was complete.
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        self.scan(chunks);
+        self.write_checkpoint();
+    }
+
+    fn scan<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            if self.is_interrupted() {
+                eprintln!(
+                    "Interrupted before chunk ({},{}), results are incomplete",
+                    chunk_x, chunk_z
+                );
+                self.aborted_at = Some((chunk_x, chunk_z));
+                break;
+            }
+
+            let chunk_loader = &mut self.chunk_loader;
+
+            let underground_only = self.options.underground_only;
+            let count_waterlogged_water = self.options.count_waterlogged_water;
+            let polygon = self.options.polygon.as_ref();
+            let in_biome = self.options.in_biome.as_deref();
+
+            // Collect all blocks before counting any of them, so that a chunk
+            // which panics halfway through isn't partially counted
+            let blocks = panic::catch_unwind(AssertUnwindSafe(|| {
+                chunk_loader.get_or_load(chunk_x, chunk_z).map(|chunk| {
+                    let mut blocks = chunk.clone().into_iter().flatten().collect::<Vec<_>>();
+                    if count_waterlogged_water {
+                        blocks.extend(
+                            chunk
+                                .section_indices()
+                                .into_iter()
+                                .filter_map(|y| chunk.get_section(y))
+                                .flat_map(ChunkSection::waterlogged_blocks),
+                        );
+                    }
+                    if underground_only {
+                        let surface_heights = chunk.surface_heights();
+                        blocks.retain(|block| {
+                            let (x, _, z) = block.chunk_pos;
+                            block.global_pos.1 < surface_heights[z * CHUNK_SIZE + x]
+                        });
+                    }
+                    if let Some(polygon) = polygon {
+                        blocks.retain(|block| {
+                            let (x, _, z) = block.global_pos;
+                            polygon.contains(x, z)
+                        });
+                    }
+                    if let Some(in_biome) = in_biome {
+                        blocks.retain(|block| {
+                            let (x, _, z) = block.chunk_pos;
+                            chunk.biome_at(x, block.global_pos.1, z) == Some(in_biome)
+                        });
+                    }
+                    (chunk.data_version, blocks)
+                })
+            }));
+
+            let (data_version, blocks) = match blocks {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => continue,
+                Err(_) => {
+                    eprintln!(
+                        "Aborting scan at chunk ({},{}), results are incomplete",
+                        chunk_x, chunk_z
+                    );
+                    self.aborted_at = Some((chunk_x, chunk_z));
+                    break;
+                }
+            };
 
             eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
 
-            for section in chunk {
-                self.count_chunk_section(section);
+            self.chunk_count += 1;
+            self.scanned_volume += self.chunk_volume(data_version);
+            self.data_versions = Some(match self.data_versions.take() {
+                Some(range) => {
+                    (*range.start()).min(data_version)..=(*range.end()).max(data_version)
+                }
+                None => data_version..=data_version,
+            });
+
+            let mut chunk_counts: HashMap<String, u32> = HashMap::new();
+            let mut chunk_layer_counts: HashMap<(i32, String), u32> = HashMap::new();
+            self.seen_columns.clear();
+
+            for block in blocks {
+                let y = block.global_pos.1;
+                if let Some(blockstate) = self.count_blockstate(block) {
+                    if self.options.chunk_rows {
+                        *chunk_layer_counts
+                            .entry((y, blockstate.clone()))
+                            .or_insert(0) += 1;
+                    }
+                    if self.options.ndjson {
+                        *chunk_counts.entry(blockstate).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            if self.options.chunk_rows {
+                let mut rows: Vec<ChunkRow> = chunk_layer_counts
+                    .into_iter()
+                    .map(|((y, blockstate), count)| ChunkRow {
+                        chunk: (chunk_x, chunk_z),
+                        y,
+                        blockstate,
+                        count,
+                    })
+                    .collect();
+                rows.sort_by(|a, b| a.y.cmp(&b.y).then(a.blockstate.cmp(&b.blockstate)));
+                self.chunk_rows.extend(rows);
+            }
+
+            if self.options.ndjson {
+                let mut chunk_counts: Vec<(String, u32)> = chunk_counts.into_iter().collect();
+                chunk_counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+                let mut out = io::stdout().lock();
+                ndjson::write_chunk_counts(&mut out, (chunk_x, chunk_z), &chunk_counts)
+                    .and_then(|_| out.flush())
+                    .unwrap();
+            }
+
+            if let Some((_, every)) = self.checkpoint {
+                if self.chunk_count.is_multiple_of(every) {
+                    self.write_checkpoint();
+                }
+            }
+        }
+    }
+
+    pub fn aborted_at(&self) -> Option<(i32, i32)> {
+        self.aborted_at
+    }
+
+    /// Number of distinct blockstates that were counted as `OTHER_BLOCKSTATE` because of `max_distinct`
+    pub fn dropped_blockstate_count(&self) -> usize {
+        self.dropped_blockstates.len()
+    }
+
+    /// Lowest and highest `DataVersion` among the scanned chunks, `None` if no chunk was scanned
+    pub fn data_versions(&self) -> Option<RangeInclusive<i32>> {
+        self.data_versions.clone()
+    }
+
+    /// Whether blocks at this Y are counted at all, according to `y_step` and `y_filter`
+    fn counts_layer(&self, y: i32) -> bool {
+        self.options
+            .y_step
+            .is_none_or(|step| y.rem_euclid(step) == 0)
+            && self.options.y_filter.contains(y)
+    }
+
+    /// Number of blocks in a chunk saved with this data version whose layer is counted
+    fn chunk_volume(&self, data_version: i32) -> u64 {
+        let layers = self
+            .options
+            .dimension
+            .y_range(data_version)
+            .filter(|&y| self.counts_layer(y))
+            .count();
+        (layers * CHUNK_SIZE * CHUNK_SIZE) as u64
+    }
+
+    /// Returns the name the block was counted under, or `None` if it was skipped
+    fn count_blockstate(&mut self, block: ChunkSectionBlock) -> Option<String> {
+        let y = block.global_pos.1;
+        let (x, _, z) = block.chunk_pos;
+        if !self.counts_layer(y) {
+            return None;
+        }
+
+        let blockstate = if self.options.normalize_names {
+            block.blockstate.trim().to_lowercase()
+        } else {
+            block.blockstate
+        };
+
+        let blockstate = match &self.options.remap {
+            Some(remap) => remap.apply(&blockstate, block.global_pos),
+            None => blockstate,
+        };
+
+        if let Some(only_blocks) = &self.options.only_blocks {
+            if !only_blocks.contains(&blockstate) {
+                return None;
+            }
+        }
+
+        let blockstate = match self.options.max_distinct {
+            Some(max_distinct)
+                if self.blockstate_map.len() >= max_distinct
+                    && !self.blockstate_map.contains_key(&blockstate) =>
+            {
+                let mut hasher = DefaultHasher::new();
+                blockstate.hash(&mut hasher);
+                self.dropped_blockstates.insert(hasher.finish());
+
+                OTHER_BLOCKSTATE.to_string()
+            }
+            _ => blockstate,
+        };
+
+        let counts_towards_total = match self.options.metric {
+            CountMetric::Blocks => true,
+            CountMetric::Columns => self.seen_columns.insert((x, z, blockstate.clone())),
+        };
+        if counts_towards_total {
+            *self.blockstate_map.entry(blockstate.clone()).or_insert(0) += 1;
+        }
+
+        self.layers.increment(blockstate.as_str(), y);
+
+        Some(blockstate)
+    }
+
+    /// Total count of every blockstate, most common first
+    pub fn totals(&self) -> Vec<(String, u64)> {
+        let mut totals: Vec<(String, u64)> = self
+            .blockstate_map
+            .iter()
+            .map(|(blockstate, count)| (blockstate.clone(), *count))
+            .collect();
+        totals.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        totals
+    }
+
+    /// Share of the scanned box (see `scanned_volume`) every blockstate takes up, as
+    /// `(blockstate, count, percent)` with the most common first. Unlike the share of the counted
+    /// blocks, this doesn't change when sections are missing or blocks are left out.
+    pub fn volume_percentages(&self) -> Vec<(String, u64, f64)> {
+        let volume = self.scanned_volume;
+
+        self.totals()
+            .into_iter()
+            .map(|(blockstate, count)| {
+                let percent = if volume == 0 {
+                    0.0
+                } else {
+                    count as f64 * 100.0 / volume as f64
+                };
+                (blockstate, count, percent)
+            })
+            .collect()
+    }
+
+    /// Prints every blockstate's share of the scanned box, see `scanned_volume`
+    pub fn print_volume_percentages(&self) {
+        let percentages = self.volume_percentages();
+        if percentages.is_empty() {
+            eprintln!("No blocks found in the scanned area");
+            return;
+        }
+
+        println!("Blockstate,Count,Percent of volume");
+        for (blockstate, count, percent) in percentages {
+            print!("{},", self.options.id_form.render(&blockstate));
+            print_count(count as i64, self.options.human_numbers);
+            println!(",{:.4}", percent);
+        }
+
+        eprintln!("Scanned a volume of {} blocks", self.scanned_volume);
+    }
+
+    /// Prints the totals as collapsed stacks of namespace, category and block, for flamegraph tools
+    pub fn print_flamegraph(&self) {
+        let stdout = io::stdout();
+        if let Err(err) = flamegraph::write_collapsed_stacks(&mut stdout.lock(), &self.totals()) {
+            eprintln!("Could not print flamegraph: {}", err);
+        }
+    }
+
+    /// How often chunks were found in the chunk cache during the scan
+    pub fn cache_stats(&self) -> CacheStats {
+        self.chunk_loader.stats()
+    }
+
+    /// This scan's counts, e.g. to save them as a sidecar and merge them with those of other scans
+    pub fn results(&self) -> CompositionResults {
+        CompositionResults {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| {
+                    let counts = layer
+                        .counts()
+                        .into_iter()
+                        .map(|(blockstate, count)| (blockstate.to_string(), count))
+                        .collect();
+                    (layer.y, counts)
+                })
+                .collect(),
+            data_versions: self.data_versions.clone(),
+        }
+    }
+
+    /// Saves a stacked bar chart of every layer's composition, showing the most common blockstates
+    pub fn print_profile_img(&self, path: &str, format: ImageFormat) {
+        let blockstates: Vec<String> = self
+            .totals()
+            .into_iter()
+            .map(|(blockstate, _)| blockstate)
+            .collect();
+
+        let (img, series) = render_profile(&self.layers, &blockstates);
+
+        let other = ("other".to_string(), OTHER_COLOR);
+        for (blockstate, color) in series.into_iter().chain(Some(other)) {
+            let [r, g, b] = color.0;
+            eprintln!("#{:02x}{:02x}{:02x} {}", r, g, b, blockstate);
+        }
+
+        save_image(&img, path, format).unwrap();
+    }
+
+    /// Appends this scan's layers and totals to a CSV collecting several runs, see `append_run`.
+    /// Counts are always absolute and written without digit grouping.
+    pub fn append_csv(&self, path: &Path) -> io::Result<u32> {
+        let totals = self.totals();
+        let columns: Vec<String> = totals
+            .iter()
+            .map(|(blockstate, _)| self.options.id_form.render(blockstate))
+            .collect();
+
+        let mut rows: CsvRows = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let counts = totals
+                    .iter()
+                    .map(|(blockstate, _)| layer.get_count(blockstate))
+                    .collect();
+                (layer.y.to_string(), counts)
+            })
+            .collect();
+        rows.push((
+            "Total".to_string(),
+            totals.iter().map(|(_, count)| *count).collect(),
+        ));
+
+        append_run(path, "Layer", &columns, rows)
+    }
+
+    /// Writes every layer's counts to its own `layer_<y>.csv` in `folder`, creating it if needed.
+    /// Returns the number of files written.
+    pub fn write_split_layers(&self, folder: &Path) -> io::Result<usize> {
+        fs::create_dir_all(folder)?;
+
+        let mut written = 0;
+        for layer in self.layers.iter() {
+            let path = folder.join(format!("layer_{}.csv", layer.y));
+            let mut file = BufWriter::new(File::create(path)?);
+
+            writeln!(file, "Blockstate,Count")?;
+            for (blockstate, count) in layer.counts() {
+                writeln!(
+                    file,
+                    "{},{}",
+                    self.options.id_form.render(blockstate),
+                    count
+                )?;
+            }
+            file.flush()?;
+
+            written += 1;
+        }
+
+        Ok(written)
+    }
+
+    /// Saves this scan's counts to a new SQLite database, with one row per blockstate and layer
+    /// in `blocks(blockstate, y, count)` and one per blockstate in `totals(blockstate, count)`
+    #[cfg(feature = "sqlite")]
+    pub fn write_sqlite(&self, path: &Path) -> io::Result<()> {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        self.insert_sqlite(path).map_err(io::Error::other)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn insert_sqlite(&self, path: &Path) -> rusqlite::Result<()> {
+        let mut connection = Connection::open(path)?;
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(
+            "CREATE TABLE blocks(blockstate TEXT, y INTEGER, count INTEGER);
+             CREATE TABLE totals(blockstate TEXT, count INTEGER);",
+        )?;
+
+        {
+            let mut insert_block = transaction.prepare("INSERT INTO blocks VALUES (?1, ?2, ?3)")?;
+            for layer in self.layers.iter() {
+                for (blockstate, count) in layer.counts() {
+                    insert_block.execute(params![
+                        self.options.id_form.render(blockstate),
+                        layer.y,
+                        count as i64
+                    ])?;
+                }
+            }
+
+            let mut insert_total = transaction.prepare("INSERT INTO totals VALUES (?1, ?2)")?;
+            for (blockstate, count) in self.totals() {
+                insert_total.execute(params![
+                    self.options.id_form.render(&blockstate),
+                    count as i64
+                ])?;
             }
         }
+
+        transaction.commit()
+    }
+
+    /// Saves every chunk's counts per layer to a new Parquet file, in long format with one row
+    /// per chunk, layer and blockstate in `chunk_x, chunk_z, y, blockstate, count`.
+    /// Needs `CompositionOptions::chunk_rows`, otherwise the file has no rows.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, path: &Path) -> io::Result<()> {
+        self.write_parquet_rows(File::create(path)?)
+            .map_err(io::Error::other)
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_parquet_rows(&self, file: File) -> Result<(), ParquetError> {
+        let schema = parse_message_type(
+            "message chunk_rows {
+                REQUIRED INT32 chunk_x;
+                REQUIRED INT32 chunk_z;
+                REQUIRED INT32 y;
+                REQUIRED BYTE_ARRAY blockstate (UTF8);
+                REQUIRED INT64 count;
+            }",
+        )?;
+        let properties = WriterProperties::builder().build();
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?;
+
+        let rows = &self.chunk_rows;
+        let chunk_x: Vec<i32> = rows.iter().map(|row| row.chunk.0).collect();
+        let chunk_z: Vec<i32> = rows.iter().map(|row| row.chunk.1).collect();
+        let y: Vec<i32> = rows.iter().map(|row| row.y).collect();
+        let blockstate: Vec<ByteArray> = rows
+            .iter()
+            .map(|row| ByteArray::from(self.options.id_form.render(&row.blockstate).as_str()))
+            .collect();
+        let count: Vec<i64> = rows.iter().map(|row| row.count as i64).collect();
+
+        let mut row_group = writer.next_row_group()?;
+        for values in &[chunk_x, chunk_z, y] {
+            let mut column = row_group.next_column()?.unwrap();
+            column
+                .typed::<Int32Type>()
+                .write_batch(values, None, None)?;
+            column.close()?;
+        }
+        let mut column = row_group.next_column()?.unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&blockstate, None, None)?;
+        column.close()?;
+        let mut column = row_group.next_column()?.unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&count, None, None)?;
+        column.close()?;
+        row_group.close()?;
+
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Lowest and highest layer every blockstate was found in, with its total count,
+    /// as `(blockstate, min_y, max_y, total)` with the most common blockstates first
+    pub fn y_bounds(&self) -> Vec<(String, i32, i32, u64)> {
+        let mut bounds: HashMap<&str, (i32, i32)> = HashMap::new();
+        // Layers are iterated from bottom to top, so the first layer seen is the lowest
+        for layer in self.layers.iter() {
+            for (blockstate, _) in layer.counts() {
+                bounds
+                    .entry(blockstate)
+                    .and_modify(|(_, max_y)| *max_y = layer.y)
+                    .or_insert((layer.y, layer.y));
+            }
+        }
+
+        self.totals()
+            .into_iter()
+            .filter_map(|(blockstate, total)| {
+                let (min_y, max_y) = *bounds.get(blockstate.as_str())?;
+                Some((blockstate, min_y, max_y, total))
+            })
+            .collect()
+    }
+
+    /// Prints the range of layers every blockstate occurs in, a compact summary of the layer matrix
+    pub fn print_y_bounds(&self) {
+        let bounds = self.y_bounds();
+        if bounds.is_empty() {
+            eprintln!("No blocks found in the scanned area");
+            return;
+        }
+
+        println!("Blockstate,Min Y,Max Y,Total");
+        for (blockstate, min_y, max_y, total) in bounds {
+            print!(
+                "{},{:5},{:5},",
+                self.options.id_form.render(&blockstate),
+                min_y,
+                max_y
+            );
+            print_count(total as i64, self.options.human_numbers);
+            println!();
+        }
     }
 
-    fn count_blockstate(&mut self, block: ChunkSectionBlock) {
-        let blockstate = block.blockstate;
+    /// Prints the ticks it takes to mine every blockstate and the total time to dig out
+    /// everything counted, see `mining_time`
+    pub fn print_mining_time(&self) {
+        let estimate = MiningEstimate::from_totals(&self.totals());
+        if estimate.blocks.is_empty() {
+            eprintln!("No blocks found in the scanned area");
+            return;
+        }
+
+        println!("Blockstate,Blocks,Ticks");
+        for (blockstate, count, ticks) in &estimate.blocks {
+            print!("{},", self.options.id_form.render(blockstate));
+            print_count(*count as i64, self.options.human_numbers);
+            print!(",");
+            print_count(*ticks as i64, self.options.human_numbers);
+            println!();
+        }
 
-        let prev_blockstate_count = *self.blockstate_map.get(&blockstate).unwrap_or(&0);
-        self.blockstate_map
-            .insert(blockstate.clone(), prev_blockstate_count + 1);
+        let total_ticks = estimate.total_ticks();
+        println!(
+            "Total,,{} ticks ({})",
+            total_ticks,
+            format_duration(total_ticks)
+        );
+
+        if estimate.unbreakable > 0 {
+            eprintln!("Left out {} unbreakable blocks", estimate.unbreakable);
+        }
+        if !estimate.unknown.is_empty() {
+            let mut unknown: Vec<&String> = estimate.unknown.keys().collect();
+            unknown.sort_unstable();
+            eprintln!(
+                "Left out {} blocks of unknown hardness: {}",
+                estimate.unknown.values().sum::<u64>(),
+                unknown
+                    .iter()
+                    .map(|blockstate| self.options.id_form.render(blockstate))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+    }
 
+    /// Prints the `top` most common blockstates of two layers side by side, e.g. to compare
+    /// the surface with the deepslate layers. Layers without blocks have empty columns.
+    /// The `top` most common blockstates of a layer, most common first
+    fn top_in_layer(&self, y: i32, top: usize) -> Vec<(&str, u64)> {
         self.layers
-            .increment(blockstate.as_str(), block.global_pos.1);
+            .get(y)
+            .map(|layer| {
+                let mut counts = layer.counts();
+                counts.truncate(top);
+                counts
+            })
+            .unwrap_or_default()
     }
 
-    fn count_chunk_section(&mut self, chunk_section: ChunkSection) {
-        for block in chunk_section {
-            self.count_blockstate(block);
+    pub fn print_slice_comparison(&self, (y1, y2): (i32, i32), top: usize) {
+        let slices = [self.top_in_layer(y1, top), self.top_in_layer(y2, top)];
+        if slices.iter().all(Vec::is_empty) {
+            eprintln!("No blocks found at Y={} or Y={}", y1, y2);
+            return;
+        }
+
+        println!("Rank,Y={},Count,Y={},Count", y1, y2);
+
+        let rows = slices[0].len().max(slices[1].len());
+        for rank in 0..rows {
+            print!("{:4}", rank + 1);
+            for slice in &slices {
+                match slice.get(rank) {
+                    Some((blockstate, count)) => {
+                        print!(",{},", self.options.id_form.render(blockstate));
+                        print_count(*count as i64, self.options.human_numbers);
+                    }
+                    None => print!(",,"),
+                }
+            }
+            println!();
         }
     }
 
     pub fn print_csv(self) {
-        let mut blockstate_list: Vec<(String, u32)> = self
+        self.write_csv(&mut io::stdout().lock()).unwrap();
+    }
+
+    pub fn write_csv<W: Write>(mut self, out: &mut W) -> io::Result<()> {
+        if self.blockstate_map.is_empty() {
+            eprintln!("No blocks found in the scanned area");
+            return Ok(());
+        }
+
+        let mut blockstate_list: Vec<(String, u64)> = self
             .blockstate_map
             .iter()
             .map(|(block_id, count)| (block_id.clone(), *count))
             .collect();
-        blockstate_list.sort_by(|(_, a), (_, b)| b.cmp(a));
+        self.options.column_order.sort(&mut blockstate_list);
 
-        print!("Layer,");
+        write!(out, "Layer,")?;
         for (id, (blockstate, _)) in blockstate_list.iter().enumerate() {
-            print!("{}", blockstate);
-            if id < blockstate_list.len() - 1 {
-                print!(",");
+            write!(out, "{}", self.options.id_form.render(blockstate))?;
+            if id + 1 < blockstate_list.len() {
+                write!(out, ",")?;
             }
         }
-        println!();
+        writeln!(out)?;
+
+        let layers = mem::take(&mut self.layers);
+        let layers = match self.options.band_height {
+            Some(height) => layers.bands(height),
+            None => layers,
+        };
 
-        for layer in self.layers {
-            print!("{:5},", layer.y);
+        let mut previous_layer: Option<Layer> = None;
+        let mut printed_totals = vec![0; blockstate_list.len()];
+
+        for layer in layers {
+            if !self.options.shows_layer(&layer) {
+                previous_layer = Some(layer);
+                continue;
+            }
+
+            for (total, (blockstate, _)) in printed_totals.iter_mut().zip(&blockstate_list) {
+                *total += layer.get_count(blockstate);
+            }
+
+            write!(out, "{:>5},", layer.label())?;
             for (index, (blockstate, _)) in blockstate_list.iter().enumerate() {
                 let layer_count = layer.get_count(blockstate);
-                print!("{:8}", layer_count);
-                if index < blockstate_list.len() - 1 {
-                    print!(",");
+
+                let count = if self.options.layer_deltas {
+                    layer.delta(previous_layer.as_ref(), blockstate)
+                } else {
+                    layer_count as i64
+                };
+                write!(out, "{}", format_count(count, self.options.human_numbers))?;
+
+                if index + 1 < blockstate_list.len() {
+                    write!(out, ",")?;
                 }
             }
-            println!();
+            writeln!(out)?;
+
+            previous_layer = Some(layer);
         }
 
-        print!("Total,");
+        if self.options.only_layers_with.is_some() {
+            for ((_, total_count), printed_total) in blockstate_list.iter_mut().zip(printed_totals)
+            {
+                *total_count = printed_total;
+            }
+        }
+
+        match self.options.normalization {
+            Normalization::None => write!(out, "Total,")?,
+            Normalization::PerChunk => write!(out, "Per chunk,")?,
+        }
         for (index, (_, total_count)) in blockstate_list.iter().enumerate() {
-            print!("{:8}", total_count);
-            if index < blockstate_list.len() - 1 {
-                print!(",");
+            match self.options.normalization {
+                Normalization::None => write!(
+                    out,
+                    "{}",
+                    format_count(*total_count as i64, self.options.human_numbers)
+                )?,
+                Normalization::PerChunk => {
+                    write!(out, "{:10.2}", average(*total_count, self.chunk_count))?
+                }
+            }
+            if index + 1 < blockstate_list.len() {
+                write!(out, ",")?;
+            }
+        }
+        writeln!(out)?;
+
+        if let Some(tags) = &self.options.tags {
+            let blockstate_map = &self.blockstate_map;
+
+            writeln!(out)?;
+            writeln!(out, "Tag,Count")?;
+
+            for (tag, blocks) in tags {
+                let count: u64 = blocks
+                    .iter()
+                    .filter_map(|block| blockstate_map.get(block))
+                    .sum();
+
+                writeln!(
+                    out,
+                    "{},{}",
+                    tag,
+                    format_count(count as i64, self.options.human_numbers)
+                )?;
+            }
+        }
+
+        if !self.options.replacements.is_empty() {
+            let (replaced, changed) =
+                simulate_replacements(&self.blockstate_map, &self.options.replacements);
+
+            let mut blockstates: Vec<&String> =
+                self.blockstate_map.keys().chain(replaced.keys()).collect();
+            blockstates.sort_unstable();
+            blockstates.dedup();
+
+            writeln!(out)?;
+            writeln!(out, "Blockstate,Before,After")?;
+
+            for blockstate in blockstates {
+                writeln!(
+                    out,
+                    "{},{},{}",
+                    self.options.id_form.render(blockstate),
+                    format_count(
+                        *self.blockstate_map.get(blockstate).unwrap_or(&0) as i64,
+                        self.options.human_numbers,
+                    ),
+                    format_count(
+                        *replaced.get(blockstate).unwrap_or(&0) as i64,
+                        self.options.human_numbers,
+                    )
+                )?;
+            }
+
+            eprintln!("Replacing would change {} blocks", changed);
+        }
+
+        Ok(())
+    }
+}
+
+/// Applies replacements to totals in order, so that with `a=b` and `b=c` all `a` ends up as `c`.
+/// Returns the new totals and how many blocks changed.
+pub fn simulate_replacements(
+    totals: &HashMap<String, u64>,
+    replacements: &[(String, String)],
+) -> (HashMap<String, u64>, u64) {
+    let mut replaced: HashMap<String, u64> = HashMap::new();
+    let mut changed = 0;
+
+    for (blockstate, count) in totals {
+        let result =
+            replacements.iter().fold(
+                blockstate,
+                |current, (from, to)| if current == from { to } else { current },
+            );
+
+        if result != blockstate {
+            changed += count;
+        }
+        *replaced.entry(result.clone()).or_insert(0) += count;
+    }
+
+    (replaced, changed)
+}
+
+/// `count` divided by the number of chunks, 0 if there are none
+fn average(count: u64, chunk_count: u64) -> f64 {
+    if chunk_count == 0 {
+        return 0.0;
+    }
+    count as f64 / chunk_count as f64
+}
+
+fn print_count(count: i64, human_numbers: bool) {
+    print!("{}", format_count(count, human_numbers));
+}
+
+fn format_count(count: i64, human_numbers: bool) -> String {
+    if human_numbers {
+        format!("{:>10}", human_number(count))
+    } else {
+        format!("{:8}", count)
+    }
+}
+
+impl Drop for CompositionAnalyzer<'_> {
+    /// Panics while counting aren't caught like those while reading chunks, but they still
+    /// leave the results counted so far in the checkpoint
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.write_checkpoint();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{area::Area, block_list::parse_block_list, test_fixtures};
+
+    fn analyze(
+        source: impl ChunkSource + 'static,
+        options: CompositionOptions,
+    ) -> CompositionAnalyzer<'static> {
+        let mut analyzer =
+            CompositionAnalyzer::with_source(source, LoaderOptions::default(), options);
+        analyzer.analyze(vec![(0, 0)]);
+        analyzer
+    }
+
+    #[test]
+    fn y_filter() {
+        let below = YFilter {
+            below: Some(0),
+            ..YFilter::default()
+        };
+        assert!(below.contains(-1) && !below.contains(0));
+
+        let above = YFilter {
+            above: Some(60),
+            ..YFilter::default()
+        };
+        assert!(above.contains(61) && !above.contains(60));
+
+        let between = YFilter {
+            range: Some(-64..=-5),
+            below: Some(0),
+            above: Some(-10),
+        };
+        let ys: Vec<i32> = (-64..=64).filter(|&y| between.contains(y)).collect();
+        assert_eq!(ys, vec![-9, -8, -7, -6, -5]);
+
+        let disjoint = YFilter {
+            below: Some(0),
+            above: Some(60),
+            ..YFilter::default()
+        };
+        assert!(disjoint.is_empty() && !between.is_empty());
+    }
+
+    #[test]
+    fn in_biome() {
+        // Stone in the lower half of the section, which is plains, and sand in its upper half,
+        // which is desert
+        let section = test_fixtures::with_biomes(
+            test_fixtures::section(0, &["minecraft:stone", "minecraft:sand"], |_, y, _| {
+                (y >= 8) as usize
+            }),
+            &["minecraft:plains", "minecraft:desert"],
+            |_, y, _| (y >= 2) as usize,
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+
+        let options = CompositionOptions {
+            in_biome: Some("minecraft:desert".to_string()),
+            ..CompositionOptions::default()
+        };
+        let analyzer = analyze(source, options);
+
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:sand".to_string(), 8 * 16 * 16)]
+        );
+    }
+
+    #[test]
+    fn only_blocks_from_list() {
+        // Stone with one block each of dirt, diamond ore and gold ore
+        let section = test_fixtures::section(
+            0,
+            &[
+                "minecraft:stone",
+                "minecraft:dirt",
+                "minecraft:diamond_ore",
+                "minecraft:gold_ore",
+            ],
+            |x, y, z| if (y, z) == (0, 0) && x < 4 { x } else { 0 },
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+
+        // As read by `--blocks-from-stdin`
+        let only_blocks = "minecraft:stone\n# ores\nminecraft:diamond_ore\n\nminecraft:gold_ore\n";
+        let options = CompositionOptions {
+            only_blocks: Some(parse_block_list(only_blocks)),
+            ..CompositionOptions::default()
+        };
+        let analyzer = analyze(source, options);
+
+        assert_eq!(
+            analyzer.totals(),
+            vec![
+                ("minecraft:stone".to_string(), 4093),
+                ("minecraft:diamond_ore".to_string(), 1),
+                ("minecraft:gold_ore".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn per_chunk_totals_do_not_depend_on_area() {
+        // Identical chunks, each with a single diamond ore in a section of stone
+        let chunks = (0..2)
+            .map(|x| {
+                let section = test_fixtures::section(
+                    0,
+                    &["minecraft:stone", "minecraft:diamond_ore"],
+                    |x, y, z| (x + y + z == 0) as usize,
+                );
+                test_fixtures::chunk(x, 0, vec![section])
+            })
+            .collect();
+        let source = test_fixtures::source(chunks);
+
+        let totals_row = |chunks: Vec<(i32, i32)>| {
+            let options = CompositionOptions {
+                normalization: Normalization::PerChunk,
+                ..CompositionOptions::default()
+            };
+            let mut analyzer =
+                CompositionAnalyzer::with_source(source.clone(), LoaderOptions::default(), options);
+            analyzer.analyze(chunks);
+
+            let mut csv = Vec::new();
+            analyzer.write_csv(&mut csv).unwrap();
+            String::from_utf8(csv)
+                .unwrap()
+                .lines()
+                .last()
+                .unwrap()
+                .to_string()
+        };
+        let small = totals_row(vec![(0, 0)]);
+
+        assert_eq!(small, "Per chunk,   4095.00,      1.00");
+        assert_eq!(small, totals_row(vec![(0, 0), (1, 0)]));
+    }
+
+    #[test]
+    fn volume_percentages() {
+        let analyzer = analyze(
+            test_fixtures::uniform_source("minecraft:stone"),
+            CompositionOptions::default(),
+        );
+
+        // The stone fills 16 of the 384 layers of the overworld
+        assert_eq!(analyzer.scanned_volume, 384 * 256);
+        assert_eq!(
+            analyzer.volume_percentages(),
+            vec![("minecraft:stone".to_string(), 4096, 100.0 / 24.0)]
+        );
+    }
+
+    #[test]
+    fn normalize_names() {
+        // Half of the section is stone written as a mod might, with stray casing and whitespace
+        let section =
+            test_fixtures::section(0, &["minecraft:stone", "minecraft:Stone "], |_, y, _| {
+                (y < 8) as usize
+            });
+        let chunks = vec![test_fixtures::chunk(0, 0, vec![section])];
+
+        let analyzer = analyze(
+            test_fixtures::source(chunks.clone()),
+            CompositionOptions::default(),
+        );
+        assert_eq!(
+            analyzer.totals(),
+            vec![
+                ("minecraft:Stone ".to_string(), 2048),
+                ("minecraft:stone".to_string(), 2048),
+            ]
+        );
+
+        let options = CompositionOptions {
+            normalize_names: true,
+            ..CompositionOptions::default()
+        };
+        let analyzer = analyze(test_fixtures::source(chunks), options);
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:stone".to_string(), 4096)]
+        );
+    }
+
+    #[test]
+    fn layer_deltas_at_deepslate_boundary() {
+        // Deepslate below Y 0 and stone above, with a mixed layer at Y 0 like the game generates
+        let palette = &["minecraft:deepslate", "minecraft:stone"];
+        let below = test_fixtures::section(-1, palette, |_, _, _| 0);
+        let above = test_fixtures::section(0, palette, |x, y, _| match y {
+            0 => x % 2,
+            _ => 1,
+        });
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![below, above])]);
+        let analyzer = analyze(source, CompositionOptions::default());
+
+        let layers: Vec<&Layer> = analyzer.layers.iter().collect();
+        let deltas = |blockstate: &str| -> Vec<(i32, i64)> {
+            layers
+                .iter()
+                .enumerate()
+                .filter(|(_, layer)| (-2..=2).contains(&layer.y))
+                .map(|(index, layer)| {
+                    let below = index.checked_sub(1).map(|index| layers[index]);
+                    (layer.y, layer.delta(below, blockstate))
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            deltas("minecraft:deepslate"),
+            vec![(-2, 0), (-1, 0), (0, -128), (1, -128), (2, 0)]
+        );
+        assert_eq!(
+            deltas("minecraft:stone"),
+            vec![(-2, 0), (-1, 0), (0, 128), (1, 128), (2, 0)]
+        );
+
+        // The lowest layer has nothing to compare against
+        assert_eq!(layers[0].y, -16);
+        assert_eq!(layers[0].delta(None, "minecraft:deepslate"), 0);
+    }
+
+    #[test]
+    fn y_step() {
+        let sections = (-4..4)
+            .map(|y| test_fixtures::uniform_section(y, "minecraft:stone"))
+            .collect();
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, sections)]);
+        let options = CompositionOptions {
+            y_step: Some(16),
+            ..CompositionOptions::default()
+        };
+        let analyzer = analyze(source, options);
+
+        let rows: Vec<i32> = analyzer.layers.iter().map(|layer| layer.y).collect();
+        assert_eq!(rows, vec![-64, -48, -32, -16, 0, 16, 32, 48]);
+        // Totals only cover the sampled layers
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:stone".to_string(), 8 * 256)]
+        );
+    }
+
+    #[test]
+    fn columns_by_namespace() {
+        let columns = |names: &[(&str, u64)]| -> Vec<(String, u64)> {
+            names
+                .iter()
+                .map(|&(name, count)| (name.to_string(), count))
+                .collect()
+        };
+        let mut blockstates = columns(&[
+            ("create:zinc_ore", 50),
+            ("minecraft:stone", 1000),
+            ("create:andesite_alloy", 2),
+            ("minecraft:andesite", 80),
+            ("biomesoplenty:mud", 300),
+        ]);
+
+        ColumnOrder::Namespace.sort(&mut blockstates);
+        assert_eq!(
+            blockstates,
+            columns(&[
+                ("minecraft:andesite", 80),
+                ("minecraft:stone", 1000),
+                ("biomesoplenty:mud", 300),
+                ("create:andesite_alloy", 2),
+                ("create:zinc_ore", 50),
+            ])
+        );
+
+        ColumnOrder::Count.sort(&mut blockstates);
+        assert_eq!(blockstates[0].0, "minecraft:stone");
+        assert_eq!(blockstates[4].0, "create:andesite_alloy");
+    }
+
+    #[test]
+    fn chunks_of_different_versions() {
+        // 17 palette entries need 5 bits, which straddle longs before 1.16 but not since
+        let names = [
+            "minecraft:air",
+            "minecraft:stone",
+            "minecraft:granite",
+            "minecraft:diorite",
+            "minecraft:andesite",
+            "minecraft:dirt",
+            "minecraft:gravel",
+            "minecraft:sand",
+            "minecraft:clay",
+            "minecraft:coal_ore",
+            "minecraft:iron_ore",
+            "minecraft:gold_ore",
+            "minecraft:diamond_ore",
+            "minecraft:bedrock",
+            "minecraft:water",
+            "minecraft:lava",
+            "minecraft:obsidian",
+        ];
+        // Every block but air in one row of X
+        let block = |x: usize, _, _| x + 1;
+        let source = test_fixtures::source(vec![
+            // 1.14
+            test_fixtures::legacy_chunk(
+                0,
+                0,
+                1976,
+                vec![test_fixtures::legacy_section(0, &names, block)],
+            ),
+            test_fixtures::chunk(1, 0, vec![test_fixtures::section(0, &names, block)]),
+        ]);
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0), (1, 0)]);
+
+        assert_eq!(
+            analyzer.data_versions(),
+            Some(1976..=test_fixtures::DATA_VERSION)
+        );
+        let totals = analyzer.totals();
+        assert_eq!(totals.len(), 16);
+        assert!(totals.iter().all(|(_, count)| *count == 2 * 256));
+    }
+
+    #[test]
+    fn data_version_spread() {
+        let scan = |chunk| {
+            let mut analyzer = CompositionAnalyzer::with_source(
+                test_fixtures::source(vec![chunk]),
+                LoaderOptions::default(),
+                CompositionOptions::default(),
+            );
+            analyzer.analyze(vec![(0, 0)]);
+            analyzer.data_versions().unwrap()
+        };
+        let names = ["minecraft:air", "minecraft:stone"];
+        // A 1.14 nether and a 1.18 overworld
+        let old = scan(test_fixtures::legacy_chunk(
+            0,
+            0,
+            1976,
+            vec![test_fixtures::legacy_section(0, &names, |_, _, _| 1)],
+        ));
+        let new = scan(test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+        ));
+
+        let warning = data_version_spread_warning(&[old, new.clone()]).unwrap();
+        assert!(warning.contains("1976 to 2975"));
+        assert_eq!(data_version_spread_warning(&[new.clone(), new]), None);
+    }
+
+    #[test]
+    fn max_distinct() {
+        let names = [
+            "minecraft:stone",
+            "minecraft:granite",
+            "minecraft:diorite",
+            "minecraft:andesite",
+            "minecraft:tuff",
+            "minecraft:calcite",
+        ];
+        // Blocks go through X first, so the first three blocks seen are stone, granite and diorite
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::section(0, &names, |x, _, _| x % 6)],
+        )]);
+        let analyzer = analyze(
+            source,
+            CompositionOptions {
+                max_distinct: Some(3),
+                ..CompositionOptions::default()
+            },
+        );
+
+        // X 0 to 15 holds each of the first four blocks 3 times and the others twice
+        let totals: HashMap<String, u64> = analyzer.totals().into_iter().collect();
+        assert_eq!(totals.len(), 4);
+        assert_eq!(totals["minecraft:stone"], 3 * 256);
+        assert_eq!(totals["minecraft:granite"], 3 * 256);
+        assert_eq!(totals["minecraft:diorite"], 3 * 256);
+        assert_eq!(totals[OTHER_BLOCKSTATE], (3 + 2 + 2) * 256);
+        assert_eq!(analyzer.dropped_blockstate_count(), 3);
+    }
+
+    #[test]
+    fn replacements_conserve_totals() {
+        let names = ["minecraft:stone", "minecraft:deepslate", "minecraft:dirt"];
+        // A quarter each of stone and dirt, the rest deepslate
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::section(0, &names, |_, y, _| match y {
+                0..=3 => 0,
+                4..=7 => 2,
+                _ => 1,
+            })],
+        )]);
+        let analyzer = analyze(source, CompositionOptions::default());
+        let totals: HashMap<String, u64> = analyzer.totals().into_iter().collect();
+
+        let replacement = |from: &str, to: &str| (from.to_string(), to.to_string());
+        let (replaced, changed) = simulate_replacements(
+            &totals,
+            &[replacement("minecraft:stone", "minecraft:deepslate")],
+        );
+        assert_eq!(changed, 1024);
+        assert_eq!(replaced.len(), 2);
+        assert_eq!(replaced["minecraft:deepslate"], 3072);
+        assert_eq!(replaced["minecraft:dirt"], 1024);
+        assert_eq!(replaced.values().sum::<u64>(), totals.values().sum::<u64>());
+
+        // Replacements apply in order, so stone turned into deepslate becomes tuff as well
+        let (replaced, changed) = simulate_replacements(
+            &totals,
+            &[
+                replacement("minecraft:stone", "minecraft:deepslate"),
+                replacement("minecraft:deepslate", "minecraft:tuff"),
+            ],
+        );
+        assert_eq!(changed, 3072);
+        assert_eq!(replaced["minecraft:tuff"], 3072);
+        assert_eq!(replaced.values().sum::<u64>(), 4096);
+    }
+
+    #[test]
+    fn only_layers_with() {
+        // Stone from Y -64 to 31 with a column of diamond ore from Y -59 to 16
+        let sections = (-4..=1)
+            .map(|section_y: i8| {
+                test_fixtures::section(
+                    section_y,
+                    &["minecraft:stone", "minecraft:diamond_ore"],
+                    move |x, y, z| {
+                        let y = section_y as i32 * 16 + y as i32;
+                        ((x, z) == (0, 0) && (-59..=16).contains(&y)) as usize
+                    },
+                )
+            })
+            .collect();
+        let options = CompositionOptions {
+            only_layers_with: Some("minecraft:diamond_ore".to_string()),
+            ..CompositionOptions::default()
+        };
+        let analyzer = analyze(
+            test_fixtures::source(vec![test_fixtures::chunk(0, 0, sections)]),
+            options.clone(),
+        );
+
+        let shown: Vec<i32> = analyzer
+            .layers
+            .iter()
+            .filter(|layer| options.shows_layer(layer))
+            .map(|layer| layer.y)
+            .collect();
+        assert_eq!(shown, (-59..=16).collect::<Vec<i32>>());
+        assert_eq!(analyzer.layers.iter().count(), 96);
+    }
+
+    #[test]
+    fn memory_source() {
+        let mut source = test_fixtures::MemoryChunkSource::new();
+        for x in [0, 2] {
+            let section = test_fixtures::uniform_section(0, "minecraft:stone");
+            source.insert(x, 0, test_fixtures::chunk(x, 0, vec![section]));
+        }
+
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        // The chunk in between is missing and skipped
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+
+        assert_eq!(analyzer.chunk_count, 2);
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:stone".to_string(), 2 * 4096)]
+        );
+    }
+
+    #[test]
+    fn split_layers() {
+        // Stone in the lower half of the section, dirt on top except for one block of stone
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::section(
+                -1,
+                &["minecraft:air", "minecraft:stone", "minecraft:dirt"],
+                |x, y, z| match y {
+                    0..=7 => 1,
+                    8..=11 if (x, y, z) == (0, 11, 0) => 1,
+                    8..=11 => 2,
+                    _ => 0,
+                },
+            )],
+        )]);
+        let analyzer = analyze(source, CompositionOptions::default());
+
+        let dir = test_fixtures::TempDir::new();
+        let written = analyzer.write_split_layers(dir.path()).unwrap();
+        let files = fs::read_dir(dir.path()).unwrap().count();
+        let non_empty = analyzer
+            .layers
+            .iter()
+            .filter(|layer| layer.counts().iter().any(|(_, count)| *count > 0))
+            .count();
+        assert_eq!((written, files), (non_empty, non_empty));
+
+        // Adding up every file gives the totals of the whole scan
+        let mut totals: HashMap<String, u64> = HashMap::new();
+        for entry in fs::read_dir(dir.path()).unwrap() {
+            let contents = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let mut lines = contents.lines();
+            assert_eq!(lines.next(), Some("Blockstate,Count"));
+            for line in lines {
+                let (blockstate, count) = line.split_once(',').unwrap();
+                *totals.entry(blockstate.to_string()).or_insert(0) += count.parse::<u64>().unwrap();
+            }
+        }
+        assert_eq!(totals, analyzer.totals().into_iter().collect());
+        assert_eq!(totals["minecraft:stone"], 8 * 256 + 1);
+        assert!(fs::read_to_string(dir.path().join("layer_-5.csv"))
+            .unwrap()
+            .contains("minecraft:stone,1\n"));
+    }
+
+    #[test]
+    fn surface_and_deep_slices() {
+        // Grass on dirt at the surface, deepslate with some stone down below
+        let surface = test_fixtures::section(
+            4,
+            &["minecraft:grass_block", "minecraft:dirt", "minecraft:air"],
+            |x, y, _| match y {
+                0 if x < 12 => 0,
+                0 => 1,
+                _ => 2,
+            },
+        );
+        let deep = test_fixtures::section(
+            -4,
+            &["minecraft:deepslate", "minecraft:stone"],
+            |x, _, _| (x >= 12) as usize,
+        );
+        let analyzer = analyze(
+            test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![deep, surface])]),
+            CompositionOptions::default(),
+        );
+
+        assert_eq!(
+            analyzer.top_in_layer(64, 2),
+            vec![("minecraft:grass_block", 192), ("minecraft:dirt", 64)]
+        );
+        assert_eq!(
+            analyzer.top_in_layer(-60, 2),
+            vec![("minecraft:deepslate", 192), ("minecraft:stone", 64)]
+        );
+        assert_eq!(analyzer.top_in_layer(-60, 1).len(), 1);
+        assert!(analyzer.top_in_layer(200, 2).is_empty());
+    }
+
+    #[test]
+    fn all_missing_area() {
+        let mut analyzer = CompositionAnalyzer::with_source(
+            test_fixtures::MemoryChunkSource::new(),
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(Area::new(-2, 2, -2, 2));
+
+        assert_eq!(analyzer.chunk_count, 0);
+        assert!(analyzer.totals().is_empty());
+        assert!(analyzer.volume_percentages().is_empty());
+        assert!(analyzer.y_bounds().is_empty());
+        assert!(analyzer.results().totals().is_empty());
+
+        // Each prints that no blocks were found instead of an empty table
+        analyzer.print_volume_percentages();
+        analyzer.print_y_bounds();
+        analyzer.print_mining_time();
+        analyzer.print_slice_comparison((0, 64), 10);
+        analyzer.results().print_csv(IdForm::default());
+        analyzer.print_csv();
+    }
+
+    #[test]
+    fn merged_sidecars_equal_single_scan() {
+        // Two chunks in each of two regions, with ores at different heights
+        let positions = [(0, 0), (5, 3), (-1, 0), (-32, 31)];
+        let chunks = positions
+            .iter()
+            .enumerate()
+            .map(|(index, &(x, z))| {
+                let section = test_fixtures::section(
+                    -1,
+                    &[
+                        "minecraft:deepslate",
+                        "minecraft:iron_ore",
+                        "minecraft:gold_ore",
+                    ],
+                    move |_, y, _| {
+                        if y == index {
+                            1
+                        } else if y == 15 - index {
+                            2
+                        } else {
+                            0
+                        }
+                    },
+                );
+                test_fixtures::chunk(x, z, vec![section])
+            })
+            .collect();
+        let source = test_fixtures::source(chunks);
+
+        let scan = |chunks: &[(i32, i32)]| {
+            let mut analyzer = CompositionAnalyzer::with_source(
+                source.clone(),
+                LoaderOptions::default(),
+                CompositionOptions::default(),
+            );
+            analyzer.analyze(chunks.to_vec());
+            analyzer.results()
+        };
+
+        let dir = test_fixtures::TempDir::new();
+        scan(&positions[..2])
+            .write_json(&dir.path().join("r.0.0.json"))
+            .unwrap();
+        scan(&positions[2..])
+            .write_json(&dir.path().join("r.-1.0.json"))
+            .unwrap();
+
+        let (merged, sidecar_count) = CompositionResults::merge_folder(dir.path()).unwrap();
+        assert_eq!(sidecar_count, 2);
+        assert_eq!(merged, scan(&positions));
+        assert_eq!(merged.layers.len(), 16);
+    }
+
+    #[test]
+    fn context_dependent_remap() {
+        // Old `grass` becomes `grass_block` in the lower half and `short_grass` above it
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::section(
+                0,
+                &["minecraft:grass", "minecraft:stone"],
+                |x, _, _| (x == 0) as usize,
+            )],
+        )]);
+        let options = CompositionOptions {
+            remap: Some(BlockRemap::new(|blockstate, (_, y, _)| {
+                match (blockstate, y) {
+                    ("minecraft:grass", 0..=7) => "minecraft:grass_block".to_string(),
+                    ("minecraft:grass", _) => "minecraft:short_grass".to_string(),
+                    _ => blockstate.to_string(),
+                }
+            })),
+            ..Default::default()
+        };
+        let analyzer = analyze(source, options);
+
+        let mut totals = analyzer.totals();
+        totals.sort();
+        assert_eq!(
+            totals,
+            vec![
+                ("minecraft:grass_block".to_string(), 8 * 15 * 16),
+                ("minecraft:short_grass".to_string(), 8 * 15 * 16),
+                ("minecraft:stone".to_string(), 16 * 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn air_totals_per_skipped_section_policy() {
+        use crate::chunk_loader::SkippedSectionPolicy;
+
+        // Section Y 1 stores neither block states nor biomes, so it can't be decoded
+        let mut undecodable = nbt::CompoundTag::new();
+        undecodable.insert_i8("Y", 1);
+        let chunk = test_fixtures::chunk(
+            0,
+            0,
+            vec![
+                test_fixtures::uniform_section(0, "minecraft:stone"),
+                undecodable,
+            ],
+        );
+
+        let air_total = |policy| {
+            let mut analyzer = CompositionAnalyzer::with_source(
+                test_fixtures::source(vec![chunk.clone()]),
+                LoaderOptions {
+                    skipped_sections: policy,
+                    ..Default::default()
+                },
+                CompositionOptions::default(),
+            );
+            analyzer.analyze(vec![(0, 0)]);
+            analyzer
+                .totals()
+                .into_iter()
+                .find(|(name, _)| name == "minecraft:air")
+                .map_or(0, |(_, count)| count)
+        };
+
+        assert_eq!(air_total(SkippedSectionPolicy::Ignore), 0);
+        assert_eq!(air_total(SkippedSectionPolicy::Count), 4096);
+    }
+
+    #[test]
+    fn underground_only() {
+        // Stone up to grass at Y 14, with air above it
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::section(
+                0,
+                &["minecraft:air", "minecraft:stone", "minecraft:grass_block"],
+                |_, y, _| match y {
+                    0..=13 => 1,
+                    14 => 2,
+                    _ => 0,
+                },
+            )],
+        )]);
+        let options = CompositionOptions {
+            underground_only: true,
+            ..Default::default()
+        };
+        let analyzer = analyze(source, options);
+
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:stone".to_string(), 14 * 256)]
+        );
+    }
+
+    #[test]
+    fn first_and_last_seen_y() {
+        // Single blocks of diamond ore at Y -3 and Y 20, in stone
+        let ore_at = |section_y: i8, ore_y: usize| {
+            test_fixtures::section(
+                section_y,
+                &["minecraft:stone", "minecraft:diamond_ore"],
+                move |x, y, z| ((x, y, z) == (0, ore_y, 0)) as usize,
+            )
+        };
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![
+                ore_at(-1, 13),
+                test_fixtures::uniform_section(0, "minecraft:stone"),
+                ore_at(1, 4),
+            ],
+        )]);
+        let analyzer = analyze(source, CompositionOptions::default());
+
+        assert_eq!(
+            analyzer.y_bounds(),
+            vec![
+                ("minecraft:stone".to_string(), -16, 31, 3 * 4096 - 2),
+                ("minecraft:diamond_ore".to_string(), -3, 20, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn column_metric() {
+        // A pillar of four blocks of obsidian in a single column
+        let source = || {
+            test_fixtures::source(vec![test_fixtures::chunk(
+                0,
+                0,
+                vec![test_fixtures::section(
+                    0,
+                    &["minecraft:air", "minecraft:obsidian"],
+                    |x, y, z| (x == 3 && z == 5 && y < 4) as usize,
+                )],
+            )])
+        };
+        let obsidian_total = |metric| {
+            let analyzer = analyze(
+                source(),
+                CompositionOptions {
+                    metric,
+                    ..Default::default()
+                },
+            );
+            analyzer
+                .totals()
+                .into_iter()
+                .find(|(name, _)| name == "minecraft:obsidian")
+                .map(|(_, count)| count)
+        };
+
+        assert_eq!(obsidian_total(CountMetric::Blocks), Some(4));
+        assert_eq!(obsidian_total(CountMetric::Columns), Some(1));
+    }
+
+    #[test]
+    fn dimensions_share_options() {
+        let world = test_fixtures::TempDir::new();
+        for (dimension, block) in [
+            (Dimension::Overworld, "minecraft:stone"),
+            (Dimension::Nether, "minecraft:netherrack"),
+        ] {
+            let region_folder = dimension.region_path(world.path());
+            fs::create_dir_all(&region_folder).unwrap();
+            let chunk = test_fixtures::chunk(0, 0, vec![test_fixtures::uniform_section(0, block)]);
+            test_fixtures::write_region_file(
+                &region_folder.join("r.0.0.mca"),
+                vec![((0, 0), 2, test_fixtures::zlib(chunk))],
+            );
+        }
+
+        // Every other layer, with the lower half of the section filtered out
+        let options = CompositionOptions {
+            y_step: Some(2),
+            y_filter: YFilter {
+                above: Some(7),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let dimensions =
+            analyze_dimensions(world.path(), LoaderOptions::default(), &options, || {
+                vec![(0, 0)]
+            });
+
+        let totals: Vec<(Dimension, Vec<(String, u64)>)> = dimensions
+            .into_iter()
+            .map(|dimension| (dimension.dimension, dimension.totals))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                (
+                    Dimension::Overworld,
+                    vec![("minecraft:stone".to_string(), 4 * 256)]
+                ),
+                (
+                    Dimension::Nether,
+                    vec![("minecraft:netherrack".to_string(), 4 * 256)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn scanned_volume_follows_filters_and_world_height() {
+        let volume =
+            |chunk, options| analyze(test_fixtures::source(vec![chunk]), options).scanned_volume;
+        let stone = || {
+            test_fixtures::chunk(
+                0,
+                0,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            )
+        };
+
+        // Every fourth of the 64 layers below Y 0
+        let filtered = CompositionOptions {
+            y_step: Some(4),
+            y_filter: YFilter {
+                below: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(volume(stone(), filtered), 16 * 256);
+
+        let nether = CompositionOptions {
+            dimension: Dimension::Nether,
+            ..Default::default()
+        };
+        assert_eq!(volume(stone(), nether), 256 * 256);
+
+        // Before 1.18, the overworld started at Y 0
+        let legacy = test_fixtures::legacy_chunk(0, 0, 2586, Vec::new());
+        assert_eq!(volume(legacy, CompositionOptions::default()), 256 * 256);
+    }
+
+    /// Chunks of stone, except that reading the chunk at `panic_at` panics
+    struct PanickingSource {
+        chunks: test_fixtures::MemoryChunkSource,
+        panic_at: (i32, i32),
+    }
+
+    impl ChunkSource for PanickingSource {
+        fn read_chunk_nbt(
+            &self,
+            chunk_x: i32,
+            chunk_z: i32,
+            skip: &[&str],
+        ) -> Result<nbt::CompoundTag, anvil_region::error::ChunkReadError> {
+            if (chunk_x, chunk_z) == self.panic_at {
+                panic!("corrupt chunk ({},{})", chunk_x, chunk_z);
+            }
+            self.chunks.read_chunk_nbt(chunk_x, chunk_z, skip)
+        }
+    }
+
+    #[test]
+    fn panic_mid_scan_leaves_checkpoint() {
+        let chunks = (0..4)
+            .map(|x| {
+                test_fixtures::chunk(
+                    x,
+                    0,
+                    vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+                )
+            })
+            .collect();
+        let source = PanickingSource {
+            chunks: test_fixtures::source(chunks),
+            panic_at: (2, 0),
+        };
+        let dir = test_fixtures::TempDir::new();
+        let checkpoint = dir.path().join("checkpoint.bin");
+
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        )
+        .with_checkpoint(checkpoint.clone(), 100);
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        assert_eq!(analyzer.aborted_at(), Some((2, 0)));
+        let partial = CompositionResults::read_binary(&checkpoint).unwrap();
+        assert_eq!(
+            partial.totals(),
+            vec![("minecraft:stone".to_string(), 2 * 4096)]
+        );
+    }
+
+    #[test]
+    fn checkpoint_every_chunk() {
+        let dir = test_fixtures::TempDir::new();
+        let checkpoint = dir.path().join("checkpoint.bin");
+
+        let mut analyzer = CompositionAnalyzer::with_source(
+            test_fixtures::uniform_source("minecraft:stone"),
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        )
+        .with_checkpoint(checkpoint.clone(), 1);
+        // The checkpoint is written as soon as the chunk is counted, before the scan ends
+        analyzer.scan(vec![(0, 0)]);
+
+        let partial = CompositionResults::read_binary(&checkpoint).unwrap();
+        assert_eq!(partial.totals(), analyzer.totals());
+    }
+
+    /// Chunks of stone, setting `flag` once the chunk at `interrupt_at` is read
+    struct InterruptingSource {
+        chunks: test_fixtures::MemoryChunkSource,
+        interrupt_at: (i32, i32),
+        flag: Arc<AtomicBool>,
+    }
+
+    impl ChunkSource for InterruptingSource {
+        fn read_chunk_nbt(
+            &self,
+            chunk_x: i32,
+            chunk_z: i32,
+            skip: &[&str],
+        ) -> Result<nbt::CompoundTag, anvil_region::error::ChunkReadError> {
+            if (chunk_x, chunk_z) == self.interrupt_at {
+                self.flag.store(true, Ordering::SeqCst);
+            }
+            self.chunks.read_chunk_nbt(chunk_x, chunk_z, skip)
+        }
+    }
+
+    #[test]
+    fn interrupt_flag_stops_scan() {
+        let chunks = (0..4)
+            .map(|x| {
+                test_fixtures::chunk(
+                    x,
+                    0,
+                    vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+                )
+            })
+            .collect();
+        let flag = Arc::new(AtomicBool::new(false));
+        let source = InterruptingSource {
+            chunks: test_fixtures::source(chunks),
+            interrupt_at: (1, 0),
+            flag: flag.clone(),
+        };
+        let dir = test_fixtures::TempDir::new();
+        let checkpoint = dir.path().join("checkpoint.bin");
+
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        )
+        .with_interrupt_flag(flag)
+        .with_checkpoint(checkpoint.clone(), 100);
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        // The chunk being read when the flag was set is still counted
+        assert_eq!(analyzer.aborted_at(), Some((2, 0)));
+        assert_eq!(analyzer.chunk_count, 2);
+        let partial = CompositionResults::read_binary(&checkpoint).unwrap();
+        assert_eq!(
+            partial.totals(),
+            vec![("minecraft:stone".to_string(), 2 * 4096)]
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_matches_csv() {
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![
+                test_fixtures::section(
+                    -1,
+                    &["minecraft:deepslate", "minecraft:stone"],
+                    |x, _, z| (x + z) % 2,
+                ),
+                test_fixtures::section(
+                    0,
+                    &["minecraft:stone", "minecraft:dirt", "minecraft:gold_ore"],
+                    |x, y, z| (x * y + z) % 3,
+                ),
+            ],
+        )]);
+        let analyzer = analyze(source, CompositionOptions::default());
+
+        let dir = test_fixtures::TempDir::new();
+        let csv_path = dir.path().join("results.csv");
+        let db_path = dir.path().join("results.db");
+        analyzer.append_csv(&csv_path).unwrap();
+        analyzer.write_sqlite(&db_path).unwrap();
+
+        // Counts per (layer, blockstate) from the CSV, skipping zeros since the database only
+        // has rows for blocks that occur in a layer
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let mut lines = csv.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').skip(2).collect();
+        let mut csv_counts = HashMap::new();
+        let mut csv_totals = HashMap::new();
+        for line in lines {
+            let mut fields = line.split(',').skip(1);
+            let label = fields.next().unwrap().to_string();
+            for (blockstate, count) in header.iter().zip(fields) {
+                let count: i64 = count.parse().unwrap();
+                if label == "Total" {
+                    csv_totals.insert(blockstate.to_string(), count);
+                } else if count > 0 {
+                    csv_counts.insert(
+                        (label.parse::<i32>().unwrap(), blockstate.to_string()),
+                        count,
+                    );
+                }
+            }
+        }
+
+        let connection = Connection::open(&db_path).unwrap();
+        let db_counts: HashMap<(i32, String), i64> = connection
+            .prepare("SELECT y, blockstate, count FROM blocks")
+            .unwrap()
+            .query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let db_totals: HashMap<String, i64> = connection
+            .prepare("SELECT blockstate, count FROM totals")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(db_counts, csv_counts);
+        assert_eq!(db_totals, csv_totals);
+
+        // Summing the layers in SQL gives the totals table
+        let db_sums: HashMap<String, i64> = connection
+            .prepare("SELECT blockstate, SUM(count) FROM blocks GROUP BY blockstate")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(db_sums, csv_totals);
+        assert_eq!(csv_totals.values().sum::<i64>(), 2 * 4096);
+
+        // Writing again replaces the database instead of adding rows
+        analyzer.write_sqlite(&db_path).unwrap();
+        let rows: i64 = Connection::open(&db_path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM totals", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 4);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_matches_csv() {
+        use parquet::{
+            file::reader::{FileReader, SerializedFileReader},
+            record::RowAccessor,
+        };
+
+        let column = |x, z| {
+            test_fixtures::chunk(
+                x,
+                z,
+                vec![test_fixtures::section(
+                    0,
+                    &["minecraft:stone", "minecraft:dirt", "minecraft:coal_ore"],
+                    move |bx, by, bz| (bx + by * x as usize + bz * z as usize) % 3,
+                )],
+            )
+        };
+        let source = test_fixtures::source(vec![column(0, 0), column(1, 0), column(1, 2)]);
+        let options = CompositionOptions {
+            chunk_rows: true,
+            ..CompositionOptions::default()
+        };
+        let mut analyzer =
+            CompositionAnalyzer::with_source(source, LoaderOptions::default(), options);
+        analyzer.analyze(vec![(0, 0), (1, 0), (1, 2)]);
+
+        let dir = test_fixtures::TempDir::new();
+        let csv_path = dir.path().join("results.csv");
+        let parquet_path = dir.path().join("results.parquet");
+        analyzer.append_csv(&csv_path).unwrap();
+        analyzer.write_parquet(&parquet_path).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&parquet_path).unwrap()).unwrap();
+        let mut chunks = HashSet::new();
+        let mut parquet_counts: HashMap<(String, String), u64> = HashMap::new();
+        for row in reader.get_row_iter(None).unwrap() {
+            let row = row.unwrap();
+            chunks.insert((row.get_int(0).unwrap(), row.get_int(1).unwrap()));
+            let y = row.get_int(2).unwrap().to_string();
+            let blockstate = row.get_string(3).unwrap().clone();
+            let count = row.get_long(4).unwrap() as u64;
+            *parquet_counts.entry((y, blockstate.clone())).or_insert(0) += count;
+            *parquet_counts
+                .entry(("Total".to_string(), blockstate))
+                .or_insert(0) += count;
+        }
+        assert_eq!(chunks, vec![(0, 0), (1, 0), (1, 2)].into_iter().collect());
+
+        // Adding up the chunks gives the layers and totals of the CSV, which leaves out zeros
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let mut lines = csv.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').skip(2).collect();
+        let mut csv_counts = HashMap::new();
+        for line in lines {
+            let mut fields = line.split(',').skip(1);
+            let label = fields.next().unwrap();
+            for (blockstate, count) in header.iter().zip(fields) {
+                let count: u64 = count.parse().unwrap();
+                if count > 0 {
+                    csv_counts.insert((label.to_string(), blockstate.to_string()), count);
+                }
             }
         }
-        println!();
+        assert_eq!(parquet_counts, csv_counts);
+        assert_eq!(
+            csv_counts
+                .iter()
+                .filter(|((label, _), _)| label == "Total")
+                .map(|(_, count)| count)
+                .sum::<u64>(),
+            3 * 4096
+        );
     }
 }
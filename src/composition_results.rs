@@ -0,0 +1,637 @@
+//! Counts of a composition scan detached from the world, saved as JSON sidecar files
+//! or in a compact binary form.
+//!
+//! Scanning each region into its own sidecar and merging them later splits a large scan across
+//! processes or machines, without any of them needing the whole world. Saved results can be
+//! reported again with different options without scanning the world again.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{self, Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{block_id::IdForm, ndjson::json_string};
+
+/// File extension of sidecar files
+pub const SIDECAR_EXTENSION: &str = "json";
+
+/// Magic bytes at the start of results saved with `write_binary`
+const BINARY_MAGIC: &[u8; 4] = b"MCAR";
+
+/// Version of the binary layout, increased whenever it changes
+const BINARY_VERSION: u8 = 1;
+
+/// Blockstate counts per layer of one or more scans
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompositionResults {
+    /// Y -> blockstate -> count
+    pub layers: BTreeMap<i32, BTreeMap<String, u64>>,
+
+    /// Lowest and highest `DataVersion` of the scanned chunks, `None` if no chunk was scanned
+    pub data_versions: Option<RangeInclusive<i32>>,
+}
+
+impl CompositionResults {
+    /// Total count of every blockstate, most common first
+    pub fn totals(&self) -> Vec<(String, u64)> {
+        let mut totals: BTreeMap<&str, u64> = BTreeMap::new();
+        for (blockstate, count) in self.layers.values().flatten() {
+            *totals.entry(blockstate).or_insert(0) += count;
+        }
+
+        let mut totals: Vec<(String, u64)> = totals
+            .into_iter()
+            .map(|(blockstate, count)| (blockstate.to_string(), count))
+            .collect();
+        totals.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        totals
+    }
+
+    /// Adds the counts of another scan to these, as if both had been a single scan
+    pub fn merge(&mut self, other: CompositionResults) {
+        for (y, counts) in other.layers {
+            let layer = self.layers.entry(y).or_default();
+            for (blockstate, count) in counts {
+                *layer.entry(blockstate).or_insert(0) += count;
+            }
+        }
+
+        self.data_versions = match (self.data_versions.take(), other.data_versions) {
+            (Some(a), Some(b)) => Some((*a.start()).min(*b.start())..=(*a.end()).max(*b.end())),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// `{"data_versions":[2865,3120],"layers":{"-64":{"minecraft:bedrock":256,...},...}}`,
+    /// with `null` data versions if no chunk was scanned
+    pub fn to_json(&self) -> String {
+        let data_versions = match &self.data_versions {
+            Some(range) => format!("[{},{}]", range.start(), range.end()),
+            None => "null".to_string(),
+        };
+
+        let layers: Vec<String> = self
+            .layers
+            .iter()
+            .map(|(y, counts)| {
+                let counts: Vec<String> = counts
+                    .iter()
+                    .map(|(blockstate, count)| format!("{}:{}", json_string(blockstate), count))
+                    .collect();
+                format!("\"{}\":{{{}}}", y, counts.join(","))
+            })
+            .collect();
+
+        format!(
+            "{{\"data_versions\":{},\"layers\":{{{}}}}}",
+            data_versions,
+            layers.join(",")
+        )
+    }
+
+    /// Parses results written by `to_json`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut parser = JsonParser {
+            bytes: json.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.bytes.len() {
+            return Err("unexpected data after the results".to_string());
+        }
+
+        let fields = match value {
+            Json::Object(fields) => fields,
+            _ => return Err("results need to be an object".to_string()),
+        };
+
+        let mut results = CompositionResults::default();
+
+        for (name, value) in fields {
+            match (name.as_str(), value) {
+                ("data_versions", Json::Null) => {}
+                ("data_versions", Json::Array(range)) => match range.as_slice() {
+                    [Json::Number(min), Json::Number(max)] => {
+                        results.data_versions = Some(*min as i32..=*max as i32);
+                    }
+                    _ => return Err("data_versions needs to be [min,max]".to_string()),
+                },
+                ("layers", Json::Object(layers)) => {
+                    for (y, counts) in layers {
+                        let y: i32 = y
+                            .parse()
+                            .map_err(|_| format!("'{}' is not a valid layer", y))?;
+                        let counts = match counts {
+                            Json::Object(counts) => counts,
+                            _ => return Err(format!("layer {} needs to be an object", y)),
+                        };
+
+                        let layer = results.layers.entry(y).or_default();
+                        for (blockstate, count) in counts {
+                            match count {
+                                Json::Number(count) if count >= 0 => {
+                                    *layer.entry(blockstate).or_insert(0) += count as u64;
+                                }
+                                _ => return Err(format!("invalid count of '{}'", blockstate)),
+                            }
+                        }
+                    }
+                }
+                (name, _) => return Err(format!("unexpected field '{}'", name)),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Compact binary form of the results, much smaller and faster to read than JSON.
+    /// All numbers are little endian: the magic bytes `MCAR`, a version byte, a byte telling
+    /// whether data versions follow as two `i32`s, the blockstate names as a `u32` count followed
+    /// by `u32`-length-prefixed UTF-8 strings, and the layers as a `u32` count followed by each
+    /// layer's `i32` Y, `u32` number of entries and `(u32 name index, u64 count)` entries.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut names: Vec<&str> = self
+            .layers
+            .values()
+            .flat_map(|counts| counts.keys().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        let indices: HashMap<&str, u32> = names
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| (name, index as u32))
+            .collect();
+
+        // Writing to a `Vec` can't fail
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.push(BINARY_VERSION);
+
+        match &self.data_versions {
+            Some(range) => {
+                bytes.push(1);
+                bytes.write_i32::<LittleEndian>(*range.start()).unwrap();
+                bytes.write_i32::<LittleEndian>(*range.end()).unwrap();
+            }
+            None => bytes.push(0),
+        }
+
+        bytes.write_u32::<LittleEndian>(names.len() as u32).unwrap();
+        for name in &names {
+            bytes.write_u32::<LittleEndian>(name.len() as u32).unwrap();
+            bytes.extend_from_slice(name.as_bytes());
+        }
+
+        bytes
+            .write_u32::<LittleEndian>(self.layers.len() as u32)
+            .unwrap();
+        for (y, counts) in &self.layers {
+            bytes.write_i32::<LittleEndian>(*y).unwrap();
+            bytes
+                .write_u32::<LittleEndian>(counts.len() as u32)
+                .unwrap();
+            for (blockstate, count) in counts {
+                bytes
+                    .write_u32::<LittleEndian>(indices[blockstate.as_str()])
+                    .unwrap();
+                bytes.write_u64::<LittleEndian>(*count).unwrap();
+            }
+        }
+
+        bytes
+    }
+
+    /// Parses results written by `to_bytes`
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut magic = [0; 4];
+        bytes.read_exact(&mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(invalid("not a results file"));
+        }
+        if bytes.read_u8()? != BINARY_VERSION {
+            return Err(invalid("unsupported results file version"));
+        }
+
+        let mut results = CompositionResults::default();
+
+        if bytes.read_u8()? != 0 {
+            let min = bytes.read_i32::<LittleEndian>()?;
+            let max = bytes.read_i32::<LittleEndian>()?;
+            results.data_versions = Some(min..=max);
+        }
+
+        let name_count = bytes.read_u32::<LittleEndian>()?;
+        let mut names = Vec::new();
+        for _ in 0..name_count {
+            let len = bytes.read_u32::<LittleEndian>()? as usize;
+            if len > bytes.len() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let (name, rest) = bytes.split_at(len);
+            let name = String::from_utf8(name.to_vec())
+                .map_err(|_| invalid("blockstate name is not valid UTF-8"))?;
+            names.push(name);
+            bytes = rest;
+        }
+
+        let layer_count = bytes.read_u32::<LittleEndian>()?;
+        for _ in 0..layer_count {
+            let y = bytes.read_i32::<LittleEndian>()?;
+            let entry_count = bytes.read_u32::<LittleEndian>()?;
+
+            let layer = results.layers.entry(y).or_default();
+            for _ in 0..entry_count {
+                let index = bytes.read_u32::<LittleEndian>()? as usize;
+                let count = bytes.read_u64::<LittleEndian>()?;
+                let name = names
+                    .get(index)
+                    .ok_or_else(|| invalid("blockstate index out of range"))?;
+                *layer.entry(name.clone()).or_insert(0) += count;
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(invalid("unexpected data after the results"));
+        }
+
+        Ok(results)
+    }
+
+    pub fn write_binary(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn read_binary(path: &Path) -> io::Result<Self> {
+        CompositionResults::from_bytes(&fs::read(path)?)
+    }
+
+    /// Drops the counts of all other blockstates, and layers left without any counts
+    pub fn retain_blocks(&mut self, blocks: &HashSet<String>) {
+        for counts in self.layers.values_mut() {
+            counts.retain(|blockstate, _| blocks.contains(blockstate));
+        }
+        self.layers.retain(|_, counts| !counts.is_empty());
+    }
+
+    /// Drops all layers outside the range
+    pub fn retain_layers(&mut self, y_range: &RangeInclusive<i32>) {
+        self.layers.retain(|y, _| y_range.contains(y));
+    }
+
+    pub fn write_json(&self, path: &Path) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "{}", self.to_json())
+    }
+
+    pub fn read_json(path: &Path) -> io::Result<Self> {
+        CompositionResults::from_json(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Reads all sidecars in a folder and merges them, reading several of them at once.
+    /// Returns the merged results and the number of sidecars.
+    pub fn merge_folder(folder: &Path) -> io::Result<(Self, usize)> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(folder)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?;
+        paths.retain(|path| {
+            path.is_file() && path.extension().is_some_and(|ext| ext == SIDECAR_EXTENSION)
+        });
+        paths.sort();
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let paths_per_thread = paths.len().div_ceil(threads).max(1);
+
+        let partial_results = thread::scope(|scope| {
+            let workers: Vec<_> = paths
+                .chunks(paths_per_thread)
+                .map(|paths| {
+                    scope.spawn(move || -> io::Result<CompositionResults> {
+                        let mut results = CompositionResults::default();
+                        for path in paths {
+                            let sidecar = CompositionResults::read_json(path).map_err(|err| {
+                                io::Error::new(err.kind(), format!("'{}': {}", path.display(), err))
+                            })?;
+                            results.merge(sidecar);
+                        }
+                        Ok(results)
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+
+        let mut results = CompositionResults::default();
+        for partial in partial_results {
+            results.merge(partial);
+        }
+
+        Ok((results, paths.len()))
+    }
+
+    /// Prints the counts in the same layout as the composition mode, one row per layer
+    /// and a column per blockstate, most common first
+    pub fn print_csv(&self, id_form: IdForm) {
+        let totals = self.totals();
+        if totals.is_empty() {
+            eprintln!("No blocks found in the results");
+            return;
+        }
+
+        let columns: Vec<String> = totals
+            .iter()
+            .map(|(blockstate, _)| id_form.render(blockstate))
+            .collect();
+        println!("Layer,{}", columns.join(","));
+
+        for (y, counts) in &self.layers {
+            let row: Vec<String> = totals
+                .iter()
+                .map(|(blockstate, _)| format!("{:8}", counts.get(blockstate).unwrap_or(&0)))
+                .collect();
+            println!("{:5},{}", y, row.join(","));
+        }
+
+        let row: Vec<String> = totals
+            .iter()
+            .map(|(_, count)| format!("{:8}", count))
+            .collect();
+        println!("Total,{}", row.join(","));
+    }
+}
+
+/// The subset of JSON that results are written in: strings only as object keys,
+/// and no floats, booleans or surrogate pairs
+enum Json {
+    Null,
+    Number(i64),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|byte| byte.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        match self.peek() {
+            Some(byte) if byte == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(format!(
+                "expected '{}' at byte {}",
+                expected as char, self.pos
+            )),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'n') if self.bytes[self.pos..].starts_with(b"null") => {
+                self.pos += 4;
+                Ok(Json::Null)
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.parse_value()?);
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b']')?;
+                Ok(Json::Array(values))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let name = self.parse_string()?;
+                    self.expect(b':')?;
+                    fields.push((name, self.parse_value()?));
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b'}')?;
+                Ok(Json::Object(fields))
+            }
+            Some(b'-') | Some(b'0'..=b'9') => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .map(Json::Number)
+                    .ok_or_else(|| format!("invalid number at byte {}", start))
+            }
+            _ => Err(format!("unexpected value at byte {}", self.pos)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+
+        let mut bytes = Vec::new();
+        loop {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| "unterminated string".to_string())?;
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = *self
+                        .bytes
+                        .get(self.pos)
+                        .ok_or_else(|| "unterminated string".to_string())?;
+                    self.pos += 1;
+
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let code = self
+                                .bytes
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| format!("invalid escape at byte {}", self.pos))?;
+                            self.pos += 4;
+                            code
+                        }
+                        _ => return Err(format!("invalid escape at byte {}", self.pos)),
+                    };
+
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| "string is not valid UTF-8".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TempDir;
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut results = CompositionResults::default();
+        let mut bottom = BTreeMap::new();
+        bottom.insert("minecraft:bedrock".to_string(), 256);
+        bottom.insert("minecraft:deepslate".to_string(), 0);
+        results.layers.insert(-64, bottom);
+        let mut top = BTreeMap::new();
+        top.insert("minecraft:stone".to_string(), u32::MAX as u64 + 1);
+        results.layers.insert(0, top);
+        results.data_versions = Some(2865..=3120);
+
+        let reloaded = CompositionResults::from_bytes(&results.to_bytes()).unwrap();
+        assert_eq!(reloaded, results);
+        assert_eq!(reloaded.to_json(), results.to_json());
+    }
+
+    #[test]
+    fn retain_blocks() {
+        let mut results = CompositionResults::default();
+        let mut layer = BTreeMap::new();
+        layer.insert("minecraft:stone".to_string(), 4000);
+        layer.insert("minecraft:diamond_ore".to_string(), 3);
+        results.layers.insert(-58, layer);
+
+        let blocks: HashSet<String> = vec!["minecraft:diamond_ore".to_string()]
+            .into_iter()
+            .collect();
+        results.retain_blocks(&blocks);
+
+        assert_eq!(
+            results.totals(),
+            vec![("minecraft:diamond_ore".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn counts_past_u32_max() {
+        // Three scans of 3 billion air blocks each in one layer, and as many in another
+        let scan = || {
+            let mut results = CompositionResults::default();
+            for y in [100, 101] {
+                let mut layer = BTreeMap::new();
+                layer.insert("minecraft:air".to_string(), 3_000_000_000);
+                results.layers.insert(y, layer);
+            }
+            results
+        };
+        let mut results = scan();
+        results.merge(scan());
+        results.merge(scan());
+
+        let total = 6 * 3_000_000_000u64;
+        assert!(total > u32::MAX as u64);
+        assert_eq!(results.totals(), vec![("minecraft:air".to_string(), total)]);
+        assert_eq!(results.layers[&100]["minecraft:air"], 9_000_000_000);
+
+        let reloaded = CompositionResults::from_json(&results.to_json()).unwrap();
+        assert_eq!(
+            reloaded.totals(),
+            vec![("minecraft:air".to_string(), total)]
+        );
+    }
+
+    #[test]
+    fn parallel_merge_is_reproducible() {
+        // Many sidecars, so that several threads merge them, with blocks tied in count
+        let dir = TempDir::new();
+        for index in 0..32u64 {
+            let mut results = CompositionResults::default();
+            let mut layer = BTreeMap::new();
+            layer.insert("minecraft:stone".to_string(), 100 + index);
+            layer.insert("minecraft:granite".to_string(), 10);
+            layer.insert("minecraft:diorite".to_string(), 10);
+            results.layers.insert(index as i32 % 4, layer);
+            results.data_versions = Some(2975..=2975 + index as i32);
+            results
+                .write_json(&dir.path().join(format!("r.{}.0.json", index)))
+                .unwrap();
+        }
+
+        let (first, count) = CompositionResults::merge_folder(dir.path()).unwrap();
+        let (second, _) = CompositionResults::merge_folder(dir.path()).unwrap();
+
+        assert_eq!(count, 32);
+        assert_eq!(first.to_json(), second.to_json());
+        assert_eq!(first.to_bytes(), second.to_bytes());
+        assert_eq!(
+            first.totals(),
+            vec![
+                (
+                    "minecraft:stone".to_string(),
+                    32 * 100 + (0..32).sum::<u64>()
+                ),
+                ("minecraft:diorite".to_string(), 320),
+                ("minecraft:granite".to_string(), 320),
+            ]
+        );
+        assert_eq!(first.data_versions, Some(2975..=3006));
+    }
+}
@@ -0,0 +1,100 @@
+use anvil_region::position::{RegionChunkPosition, RegionPosition};
+
+use crate::chunk_section::CHUNK_SIZE;
+
+/// Where a block is stored, from the region file down to its position within a section
+#[derive(Debug, Clone, Copy)]
+pub struct CoordinateParts {
+    /// Region file the block is stored in
+    pub region: RegionPosition,
+    /// Position of the chunk within its region
+    pub region_chunk: RegionChunkPosition,
+    /// Global chunk coordinates `(chunk_x, chunk_z)`
+    pub chunk: (i32, i32),
+    /// Index of the section within the chunk, negative below Y=0
+    pub section_y: i8,
+    /// Coordinates `(x, y, z)` of the block within its section
+    pub local: (usize, usize, usize),
+}
+
+/// Splits a global block position into its region, chunk, section and in-section coordinates.
+/// Uses euclidean division so that negative coordinates end up in the right chunk and section.
+pub fn decompose(x: i64, y: i32, z: i64) -> CoordinateParts {
+    let chunk_x = x.div_euclid(CHUNK_SIZE as i64) as i32;
+    let chunk_z = z.div_euclid(CHUNK_SIZE as i64) as i32;
+
+    CoordinateParts {
+        region: RegionPosition::from_chunk_position(chunk_x, chunk_z),
+        region_chunk: RegionChunkPosition::from_chunk_position(chunk_x, chunk_z),
+        chunk: (chunk_x, chunk_z),
+        section_y: y.div_euclid(CHUNK_SIZE as i32) as i8,
+        local: (
+            x.rem_euclid(CHUNK_SIZE as i64) as usize,
+            y.rem_euclid(CHUNK_SIZE as i32) as usize,
+            z.rem_euclid(CHUNK_SIZE as i64) as usize,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Region, chunk within the region, chunk, section Y and local coordinates
+    type Parts = ((i32, i32), (u8, u8), (i32, i32), i8, (usize, usize, usize));
+
+    fn parts(x: i64, y: i32, z: i64) -> Parts {
+        let parts = decompose(x, y, z);
+        (
+            (parts.region.x, parts.region.z),
+            (parts.region_chunk.x, parts.region_chunk.z),
+            parts.chunk,
+            parts.section_y,
+            parts.local,
+        )
+    }
+
+    #[test]
+    fn origin_and_boundaries() {
+        assert_eq!(parts(0, 0, 0), ((0, 0), (0, 0), (0, 0), 0, (0, 0, 0)));
+        assert_eq!(parts(15, 15, 15), ((0, 0), (0, 0), (0, 0), 0, (15, 15, 15)));
+        assert_eq!(parts(16, 16, 16), ((0, 0), (1, 1), (1, 1), 1, (0, 0, 0)));
+        // Last block of region 0, 0 and first of region 1, 1
+        assert_eq!(
+            parts(511, 100, 511),
+            ((0, 0), (31, 31), (31, 31), 6, (15, 4, 15))
+        );
+        assert_eq!(
+            parts(512, 100, 512),
+            ((1, 1), (0, 0), (32, 32), 6, (0, 4, 0))
+        );
+    }
+
+    #[test]
+    fn negative() {
+        assert_eq!(
+            parts(-1, -1, -1),
+            ((-1, -1), (31, 31), (-1, -1), -1, (15, 15, 15))
+        );
+        assert_eq!(
+            parts(-16, -16, -16),
+            ((-1, -1), (31, 31), (-1, -1), -1, (0, 0, 0))
+        );
+        assert_eq!(
+            parts(-17, -17, -17),
+            ((-1, -1), (30, 30), (-2, -2), -2, (15, 15, 15))
+        );
+        assert_eq!(
+            parts(-512, 0, -513),
+            ((-1, -2), (0, 31), (-32, -33), 0, (0, 0, 15))
+        );
+    }
+
+    #[test]
+    fn world_height_since_1_18() {
+        assert_eq!(parts(5, -64, 5).3, -4);
+        assert_eq!(parts(5, -64, 5).4, (5, 0, 5));
+        assert_eq!(parts(5, 319, 5).3, 19);
+        assert_eq!(parts(5, 319, 5).4, (5, 15, 5));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    chunk::Chunk, chunk_loader::ChunkLoader, chunk_section::CHUNK_SIZE, coords,
+    dimension::Dimension,
+};
+
+/// A sampled column's X and Z, with its blockstate at every Y from top to bottom
+pub type ColumnSample = ((i64, i64), Vec<(i32, String)>);
+
+/// The blockstate at every Y of the block column at X and Z, from top to bottom, like a
+/// geological core sample. Without a `y_range`, the column goes from the bottom to the top of
+/// the dimension in the version its chunk was saved in. Layers whose section isn't stored are
+/// left out, and nothing is returned if the chunk isn't stored.
+pub fn core_sample(
+    chunk_loader: &mut ChunkLoader,
+    x: i64,
+    z: i64,
+    y_range: Option<RangeInclusive<i32>>,
+    dimension: Dimension,
+) -> Vec<(i32, String)> {
+    let (chunk_x, chunk_z) = coords::decompose(x, 0, z).chunk;
+    let chunk = match chunk_loader.get_or_load(chunk_x, chunk_z) {
+        Some(chunk) => chunk,
+        None => return Vec::new(),
+    };
+
+    y_range
+        .unwrap_or_else(|| column_y_range(chunk, dimension))
+        .rev()
+        .filter_map(|y| {
+            let parts = coords::decompose(x, y, z);
+            let (local_x, local_y, local_z) = parts.local;
+            let blockstate = chunk
+                .get_section(parts.section_y)?
+                .get_block_at(local_x, local_y, local_z)?;
+            Some((y, blockstate.to_string()))
+        })
+        .collect()
+}
+
+/// Ys a column of the chunk can have blocks at: those of the dimension, limited to the sections
+/// vanilla stores for the chunk's version
+fn column_y_range(chunk: &Chunk, dimension: Dimension) -> RangeInclusive<i32> {
+    let sections = chunk.section_y_bounds();
+    let world = dimension.default_y_range();
+
+    let bottom = (*sections.start() as i32 * CHUNK_SIZE as i32).max(*world.start());
+    let top = ((*sections.end() as i32 + 1) * CHUNK_SIZE as i32 - 1).min(*world.end());
+    bottom..=top
+}
+
+/// Writes the core samples side by side, with a row per Y from top to bottom and a column per
+/// sampled X and Z. Cells of Ys a column has no block at are left empty.
+pub fn write_core_samples<W: Write>(out: &mut W, samples: &[ColumnSample]) -> io::Result<()> {
+    write!(out, "Y")?;
+    for ((x, z), _) in samples {
+        write!(out, ",X={} Z={}", x, z)?;
+    }
+    writeln!(out)?;
+
+    let columns: Vec<HashMap<i32, &str>> = samples
+        .iter()
+        .map(|(_, sample)| {
+            sample
+                .iter()
+                .map(|(y, blockstate)| (*y, blockstate.as_str()))
+                .collect()
+        })
+        .collect();
+    let ys: BTreeSet<i32> = columns
+        .iter()
+        .flat_map(|column| column.keys())
+        .copied()
+        .collect();
+
+    for y in ys.into_iter().rev() {
+        write!(out, "{:5}", y)?;
+        for column in &columns {
+            write!(out, ",{}", column.get(&y).copied().unwrap_or(""))?;
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk_loader::LoaderOptions, test_fixtures};
+
+    #[test]
+    fn column_from_top_to_bottom() {
+        // Palette index of each Y of column (0, 0), from bottom to top
+        let column = [0, 1, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 4];
+        let section = test_fixtures::section(
+            0,
+            &[
+                "minecraft:bedrock",
+                "minecraft:deepslate",
+                "minecraft:stone",
+                "minecraft:dirt",
+                "minecraft:grass_block",
+            ],
+            |x, y, z| if (x, z) == (0, 0) { column[y] } else { 0 },
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+        let mut chunk_loader = ChunkLoader::with_source(source, None, LoaderOptions::default());
+
+        let sample = core_sample(&mut chunk_loader, 0, 0, None, Dimension::Overworld);
+        assert_eq!(sample.len(), 16);
+        assert_eq!(sample[0], (15, "minecraft:grass_block".to_string()));
+
+        let mut layers: Vec<&str> = sample
+            .iter()
+            .map(|(_, blockstate)| blockstate.as_str())
+            .collect();
+        layers.dedup();
+        assert_eq!(
+            layers,
+            vec![
+                "minecraft:grass_block",
+                "minecraft:dirt",
+                "minecraft:stone",
+                "minecraft:deepslate",
+                "minecraft:bedrock",
+            ]
+        );
+    }
+
+    #[test]
+    fn height_of_the_dimension() {
+        // Stone below Y 0, from Y 0 and above Y 255
+        let sections = [-4, 0, 16]
+            .iter()
+            .map(|&y| test_fixtures::uniform_section(y, "minecraft:stone"))
+            .collect();
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, sections)]);
+        let mut chunk_loader = ChunkLoader::with_source(source, None, LoaderOptions::default());
+
+        let mut ys = |dimension| {
+            let sample = core_sample(&mut chunk_loader, 0, 0, None, dimension);
+            (sample.len(), sample[0].0, sample[sample.len() - 1].0)
+        };
+        assert_eq!(ys(Dimension::Overworld), (48, 271, -64));
+        assert_eq!(ys(Dimension::Nether), (16, 15, 0));
+    }
+
+    #[test]
+    fn samples_side_by_side() {
+        let samples = vec![
+            (
+                (0, 0),
+                vec![
+                    (1, "minecraft:grass_block".to_string()),
+                    (0, "minecraft:dirt".to_string()),
+                ],
+            ),
+            ((5, -3), vec![(0, "minecraft:sand".to_string())]),
+        ];
+
+        let mut csv = Vec::new();
+        write_core_samples(&mut csv, &samples).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Y,X=0 Z=0,X=5 Z=-3\n    1,minecraft:grass_block,\n    0,minecraft:dirt,minecraft:sand\n"
+        );
+    }
+}
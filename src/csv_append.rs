@@ -0,0 +1,159 @@
+use std::{fs, io, path::Path};
+
+/// Rows of a run's results, each a label (e.g. a layer) and one count per column
+pub type CsvRows = Vec<(String, Vec<u64>)>;
+
+/// Appends the results of a run to a CSV file that collects the results of many runs,
+/// with a `Run` column numbering the runs from 1.
+///
+/// Columns that are new in this run are added to the end of the header and filled with zeros
+/// in the rows of earlier runs, which means the file is rewritten as a whole.
+/// Columns this run doesn't have are zero in its rows. Returns the number of the appended run.
+pub fn append_run(path: &Path, label: &str, columns: &[String], rows: CsvRows) -> io::Result<u32> {
+    let existing = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut lines = existing.lines().filter(|line| !line.trim().is_empty());
+
+    let mut header: Vec<String> = match lines.next() {
+        Some(header) => {
+            let mut fields = header.split(',');
+            if fields.next() != Some("Run") || fields.next() != Some(label) {
+                return Err(invalid(format!(
+                    "'{}' doesn't start with the columns Run,{}",
+                    path.display(),
+                    label
+                )));
+            }
+            fields.map(String::from).collect()
+        }
+        None => Vec::new(),
+    };
+
+    // Earlier rows as (run, label, counts in the order of the old header)
+    let mut previous_rows: Vec<(u32, String, Vec<u64>)> = Vec::new();
+    for line in lines {
+        let mut fields = line.split(',').map(str::trim);
+        let run = fields.next().and_then(|run| run.parse().ok());
+        let row_label = fields.next();
+        let counts: Result<Vec<u64>, _> = fields.map(str::parse).collect();
+
+        match (run, row_label, counts) {
+            (Some(run), Some(row_label), Ok(counts)) if counts.len() == header.len() => {
+                previous_rows.push((run, row_label.to_string(), counts))
+            }
+            _ => return Err(invalid(format!("Invalid row '{}'", line))),
+        }
+    }
+
+    for column in columns {
+        if !header.contains(column) {
+            header.push(column.clone());
+        }
+    }
+
+    let run = previous_rows
+        .iter()
+        .map(|(run, _, _)| *run)
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut contents = format!("Run,{}", label);
+    for column in &header {
+        contents.push(',');
+        contents.push_str(column);
+    }
+    contents.push('\n');
+
+    for (previous_run, row_label, mut counts) in previous_rows {
+        counts.resize(header.len(), 0);
+        push_row(&mut contents, previous_run, &row_label, &counts);
+    }
+
+    for (row_label, counts) in rows {
+        let mut aligned = vec![0; header.len()];
+        for (column, count) in columns.iter().zip(counts) {
+            let index = header.iter().position(|name| name == column).unwrap();
+            aligned[index] = count;
+        }
+        push_row(&mut contents, run, &row_label, &aligned);
+    }
+
+    // Write next to the file first, so that a failed write doesn't lose earlier runs
+    let temp_path = path.with_extension("csv.tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)?;
+
+    Ok(run)
+}
+
+fn push_row(contents: &mut String, run: u32, label: &str, counts: &[u64]) {
+    contents.push_str(&format!("{},{}", run, label));
+    for count in counts {
+        contents.push_str(&format!(",{}", count));
+    }
+    contents.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TempDir;
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn second_run_extends_columns() {
+        let dir = TempDir::new();
+        let path = dir.path().join("runs.csv");
+
+        let first = append_run(
+            &path,
+            "Layer",
+            &columns(&["stone", "dirt"]),
+            vec![
+                ("0".to_string(), vec![10, 5]),
+                ("1".to_string(), vec![3, 7]),
+            ],
+        )
+        .unwrap();
+        let second = append_run(
+            &path,
+            "Layer",
+            &columns(&["granite", "dirt"]),
+            vec![("0".to_string(), vec![2, 4])],
+        )
+        .unwrap();
+
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Run,Layer,stone,dirt,granite\n\
+             1,0,10,5,0\n\
+             1,1,3,7,0\n\
+             2,0,0,4,2\n"
+        );
+    }
+
+    #[test]
+    fn other_label_is_rejected() {
+        let dir = TempDir::new();
+        let path = dir.path().join("runs.csv");
+        fs::write(&path, "Run,Chunk,stone\n1,0,10\n").unwrap();
+
+        let result = append_run(&path, "Layer", &columns(&["stone"]), Vec::new());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Run,Chunk,stone\n1,0,10\n"
+        );
+    }
+}
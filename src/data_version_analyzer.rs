@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// `DataVersion` of every release since the tag was introduced in 1.9
+const RELEASE_DATA_VERSIONS: &[(i32, &str)] = &[
+    (169, "1.9"),
+    (175, "1.9.1"),
+    (176, "1.9.2"),
+    (183, "1.9.3"),
+    (184, "1.9.4"),
+    (510, "1.10"),
+    (511, "1.10.1"),
+    (512, "1.10.2"),
+    (819, "1.11"),
+    (921, "1.11.1"),
+    (922, "1.11.2"),
+    (1139, "1.12"),
+    (1241, "1.12.1"),
+    (1343, "1.12.2"),
+    (1519, "1.13"),
+    (1628, "1.13.1"),
+    (1631, "1.13.2"),
+    (1952, "1.14"),
+    (1957, "1.14.1"),
+    (1963, "1.14.2"),
+    (1968, "1.14.3"),
+    (1976, "1.14.4"),
+    (2225, "1.15"),
+    (2227, "1.15.1"),
+    (2230, "1.15.2"),
+    (2566, "1.16"),
+    (2567, "1.16.1"),
+    (2578, "1.16.2"),
+    (2580, "1.16.3"),
+    (2584, "1.16.4"),
+    (2586, "1.16.5"),
+    (2724, "1.17"),
+    (2730, "1.17.1"),
+    (2860, "1.18"),
+    (2865, "1.18.1"),
+    (2975, "1.18.2"),
+    (3105, "1.19"),
+    (3117, "1.19.1"),
+    (3120, "1.19.2"),
+    (3218, "1.19.3"),
+    (3337, "1.19.4"),
+    (3463, "1.20"),
+    (3465, "1.20.1"),
+    (3578, "1.20.2"),
+    (3698, "1.20.3"),
+    (3700, "1.20.4"),
+    (3837, "1.20.5"),
+    (3839, "1.20.6"),
+    (3953, "1.21"),
+    (3955, "1.21.1"),
+    (4080, "1.21.2"),
+    (4082, "1.21.3"),
+    (4189, "1.21.4"),
+];
+
+/// Name of the release that saves chunks with this `DataVersion`, `None` for snapshots and
+/// unknown versions. Chunks without the tag (version 0) were saved before 1.9.
+pub fn version_name(data_version: i32) -> Option<&'static str> {
+    if data_version == 0 {
+        return Some("before 1.9");
+    }
+
+    RELEASE_DATA_VERSIONS
+        .iter()
+        .find(|&&(version, _)| version == data_version)
+        .map(|&(_, name)| name)
+}
+
+/// Counts chunks per `DataVersion`, showing which areas were last saved by which version
+pub struct DataVersionAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// data version -> number of chunks
+    chunks_by_version: HashMap<i32, u32>,
+}
+
+impl<'a> DataVersionAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        DataVersionAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Counts chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        DataVersionAnalyzer {
+            // Blocks aren't needed, so don't decode any sections
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                Some(0..0),
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            chunks_by_version: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let data_version = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.data_version,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            *self.chunks_by_version.entry(data_version).or_insert(0) += 1;
+        }
+    }
+
+    /// Number of chunks of every `DataVersion` found, as `(data_version, release name, chunks)`
+    /// ordered by version
+    pub fn chunk_counts(&self) -> Vec<(i32, Option<&'static str>, u32)> {
+        let mut counts: Vec<(i32, Option<&'static str>, u32)> = self
+            .chunks_by_version
+            .iter()
+            .map(|(&version, &chunks)| (version, version_name(version), chunks))
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
+
+    pub fn print_csv(&self) {
+        println!("DataVersion,Version,Chunks");
+        for (data_version, name, chunks) in self.chunk_counts() {
+            println!("{},{},{:8}", data_version, name.unwrap_or(""), chunks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn chunk_counts_per_version() {
+        // Two 1.16.5 chunks and one 1.18.2 chunk, without any blocks
+        let source = test_fixtures::source(vec![
+            test_fixtures::legacy_chunk(0, 0, 2586, Vec::new()),
+            test_fixtures::legacy_chunk(1, 0, 2586, Vec::new()),
+            test_fixtures::chunk(2, 0, Vec::new()),
+        ]);
+
+        let mut analyzer = DataVersionAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        assert_eq!(
+            analyzer.chunk_counts(),
+            vec![(2586, Some("1.16.5"), 2), (2975, Some("1.18.2"), 1)]
+        );
+    }
+}
@@ -1,8 +1,26 @@
-use std::collections::{HashMap, HashSet};
-
-use image::{ImageBuffer, Rgb, RgbImage};
-
-use crate::{area::Area, chunk::Chunk, chunk_loader::ChunkLoader};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, Write},
+    ops::{Range, RangeInclusive},
+    path::Path,
+};
+
+use image::{imageops, ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    block_id::IdForm,
+    chunk::Chunk,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::ChunkSource,
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+    legend::with_legend,
+    ore_defs::builtin_ore_groups,
+    output_dir::OutputDir,
+};
 
 struct Vein {
     blocks: HashSet<(i64, i32, i64)>,
@@ -10,19 +28,54 @@ struct Vein {
     /// Smallest coordinate in the vein.
     /// Priority: x y z
     location: (i64, i32, i64),
+
+    /// Value of the connecting property shared by all blocks in the vein, if there is one
+    property_value: Option<String>,
 }
 
-const MAX_VEIN_SIZE: usize = 16;
+/// Veins with at least this many blocks aren't counted, unless configured otherwise
+pub const DEFAULT_MAX_VEIN_SIZE: usize = 16;
+
+/// Diamond counts at which the image reaches its darkest color
+const MAX_IMG_DIAMOND_COUNT: u32 = 16;
+
+/// Section Ys that are searched for veins, unless configured otherwise
+pub const DEFAULT_VEIN_SECTIONS: Range<i8> = 0..4;
 
-const DIAMOND_ORES: &[&str] = &["minecraft:diamond_ore", "minecraft:deepslate_diamond_ore"];
+/// Highest Fortune level obtainable in survival
+pub const MAX_FORTUNE: u8 = 3;
+
+/// Average number of items an ore like diamond ore drops when mined with a Fortune pickaxe.
+/// Fortune adds a bonus of 0 to `fortune` items, where the chance of no bonus is `2 / (fortune + 2)`
+/// and each other bonus has a chance of `1 / (fortune + 2)`.
+pub fn expected_drops_per_ore(fortune: u8) -> f64 {
+    let fortune = fortune as f64;
+    1.0 + fortune * (fortune + 1.0) / 2.0 / (fortune + 2.0)
+}
 
 pub struct DiamondVeinAnalyzer<'a> {
     chunk_loader: ChunkLoader<'a>,
 
+    /// Blockstates that make up a vein, e.g. both diamond ore variants
+    ores: Vec<String>,
+
+    /// If set, neighboring blocks are only part of the same vein if they have
+    /// the same value for this blockstate property (e.g. `instrument` for note blocks)
+    connect_property: Option<String>,
+
+    /// Veins with at least this many blocks are ignored
+    max_vein_size: usize,
+
+    /// If set, only ores within these Y levels are counted or connect veins
+    y_range: Option<RangeInclusive<i32>>,
+
     found_veins: HashSet<(i64, i32, i64)>,
 
     /// size -> count
-    vein_count_by_size: HashMap<u8, u32>,
+    vein_count_by_size: HashMap<usize, u32>,
+
+    /// Size and location of the largest vein found so far
+    largest_vein: Option<(usize, (i64, i32, i64))>,
 
     /// height -> count
     vein_count_by_height: HashMap<i16, u32>,
@@ -30,44 +83,56 @@ pub struct DiamondVeinAnalyzer<'a> {
     /// diamond count -> # chunks with that diamond count
     diamonds_per_chunk: HashMap<u8, u32>,
 
+    /// ore blockstate -> count, to tell stone and deepslate diamonds apart
+    diamonds_by_variant: HashMap<String, u32>,
+
+    /// Fortune level to estimate the dropped items for, if any
+    fortune: Option<u8>,
+
     diamond_img: RgbImage,
 
     area: Area,
 }
 
 impl<'a> DiamondVeinAnalyzer<'a> {
-    pub fn new(path: &'a str, area: Area) -> Self {
-        let chunk_loader = ChunkLoader::new(path, Some(0..4));
-
-        let diamond_img: RgbImage = ImageBuffer::from_pixel(
-            area.chunk_width_x(),
-            area.chunk_width_z(),
-            Rgb([255, 255, 255]),
-        );
-
-        DiamondVeinAnalyzer {
-            chunk_loader,
-            found_veins: HashSet::new(),
-            vein_count_by_size: HashMap::new(),
-            vein_count_by_height: HashMap::new(),
-            diamonds_per_chunk: HashMap::new(),
-            diamond_img,
+    /// Configures an analyzer reading chunks from any source.
+    /// Everything that isn't set explicitly has the same default as on the command line.
+    pub fn builder<S: ChunkSource + 'a>(source: S, area: Area) -> DiamondVeinAnalyzerBuilder<'a> {
+        DiamondVeinAnalyzerBuilder {
+            source: Box::new(source),
             area,
+            ores: builtin_ore_groups().remove("diamond").unwrap_or_default(),
+            connect_property: None,
+            max_vein_size: DEFAULT_MAX_VEIN_SIZE,
+            y_range: None,
+            fortune: None,
+            loader_options: LoaderOptions::default(),
         }
     }
 
+    /// Number of ore blocks found so far, of all variants
+    pub fn ore_count(&self) -> u32 {
+        self.diamonds_by_variant.values().sum()
+    }
+
+    /// Average number of items all ore blocks found so far drop when mined with the configured
+    /// Fortune level, `None` if no Fortune level was configured
+    pub fn expected_yield(&self) -> Option<f64> {
+        let fortune = self.fortune?;
+        Some(self.ore_count() as f64 * expected_drops_per_ore(fortune))
+    }
+
     pub fn clean_found_veins(&mut self, (x, z): (i64, i64)) {
-        self.found_veins = self
-            .found_veins
-            .iter()
-            .filter(|&(lx, _, lz)| *lx < x && *lz < z)
-            .cloned()
-            .collect();
+        self.found_veins.retain(|&(lx, _, lz)| lx < x && lz < z);
     }
 
-    pub fn analyze(&mut self) {
-        for (chunk_x, chunk_z) in self.area {
-            let chunk = self.chunk_loader.get_or_load(chunk_x, chunk_z).clone();
+    /// All chunks need to lie within the area the analyzer was created with
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
             let chunk_pos = chunk.get_global_pos();
 
             eprintln!(
@@ -101,12 +166,12 @@ impl<'a> DiamondVeinAnalyzer<'a> {
 
         for section in chunk {
             for block in section {
-                if DIAMOND_ORES.contains(&block.blockstate.as_str()) {
-                    let (x, y, z) = block.global_pos;
-
+                let (x, y, z) = block.global_pos;
+                if self.ores.contains(&block.blockstate) && self.in_y_range(y) {
                     let vein = Vein {
                         blocks: HashSet::new(),
                         location: (x, y, z),
+                        property_value: self.get_connect_property_at(x, y, z),
                     };
 
                     if let Some(vein) = self.explore_vein(vein, x, y, z) {
@@ -114,10 +179,13 @@ impl<'a> DiamondVeinAnalyzer<'a> {
                             self.found_veins.insert(pos);
                         });
 
-                        *self
-                            .vein_count_by_size
-                            .entry(vein.blocks.len() as u8)
-                            .or_insert(0) += 1;
+                        let size = vein.blocks.len();
+
+                        *self.vein_count_by_size.entry(size).or_insert(0) += 1;
+
+                        if self.largest_vein.is_none_or(|(largest, _)| size > largest) {
+                            self.largest_vein = Some((size, vein.location));
+                        }
 
                         *self
                             .vein_count_by_height
@@ -125,6 +193,11 @@ impl<'a> DiamondVeinAnalyzer<'a> {
                             .or_insert(0) += 1;
                     }
 
+                    *self
+                        .diamonds_by_variant
+                        .entry(block.blockstate.clone())
+                        .or_insert(0) += 1;
+
                     diamond_count += 1;
                 }
             }
@@ -134,7 +207,7 @@ impl<'a> DiamondVeinAnalyzer<'a> {
     }
 
     fn explore_vein(&mut self, mut vein: Vein, x: i64, y: i32, z: i64) -> Option<Vein> {
-        if vein.blocks.len() >= MAX_VEIN_SIZE || self.found_veins.contains(&(x, y, z)) {
+        if vein.blocks.len() >= self.max_vein_size {
             return None;
         }
 
@@ -143,7 +216,16 @@ impl<'a> DiamondVeinAnalyzer<'a> {
         }
 
         if let Some(block) = self.chunk_loader.get_blockstate_at(x, y, z) {
-            if DIAMOND_ORES.contains(&block) {
+            // Ores outside the Y range are boundaries like any other block
+            let is_ore = self.ores.iter().any(|ore| ore == block) && self.in_y_range(y);
+
+            if is_ore && self.get_connect_property_at(x, y, z) == vein.property_value {
+                // Part of a vein that was already counted. Found blocks with a different
+                // property value belong to another vein and are only a boundary.
+                if self.found_veins.contains(&(x, y, z)) {
+                    return None;
+                }
+
                 vein.blocks.insert((x, y, z));
                 vein.location = min_coord(vein.location, (x, y, z));
 
@@ -164,21 +246,59 @@ impl<'a> DiamondVeinAnalyzer<'a> {
         }
     }
 
-    pub fn print_csv(&self) {
+    fn in_y_range(&self, y: i32) -> bool {
+        self.y_range
+            .as_ref()
+            .is_none_or(|y_range| y_range.contains(&y))
+    }
+
+    fn get_connect_property_at(&mut self, x: i64, y: i32, z: i64) -> Option<String> {
+        let property = self.connect_property.as_ref()?;
+
+        self.chunk_loader
+            .get_properties_at(x, y, z)?
+            .get(property)
+            .cloned()
+    }
+
+    pub fn print_csv(&self, id_form: IdForm) {
+        self.write_csv(&mut io::stdout().lock(), id_form).unwrap();
+    }
+
+    pub fn write_csv<W: Write>(&self, out: &mut W, id_form: IdForm) -> io::Result<()> {
+        writeln!(out, "Ore,Count")?;
+        for ore in &self.ores {
+            let count = self.diamonds_by_variant.get(ore).unwrap_or(&0);
+            writeln!(out, "{},{:8}", id_form.render(ore), count)?;
+        }
+        writeln!(out)?;
+
+        if let (Some(fortune), Some(expected_yield)) = (self.fortune, self.expected_yield()) {
+            writeln!(out, "Fortune,Drops per ore,Expected yield")?;
+            writeln!(
+                out,
+                "{},{:.3},{:.1}",
+                fortune,
+                expected_drops_per_ore(fortune),
+                expected_yield
+            )?;
+            writeln!(out)?;
+        }
+
         eprintln!("Printing number of diamonds / chunk");
 
-        println!("Number of diamonds,Chunks");
+        writeln!(out, "Number of diamonds,Chunks")?;
         let mut diamonds_per_chunk: Vec<(&u8, &u32)> = self.diamonds_per_chunk.iter().collect();
         diamonds_per_chunk.sort_unstable();
 
         for (diamonds, chunks) in diamonds_per_chunk {
-            println!("{:8},{:8}", diamonds, chunks);
+            writeln!(out, "{:8},{:8}", diamonds, chunks)?;
         }
-        println!();
+        writeln!(out)?;
 
         eprintln!("Preparing to print diamond vein tables...");
 
-        let mut sizes: Vec<(&u8, &u32)> = self.vein_count_by_size.iter().collect();
+        let mut sizes: Vec<(&usize, &u32)> = self.vein_count_by_size.iter().collect();
         sizes.sort_unstable();
         sizes.dedup();
 
@@ -188,41 +308,111 @@ impl<'a> DiamondVeinAnalyzer<'a> {
 
         eprintln!("Printing diamond vein size table...");
 
-        println!("Vein Size,Vein Count");
+        writeln!(out, "Vein Size,Vein Count")?;
         for (size, count) in sizes {
-            println!("{:8},{:8}", size, count)
+            writeln!(out, "{:8},{:8}", size, count)?;
         }
-        println!();
+        writeln!(out)?;
 
         eprintln!("Printing diamond vein height table...");
 
-        println!("Vein Height,Vein Count");
+        writeln!(out, "Vein Height,Vein Count")?;
         for (height, count) in heights {
-            println!("{:8},{:8}", height, count);
+            writeln!(out, "{:8},{:8}", height, count)?;
+        }
+
+        if let Some((size, (x, y, z))) = self.largest_vein {
+            writeln!(out)?;
+            writeln!(out, "Largest Vein Size,X,Y,Z")?;
+            writeln!(out, "{:8},{},{},{}", size, x, y, z)?;
         }
 
         eprintln!("Done printing CSV!");
+
+        Ok(())
     }
 
     pub fn update_img(&mut self, chunk_x: i32, chunk_z: i32, diamond_count: u8) {
         let (x, y) = self.area.get_positive_coords(chunk_x, chunk_z);
         let y = self.area.chunk_width_z() - y - 1;
 
-        let brightness = 255u32.saturating_sub(diamond_count as u32 * 16) as u8;
-        let pixel = Rgb([0, 0, brightness]);
+        self.diamond_img
+            .put_pixel(x, y, diamond_color(diamond_count as u32));
+    }
 
-        self.diamond_img.put_pixel(x, y, pixel);
+    /// With a tile size, the map is split into tiles as described in `save_tiles`, without a legend
+    /// or grid. Otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    /// Writes the CSV as `veins.csv` and the image as `veins.<extension>` into the output folder
+    pub fn save_to_dir(
+        &self,
+        output_dir: &OutputDir,
+        id_form: IdForm,
+        legend: bool,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) -> io::Result<()> {
+        let csv_path = output_dir.file("veins.csv");
+        let mut out = io::BufWriter::new(File::create(csv_path)?);
+        self.write_csv(&mut out, id_form)?;
+        out.flush()?;
+
+        let img_path = output_dir.file(&format!("veins.{}", format.extension()));
+        self.print_img(img_path.to_str().unwrap(), legend, format, tile_size, grid);
+        Ok(())
     }
 
-    pub fn print_img(&self, path: &str) {
+    pub fn print_img(
+        &self,
+        path: &str,
+        legend: bool,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
         eprintln!("Saving image...");
 
-        self.diamond_img.save(path).unwrap();
+        if let Some(tile_size) = tile_size {
+            let img = &self.diamond_img;
+            save_tiles(
+                Path::new(path),
+                img.dimensions(),
+                tile_size,
+                format,
+                |x, y, width, height| imageops::crop_imm(img, x, y, width, height).to_image(),
+            )
+            .unwrap();
+        } else {
+            let mut img = self.diamond_img.clone();
+            if let Some(spacing) = grid {
+                draw_grid(&mut img, &self.area, spacing);
+            }
+
+            if legend {
+                let values: Vec<u32> = (0..=MAX_IMG_DIAMOND_COUNT).step_by(2).collect();
+                img = with_legend(&img, &values, diamond_color);
+            }
+            save_image(&img, path, format).unwrap();
+        }
 
         eprintln!("Done printing image!");
     }
 }
 
+fn diamond_color(diamond_count: u32) -> Rgb<u8> {
+    let brightness = 255u32.saturating_sub(diamond_count * 256 / MAX_IMG_DIAMOND_COUNT) as u8;
+    Rgb([0, 0, brightness])
+}
+
+/// Section Ys containing any of the Y levels
+fn sections_overlapping(y_range: &RangeInclusive<i32>) -> Range<i8> {
+    let section_y = |y: i32| {
+        y.div_euclid(CHUNK_SIZE as i32)
+            .clamp(i8::MIN as i32, i8::MAX as i32) as i8
+    };
+    section_y(*y_range.start())..section_y(*y_range.end()).saturating_add(1)
+}
+
 fn min_coord(a: (i64, i32, i64), b: (i64, i32, i64)) -> (i64, i32, i64) {
     if a.0 < b.0 || a.1 < b.1 || a.2 < b.2 {
         a
@@ -230,3 +420,344 @@ fn min_coord(a: (i64, i32, i64), b: (i64, i32, i64)) -> (i64, i32, i64) {
         b
     }
 }
+
+/// Configures a `DiamondVeinAnalyzer`, see `DiamondVeinAnalyzer::builder`
+pub struct DiamondVeinAnalyzerBuilder<'a> {
+    source: Box<dyn ChunkSource + 'a>,
+    area: Area,
+    ores: Vec<String>,
+    connect_property: Option<String>,
+    max_vein_size: usize,
+    y_range: Option<RangeInclusive<i32>>,
+    fortune: Option<u8>,
+    loader_options: LoaderOptions,
+}
+
+impl<'a> DiamondVeinAnalyzerBuilder<'a> {
+    /// Blockstates that make up a vein, diamond ore in both variants by default
+    pub fn ores(mut self, ores: Vec<String>) -> Self {
+        self.ores = ores;
+        self
+    }
+
+    /// Only connects neighboring blocks with the same value for this blockstate property
+    pub fn connect_property(mut self, property: String) -> Self {
+        self.connect_property = Some(property);
+        self
+    }
+
+    /// Veins with at least this many blocks are ignored, `DEFAULT_MAX_VEIN_SIZE` by default
+    pub fn max_vein_size(mut self, max_vein_size: usize) -> Self {
+        self.max_vein_size = max_vein_size;
+        self
+    }
+
+    /// Only counts ores within these Y levels, e.g. to leave out ores placed near the surface.
+    /// Veins don't continue outside the range, and only the sections overlapping it are searched,
+    /// instead of `DEFAULT_VEIN_SECTIONS`.
+    pub fn y_range(mut self, y_range: RangeInclusive<i32>) -> Self {
+        self.y_range = Some(y_range);
+        self
+    }
+
+    /// Also estimates the items the ores drop when mined with this Fortune level,
+    /// see `expected_yield`
+    pub fn fortune(mut self, fortune: u8) -> Self {
+        self.fortune = Some(fortune);
+        self
+    }
+
+    pub fn loader_options(mut self, loader_options: LoaderOptions) -> Self {
+        self.loader_options = loader_options;
+        self
+    }
+
+    pub fn build(self) -> DiamondVeinAnalyzer<'a> {
+        let area = self.area;
+        let diamond_img: RgbImage = ImageBuffer::from_pixel(
+            area.chunk_width_x(),
+            area.chunk_width_z(),
+            Rgb([255, 255, 255]),
+        );
+
+        let sections = match &self.y_range {
+            Some(y_range) => sections_overlapping(y_range),
+            None => DEFAULT_VEIN_SECTIONS,
+        };
+
+        DiamondVeinAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                self.source,
+                Some(sections),
+                self.loader_options,
+            ),
+            ores: self.ores,
+            connect_property: self.connect_property,
+            max_vein_size: self.max_vein_size,
+            y_range: self.y_range,
+            found_veins: HashSet::new(),
+            vein_count_by_size: HashMap::new(),
+            largest_vein: None,
+            vein_count_by_height: HashMap::new(),
+            diamonds_per_chunk: HashMap::new(),
+            diamonds_by_variant: HashMap::new(),
+            fortune: self.fortune,
+            diamond_img,
+            area,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image_output::to_svg,
+        test_fixtures::{self, TempDir},
+    };
+
+    /// Stone with diamond ore at the given blocks of section Y 0
+    fn diamonds(ores: &'static [(usize, usize, usize)]) -> impl ChunkSource {
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:diamond_ore"],
+            |x, y, z| ores.contains(&(x, y, z)) as usize,
+        );
+        test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])])
+    }
+
+    /// Number of veins found, as summed up in the vein size table
+    fn vein_count(analyzer: &DiamondVeinAnalyzer) -> u32 {
+        analyzer.vein_count_by_size.values().sum()
+    }
+
+    #[test]
+    fn expected_drops() {
+        assert_eq!(expected_drops_per_ore(0), 1.0);
+        assert!((expected_drops_per_ore(3) - 2.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn expected_yield_with_fortune() {
+        let area = Area::new(0, 1, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(diamonds(&[(0, 0, 0)]), area)
+            .ores(vec!["minecraft:diamond_ore".to_string()])
+            .max_vein_size(32)
+            .fortune(3)
+            .build();
+        analyzer.analyze(area);
+
+        assert_eq!(vein_count(&analyzer), 1);
+        // A single ore drops 2.2 diamonds on average with Fortune III
+        assert!((analyzer.expected_yield().unwrap() - 2.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn y_range_leaves_out_ores_and_vein_parts() {
+        // Diamond ore at Y 2 and right above it at Y 3
+        let area = Area::new(0, 1, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(diamonds(&[(0, 2, 0), (0, 3, 0)]), area)
+            .ores(vec!["minecraft:diamond_ore".to_string()])
+            .y_range(3..=10)
+            .build();
+        analyzer.analyze(area);
+
+        // The ore at Y 2 is neither counted nor part of the vein
+        assert_eq!(analyzer.ore_count(), 1);
+        assert_eq!(vein_count(&analyzer), 1);
+    }
+
+    #[test]
+    fn variant_split() {
+        // One diamond ore in stone and one deepslate diamond ore further away, in separate veins
+        let section = test_fixtures::section(
+            0,
+            &[
+                "minecraft:stone",
+                "minecraft:diamond_ore",
+                "minecraft:deepslate_diamond_ore",
+            ],
+            |x, y, z| match (x, y, z) {
+                (1, 1, 1) => 1,
+                (10, 1, 10) => 2,
+                _ => 0,
+            },
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+        let area = Area::new(0, 1, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(source, area).build();
+        analyzer.analyze(area);
+
+        assert_eq!(
+            analyzer.diamonds_by_variant,
+            HashMap::from([
+                ("minecraft:diamond_ore".to_string(), 1),
+                ("minecraft:deepslate_diamond_ore".to_string(), 1),
+            ])
+        );
+        assert_eq!(vein_count(&analyzer), 2);
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv, IdForm::Short).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(
+            csv.starts_with("Ore,Count\ndiamond_ore,       1\ndeepslate_diamond_ore,       1\n")
+        );
+    }
+
+    #[test]
+    fn note_blocks_connected_by_instrument() {
+        // Three note blocks in a row, the last one on a different instrument
+        let palette = vec![
+            test_fixtures::palette_entry("minecraft:air", &[]),
+            test_fixtures::palette_entry("minecraft:note_block", &[("instrument", "harp")]),
+            test_fixtures::palette_entry("minecraft:note_block", &[("instrument", "bass")]),
+        ];
+        let section = test_fixtures::section_with_palette(0, palette, |x, y, z| match (x, y, z) {
+            (0, 0, 0) | (1, 0, 0) => 1,
+            (2, 0, 0) => 2,
+            _ => 0,
+        });
+        let chunks = vec![test_fixtures::chunk(0, 0, vec![section])];
+        let area = Area::new(0, 1, 0, 1);
+        let note_blocks = vec!["minecraft:note_block".to_string()];
+
+        let mut analyzer =
+            DiamondVeinAnalyzer::builder(test_fixtures::source(chunks.clone()), area)
+                .ores(note_blocks.clone())
+                .build();
+        analyzer.analyze(area);
+        assert_eq!(vein_count(&analyzer), 1);
+
+        let mut analyzer = DiamondVeinAnalyzer::builder(test_fixtures::source(chunks), area)
+            .ores(note_blocks)
+            .connect_property("instrument".to_string())
+            .build();
+        analyzer.analyze(area);
+        assert_eq!(analyzer.ore_count(), 3);
+        assert_eq!(vein_count(&analyzer), 2);
+    }
+
+    #[test]
+    fn svg_heatmap() {
+        // One diamond in chunk (0, 0), none in chunk (1, 0) and chunk (2, 0) missing
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(
+                0,
+                0,
+                vec![test_fixtures::section(
+                    0,
+                    &["minecraft:stone", "minecraft:diamond_ore"],
+                    |x, y, z| ((x, y, z) == (0, 0, 0)) as usize,
+                )],
+            ),
+            test_fixtures::chunk(
+                1,
+                0,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            ),
+        ]);
+        let area = Area::new(0, 3, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(source, area).build();
+        analyzer.analyze(area);
+
+        let svg = to_svg(&analyzer.diamond_img);
+        let fills: Vec<&str> = svg
+            .lines()
+            .filter(|line| line.starts_with("<rect"))
+            .map(|line| {
+                line.split("fill=\"")
+                    .nth(1)
+                    .unwrap()
+                    .trim_end_matches("\"/>")
+            })
+            .collect();
+
+        let [_, _, one] = diamond_color(1).0;
+        assert_eq!(
+            fills,
+            vec![format!("#0000{:02x}", one).as_str(), "#0000ff", "#ffffff"]
+        );
+    }
+
+    #[test]
+    fn id_forms() {
+        let area = Area::new(0, 1, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(diamonds(&[(0, 0, 0)]), area)
+            .ores(vec!["minecraft:diamond_ore".to_string()])
+            .build();
+        analyzer.analyze(area);
+
+        let first_row = |id_form| {
+            let mut csv = Vec::new();
+            analyzer.write_csv(&mut csv, id_form).unwrap();
+            String::from_utf8(csv)
+                .unwrap()
+                .lines()
+                .nth(1)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(first_row(IdForm::Short), "diamond_ore,       1");
+        assert_eq!(first_row(IdForm::Full), "minecraft:diamond_ore,       1");
+    }
+
+    #[test]
+    fn csv_and_image_in_output_dir() {
+        let area = Area::new(0, 1, 0, 1);
+        let mut analyzer = DiamondVeinAnalyzer::builder(diamonds(&[(0, 0, 0)]), area).build();
+        analyzer.analyze(area);
+
+        // Like `--output-dir out/`, which doesn't exist yet
+        let folder = TempDir::new();
+        let output_dir = OutputDir::create(&folder.path().join("out")).unwrap();
+        analyzer
+            .save_to_dir(
+                &output_dir,
+                IdForm::Full,
+                false,
+                ImageFormat::Png,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let csv = std::fs::read_to_string(output_dir.file("veins.csv")).unwrap();
+        assert!(csv.starts_with("Ore,Count\nminecraft:diamond_ore,       1\n"));
+        let img = image::open(output_dir.file("veins.png")).unwrap();
+        assert_eq!(img.to_rgb8().dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn largest_vein_with_raised_cap() {
+        // A 5x5 field of diamond ore at Y 2, and a vein of 2 further away
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:diamond_ore"],
+            |x, y, z| {
+                let field = y == 2 && (3..8).contains(&x) && (4..9).contains(&z);
+                let small = y == 10 && z == 14 && (12..14).contains(&x);
+                (field || small) as usize
+            },
+        );
+        let chunks = vec![test_fixtures::chunk(0, 0, vec![section])];
+        let area = Area::new(0, 1, 0, 1);
+
+        // With the default cap, the field is too large to count as a vein
+        let mut analyzer =
+            DiamondVeinAnalyzer::builder(test_fixtures::source(chunks.clone()), area).build();
+        analyzer.analyze(area);
+        assert_eq!(analyzer.largest_vein, Some((2, (12, 10, 14))));
+
+        let mut analyzer = DiamondVeinAnalyzer::builder(test_fixtures::source(chunks), area)
+            .max_vein_size(32)
+            .build();
+        analyzer.analyze(area);
+        assert_eq!(analyzer.largest_vein, Some((25, (3, 2, 4))));
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv, IdForm::Full).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.ends_with("Largest Vein Size,X,Y,Z\n      25,3,2,4\n"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use crate::{artificial_block_analyzer::NATURAL_BLOCKS, chunk::NEGATIVE_Y_DATA_VERSION};
+
+/// The vanilla dimensions of a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+    End,
+}
+
+const NETHER_NATURAL_BLOCKS: &[&str] = &[
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:bedrock",
+    "minecraft:netherrack",
+    "minecraft:soul_sand",
+    "minecraft:soul_soil",
+    "minecraft:basalt",
+    "minecraft:blackstone",
+    "minecraft:magma_block",
+    "minecraft:glowstone",
+    "minecraft:gravel",
+    "minecraft:lava",
+    "minecraft:fire",
+    "minecraft:soul_fire",
+    "minecraft:crimson_nylium",
+    "minecraft:warped_nylium",
+    "minecraft:crimson_stem",
+    "minecraft:warped_stem",
+    "minecraft:nether_wart_block",
+    "minecraft:warped_wart_block",
+    "minecraft:shroomlight",
+    "minecraft:crimson_roots",
+    "minecraft:warped_roots",
+    "minecraft:nether_sprouts",
+    "minecraft:crimson_fungus",
+    "minecraft:warped_fungus",
+    "minecraft:weeping_vines",
+    "minecraft:weeping_vines_plant",
+    "minecraft:twisting_vines",
+    "minecraft:twisting_vines_plant",
+    "minecraft:brown_mushroom",
+    "minecraft:red_mushroom",
+];
+
+const END_NATURAL_BLOCKS: &[&str] = &[
+    "minecraft:air",
+    "minecraft:end_stone",
+    "minecraft:obsidian",
+    "minecraft:bedrock",
+    "minecraft:chorus_plant",
+    "minecraft:chorus_flower",
+];
+
+impl Dimension {
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
+    /// Name of the dimension without the `minecraft:` namespace, e.g. `the_nether`
+    pub fn name(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "the_nether",
+            Dimension::End => "the_end",
+        }
+    }
+
+    /// Folder of the dimension's region files inside the world folder
+    pub fn region_path(self, world_root: &Path) -> PathBuf {
+        match self {
+            Dimension::Overworld => world_root.join("region"),
+            Dimension::Nether => world_root.join("DIM-1").join("region"),
+            Dimension::End => world_root.join("DIM1").join("region"),
+        }
+    }
+
+    /// Dimension of a region folder, judged by the folder it's in: `DIM-1` for the nether,
+    /// `DIM1` for the end and the overworld otherwise
+    pub fn of_region_folder(region_folder: &Path) -> Self {
+        let parent = region_folder
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str());
+
+        match parent {
+            Some("DIM-1") => Dimension::Nether,
+            Some("DIM1") => Dimension::End,
+            _ => Dimension::Overworld,
+        }
+    }
+
+    /// Y coordinates of the lowest and highest block of the dimension since 1.18
+    pub fn default_y_range(self) -> RangeInclusive<i32> {
+        match self {
+            Dimension::Overworld => -64..=319,
+            Dimension::Nether | Dimension::End => 0..=255,
+        }
+    }
+
+    /// Y coordinates of the lowest and highest block of the dimension in chunks saved with
+    /// the given data version. The overworld only went from 0 to 255 before 1.18.
+    pub fn y_range(self, data_version: i32) -> RangeInclusive<i32> {
+        if data_version >= NEGATIVE_Y_DATA_VERSION {
+            self.default_y_range()
+        } else {
+            0..=255
+        }
+    }
+
+    /// Blocks that are considered part of the dimension's naturally generated terrain
+    pub fn natural_blocks(self) -> &'static [&'static str] {
+        match self {
+            Dimension::Overworld => NATURAL_BLOCKS,
+            Dimension::Nether => NETHER_NATURAL_BLOCKS,
+            Dimension::End => END_NATURAL_BLOCKS,
+        }
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_paths() {
+        let root = Path::new("saves/world");
+        assert_eq!(Dimension::Overworld.region_path(root), root.join("region"));
+        assert_eq!(
+            Dimension::Nether.region_path(root),
+            Path::new("saves/world/DIM-1/region")
+        );
+        assert_eq!(
+            Dimension::End.region_path(root),
+            Path::new("saves/world/DIM1/region")
+        );
+    }
+
+    #[test]
+    fn default_y_ranges() {
+        assert_eq!(Dimension::Overworld.default_y_range(), -64..=319);
+        assert_eq!(Dimension::Nether.default_y_range(), 0..=255);
+        assert_eq!(Dimension::End.default_y_range(), 0..=255);
+    }
+
+    #[test]
+    fn y_range_by_version() {
+        assert_eq!(Dimension::Overworld.y_range(2975), -64..=319);
+        assert_eq!(Dimension::Overworld.y_range(2586), 0..=255);
+        assert_eq!(Dimension::Nether.y_range(2975), 0..=255);
+    }
+
+    #[test]
+    fn dimension_of_region_folder() {
+        let world = Path::new("saves/world");
+        for dimension in Dimension::ALL {
+            assert_eq!(
+                Dimension::of_region_folder(&dimension.region_path(world)),
+                dimension
+            );
+        }
+    }
+}
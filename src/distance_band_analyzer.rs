@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Counts blocks by their horizontal distance from a center point, in bands of equal width
+pub struct DistanceBandAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Block coordinates (x, z) distances are measured from
+    center: (i64, i64),
+
+    /// Width of a distance band in blocks
+    band_width: u32,
+
+    /// blockstate -> band index -> count
+    counts: HashMap<String, HashMap<u32, u32>>,
+}
+
+impl<'a> DistanceBandAnalyzer<'a> {
+    pub fn new(
+        path: &'a str,
+        loader_options: LoaderOptions,
+        center: (i64, i64),
+        band_width: u32,
+    ) -> Self {
+        DistanceBandAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            loader_options,
+            center,
+            band_width,
+        )
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        loader_options: LoaderOptions,
+        center: (i64, i64),
+        band_width: u32,
+    ) -> Self {
+        assert!(band_width > 0, "Band width must be positive");
+
+        DistanceBandAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            center,
+            band_width,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Index of the band a block with the given horizontal coordinates falls into
+    pub fn band_of(&self, x: i64, z: i64) -> u32 {
+        let (dx, dz) = ((x - self.center.0) as f64, (z - self.center.1) as f64);
+        let distance = (dx * dx + dz * dz).sqrt();
+
+        (distance / self.band_width as f64) as u32
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for block in chunk.into_iter().flatten() {
+                let (x, _, z) = block.global_pos;
+                let band = self.band_of(x, z);
+
+                *self
+                    .counts
+                    .entry(block.blockstate)
+                    .or_default()
+                    .entry(band)
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let band_count = self
+            .counts
+            .values()
+            .flat_map(|bands| bands.keys())
+            .max()
+            .map_or(0, |&band| band + 1);
+
+        let mut blockstates: Vec<(&String, u32)> = self
+            .counts
+            .iter()
+            .map(|(blockstate, bands)| (blockstate, bands.values().sum()))
+            .collect();
+        blockstates.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        print!("Blockstate");
+        for band in 0..band_count {
+            print!(
+                ",{}-{}",
+                band * self.band_width,
+                (band + 1) * self.band_width
+            );
+        }
+        println!();
+
+        for (blockstate, _) in blockstates {
+            print!("{}", blockstate);
+            for band in 0..band_count {
+                print!(",{:8}", self.counts[blockstate].get(&band).unwrap_or(&0));
+            }
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn block_at_distance_150() {
+        // A diamond ore at X 150, Z 0 in a chunk of stone
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:diamond_ore"],
+            |x, y, z| ((x, y, z) == (6, 0, 0)) as usize,
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(9, 0, vec![section])]);
+        let mut analyzer =
+            DistanceBandAnalyzer::with_source(source, LoaderOptions::default(), (0, 0), 100);
+        analyzer.analyze(vec![(9, 0)]);
+
+        assert_eq!(analyzer.band_of(150, 0), 1);
+        assert_eq!(analyzer.band_of(90, -120), 1);
+        assert_eq!(analyzer.band_of(99, 0), 0);
+        assert_eq!(analyzer.band_of(200, 0), 2);
+        assert_eq!(
+            analyzer.counts["minecraft:diamond_ore"],
+            HashMap::from([(1, 1)])
+        );
+    }
+}
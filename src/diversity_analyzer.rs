@@ -0,0 +1,200 @@
+use std::{collections::HashSet, path::Path};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+};
+
+const MISSING_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Counts the distinct blockstates in every chunk. Plain terrain only has a handful of them,
+/// while builds, structures and varied terrain stand out with many more.
+pub struct DiversityAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    area: Area,
+
+    /// Chunk coordinates and number of distinct blockstates, in the order they were scanned
+    diversity: Vec<((i32, i32), usize)>,
+}
+
+impl<'a> DiversityAnalyzer<'a> {
+    pub fn new(path: &'a str, area: Area, loader_options: LoaderOptions) -> Self {
+        DiversityAnalyzer::with_source(RegionFolderSource::new(path), area, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        DiversityAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            area,
+            diversity: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let blockstates: HashSet<String> = chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .flat_map(|section| section.blocks().map(|block| block.blockstate))
+                .collect();
+
+            self.diversity.push(((chunk_x, chunk_z), blockstates.len()));
+        }
+    }
+
+    pub fn print_csv(&self) {
+        println!("X,Z,Distinct blockstates");
+        for ((x, z), count) in &self.diversity {
+            println!("{},{},{}", x, z, count);
+        }
+    }
+
+    /// Saves a heatmap with one pixel per chunk, brighter the more distinct blockstates it has
+    /// relative to the most diverse chunk. Chunks that weren't analyzed are black.
+    /// With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, diversity) = (&self.area, self.diversity.as_slice());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, diversity, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, diversity, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+/// Renders the part of the heatmap with its top left corner at `(x, y)`
+fn render(
+    area: &Area,
+    diversity: &[((i32, i32), usize)],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, MISSING_COLOR);
+    let map_height = area.chunk_width_z();
+    let max_count = diversity
+        .iter()
+        .map(|&(_, count)| count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    for &((chunk_x, chunk_z), count) in diversity {
+        let (pixel_x, pixel_z) = area.get_positive_coords(chunk_x, chunk_z);
+        if pixel_z >= map_height {
+            continue;
+        }
+
+        let pixel_y = map_height - pixel_z - 1;
+        if (x..x + width).contains(&pixel_x) && (y..y + height).contains(&pixel_y) {
+            let brightness = (count * u8::MAX as usize / max_count) as u8;
+            img.put_pixel(
+                pixel_x - x,
+                pixel_y - y,
+                Rgb([brightness, brightness, brightness]),
+            );
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn plain_and_decorated_chunks() {
+        // Stone below dirt and grass
+        let plain = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:dirt", "minecraft:grass_block"],
+            |_, y, _| match y {
+                0..=11 => 0,
+                12..=14 => 1,
+                _ => 2,
+            },
+        );
+        // The same with a build of twelve different blocks on top
+        let build = [
+            "minecraft:oak_planks",
+            "minecraft:oak_log",
+            "minecraft:glass",
+            "minecraft:cobblestone",
+            "minecraft:torch",
+            "minecraft:chest",
+            "minecraft:crafting_table",
+            "minecraft:furnace",
+            "minecraft:white_bed",
+            "minecraft:oak_door",
+            "minecraft:bookshelf",
+            "minecraft:lantern",
+        ];
+        let mut names = vec!["minecraft:air"];
+        names.extend_from_slice(&build);
+        let decorated = test_fixtures::section(1, &names, |x, y, z| {
+            if y == 0 && z == 0 && x < build.len() {
+                x + 1
+            } else {
+                0
+            }
+        });
+
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(0, 0, vec![plain.clone()]),
+            test_fixtures::chunk(1, 0, vec![plain, decorated]),
+        ]);
+        let area = Area::new(0, 2, 0, 1);
+        let mut analyzer = DiversityAnalyzer::with_source(source, area, LoaderOptions::default());
+        analyzer.analyze(area);
+
+        assert_eq!(analyzer.diversity, vec![((0, 0), 3), ((1, 0), 16)]);
+
+        // The decorated chunk is the brightest, the plain one much darker
+        let img = render(&analyzer.area, &analyzer.diversity, (0, 0), (2, 1));
+        assert_eq!(*img.get_pixel(1, 0), Rgb([255, 255, 255]));
+        assert_eq!(*img.get_pixel(0, 0), Rgb([47, 47, 47]));
+    }
+}
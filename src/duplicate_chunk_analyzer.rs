@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Neighbors a chunk is compared with, sharing a side with it
+const NEIGHBOR_OFFSETS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Finds neighboring chunks with the same composition fingerprint (see
+/// `Chunk::composition_fingerprint`), which are typical for pasted pieces and superflat test areas
+/// but rare in generated terrain. Unlike `FingerprintAnalyzer`, only adjacent chunks are matched.
+pub struct DuplicateChunkAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Fingerprint of every chunk scanned so far
+    fingerprints: HashMap<(i32, i32), u64>,
+
+    /// Pairs of adjacent chunks with the same fingerprint, the later scanned one second
+    duplicate_pairs: Vec<((i32, i32), (i32, i32))>,
+}
+
+impl<'a> DuplicateChunkAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        DuplicateChunkAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Compares chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        DuplicateChunkAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            fingerprints: HashMap::new(),
+            duplicate_pairs: Vec::new(),
+        }
+    }
+
+    /// Every chunk is compared with those of its neighbors that were already scanned,
+    /// so every pair of neighbors is compared once regardless of the scan order
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let fingerprint = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.composition_fingerprint(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for (dx, dz) in NEIGHBOR_OFFSETS {
+                let neighbor = (chunk_x + dx, chunk_z + dz);
+                if self.fingerprints.get(&neighbor) == Some(&fingerprint) {
+                    self.duplicate_pairs.push((neighbor, (chunk_x, chunk_z)));
+                }
+            }
+
+            self.fingerprints.insert((chunk_x, chunk_z), fingerprint);
+        }
+    }
+
+    /// Groups of chunks connected by identical neighbors, as `(fingerprint, chunks)`,
+    /// largest groups first. Chunks within a group are sorted by X, then Z.
+    pub fn runs(&self) -> Vec<(u64, Vec<(i32, i32)>)> {
+        let mut neighbors: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+        for &(a, b) in &self.duplicate_pairs {
+            neighbors.entry(a).or_default().push(b);
+            neighbors.entry(b).or_default().push(a);
+        }
+
+        let mut starts: Vec<(i32, i32)> = neighbors.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut visited: HashSet<(i32, i32)> = HashSet::new();
+        let mut runs = Vec::new();
+
+        for start in starts {
+            if !visited.insert(start) {
+                continue;
+            }
+
+            let mut run = vec![start];
+            let mut stack = vec![start];
+            while let Some(chunk) = stack.pop() {
+                for &neighbor in &neighbors[&chunk] {
+                    if visited.insert(neighbor) {
+                        run.push(neighbor);
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            run.sort_unstable();
+            runs.push((self.fingerprints[&start], run));
+        }
+
+        runs.sort_by(|(_, a), (_, b)| b.len().cmp(&a.len()).then(a.cmp(b)));
+        runs
+    }
+
+    /// Prints every group of identical neighboring chunks, largest groups first
+    pub fn print_csv(&self) {
+        println!("Fingerprint,Chunks,Chunk coordinates");
+        for (fingerprint, chunks) in self.runs() {
+            let coords: Vec<String> = chunks
+                .iter()
+                .map(|(chunk_x, chunk_z)| format!("{}:{}", chunk_x, chunk_z))
+                .collect();
+            println!(
+                "{:016x},{:8},{}",
+                fingerprint,
+                chunks.len(),
+                coords.join(" ")
+            );
+        }
+
+        eprintln!(
+            "Found {} pairs of identical neighboring chunks",
+            self.duplicate_pairs.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn neighboring_duplicates() {
+        // Two chunks of stone next to each other, followed by one of dirt
+        let source = test_fixtures::source(
+            [
+                (0, "minecraft:stone"),
+                (1, "minecraft:stone"),
+                (2, "minecraft:dirt"),
+            ]
+            .iter()
+            .map(|&(x, block)| {
+                test_fixtures::chunk(x, 0, vec![test_fixtures::uniform_section(0, block)])
+            })
+            .collect(),
+        );
+
+        let mut analyzer = DuplicateChunkAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+
+        let runs = analyzer.runs();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].1, vec![(0, 0), (1, 0)]);
+    }
+}
@@ -0,0 +1,166 @@
+use nbt::CompoundTag;
+
+/// Groups of mobs that share a mob cap, e.g. all hostile mobs count towards the `monster` cap.
+/// Water mobs are combined into a single category, although the game splits them further.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SpawnCategory {
+    Monster,
+    Creature,
+    Ambient,
+    Water,
+    /// Everything without a mob cap, e.g. items, minecarts and villagers
+    Misc,
+}
+
+/// Entity ids of every category except `Misc`, without namespace
+const SPAWN_CATEGORIES: &[(&str, SpawnCategory)] = &[
+    ("blaze", SpawnCategory::Monster),
+    ("bogged", SpawnCategory::Monster),
+    ("breeze", SpawnCategory::Monster),
+    ("cave_spider", SpawnCategory::Monster),
+    ("creaking", SpawnCategory::Monster),
+    ("creeper", SpawnCategory::Monster),
+    ("drowned", SpawnCategory::Monster),
+    ("elder_guardian", SpawnCategory::Monster),
+    ("ender_dragon", SpawnCategory::Monster),
+    ("enderman", SpawnCategory::Monster),
+    ("endermite", SpawnCategory::Monster),
+    ("evoker", SpawnCategory::Monster),
+    ("ghast", SpawnCategory::Monster),
+    ("giant", SpawnCategory::Monster),
+    ("guardian", SpawnCategory::Monster),
+    ("hoglin", SpawnCategory::Monster),
+    ("husk", SpawnCategory::Monster),
+    ("illusioner", SpawnCategory::Monster),
+    ("magma_cube", SpawnCategory::Monster),
+    ("phantom", SpawnCategory::Monster),
+    ("piglin", SpawnCategory::Monster),
+    ("piglin_brute", SpawnCategory::Monster),
+    ("pillager", SpawnCategory::Monster),
+    ("ravager", SpawnCategory::Monster),
+    ("shulker", SpawnCategory::Monster),
+    ("silverfish", SpawnCategory::Monster),
+    ("skeleton", SpawnCategory::Monster),
+    ("slime", SpawnCategory::Monster),
+    ("spider", SpawnCategory::Monster),
+    ("stray", SpawnCategory::Monster),
+    ("vex", SpawnCategory::Monster),
+    ("vindicator", SpawnCategory::Monster),
+    ("warden", SpawnCategory::Monster),
+    ("witch", SpawnCategory::Monster),
+    ("wither", SpawnCategory::Monster),
+    ("wither_skeleton", SpawnCategory::Monster),
+    ("zoglin", SpawnCategory::Monster),
+    ("zombie", SpawnCategory::Monster),
+    ("zombie_villager", SpawnCategory::Monster),
+    ("zombified_piglin", SpawnCategory::Monster),
+    ("allay", SpawnCategory::Creature),
+    ("armadillo", SpawnCategory::Creature),
+    ("bee", SpawnCategory::Creature),
+    ("camel", SpawnCategory::Creature),
+    ("cat", SpawnCategory::Creature),
+    ("chicken", SpawnCategory::Creature),
+    ("cow", SpawnCategory::Creature),
+    ("donkey", SpawnCategory::Creature),
+    ("fox", SpawnCategory::Creature),
+    ("frog", SpawnCategory::Creature),
+    ("goat", SpawnCategory::Creature),
+    ("horse", SpawnCategory::Creature),
+    ("llama", SpawnCategory::Creature),
+    ("mooshroom", SpawnCategory::Creature),
+    ("mule", SpawnCategory::Creature),
+    ("ocelot", SpawnCategory::Creature),
+    ("panda", SpawnCategory::Creature),
+    ("parrot", SpawnCategory::Creature),
+    ("pig", SpawnCategory::Creature),
+    ("polar_bear", SpawnCategory::Creature),
+    ("rabbit", SpawnCategory::Creature),
+    ("sheep", SpawnCategory::Creature),
+    ("skeleton_horse", SpawnCategory::Creature),
+    ("sniffer", SpawnCategory::Creature),
+    ("strider", SpawnCategory::Creature),
+    ("tadpole", SpawnCategory::Creature),
+    ("trader_llama", SpawnCategory::Creature),
+    ("turtle", SpawnCategory::Creature),
+    ("wolf", SpawnCategory::Creature),
+    ("zombie_horse", SpawnCategory::Creature),
+    ("bat", SpawnCategory::Ambient),
+    ("axolotl", SpawnCategory::Water),
+    ("cod", SpawnCategory::Water),
+    ("dolphin", SpawnCategory::Water),
+    ("glow_squid", SpawnCategory::Water),
+    ("pufferfish", SpawnCategory::Water),
+    ("salmon", SpawnCategory::Water),
+    ("squid", SpawnCategory::Water),
+    ("tropical_fish", SpawnCategory::Water),
+];
+
+impl SpawnCategory {
+    pub const ALL: [SpawnCategory; 5] = [
+        SpawnCategory::Monster,
+        SpawnCategory::Creature,
+        SpawnCategory::Ambient,
+        SpawnCategory::Water,
+        SpawnCategory::Misc,
+    ];
+
+    /// Category of an entity id like `minecraft:zombie`, `Misc` for ids that aren't known
+    pub fn of(id: &str) -> Self {
+        let id = match id.strip_prefix("minecraft:") {
+            Some(id) => id,
+            None if id.contains(':') => return SpawnCategory::Misc,
+            None => id,
+        };
+
+        SPAWN_CATEGORIES
+            .iter()
+            .find(|(entity, _)| *entity == id)
+            .map_or(SpawnCategory::Misc, |&(_, category)| category)
+    }
+
+    /// Name of the category as used by the game
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnCategory::Monster => "monster",
+            SpawnCategory::Creature => "creature",
+            SpawnCategory::Ambient => "ambient",
+            SpawnCategory::Water => "water",
+            SpawnCategory::Misc => "misc",
+        }
+    }
+}
+
+/// Ids of the entities stored in a chunk. Since 1.17 entities are stored in the chunks of the
+/// `entities` folder, with the list at the top level; before that they're in `Level.Entities`.
+/// Entities without an id are left out.
+pub fn parse_entity_ids(nbt: &CompoundTag) -> Vec<String> {
+    let entities = match nbt.get_compound_tag_vec("Entities").or_else(|_| {
+        nbt.get_compound_tag("Level")
+            .and_then(|level| level.get_compound_tag_vec("Entities"))
+    }) {
+        Ok(entities) => entities,
+        Err(_) => return Vec::new(),
+    };
+
+    entities
+        .into_iter()
+        .filter_map(|entity| entity.get_str("id").ok())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_categories() {
+        assert_eq!(
+            SpawnCategory::of("minecraft:zombie"),
+            SpawnCategory::Monster
+        );
+        assert_eq!(SpawnCategory::of("minecraft:cow"), SpawnCategory::Creature);
+        assert_eq!(SpawnCategory::of("minecraft:item"), SpawnCategory::Misc);
+        assert_eq!(SpawnCategory::of("somemod:dragon"), SpawnCategory::Misc);
+    }
+}
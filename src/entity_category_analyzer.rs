@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use anvil_region::error::ChunkReadError;
+
+use crate::{
+    chunk_source::{ChunkSource, RegionFolderSource},
+    entities::{parse_entity_ids, SpawnCategory},
+};
+
+/// Counts entities by the mob cap they count towards, e.g. to see whether hostile mobs fill up
+/// the cap of a server. Reads a dimension's `entities` folder for worlds since 1.17, or the region
+/// folder for older worlds, which store entities in the chunks themselves.
+pub struct EntityCategoryAnalyzer<'a> {
+    source: Box<dyn ChunkSource + 'a>,
+
+    /// category -> number of entities
+    category_counts: HashMap<SpawnCategory, u32>,
+}
+
+impl<'a> EntityCategoryAnalyzer<'a> {
+    pub fn new(folder: &'a str) -> Self {
+        EntityCategoryAnalyzer::with_source(RegionFolderSource::new(folder))
+    }
+
+    /// Counts entities in chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S) -> Self {
+        EntityCategoryAnalyzer {
+            source: Box::new(source),
+            category_counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let nbt = match self.source.read_chunk_nbt(chunk_x, chunk_z, &[]) {
+                Ok(nbt) => nbt,
+                // Chunks of the entities folder are only stored once they had entities
+                Err(ChunkReadError::ChunkNotFound { .. }) => continue,
+                Err(err) => {
+                    eprintln!(
+                        "Could not read entities of chunk ({},{}): {:?}",
+                        chunk_x, chunk_z, err
+                    );
+                    continue;
+                }
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for id in parse_entity_ids(&nbt) {
+                *self
+                    .category_counts
+                    .entry(SpawnCategory::of(&id))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Number of entities found so far in the category
+    pub fn count(&self, category: SpawnCategory) -> u32 {
+        self.category_counts.get(&category).copied().unwrap_or(0)
+    }
+
+    pub fn print_csv(&self) {
+        println!("Category,Entities");
+        for category in SpawnCategory::ALL {
+            println!("{},{:8}", category.name(), self.count(category));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures::{self, MemoryChunkSource};
+
+    #[test]
+    fn counts_per_category() {
+        // A chunk of the entities folder with two hostile mobs and a cow
+        let entities = ["minecraft:zombie", "minecraft:skeleton", "minecraft:cow"]
+            .iter()
+            .map(|id| {
+                let mut entity = CompoundTag::new();
+                entity.insert_str("id", id);
+                entity
+            })
+            .collect();
+
+        let mut chunk = CompoundTag::new();
+        chunk.insert_i32("DataVersion", test_fixtures::DATA_VERSION);
+        chunk.insert_compound_tag_vec("Entities", entities);
+
+        let mut source = MemoryChunkSource::new();
+        source.insert(0, 0, chunk);
+
+        let mut analyzer = EntityCategoryAnalyzer::with_source(source);
+        analyzer.analyze(vec![(0, 0)]);
+
+        assert_eq!(analyzer.count(SpawnCategory::Monster), 2);
+        assert_eq!(analyzer.count(SpawnCategory::Creature), 1);
+        assert_eq!(analyzer.count(SpawnCategory::Misc), 0);
+    }
+}
@@ -0,0 +1,218 @@
+//! Machine-readable progress of a scan, written as one line of JSON per event, e.g. for a
+//! supervisor that tracks long scans. Unlike the messages on stderr, the format is stable:
+//! every line is an object with an `event` field and the fields of that event.
+
+use std::{io::Write, time::Instant};
+
+use crate::{area::REGION_SIZE, ndjson::json_string};
+
+/// Something that happened during a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The scan started, with the number of chunks it's going to look at
+    Start {
+        chunks: usize,
+    },
+    /// The scan moved on to chunks of another region
+    Region {
+        x: i32,
+        z: i32,
+    },
+    ChunkStarted {
+        x: i32,
+        z: i32,
+    },
+    /// A chunk was done, including chunks that are missing from the world
+    ChunkFinished {
+        x: i32,
+        z: i32,
+        millis: u128,
+    },
+    Error {
+        message: String,
+    },
+    /// The scan went through all of its chunks
+    Summary {
+        chunks: usize,
+        millis: u128,
+    },
+}
+
+impl Event {
+    /// Name of the event as written to the `event` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Start { .. } => "start",
+            Event::Region { .. } => "region",
+            Event::ChunkStarted { .. } => "chunk_started",
+            Event::ChunkFinished { .. } => "chunk_finished",
+            Event::Error { .. } => "error",
+            Event::Summary { .. } => "summary",
+        }
+    }
+
+    /// The event as a single line of JSON, without the trailing newline
+    pub fn to_json(&self) -> String {
+        let fields = match self {
+            Event::Start { chunks } => format!("\"chunks\":{}", chunks),
+            Event::Region { x, z } | Event::ChunkStarted { x, z } => {
+                format!("\"x\":{},\"z\":{}", x, z)
+            }
+            Event::ChunkFinished { x, z, millis } => {
+                format!("\"x\":{},\"z\":{},\"millis\":{}", x, z, millis)
+            }
+            Event::Error { message } => format!("\"message\":{}", json_string(message)),
+            Event::Summary { chunks, millis } => {
+                format!("\"chunks\":{},\"millis\":{}", chunks, millis)
+            }
+        };
+
+        format!("{{\"event\":\"{}\",{}}}", self.name(), fields)
+    }
+}
+
+/// Writes events as newline-delimited JSON
+pub struct EventLog<W: Write> {
+    out: W,
+}
+
+impl<W: Write> EventLog<W> {
+    pub fn new(out: W) -> Self {
+        EventLog { out }
+    }
+
+    /// Writes the event. Failing to write it only prints a warning, so that a full disk
+    /// doesn't abort the scan the log is about.
+    pub fn emit(&mut self, event: &Event) {
+        if let Err(err) = writeln!(self.out, "{}", event.to_json()) {
+            eprintln!("Warning: could not write to the event log: {}", err);
+        }
+    }
+}
+
+/// Wraps a chunk iterator, logging an event whenever a chunk is handed out or done,
+/// if there is a log. Like `ProgressIter`, a chunk counts as done once the next one is requested.
+pub struct EventLogIter<I: Iterator<Item = (i32, i32)>, W: Write> {
+    chunks: I,
+    total: usize,
+    log: Option<EventLog<W>>,
+
+    /// When the first chunk was requested
+    started: Option<Instant>,
+    /// Chunk currently being processed and when it was handed out
+    current: Option<((i32, i32), Instant)>,
+    region: Option<(i32, i32)>,
+    done: usize,
+    finished: bool,
+}
+
+impl<I: Iterator<Item = (i32, i32)>, W: Write> EventLogIter<I, W> {
+    pub fn new(chunks: I, total: usize, log: Option<EventLog<W>>) -> Self {
+        EventLogIter {
+            chunks,
+            total,
+            log,
+            started: None,
+            current: None,
+            region: None,
+            done: 0,
+            finished: false,
+        }
+    }
+
+    fn emit(&mut self, event: Event) {
+        if let Some(log) = &mut self.log {
+            log.emit(&event);
+        }
+    }
+}
+
+impl<I: Iterator<Item = (i32, i32)>, W: Write> Iterator for EventLogIter<I, W> {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.chunks.next();
+        if self.log.is_none() {
+            return next;
+        }
+
+        let now = Instant::now();
+        let started = match self.started {
+            Some(started) => started,
+            None => {
+                self.emit(Event::Start { chunks: self.total });
+                self.started = Some(now);
+                now
+            }
+        };
+
+        if let Some(((x, z), chunk_started)) = self.current.take() {
+            let millis = now.duration_since(chunk_started).as_millis();
+            self.emit(Event::ChunkFinished { x, z, millis });
+            self.done += 1;
+        }
+
+        match next {
+            Some((x, z)) => {
+                let region = (x.div_euclid(REGION_SIZE), z.div_euclid(REGION_SIZE));
+                if self.region != Some(region) {
+                    self.emit(Event::Region {
+                        x: region.0,
+                        z: region.1,
+                    });
+                    self.region = Some(region);
+                }
+
+                self.emit(Event::ChunkStarted { x, z });
+                self.current = Some(((x, z), now));
+            }
+            None if !self.finished => {
+                let millis = now.duration_since(started).as_millis();
+                self.emit(Event::Summary {
+                    chunks: self.done,
+                    millis,
+                });
+                self.finished = true;
+            }
+            None => {}
+        }
+
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_of_a_scan() {
+        let chunks = vec![(0, 0), (1, 0), (32, 0)];
+        let mut out = Vec::new();
+        let events = EventLogIter::new(chunks.into_iter(), 3, Some(EventLog::new(&mut out)));
+        for _ in events {}
+
+        let log = String::from_utf8(out).unwrap();
+        let names: Vec<&str> = log
+            .lines()
+            .map(|line| line.split('"').nth(3).unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "start",
+                "region",
+                "chunk_started",
+                "chunk_finished",
+                "chunk_started",
+                "chunk_finished",
+                "region",
+                "chunk_started",
+                "chunk_finished",
+                "summary",
+            ]
+        );
+        assert_eq!(log.lines().next(), Some(r#"{"event":"start","chunks":3}"#));
+        assert!(log.contains(r#"{"event":"region","x":1,"z":0}"#));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    palette::is_air,
+};
+
+const NEIGHBOR_OFFSETS: [(i64, i32, i64); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// Counts how many blocks of an ore are exposed, i.e. have air on at least one side
+/// and can be reached through caves without mining, and how many are buried
+pub struct ExposureAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Blockstates to check, e.g. both diamond ore variants
+    ores: Vec<String>,
+
+    /// ore blockstate -> (exposed, buried)
+    counts: HashMap<String, (u32, u32)>,
+}
+
+impl<'a> ExposureAnalyzer<'a> {
+    pub fn new(path: &'a str, ores: Vec<String>, loader_options: LoaderOptions) -> Self {
+        ExposureAnalyzer::with_source(RegionFolderSource::new(path), ores, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        ores: Vec<String>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        ExposureAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            ores,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for section in chunk {
+                for block in section.blocks() {
+                    if !self.ores.contains(&block.blockstate) {
+                        continue;
+                    }
+
+                    let exposed = self.is_exposed(block.global_pos);
+
+                    let (exposed_count, buried_count) =
+                        self.counts.entry(block.blockstate).or_insert((0, 0));
+                    if exposed {
+                        *exposed_count += 1;
+                    } else {
+                        *buried_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Neighbors in other chunks are loaded as needed. Neighbors outside the world
+    /// (e.g. below the bottom) don't count as air.
+    fn is_exposed(&mut self, (x, y, z): (i64, i32, i64)) -> bool {
+        NEIGHBOR_OFFSETS.iter().any(|(dx, dy, dz)| {
+            self.chunk_loader
+                .get_blockstate_at(x + dx, y + dy, z + dz)
+                .is_some_and(is_air)
+        })
+    }
+
+    pub fn print_csv(&self, id_form: IdForm) {
+        let mut counts: Vec<(&String, &(u32, u32))> = self.counts.iter().collect();
+        counts.sort_by(
+            |(a_name, (a_exposed, a_buried)), (b_name, (b_exposed, b_buried))| {
+                (b_exposed + b_buried)
+                    .cmp(&(a_exposed + a_buried))
+                    .then(a_name.cmp(b_name))
+            },
+        );
+
+        println!("Blockstate,Exposed,Buried");
+        for (blockstate, (exposed, buried)) in counts {
+            println!("{},{:8},{:8}", id_form.render(blockstate), exposed, buried);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn ore_next_to_cave_air_is_exposed() {
+        let names = [
+            "minecraft:stone",
+            "minecraft:diamond_ore",
+            "minecraft:cave_air",
+        ];
+        // Ore next to cave air, ore surrounded by stone and ore at the edge of the chunk
+        let ores = test_fixtures::section(0, &names, |x, y, z| match (x, y, z) {
+            (8, 5, 5) | (3, 5, 5) | (15, 5, 5) => 1,
+            (8, 6, 5) => 2,
+            _ => 0,
+        });
+        // Cave air right next to the ore at the edge
+        let neighbor =
+            test_fixtures::section(
+                0,
+                &names,
+                |x, y, z| {
+                    if (x, y, z) == (0, 5, 5) {
+                        2
+                    } else {
+                        0
+                    }
+                },
+            );
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(0, 0, vec![ores]),
+            test_fixtures::chunk(1, 0, vec![neighbor]),
+        ]);
+
+        let mut analyzer = ExposureAnalyzer::with_source(
+            source,
+            vec!["minecraft:diamond_ore".to_string()],
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        assert_eq!(analyzer.counts["minecraft:diamond_ore"], (2, 1));
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::chunk_loader::{ChunkLoader, LoaderOptions};
+
+/// Finds chunks with identical block composition, e.g. superflat areas or pasted structures
+pub struct FingerprintAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// composition fingerprint -> chunks with that fingerprint
+    chunks_by_fingerprint: HashMap<u64, Vec<(i32, i32)>>,
+}
+
+impl<'a> FingerprintAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        FingerprintAnalyzer {
+            chunk_loader: ChunkLoader::new(path, None, loader_options),
+            chunks_by_fingerprint: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let fingerprint = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.composition_fingerprint(),
+                None => continue,
+            };
+
+            self.chunks_by_fingerprint
+                .entry(fingerprint)
+                .or_default()
+                .push((chunk_x, chunk_z));
+        }
+    }
+
+    /// Prints every fingerprint shared by more than one chunk, largest groups first
+    pub fn print_csv(&self) {
+        let mut groups: Vec<(&u64, &Vec<(i32, i32)>)> = self
+            .chunks_by_fingerprint
+            .iter()
+            .filter(|(_, chunks)| chunks.len() > 1)
+            .collect();
+        groups.sort_by(|(a_hash, a), (b_hash, b)| b.len().cmp(&a.len()).then(a_hash.cmp(b_hash)));
+
+        println!("Fingerprint,Chunks,Chunk coordinates");
+        for (fingerprint, chunks) in groups {
+            let coords: Vec<String> = chunks
+                .iter()
+                .map(|(chunk_x, chunk_z)| format!("{}:{}", chunk_x, chunk_z))
+                .collect();
+            println!(
+                "{:016x},{:8},{}",
+                fingerprint,
+                chunks.len(),
+                coords.join(" ")
+            );
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Block counts in the collapsed-stack format that flamegraph tools read, one
+//! `namespace;category;block count` line per blockstate, e.g. `minecraft;ore;diamond_ore 1234`.
+//! This shows the composition of an area as a flamegraph that can be drilled down into.
+
+use std::io::{self, Write};
+
+use crate::{block_id::split_namespace, palette::is_air};
+
+/// Category of blocks whose name ends in the suffix
+const CATEGORY_SUFFIXES: &[(&str, &str)] = &[
+    ("_ore", "ore"),
+    ("_log", "tree"),
+    ("_wood", "tree"),
+    ("_leaves", "tree"),
+    ("_stem", "tree"),
+    ("_hyphae", "tree"),
+];
+
+/// Category of blocks that don't share a suffix with others of their kind
+const CATEGORY_BLOCKS: &[(&str, &str)] = &[
+    ("ancient_debris", "ore"),
+    ("water", "fluid"),
+    ("lava", "fluid"),
+    ("stone", "stone"),
+    ("deepslate", "stone"),
+    ("granite", "stone"),
+    ("diorite", "stone"),
+    ("andesite", "stone"),
+    ("tuff", "stone"),
+    ("calcite", "stone"),
+    ("bedrock", "stone"),
+    ("netherrack", "stone"),
+    ("end_stone", "stone"),
+    ("blackstone", "stone"),
+    ("basalt", "stone"),
+    ("dirt", "soil"),
+    ("coarse_dirt", "soil"),
+    ("grass_block", "soil"),
+    ("podzol", "soil"),
+    ("mycelium", "soil"),
+    ("sand", "soil"),
+    ("red_sand", "soil"),
+    ("gravel", "soil"),
+    ("clay", "soil"),
+    ("sandstone", "soil"),
+    ("red_sandstone", "soil"),
+];
+
+/// Rough category of a blockstate for the middle level of the flamegraph, `other` if it has none.
+/// Categories go by vanilla names, so modded blocks only get one if they're named alike.
+pub fn block_category(blockstate: &str) -> &'static str {
+    if is_air(blockstate) {
+        return "air";
+    }
+
+    let (_, name) = split_namespace(blockstate);
+    CATEGORY_BLOCKS
+        .iter()
+        .find(|(block, _)| *block == name)
+        .or_else(|| {
+            CATEGORY_SUFFIXES
+                .iter()
+                .find(|(suffix, _)| name.ends_with(suffix))
+        })
+        .map_or("other", |&(_, category)| category)
+}
+
+/// Writes one collapsed-stack line per blockstate with a count above 0
+pub fn write_collapsed_stacks<W: Write>(out: &mut W, totals: &[(String, u64)]) -> io::Result<()> {
+    for (blockstate, count) in totals {
+        if *count == 0 {
+            continue;
+        }
+
+        let (namespace, name) = split_namespace(blockstate);
+        writeln!(
+            out,
+            "{};{};{} {}",
+            namespace,
+            block_category(blockstate),
+            name,
+            count
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories() {
+        assert_eq!(block_category("minecraft:deepslate_diamond_ore"), "ore");
+        assert_eq!(block_category("minecraft:cave_air"), "air");
+        assert_eq!(block_category("minecraft:oak_log"), "tree");
+        assert_eq!(block_category("minecraft:chest"), "other");
+    }
+
+    #[test]
+    fn collapsed_stacks() {
+        let totals = vec![
+            ("minecraft:stone".to_string(), 1000),
+            ("minecraft:diamond_ore".to_string(), 12),
+            ("create:zinc_ore".to_string(), 3),
+        ];
+        let mut out = Vec::new();
+        write_collapsed_stacks(&mut out, &totals).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("minecraft;ore;diamond_ore 12\n"));
+        assert!(out.contains("create;ore;zinc_ore 3\n"));
+
+        // Every line is a stack of three frames and a count, adding up to the totals
+        let mut sum = 0;
+        for line in out.lines() {
+            let (stack, count) = line.rsplit_once(' ').unwrap();
+            assert_eq!(stack.split(';').count(), 3);
+            sum += count.parse::<u64>().unwrap();
+        }
+        assert_eq!(sum, 1015);
+    }
+}
@@ -0,0 +1,505 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+    iter,
+};
+
+use crate::{
+    block_id::DEFAULT_NAMESPACE,
+    chunk::Chunk,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::{ChunkSource, RegionFolderSource},
+    palette::{is_air, UNKNOWN_BLOCK},
+};
+
+/// Confidence from which a world is reported as flat
+const FLAT_CONFIDENCE_THRESHOLD: f64 = 0.9;
+
+/// Label for layers that aren't made up of a single blockstate
+const MIXED_LAYER: &str = "mixed";
+
+/// Layers a superflat world is meant to consist of, from the bottom of the world up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatRecipe {
+    layers: Vec<String>,
+}
+
+impl FlatRecipe {
+    /// Parses a recipe like `bedrock,2*dirt,grass_block`, listing the layers from the bottom up
+    /// like the superflat customization screen. Blocks without namespace are in `minecraft`.
+    pub fn parse(recipe: &str) -> Option<Self> {
+        let mut layers = Vec::new();
+
+        for part in recipe.split(',').map(str::trim) {
+            let (count, block) = match part.split_once('*') {
+                Some((count, block)) => (count.trim().parse::<usize>().ok()?, block.trim()),
+                None => (1, part),
+            };
+            if count == 0 || block.is_empty() {
+                return None;
+            }
+
+            let block = if block.contains(':') {
+                block.to_string()
+            } else {
+                format!("{}:{}", DEFAULT_NAMESPACE, block)
+            };
+            layers.extend(iter::repeat_n(block, count));
+        }
+
+        Some(FlatRecipe { layers })
+    }
+
+    /// Number of layers, not counting the air above them
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Blockstate of the layer `index` levels above the bottom of the world,
+    /// air above the recipe
+    pub fn expected(&self, index: usize) -> &str {
+        self.layers
+            .get(index)
+            .map_or("minecraft:air", String::as_str)
+    }
+}
+
+/// Columns of a chunk's layer that don't match the recipe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatDeviation {
+    pub chunk: (i32, i32),
+    pub y: i32,
+    pub expected: String,
+    pub found: String,
+    pub columns: u32,
+}
+
+/// Detects superflat worlds, where every layer consists of one blockstate everywhere.
+/// Given a recipe, it also checks every column of every chunk against it.
+pub struct FlatWorldAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    recipe: Option<FlatRecipe>,
+
+    /// Layers not matching the recipe, in the order the chunks were scanned
+    deviations: Vec<FlatDeviation>,
+
+    /// Y -> blockstate the layer consists of in a chunk (`None` if mixed) -> number of chunks.
+    /// Chunks without a section at some Y aren't counted there, they're implicitly air.
+    layer_states: BTreeMap<i32, HashMap<Option<String>, u32>>,
+
+    chunk_count: u32,
+}
+
+impl<'a> FlatWorldAnalyzer<'a> {
+    pub fn new(path: &'a str, recipe: Option<FlatRecipe>, loader_options: LoaderOptions) -> Self {
+        FlatWorldAnalyzer::with_source(RegionFolderSource::new(path), recipe, loader_options)
+    }
+
+    /// Analyzes chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        recipe: Option<FlatRecipe>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        FlatWorldAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            recipe,
+            deviations: Vec::new(),
+            layer_states: BTreeMap::new(),
+            chunk_count: 0,
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            self.chunk_count += 1;
+
+            if let Some(recipe) = &self.recipe {
+                self.deviations
+                    .extend(deviations_in(&chunk, (chunk_x, chunk_z), recipe));
+            }
+
+            for section in chunk {
+                for local_y in 0..CHUNK_SIZE {
+                    let y = section.pos.1 as i32 * CHUNK_SIZE as i32 + local_y as i32;
+
+                    let first = section.get_block_at(0, local_y, 0);
+                    let uniform = (0..CHUNK_SIZE)
+                        .flat_map(|x| (0..CHUNK_SIZE).map(move |z| (x, z)))
+                        .all(|(x, z)| section.get_block_at(x, local_y, z) == first);
+
+                    let state = if uniform {
+                        first.map(String::from)
+                    } else {
+                        None
+                    };
+
+                    *self
+                        .layer_states
+                        .entry(y)
+                        .or_default()
+                        .entry(state)
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    /// Whether chunks were scanned and all of them match the recipe
+    pub fn matches_recipe(&self) -> bool {
+        self.chunk_count > 0 && self.deviations.is_empty()
+    }
+
+    pub fn print_verification(&self) {
+        self.write_verification(&mut io::stdout().lock()).unwrap();
+    }
+
+    /// Writes whether the scanned chunks match the recipe, and every layer that doesn't
+    pub fn write_verification<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "Matches recipe,{}",
+            if self.matches_recipe() { "yes" } else { "no" }
+        )?;
+        writeln!(out, "Chunks,{}", self.chunk_count)?;
+        writeln!(out, "Deviating layers,{}", self.deviations.len())?;
+        writeln!(out)?;
+
+        writeln!(out, "Chunk X,Chunk Z,Y,Expected,Found,Columns")?;
+        for deviation in &self.deviations {
+            writeln!(
+                out,
+                "{},{},{:5},{},{},{:4}",
+                deviation.chunk.0,
+                deviation.chunk.1,
+                deviation.y,
+                deviation.expected,
+                deviation.found,
+                deviation.columns
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Most common state of each layer, together with the number of chunks it was found in
+    fn modal_layers(&self) -> Vec<(i32, Option<String>, u32)> {
+        self.layer_states
+            .iter()
+            .map(|(&y, states)| {
+                let counted: u32 = states.values().sum();
+                let implicit_air = self.chunk_count - counted;
+
+                let mut states: Vec<(Option<String>, u32)> = states
+                    .iter()
+                    .map(|(state, &count)| (state.clone(), count))
+                    .collect();
+
+                match states
+                    .iter_mut()
+                    .find(|(state, _)| state.as_deref().is_some_and(is_air))
+                {
+                    Some((_, count)) => *count += implicit_air,
+                    None => states.push((Some("minecraft:air".to_string()), implicit_air)),
+                }
+
+                let (state, count) = states
+                    .into_iter()
+                    .max_by(|(a_state, a), (b_state, b)| a.cmp(b).then(b_state.cmp(a_state)))
+                    .unwrap();
+
+                (y, state, count)
+            })
+            .collect()
+    }
+
+    /// Judges how flat the scanned chunks are from the most common state of each layer,
+    /// between the lowest and the highest layer that isn't air
+    pub fn detect(&self) -> FlatDetection {
+        let layers = self.modal_layers();
+
+        let is_ground = |state: &Option<String>| !state.as_deref().is_some_and(is_air);
+        let bottom = layers.iter().position(|(_, state, _)| is_ground(state));
+        let top = layers.iter().rposition(|(_, state, _)| is_ground(state));
+
+        let layers = match (bottom, top) {
+            (Some(bottom), Some(top)) => layers[bottom..=top].to_vec(),
+            _ => Vec::new(),
+        };
+
+        // A chunk can only match the recipe in every layer if it matches it in the worst one
+        let confidence = if layers.iter().any(|(_, state, _)| state.is_none()) {
+            0.0
+        } else {
+            layers
+                .iter()
+                .map(|&(_, _, count)| count as f64 / self.chunk_count as f64)
+                .fold(if layers.is_empty() { 0.0 } else { 1.0 }, f64::min)
+        };
+
+        FlatDetection { confidence, layers }
+    }
+
+    pub fn print_csv(&self) {
+        let detection = self.detect();
+
+        println!("Flat,{}", if detection.is_flat() { "yes" } else { "no" });
+        println!("Confidence,{:.3}", detection.confidence);
+        println!("Recipe,{}", detection.recipe());
+        println!();
+
+        println!("Y,Blockstate,Chunks");
+        for (y, state, count) in &detection.layers {
+            println!(
+                "{:5},{},{:8}",
+                y,
+                state.as_deref().unwrap_or(MIXED_LAYER),
+                count
+            );
+        }
+    }
+}
+
+/// Outcome of `FlatWorldAnalyzer::detect`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatDetection {
+    /// Share of chunks matching the detected recipe in its least common layer,
+    /// 0 if any layer is mixed within a chunk
+    pub confidence: f64,
+
+    /// Y, blockstate (`None` if mixed) and number of chunks of every layer of the recipe,
+    /// from the bottom up
+    pub layers: Vec<(i32, Option<String>, u32)>,
+}
+
+impl FlatDetection {
+    pub fn is_flat(&self) -> bool {
+        self.confidence >= FLAT_CONFIDENCE_THRESHOLD
+    }
+
+    /// The detected layers in the superflat customization format, e.g. `minecraft:dirt*2`,
+    /// separated by `;` to keep it in a single CSV column
+    pub fn recipe(&self) -> String {
+        let mut recipe_parts: Vec<(&str, u32)> = Vec::new();
+        for (_, state, _) in &self.layers {
+            let state = state.as_deref().unwrap_or(MIXED_LAYER);
+            match recipe_parts.last_mut() {
+                Some((last, count)) if *last == state => *count += 1,
+                _ => recipe_parts.push((state, 1)),
+            }
+        }
+
+        let recipe_parts: Vec<String> = recipe_parts
+            .into_iter()
+            .map(|(state, count)| {
+                if count > 1 {
+                    format!("{}*{}", state, count)
+                } else {
+                    state.to_string()
+                }
+            })
+            .collect();
+        recipe_parts.join(";")
+    }
+}
+
+/// Layers of the chunk that don't match the recipe, checking every column from the bottom of the
+/// world up to the recipe's top or the chunk's highest section, whichever is higher.
+/// Missing sections are air.
+fn deviations_in(chunk: &Chunk, pos: (i32, i32), recipe: &FlatRecipe) -> Vec<FlatDeviation> {
+    let min_y = chunk.min_y();
+    let section_top = chunk
+        .section_indices()
+        .last()
+        .map_or(min_y, |&y| (y as i32 + 1) * CHUNK_SIZE as i32);
+    let max_y = section_top.max(min_y + recipe.len() as i32);
+
+    let mut deviations = Vec::new();
+    for y in min_y..max_y {
+        let expected = recipe.expected((y - min_y) as usize);
+        let section = chunk.get_section(y.div_euclid(CHUNK_SIZE as i32) as i8);
+        let local_y = y.rem_euclid(CHUNK_SIZE as i32) as usize;
+
+        let mut found: BTreeMap<&str, u32> = BTreeMap::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let block = match section {
+                    Some(section) => section.get_block_at(x, local_y, z).unwrap_or(UNKNOWN_BLOCK),
+                    None => "minecraft:air",
+                };
+
+                let matches = block == expected || (is_air(block) && is_air(expected));
+                if !matches {
+                    *found.entry(block).or_insert(0) += 1;
+                }
+            }
+        }
+
+        deviations.extend(found.into_iter().map(|(block, columns)| FlatDeviation {
+            chunk: pos,
+            y,
+            expected: expected.to_string(),
+            found: block.to_string(),
+            columns,
+        }));
+    }
+
+    deviations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    /// A superflat chunk with one layer of bedrock, two of dirt and one of grass at the bottom of
+    /// the world, except for the blocks `replace` returns another palette entry for
+    fn superflat<F: Fn(usize, usize, usize) -> Option<usize>>(replace: F) -> impl ChunkSource {
+        let section = test_fixtures::section(
+            -4,
+            &[
+                "minecraft:air",
+                "minecraft:bedrock",
+                "minecraft:dirt",
+                "minecraft:grass_block",
+            ],
+            |x, y, z| {
+                replace(x, y, z).unwrap_or(match y {
+                    0 => 1,
+                    1 | 2 => 2,
+                    3 => 3,
+                    _ => 0,
+                })
+            },
+        );
+        test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])])
+    }
+
+    fn verification(source: impl ChunkSource) -> Vec<String> {
+        let recipe = FlatRecipe::parse("bedrock,2*dirt,grass_block").unwrap();
+        let mut analyzer =
+            FlatWorldAnalyzer::with_source(source, Some(recipe), LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0)]);
+
+        let mut csv = Vec::new();
+        analyzer.write_verification(&mut csv).unwrap();
+        String::from_utf8(csv)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn parse_recipe() {
+        let recipe = FlatRecipe::parse("bedrock,2*dirt,grass_block").unwrap();
+        assert_eq!(recipe.len(), 4);
+        assert_eq!(recipe.expected(2), "minecraft:dirt");
+        assert_eq!(recipe.expected(4), "minecraft:air");
+
+        assert!(FlatRecipe::parse("0*dirt").is_none());
+    }
+
+    #[test]
+    fn matching_recipe() {
+        let verification = verification(superflat(|_, _, _| None));
+        assert_eq!(verification[0], "Matches recipe,yes");
+        assert_eq!(verification[2], "Deviating layers,0");
+    }
+
+    #[test]
+    fn block_replaced() {
+        // A block of dirt in the lower dirt layer replaced with bedrock
+        let source = superflat(|x, y, z| {
+            if (x, y, z) == (0, 1, 0) {
+                Some(1)
+            } else {
+                None
+            }
+        });
+        let verification = verification(source);
+        assert_eq!(verification[0], "Matches recipe,no");
+        assert_eq!(verification[2], "Deviating layers,1");
+        assert_eq!(
+            verification[5],
+            "0,0,  -63,minecraft:dirt,minecraft:bedrock,   1"
+        );
+    }
+
+    fn detect(chunks: Vec<nbt::CompoundTag>) -> FlatDetection {
+        let positions: Vec<(i32, i32)> = (0..chunks.len() as i32).map(|x| (x, 0)).collect();
+        let mut analyzer = FlatWorldAnalyzer::with_source(
+            test_fixtures::source(chunks),
+            None,
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(positions);
+        analyzer.detect()
+    }
+
+    #[test]
+    fn superflat_detected() {
+        let chunks = (0..4)
+            .map(|x| {
+                let section = test_fixtures::section(
+                    -4,
+                    &[
+                        "minecraft:air",
+                        "minecraft:bedrock",
+                        "minecraft:dirt",
+                        "minecraft:grass_block",
+                    ],
+                    |_, y, _| match y {
+                        0 => 1,
+                        1 | 2 => 2,
+                        3 => 3,
+                        _ => 0,
+                    },
+                );
+                test_fixtures::chunk(x, 0, vec![section])
+            })
+            .collect();
+        let detection = detect(chunks);
+
+        assert!(detection.is_flat());
+        assert_eq!(detection.confidence, 1.0);
+        assert_eq!(
+            detection.recipe(),
+            "minecraft:bedrock;minecraft:dirt*2;minecraft:grass_block"
+        );
+    }
+
+    #[test]
+    fn normal_terrain_not_detected() {
+        // Stone with hills of varying height in every chunk
+        let chunks = (0..4)
+            .map(|chunk_x| {
+                let section = test_fixtures::section(
+                    -4,
+                    &["minecraft:air", "minecraft:stone", "minecraft:grass_block"],
+                    move |x, y, z| {
+                        let height = 4 + (x * 3 + z * 5 + chunk_x as usize * 7) % 9;
+                        match y.cmp(&height) {
+                            std::cmp::Ordering::Less => 1,
+                            std::cmp::Ordering::Equal => 2,
+                            std::cmp::Ordering::Greater => 0,
+                        }
+                    },
+                );
+                test_fixtures::chunk(chunk_x, 0, vec![section])
+            })
+            .collect();
+        let detection = detect(chunks);
+
+        assert!(!detection.is_flat());
+        assert_eq!(detection.confidence, 0.0);
+    }
+}
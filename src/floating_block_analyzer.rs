@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    palette::is_air,
+};
+
+/// Below and the four sides, a block with air on all of them isn't held up by anything
+const SUPPORT_OFFSETS: [(i64, i32, i64); 5] =
+    [(1, 0, 0), (-1, 0, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+/// Name endings of blocks that can legitimately float or hang in the air, e.g. leaves and torches,
+/// or aren't solid at all
+pub const FLOATING_BLOCK_SUFFIXES: &[&str] = &[
+    "_leaves",
+    "torch",
+    "lantern",
+    "vines",
+    ":vine",
+    ":chain",
+    "_banner",
+    "_sign",
+    ":scaffolding",
+    ":cobweb",
+    ":end_rod",
+    ":lightning_rod",
+    ":bell",
+    ":ladder",
+    ":lever",
+    "_button",
+    ":tripwire_hook",
+    "_pane",
+    ":iron_bars",
+    ":spore_blossom",
+    ":hanging_roots",
+    ":glow_lichen",
+    ":sculk_vein",
+    ":pointed_dripstone",
+    ":water",
+    ":lava",
+    ":bubble_column",
+    ":light",
+    ":barrier",
+    ":structure_void",
+];
+
+/// Finds blocks without support, i.e. with air below and on all four sides, which are often
+/// leftovers of editing mistakes. Blocks in `FLOATING_BLOCK_SUFFIXES` are never reported.
+pub struct FloatingBlockAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// blockstate -> count
+    counts: HashMap<String, u32>,
+
+    /// Location of every floating block, in the order they were found
+    positions: Vec<((i64, i32, i64), String)>,
+}
+
+impl<'a> FloatingBlockAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        FloatingBlockAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        FloatingBlockAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            counts: HashMap::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for section in chunk {
+                for block in section.blocks() {
+                    if is_air(&block.blockstate) || can_float(&block.blockstate) {
+                        continue;
+                    }
+
+                    if !self.is_unsupported(block.global_pos) {
+                        continue;
+                    }
+
+                    *self.counts.entry(block.blockstate.clone()).or_insert(0) += 1;
+                    self.positions.push((block.global_pos, block.blockstate));
+                }
+            }
+        }
+    }
+
+    /// Neighbors in other chunks are loaded as needed. Neighbors that can't be loaded
+    /// (e.g. below the bottom of the world or in missing chunks) count as support.
+    fn is_unsupported(&mut self, (x, y, z): (i64, i32, i64)) -> bool {
+        SUPPORT_OFFSETS.iter().all(|(dx, dy, dz)| {
+            self.chunk_loader
+                .get_blockstate_at(x + dx, y + dy, z + dz)
+                .is_some_and(is_air)
+        })
+    }
+
+    /// Prints the counts per block, followed by the coordinates of every floating block
+    pub fn print_csv(&self, id_form: IdForm) {
+        let mut counts: Vec<(&String, &u32)> = self.counts.iter().collect();
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("Floating block,Count");
+        for (blockstate, count) in counts {
+            println!("{},{:8}", id_form.render(blockstate), count);
+        }
+
+        println!();
+
+        println!("X,Y,Z,Floating block");
+        for ((x, y, z), blockstate) in &self.positions {
+            println!("{},{},{},{}", x, y, z, id_form.render(blockstate));
+        }
+    }
+}
+
+fn can_float(blockstate: &str) -> bool {
+    FLOATING_BLOCK_SUFFIXES
+        .iter()
+        .any(|suffix| blockstate.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn isolated_stone_floats() {
+        // A single block of stone in the air, a pillar of stone standing on the bottom of the
+        // world, and a torch which may float
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:air", "minecraft:stone", "minecraft:torch"],
+            |x, y, z| match (x, y, z) {
+                (5, 8, 5) => 1,
+                (10, 0..=8, 10) => 1,
+                (2, 12, 2) => 2,
+                _ => 0,
+            },
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]);
+
+        let mut analyzer = FloatingBlockAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0)]);
+
+        assert_eq!(
+            analyzer.positions,
+            vec![((5, 8, 5), "minecraft:stone".to_string())]
+        );
+        assert_eq!(analyzer.counts["minecraft:stone"], 1);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+const EXPECTED_FLOOR: &str = "minecraft:bedrock";
+
+pub struct FloorAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// blockstate -> number of columns with that block at the bottom
+    floor_blocks: HashMap<String, u32>,
+
+    /// Columns whose bottommost block isn't bedrock
+    unexpected_floors: Vec<((i64, i32, i64), String)>,
+}
+
+impl<'a> FloorAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        FloorAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        // Only the lowest section is needed, which is either Y=0 or Y=-4
+        // depending on the world height of the chunk's version
+        let chunk_loader = ChunkLoader::with_source(source, Some(-4..1), loader_options);
+
+        FloorAnalyzer {
+            chunk_loader,
+            floor_blocks: HashMap::new(),
+            unexpected_floors: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let (start_x, start_z) = chunk.get_global_pos();
+            let floor_y = chunk.min_y();
+
+            for z in start_z..start_z + CHUNK_SIZE as i64 {
+                for x in start_x..start_x + CHUNK_SIZE as i64 {
+                    // A missing section at the bottom of the world means there's nothing there
+                    let blockstate = self
+                        .chunk_loader
+                        .get_blockstate_at(x, floor_y, z)
+                        .unwrap_or("minecraft:air")
+                        .to_string();
+
+                    self.count_floor_block((x, floor_y, z), blockstate);
+                }
+            }
+        }
+    }
+
+    fn count_floor_block(&mut self, pos: (i64, i32, i64), blockstate: String) {
+        *self.floor_blocks.entry(blockstate.clone()).or_insert(0) += 1;
+
+        if blockstate != EXPECTED_FLOOR {
+            self.unexpected_floors.push((pos, blockstate));
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut floor_blocks: Vec<(&String, &u32)> = self.floor_blocks.iter().collect();
+        floor_blocks.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("Floor block,Columns");
+        for (blockstate, count) in floor_blocks {
+            println!("{},{:8}", blockstate, count);
+        }
+        println!();
+
+        println!("X,Y,Z,Non-bedrock floor block");
+        for ((x, y, z), blockstate) in &self.unexpected_floors {
+            println!("{},{},{},{}", x, y, z, blockstate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn stone_in_the_bedrock_layer() {
+        // Bedrock at the bottom of the world, except for one block of stone, with stone above
+        let section =
+            test_fixtures::section(-4, &["minecraft:stone", "minecraft:bedrock"], |x, y, z| {
+                (y == 0 && (x, z) != (3, 5)) as usize
+            });
+        let source = test_fixtures::source(vec![test_fixtures::chunk(1, 0, vec![section])]);
+
+        let mut analyzer = FloorAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(1, 0)]);
+
+        assert_eq!(analyzer.floor_blocks.len(), 2);
+        assert_eq!(analyzer.floor_blocks["minecraft:bedrock"], 255);
+        assert_eq!(analyzer.floor_blocks["minecraft:stone"], 1);
+        assert_eq!(
+            analyzer.unexpected_floors,
+            vec![((19, -64, 5), "minecraft:stone".to_string())]
+        );
+    }
+}
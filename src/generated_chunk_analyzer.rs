@@ -0,0 +1,148 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    area::{Area, REGION_SIZE},
+    chunk_loader::read_present_chunks,
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+    presence_analyzer::render,
+};
+
+/// Maps which chunks have been generated, only by looking at the location tables of region files.
+/// No chunk is decoded, which makes this fast enough to get an overview of huge worlds.
+pub struct ChunkPresenceMapAnalyzer<'a> {
+    region_folder: &'a str,
+
+    area: Area,
+
+    /// region coordinates -> whether each of its chunks is stored, see `read_present_chunks`
+    regions: HashMap<(i32, i32), Vec<bool>>,
+
+    /// Generated chunks, in the order they were scanned
+    generated: Vec<(i32, i32)>,
+    missing_count: usize,
+}
+
+impl<'a> ChunkPresenceMapAnalyzer<'a> {
+    pub fn new(region_folder: &'a str, area: Area) -> Self {
+        ChunkPresenceMapAnalyzer {
+            region_folder,
+            area,
+            regions: HashMap::new(),
+            generated: Vec::new(),
+            missing_count: 0,
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let region = (
+                chunk_x.div_euclid(REGION_SIZE),
+                chunk_z.div_euclid(REGION_SIZE),
+            );
+            let region_folder = self.region_folder;
+
+            let present_chunks = self.regions.entry(region).or_insert_with(|| {
+                read_present_chunks(region_folder, region.0, region.1).unwrap_or_else(|err| {
+                    eprintln!(
+                        "Could not read region r.{}.{}.mca, treating it as empty: {}",
+                        region.0, region.1, err
+                    );
+                    vec![false; (REGION_SIZE * REGION_SIZE) as usize]
+                })
+            });
+
+            let index =
+                chunk_x.rem_euclid(REGION_SIZE) + chunk_z.rem_euclid(REGION_SIZE) * REGION_SIZE;
+            if present_chunks[index as usize] {
+                self.generated.push((chunk_x, chunk_z));
+            } else {
+                self.missing_count += 1;
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        println!("Generated,Missing");
+        println!("{},{}", self.generated.len(), self.missing_count);
+    }
+
+    /// Saves a map with one pixel per chunk, white for generated chunks and black for missing ones.
+    /// With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, generated) = (&self.area, self.generated.as_slice());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, generated, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, generated, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+    use crate::test_fixtures::{self, TempDir};
+
+    #[test]
+    fn generated_and_missing_pattern() {
+        let dir = TempDir::new();
+        let chunks = [(0, 0), (2, 0), (1, 1)]
+            .iter()
+            .map(|&(x, z)| {
+                (
+                    (x, z),
+                    2,
+                    test_fixtures::zlib(test_fixtures::chunk(x, z, Vec::new())),
+                )
+            })
+            .collect();
+        test_fixtures::write_region_file(&dir.path().join("r.0.0.mca"), chunks);
+
+        // The chunks at negative X lie in a region without a file
+        let area = Area::new(-1, 3, 0, 2);
+        let mut analyzer = ChunkPresenceMapAnalyzer::new(dir.as_str(), area);
+        analyzer.analyze(area);
+
+        assert_eq!(analyzer.generated.len(), 3);
+        assert_eq!(analyzer.missing_count, 5);
+
+        let path = dir.path().join("map.png");
+        analyzer.print_img(path.to_str().unwrap(), ImageFormat::Png, None, None);
+        let img = image::open(&path).unwrap().to_rgb8();
+        assert_eq!(img.dimensions(), (4, 2));
+
+        // Z increases upwards, so the row of Z 1 is at the top
+        let (white, black) = (Rgb([255, 255, 255]), Rgb([0, 0, 0]));
+        let rows: Vec<Vec<Rgb<u8>>> = (0..2)
+            .map(|y| (0..4).map(|x| *img.get_pixel(x, y)).collect())
+            .collect();
+        assert_eq!(rows[0], vec![black, black, white, black]);
+        assert_eq!(rows[1], vec![black, white, black, white]);
+    }
+}
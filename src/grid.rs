@@ -0,0 +1,80 @@
+use image::{Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    legend::{draw_number, GLYPH_HEIGHT},
+};
+
+/// Green, to stand out on the grayscale, red and blue heatmaps
+const GRID_COLOR: Rgb<u8> = Rgb([0, 255, 0]);
+
+/// Space between a gridline and its label
+const LABEL_OFFSET: u32 = 2;
+
+/// Draws gridlines onto a map with one pixel per chunk and north at the bottom, as rendered by
+/// the heatmap analyzers, at every chunk X and Z that's a multiple of `spacing`.
+/// Every line is labeled with its chunk coordinate, at the top for X and on the left for Z.
+pub fn draw_grid(img: &mut RgbImage, area: &Area, spacing: u32) {
+    let spacing = spacing as i32;
+    let (min_x, min_z) = area.min_chunk();
+    let (width, height) = img.dimensions();
+
+    for pixel_x in 0..width {
+        let chunk_x = min_x + pixel_x as i32;
+        if chunk_x.rem_euclid(spacing) != 0 {
+            continue;
+        }
+
+        for y in 0..height {
+            img.put_pixel(pixel_x, y, GRID_COLOR);
+        }
+        draw_number(
+            img,
+            &chunk_x.to_string(),
+            pixel_x + LABEL_OFFSET,
+            LABEL_OFFSET,
+            GRID_COLOR,
+        );
+    }
+
+    for pixel_z in 0..height {
+        let chunk_z = min_z + pixel_z as i32;
+        if chunk_z.rem_euclid(spacing) != 0 {
+            continue;
+        }
+
+        let pixel_y = height - pixel_z - 1;
+        for x in 0..width {
+            img.put_pixel(x, pixel_y, GRID_COLOR);
+        }
+        // Above the line, since the rows below it belong to lower Z
+        if let Some(label_y) = pixel_y.checked_sub(LABEL_OFFSET + GLYPH_HEIGHT) {
+            draw_number(img, &chunk_z.to_string(), LABEL_OFFSET, label_y, GRID_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::ImageBuffer;
+
+    use super::*;
+
+    #[test]
+    fn lines_every_8_chunks() {
+        let area = Area::new(-16, 16, 0, 32);
+        let mut img: RgbImage = ImageBuffer::from_pixel(32, 32, Rgb([255, 255, 255]));
+        draw_grid(&mut img, &area, 8);
+
+        // Chunk X -16, -8, 0 and 8
+        for &x in &[0, 8, 16, 24] {
+            assert_eq!(*img.get_pixel(x, 21), GRID_COLOR);
+            assert_ne!(*img.get_pixel(x + 4, 21), GRID_COLOR);
+        }
+        // Chunk Z 0, 8, 16 and 24, counting from the bottom
+        for &y in &[31, 23, 15, 7] {
+            assert_eq!(*img.get_pixel(21, y), GRID_COLOR);
+            assert_ne!(*img.get_pixel(21, y - 1), GRID_COLOR);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use image::RgbImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// Vector image with one `<rect>` per pixel, for reports and zoomable viewing
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+
+    /// File extension for images in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+}
+
+pub fn save_image(img: &RgbImage, path: &str, format: ImageFormat) -> io::Result<()> {
+    match format {
+        ImageFormat::Png => img.save(path).map_err(io::Error::other),
+        ImageFormat::Svg => fs::write(Path::new(path), to_svg(img)),
+    }
+}
+
+/// Saves an image of the given size as square tiles of at most `tile_size` pixels instead of one file,
+/// so that the whole image never has to be held in memory. `render(x, y, width, height)` renders the
+/// part of the image with its top left corner at `(x, y)`. Tiles are independent and rendered in parallel.
+///
+/// Tiles are saved next to `path` as `<stem>_<column>_<row>.<extension>`, counting from the top left.
+/// Returns the paths of all tiles.
+pub fn save_tiles<F>(
+    path: &Path,
+    (width, height): (u32, u32),
+    tile_size: u32,
+    format: ImageFormat,
+    render: F,
+) -> io::Result<Vec<PathBuf>>
+where
+    F: Fn(u32, u32, u32, u32) -> RgbImage + Sync,
+{
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let tiles: Vec<(u32, u32, PathBuf)> = (0..height.div_ceil(tile_size))
+        .flat_map(|row| (0..width.div_ceil(tile_size)).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let name = format!("{}_{}_{}.{}", stem, column, row, format.extension());
+            (column, row, path.with_file_name(name))
+        })
+        .collect();
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let tiles_per_thread = tiles.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = tiles
+            .chunks(tiles_per_thread)
+            .map(|tiles| {
+                let render = &render;
+                scope.spawn(move || -> io::Result<()> {
+                    for (column, row, tile_path) in tiles {
+                        let (x, y) = (column * tile_size, row * tile_size);
+                        let tile =
+                            render(x, y, tile_size.min(width - x), tile_size.min(height - y));
+                        save_image(&tile, tile_path.to_str().unwrap(), format)?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().unwrap())
+    })?;
+
+    Ok(tiles
+        .into_iter()
+        .map(|(_, _, tile_path)| tile_path)
+        .collect())
+}
+
+pub fn to_svg(img: &RgbImage) -> String {
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" shape-rendering="crispEdges">"#,
+        img.width(),
+        img.height()
+    )
+    .unwrap();
+
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let [r, g, b] = pixel.0;
+        writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="1" height="1" fill="#{:02x}{:02x}{:02x}"/>"##,
+            x, y, r, g, b
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgb};
+
+    use super::*;
+    use crate::test_fixtures::TempDir;
+
+    #[test]
+    fn tiles() {
+        let dir = TempDir::new();
+        let path = dir.path().join("map.png");
+
+        // Every pixel is colored by its position in the whole image
+        let tiles = save_tiles(&path, (5, 3), 2, ImageFormat::Png, |x, y, width, height| {
+            ImageBuffer::from_fn(width, height, |tile_x, tile_y| {
+                Rgb([(x + tile_x) as u8, (y + tile_y) as u8, 0])
+            })
+        })
+        .unwrap();
+
+        assert_eq!(tiles.len(), 3 * 2);
+        for column in 0..3 {
+            for row in 0..2 {
+                let tile_path = dir.path().join(format!("map_{}_{}.png", column, row));
+                assert!(tiles.contains(&tile_path));
+
+                let tile = image::open(&tile_path).unwrap().to_rgb8();
+                // Tiles in the last column and row only cover what's left of the image
+                let size = (
+                    if column == 2 { 1 } else { 2 },
+                    if row == 1 { 1 } else { 2 },
+                );
+                assert_eq!(tile.dimensions(), size);
+                assert_eq!(
+                    *tile.get_pixel(0, 0),
+                    Rgb([column as u8 * 2, row as u8 * 2, 0])
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Stopping long scans early with Ctrl-C while keeping the results gathered so far.
+//!
+//! Once the handler is installed, the first Ctrl-C only sets a flag that analyzers check between
+//! chunks. A second Ctrl-C exits right away, in case the current chunk takes too long.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C request an interrupt instead of killing the process.
+/// Returns `false` if the handler couldn't be installed, Ctrl-C then works as usual.
+pub fn install_handler() -> bool {
+    platform::install()
+}
+
+/// Whether the scan should stop
+pub fn is_requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::raw::c_int;
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: c_int = 2;
+
+    /// `SIG_ERR`, which is `(void (*)(int)) -1`
+    const SIG_ERR: usize = usize::MAX;
+
+    /// Exit status of processes killed by SIGINT, as reported by shells
+    const INTERRUPTED_STATUS: c_int = 130;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    /// Only does what's allowed in a signal handler: an atomic swap and `_exit`
+    extern "C" fn handle(_signum: c_int) {
+        if super::INTERRUPTED.swap(true, Ordering::SeqCst) {
+            unsafe { _exit(INTERRUPTED_STATUS) }
+        }
+    }
+
+    pub fn install() -> bool {
+        unsafe { signal(SIGINT, handle) != SIG_ERR }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::sync::atomic::Ordering;
+
+    const CTRL_C_EVENT: u32 = 0;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: extern "system" fn(u32) -> i32, add: i32) -> i32;
+    }
+
+    /// Returning 0 passes the event on to the default handler, which exits the process
+    extern "system" fn handle(ctrl_type: u32) -> i32 {
+        if ctrl_type != CTRL_C_EVENT || super::INTERRUPTED.swap(true, Ordering::SeqCst) {
+            return 0;
+        }
+        1
+    }
+
+    pub fn install() -> bool {
+        unsafe { SetConsoleCtrlHandler(handle, 1) != 0 }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn install() -> bool {
+        false
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use crate::chunk_loader::{ChunkLoader, LoaderOptions};
+
+/// Histogram of when chunks were last updated, to find long-dormant areas
+pub struct LastUpdateAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Width of a histogram bucket in game ticks
+    bucket_size: i64,
+
+    /// first tick of bucket -> number of chunks last updated within that bucket
+    chunks_by_bucket: HashMap<i64, u32>,
+}
+
+impl<'a> LastUpdateAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions, bucket_size: i64) -> Self {
+        assert!(bucket_size > 0, "Bucket size must be positive");
+
+        LastUpdateAnalyzer {
+            // Blocks aren't needed, so don't decode any sections
+            chunk_loader: ChunkLoader::new(path, Some(0..0), loader_options),
+            bucket_size,
+            chunks_by_bucket: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let last_update = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.last_update,
+                None => continue,
+            };
+
+            let bucket = last_update.div_euclid(self.bucket_size) * self.bucket_size;
+            *self.chunks_by_bucket.entry(bucket).or_insert(0) += 1;
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut chunks_by_bucket: Vec<(&i64, &u32)> = self.chunks_by_bucket.iter().collect();
+        chunks_by_bucket.sort_unstable();
+
+        println!("Last update (tick),Chunks");
+        for (bucket, chunks) in chunks_by_bucket {
+            println!("{:12},{:8}", bucket, chunks);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, ops::RangeInclusive, path::Path};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::{ChunkSource, RegionFolderSource},
+    image_output::{save_image, save_tiles, ImageFormat},
+};
+
+/// Number of slices above which a warning about the amount of generated files is printed
+const MANY_SLICES: usize = 64;
+
+/// Renders one image per Y level showing where a blockstate occurs in that layer,
+/// with one pixel per block column
+pub struct LayerSliceAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    target: String,
+    y_range: RangeInclusive<i32>,
+    area: Area,
+
+    /// Y -> pixel positions of all target blocks in that layer
+    hits: HashMap<i32, Vec<(u32, u32)>>,
+}
+
+impl<'a> LayerSliceAnalyzer<'a> {
+    pub fn new(
+        path: &'a str,
+        area: Area,
+        target: String,
+        y_range: RangeInclusive<i32>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        LayerSliceAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            area,
+            target,
+            y_range,
+            loader_options,
+        )
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        target: String,
+        y_range: RangeInclusive<i32>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        let sections = y_range.start().div_euclid(CHUNK_SIZE as i32) as i8
+            ..y_range.end().div_euclid(CHUNK_SIZE as i32) as i8 + 1;
+
+        let slice_count = y_range.clone().count();
+        if slice_count > MANY_SLICES {
+            eprintln!(
+                "Warning: this will write {} images, one for every Y level",
+                slice_count
+            );
+        }
+
+        LayerSliceAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, Some(sections), loader_options),
+            target,
+            y_range,
+            area,
+            hits: HashMap::new(),
+        }
+    }
+
+    /// All chunks need to lie within the area the analyzer was created with
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for section in chunk {
+                for block in section.blocks() {
+                    let (x, y, z) = block.global_pos;
+
+                    if block.blockstate != self.target || !self.y_range.contains(&y) {
+                        continue;
+                    }
+
+                    let (pixel_x, pixel_z) = self.area.get_positive_block_coords(x, z);
+                    let pixel_y = self.area.block_width_z() - pixel_z - 1;
+
+                    self.hits.entry(y).or_default().push((pixel_x, pixel_y));
+                }
+            }
+        }
+    }
+
+    /// Writes `slice_<Y>.<format>` for every Y level in the range into the given folder.
+    /// With a tile size, every slice is split into tiles as described in `save_tiles`.
+    pub fn print_imgs(&self, folder: &Path, format: ImageFormat, tile_size: Option<u32>) {
+        let size = (self.area.block_width_x(), self.area.block_width_z());
+
+        for y in self.y_range.clone() {
+            let path = folder.join(format!("slice_{}.{}", y, format.extension()));
+            eprintln!("Saving {}", path.display());
+
+            let hits = self.hits.get(&y).map_or(&[][..], Vec::as_slice);
+
+            match tile_size {
+                Some(tile_size) => {
+                    save_tiles(&path, size, tile_size, format, |x, y, width, height| {
+                        render_hits(hits, x, y, width, height)
+                    })
+                    .unwrap();
+                }
+                None => {
+                    let img = render_hits(hits, 0, 0, size.0, size.1);
+                    save_image(&img, path.to_str().unwrap(), format).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Renders the part of a slice with its top left corner at `(x, y)`
+fn render_hits(hits: &[(u32, u32)], x: u32, y: u32, width: u32, height: u32) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, Rgb([255, 255, 255]));
+
+    for &(hit_x, hit_y) in hits {
+        if (x..x + width).contains(&hit_x) && (y..y + height).contains(&hit_y) {
+            img.put_pixel(hit_x - x, hit_y - y, Rgb([0, 0, 0]));
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, TempDir};
+
+    #[test]
+    fn block_at_its_pixel() {
+        // A single diamond ore at X 19, Y 5, Z 4
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:diamond_ore"],
+            |x, y, z| ((x, y, z) == (3, 5, 4)) as usize,
+        );
+        let source = test_fixtures::source(vec![test_fixtures::chunk(1, 0, vec![section])]);
+        let area = Area::new(0, 2, 0, 1);
+        let mut analyzer = LayerSliceAnalyzer::with_source(
+            source,
+            area,
+            "minecraft:diamond_ore".to_string(),
+            4..=6,
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(area);
+
+        let folder = TempDir::new();
+        analyzer.print_imgs(folder.path(), ImageFormat::Png, None);
+
+        // North is up, so Z 4 is the 5th row from the bottom
+        let black = Rgb([0, 0, 0]);
+        for y in 4..=6 {
+            let img = image::open(folder.path().join(format!("slice_{}.png", y)))
+                .unwrap()
+                .to_rgb8();
+            assert_eq!(img.dimensions(), (32, 16));
+
+            let black_pixels: Vec<(u32, u32)> = img
+                .enumerate_pixels()
+                .filter(|(_, _, pixel)| **pixel == black)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            if y == 5 {
+                assert_eq!(black_pixels, vec![(19, 11)]);
+            } else {
+                assert!(black_pixels.is_empty());
+            }
+        }
+    }
+}
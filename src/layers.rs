@@ -1,15 +1,50 @@
 use std::collections::HashMap;
 
 pub struct Layer {
-    composition: HashMap<String, u32>,
+    composition: HashMap<String, u64>,
+    /// Lowest Y of the layer
     pub y: i32,
+    /// Number of Y levels merged into this layer, 1 unless it's a band of `Layers::bands`
+    pub height: i32,
 }
 
 impl Layer {
-    pub fn get_count(&self, blockstate: &str) -> u32 {
+    /// The Y of the layer, or the range of Y levels of a band, e.g. `-64..-49`
+    pub fn label(&self) -> String {
+        if self.height == 1 {
+            self.y.to_string()
+        } else {
+            format!("{}..{}", self.y, self.y + self.height - 1)
+        }
+    }
+
+    pub fn get_count(&self, blockstate: &str) -> u64 {
         *self.composition.get(blockstate).unwrap_or(&0)
     }
 
+    /// Change in the count of a blockstate from the layer below, 0 for the lowest layer
+    pub fn delta(&self, below: Option<&Layer>, blockstate: &str) -> i64 {
+        let count = self.get_count(blockstate);
+        let below_count = below.map_or(count, |below| below.get_count(blockstate));
+        count as i64 - below_count as i64
+    }
+
+    /// Number of blocks counted in this layer
+    pub fn total(&self) -> u64 {
+        self.composition.values().sum()
+    }
+
+    /// Count of every blockstate in this layer, most common first
+    pub fn counts(&self) -> Vec<(&str, u64)> {
+        let mut counts: Vec<(&str, u64)> = self
+            .composition
+            .iter()
+            .map(|(blockstate, count)| (blockstate.as_str(), *count))
+            .collect();
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        counts
+    }
+
     pub fn increment(&mut self, blockstate: &str) {
         let prev_count = self.get_count(blockstate);
         self.composition
@@ -17,6 +52,7 @@ impl Layer {
     }
 }
 
+#[derive(Default)]
 pub struct Layers {
     layers: HashMap<i32, Layer>,
 }
@@ -38,12 +74,47 @@ impl Layers {
                 Layer {
                     composition,
                     y: layer,
+                    height: 1,
                 },
             );
         }
     }
 }
 
+impl Layers {
+    pub fn get(&self, y: i32) -> Option<&Layer> {
+        self.layers.get(&y)
+    }
+
+    /// Merges the layers into bands of `height` Y levels, summing their counts.
+    /// Bands start at multiples of `height`, so that e.g. bands of 16 match the sections.
+    pub fn bands(self, height: i32) -> Layers {
+        let mut bands: HashMap<i32, Layer> = HashMap::new();
+
+        for (y, layer) in self.layers {
+            let band_y = y.div_euclid(height) * height;
+            let band = bands.entry(band_y).or_insert_with(|| Layer {
+                composition: HashMap::new(),
+                y: band_y,
+                height,
+            });
+
+            for (blockstate, count) in layer.composition {
+                *band.composition.entry(blockstate).or_insert(0) += count;
+            }
+        }
+
+        Layers { layers: bands }
+    }
+
+    /// All layers sorted from bottom to top
+    pub fn iter(&self) -> impl Iterator<Item = &Layer> {
+        let mut list: Vec<&Layer> = self.layers.values().collect();
+        list.sort_by_key(|layer| layer.y);
+        list.into_iter()
+    }
+}
+
 impl IntoIterator for Layers {
     type Item = Layer;
 
@@ -51,10 +122,61 @@ impl IntoIterator for Layers {
 
     fn into_iter(self) -> Self::IntoIter {
         let mut list = self.layers.into_iter().collect::<Vec<_>>();
-        list.sort_by(|a, b| a.0.cmp(&b.0));
+        list.sort_by_key(|(y, _)| *y);
 
         let list = list.into_iter().map(|(_, layer)| layer).collect::<Vec<_>>();
 
         list.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands() {
+        // One stone in each of 48 layers, and two dirt in each of the lowest 16
+        let mut layers = Layers::new();
+        for y in 0..48 {
+            layers.increment("minecraft:stone", y);
+            if y < 16 {
+                layers.increment("minecraft:dirt", y);
+                layers.increment("minecraft:dirt", y);
+            }
+        }
+
+        let bands: Vec<_> = layers.bands(16).into_iter().collect();
+        assert_eq!(bands.len(), 3);
+
+        let labels: Vec<String> = bands.iter().map(|band| band.label()).collect();
+        assert_eq!(labels, vec!["0..15", "16..31", "32..47"]);
+
+        let stone: Vec<u64> = bands
+            .iter()
+            .map(|band| band.get_count("minecraft:stone"))
+            .collect();
+        assert_eq!(stone, vec![16, 16, 16]);
+        let dirt: Vec<u64> = bands
+            .iter()
+            .map(|band| band.get_count("minecraft:dirt"))
+            .collect();
+        assert_eq!(dirt, vec![32, 0, 0]);
+    }
+
+    #[test]
+    fn bands_past_u32_max() {
+        let layer = |y| Layer {
+            composition: HashMap::from([("minecraft:air".to_string(), u32::MAX as u64)]),
+            y,
+            height: 1,
+        };
+        let layers = Layers {
+            layers: HashMap::from([(0, layer(0)), (1, layer(1))]),
+        };
+
+        let band = layers.bands(16).into_iter().next().unwrap();
+        assert_eq!(band.get_count("minecraft:air"), 2 * u32::MAX as u64);
+        assert_eq!(band.total(), 2 * u32::MAX as u64);
+    }
+}
@@ -0,0 +1,137 @@
+use std::cmp::max;
+
+use image::{imageops, ImageBuffer, Rgb, RgbImage};
+
+const SWATCH_SIZE: u32 = 12;
+const MARGIN: u32 = 4;
+const LABEL_SPACING: u32 = 2;
+
+pub(crate) const GLYPH_WIDTH: u32 = 3;
+pub(crate) const GLYPH_HEIGHT: u32 = 5;
+
+/// 3x5 bitmap glyphs for the digits 0-9, one row per entry, most significant bit on the left
+const DIGIT_GLYPHS: [[u8; GLYPH_HEIGHT as usize]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Glyph for the minus sign of negative numbers
+const MINUS_GLYPH: [u8; GLYPH_HEIGHT as usize] = [0b000, 0b000, 0b111, 0b000, 0b000];
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const FOREGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Returns a copy of `map` with a legend strip below it.
+/// The legend shows one color swatch per entry of `values`, labeled with that value,
+/// so the canvas grows by the height of the legend (and in width if the legend is wider than the map).
+pub fn with_legend<F>(map: &RgbImage, values: &[u32], color_for: F) -> RgbImage
+where
+    F: Fn(u32) -> Rgb<u8>,
+{
+    let legend_width = values.len() as u32 * SWATCH_SIZE + 2 * MARGIN;
+    let legend_height = MARGIN + SWATCH_SIZE + LABEL_SPACING + GLYPH_HEIGHT + MARGIN;
+
+    let mut canvas: RgbImage = ImageBuffer::from_pixel(
+        max(map.width(), legend_width),
+        map.height() + legend_height,
+        BACKGROUND,
+    );
+    imageops::replace(&mut canvas, map, 0, 0);
+
+    let swatch_y = map.height() + MARGIN;
+
+    for (index, &value) in values.iter().enumerate() {
+        let swatch_x = MARGIN + index as u32 * SWATCH_SIZE;
+        let color = color_for(value);
+
+        for y in swatch_y..swatch_y + SWATCH_SIZE {
+            for x in swatch_x..swatch_x + SWATCH_SIZE {
+                canvas.put_pixel(x, y, color);
+            }
+        }
+
+        let label = value.to_string();
+        let label_width = label.len() as u32 * (GLYPH_WIDTH + 1) - 1;
+        let label_x = (swatch_x + SWATCH_SIZE / 2).saturating_sub(label_width / 2);
+
+        draw_number(
+            &mut canvas,
+            &label,
+            label_x,
+            swatch_y + SWATCH_SIZE + LABEL_SPACING,
+            FOREGROUND,
+        );
+    }
+
+    canvas
+}
+
+/// Draws a number of digits and minus signs, leaving out pixels outside the canvas
+pub(crate) fn draw_number(canvas: &mut RgbImage, number: &str, x: u32, y: u32, color: Rgb<u8>) {
+    let glyphs = number.chars().filter_map(|c| match c {
+        '-' => Some(MINUS_GLYPH),
+        c => Some(DIGIT_GLYPHS[c.to_digit(10)? as usize]),
+    });
+
+    for (index, glyph) in glyphs.enumerate() {
+        let glyph_x = x + index as u32 * (GLYPH_WIDTH + 1);
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                let (px, py) = (glyph_x + column, y + row as u32);
+                if px < canvas.width() && py < canvas.height() {
+                    canvas.put_pixel(px, py, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: u32) -> Rgb<u8> {
+        Rgb([value as u8; 3])
+    }
+
+    #[test]
+    fn legend_below_map() {
+        let map: RgbImage = ImageBuffer::from_pixel(64, 32, Rgb([1, 2, 3]));
+        let canvas = with_legend(&map, &[0, 10, 20], gray);
+
+        assert_eq!(canvas.width(), 64);
+        assert_eq!(
+            canvas.height(),
+            32 + MARGIN + SWATCH_SIZE + LABEL_SPACING + GLYPH_HEIGHT + MARGIN
+        );
+        // The map is unchanged, and the second swatch is right below it
+        assert_eq!(*canvas.get_pixel(63, 31), Rgb([1, 2, 3]));
+        assert_eq!(
+            *canvas.get_pixel(MARGIN + SWATCH_SIZE, 32 + MARGIN),
+            gray(10)
+        );
+    }
+
+    #[test]
+    fn legend_wider_than_map() {
+        let map: RgbImage = ImageBuffer::from_pixel(8, 8, Rgb([0, 0, 0]));
+        let values: Vec<u32> = (0..10).collect();
+        let canvas = with_legend(&map, &values, gray);
+
+        assert_eq!(canvas.width(), 10 * SWATCH_SIZE + 2 * MARGIN);
+        assert!(canvas.height() > map.height());
+    }
+}
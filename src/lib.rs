@@ -0,0 +1,98 @@
+//! The `mca-analyzer` command line tool. Everything is run through `run`. Besides it, only the parts
+//! that the benchmarks measure are public, and `CompositionAnalyzer` for library users who want to
+//! rename blocks while counting with a `BlockRemap`.
+
+mod adjacency_analyzer;
+mod area;
+mod artificial_block_analyzer;
+mod biome_height_analyzer;
+mod biome_naturalness_analyzer;
+mod block_entities;
+mod block_entity_diff_analyzer;
+mod block_id;
+mod block_list;
+mod block_tags;
+mod carving_analyzer;
+mod centroid_analyzer;
+mod chunk;
+mod chunk_dump;
+mod chunk_list;
+mod chunk_loader;
+mod chunk_section;
+mod chunk_source;
+mod chunk_summary_analyzer;
+mod cli;
+mod co_occurrence_analyzer;
+mod composition_analyzer;
+mod composition_results;
+mod coords;
+mod core_sample;
+mod csv_append;
+mod data_version_analyzer;
+mod diamond_vein_analyzer;
+mod dimension;
+mod distance_band_analyzer;
+mod diversity_analyzer;
+mod duplicate_chunk_analyzer;
+mod entities;
+mod entity_category_analyzer;
+mod event_log;
+mod exposure_analyzer;
+mod fingerprint_analyzer;
+mod flamegraph;
+mod flat_world_analyzer;
+mod floating_block_analyzer;
+mod floor_analyzer;
+mod generated_chunk_analyzer;
+mod grid;
+mod image_output;
+mod interrupt;
+mod last_update_analyzer;
+mod layer_slice_analyzer;
+mod layers;
+mod legend;
+mod light_source_analyzer;
+mod mcregion;
+mod mining_time;
+mod ndjson;
+mod number_format;
+mod ore_defs;
+mod output_dir;
+mod overview_analyzer;
+mod packed_longs;
+mod palette;
+mod palette_efficiency_analyzer;
+mod poi_analyzer;
+mod polygon_area;
+mod presence_analyzer;
+mod presets;
+mod profile_chart;
+mod progress;
+mod redstone_analyzer;
+mod region_archive;
+mod region_list;
+mod region_matrix_analyzer;
+mod run_config;
+mod scan_size;
+mod scheduled_tick_analyzer;
+mod section_density_analyzer;
+mod section_index_analyzer;
+mod selective_nbt;
+mod structure_block_analyzer;
+mod structure_composition_analyzer;
+mod structures;
+mod technical_block_analyzer;
+#[cfg(test)]
+mod test_fixtures;
+mod world_path;
+
+pub use crate::{
+    chunk::ChunkFormat,
+    chunk_loader::{CacheStats, LoaderOptions, UNUSED_CHUNK_TAGS},
+    chunk_section::{ChunkSection, CHUNK_SIZE},
+    cli::run,
+    composition_analyzer::{BlockRemap, CompositionAnalyzer, CompositionOptions},
+    composition_results::CompositionResults,
+    palette::PaletteOptions,
+    selective_nbt::read_compound_tag_skipping,
+};
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    palette::Properties,
+};
+
+/// How much light a block emits, which for some blocks depends on their state
+#[derive(Debug, Clone, Copy)]
+pub enum LightEmission {
+    Always(u8),
+    /// Only while the `lit` property is `true`, e.g. furnaces
+    WhenLit(u8),
+    /// Only while the given property is `true`, e.g. `berries` of cave vines
+    When(&'static str, u8),
+    /// Given by the `level` property, for light blocks
+    Level,
+    /// 3 per candle while lit
+    Candles,
+    /// 3 per pickle while waterlogged
+    Pickles,
+    /// Depends on the number of `charges`
+    Charges,
+}
+
+impl LightEmission {
+    /// Light level of a block with the given properties, 0 if it doesn't emit any
+    pub fn light_level(self, properties: Option<&Properties>) -> u8 {
+        let property = |name: &str| properties.and_then(|properties| properties.get(name));
+        let is_true = |name: &str| property(name).is_some_and(|value| value == "true");
+        let number = |name: &str| {
+            property(name)
+                .and_then(|value| value.parse::<u8>().ok())
+                .unwrap_or(0)
+        };
+
+        match self {
+            LightEmission::Always(level) => level,
+            LightEmission::WhenLit(level) if is_true("lit") => level,
+            LightEmission::When(name, level) if is_true(name) => level,
+            LightEmission::Level => number("level"),
+            LightEmission::Candles if is_true("lit") => 3 * number("candles"),
+            LightEmission::Pickles if is_true("waterlogged") => 3 * number("pickles"),
+            LightEmission::Charges => [0, 3, 7, 11, 15][number("charges").min(4) as usize],
+            _ => 0,
+        }
+    }
+}
+
+/// Blocks that emit light and how much, as of 1.20
+pub const LIGHT_SOURCES: &[(&str, LightEmission)] = &[
+    ("minecraft:beacon", LightEmission::Always(15)),
+    ("minecraft:conduit", LightEmission::Always(15)),
+    ("minecraft:end_gateway", LightEmission::Always(15)),
+    ("minecraft:end_portal", LightEmission::Always(15)),
+    ("minecraft:fire", LightEmission::Always(15)),
+    ("minecraft:glowstone", LightEmission::Always(15)),
+    ("minecraft:jack_o_lantern", LightEmission::Always(15)),
+    ("minecraft:lantern", LightEmission::Always(15)),
+    ("minecraft:lava", LightEmission::Always(15)),
+    ("minecraft:sea_lantern", LightEmission::Always(15)),
+    ("minecraft:shroomlight", LightEmission::Always(15)),
+    ("minecraft:ochre_froglight", LightEmission::Always(15)),
+    ("minecraft:verdant_froglight", LightEmission::Always(15)),
+    ("minecraft:pearlescent_froglight", LightEmission::Always(15)),
+    ("minecraft:campfire", LightEmission::WhenLit(15)),
+    ("minecraft:redstone_lamp", LightEmission::WhenLit(15)),
+    ("minecraft:respawn_anchor", LightEmission::Charges),
+    ("minecraft:torch", LightEmission::Always(14)),
+    ("minecraft:wall_torch", LightEmission::Always(14)),
+    ("minecraft:end_rod", LightEmission::Always(14)),
+    ("minecraft:cave_vines", LightEmission::When("berries", 14)),
+    (
+        "minecraft:cave_vines_plant",
+        LightEmission::When("berries", 14),
+    ),
+    ("minecraft:furnace", LightEmission::WhenLit(13)),
+    ("minecraft:blast_furnace", LightEmission::WhenLit(13)),
+    ("minecraft:smoker", LightEmission::WhenLit(13)),
+    ("minecraft:sea_pickle", LightEmission::Pickles),
+    ("minecraft:nether_portal", LightEmission::Always(11)),
+    ("minecraft:crying_obsidian", LightEmission::Always(10)),
+    ("minecraft:soul_fire", LightEmission::Always(10)),
+    ("minecraft:soul_lantern", LightEmission::Always(10)),
+    ("minecraft:soul_torch", LightEmission::Always(10)),
+    ("minecraft:soul_wall_torch", LightEmission::Always(10)),
+    ("minecraft:soul_campfire", LightEmission::WhenLit(10)),
+    ("minecraft:redstone_ore", LightEmission::WhenLit(9)),
+    (
+        "minecraft:deepslate_redstone_ore",
+        LightEmission::WhenLit(9),
+    ),
+    ("minecraft:enchanting_table", LightEmission::Always(7)),
+    ("minecraft:ender_chest", LightEmission::Always(7)),
+    ("minecraft:glow_lichen", LightEmission::Always(7)),
+    ("minecraft:redstone_torch", LightEmission::WhenLit(7)),
+    ("minecraft:redstone_wall_torch", LightEmission::WhenLit(7)),
+    ("minecraft:sculk_catalyst", LightEmission::Always(6)),
+    ("minecraft:amethyst_cluster", LightEmission::Always(5)),
+    ("minecraft:large_amethyst_bud", LightEmission::Always(4)),
+    ("minecraft:magma_block", LightEmission::Always(3)),
+    ("minecraft:candle_cake", LightEmission::WhenLit(3)),
+    ("minecraft:medium_amethyst_bud", LightEmission::Always(2)),
+    ("minecraft:brewing_stand", LightEmission::Always(1)),
+    ("minecraft:brown_mushroom", LightEmission::Always(1)),
+    ("minecraft:dragon_egg", LightEmission::Always(1)),
+    ("minecraft:end_portal_frame", LightEmission::Always(1)),
+    ("minecraft:sculk_sensor", LightEmission::Always(1)),
+    (
+        "minecraft:calibrated_sculk_sensor",
+        LightEmission::Always(1),
+    ),
+    ("minecraft:small_amethyst_bud", LightEmission::Always(1)),
+    ("minecraft:light", LightEmission::Level),
+];
+
+/// How a blockstate emits light, if it does at all
+pub fn light_emission(blockstate: &str) -> Option<LightEmission> {
+    if let Some((_, emission)) = LIGHT_SOURCES.iter().find(|(name, _)| *name == blockstate) {
+        return Some(*emission);
+    }
+
+    // Candles come in 17 colors, all of which work the same way
+    if blockstate.ends_with("candle") {
+        return Some(LightEmission::Candles);
+    }
+    if blockstate.ends_with("candle_cake") {
+        return Some(LightEmission::WhenLit(3));
+    }
+
+    None
+}
+
+/// Counts light-emitting blocks by the light level they currently emit.
+/// Blocks that could emit light but currently don't (e.g. unlit furnaces) aren't counted.
+pub struct LightSourceAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// (blockstate, light level) -> count
+    counts: HashMap<(String, u8), u32>,
+}
+
+impl<'a> LightSourceAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        LightSourceAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        LightSourceAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for section in chunk {
+                for block in section.blocks() {
+                    let emission = match light_emission(&block.blockstate) {
+                        Some(emission) => emission,
+                        None => continue,
+                    };
+
+                    let (x, y, z) = block.chunk_pos;
+                    let light_level = emission.light_level(section.get_properties_at(x, y, z));
+                    if light_level == 0 {
+                        continue;
+                    }
+
+                    *self
+                        .counts
+                        .entry((block.blockstate, light_level))
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    /// Prints the brightest light sources first
+    pub fn print_csv(&self, id_form: IdForm) {
+        let mut counts: Vec<(&(String, u8), &u32)> = self.counts.iter().collect();
+        counts.sort_by(|((a_name, a_level), a), ((b_name, b_level), b)| {
+            b_level.cmp(a_level).then(b.cmp(a)).then(a_name.cmp(b_name))
+        });
+
+        println!("Block,Light level,Count");
+        for ((blockstate, light_level), count) in counts {
+            println!("{},{},{:8}", id_form.render(blockstate), light_level, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn torches_and_glowstone() {
+        let palette = vec![
+            test_fixtures::palette_entry("minecraft:air", &[]),
+            test_fixtures::palette_entry("minecraft:torch", &[]),
+            test_fixtures::palette_entry("minecraft:glowstone", &[]),
+            test_fixtures::palette_entry("minecraft:furnace", &[("lit", "false")]),
+        ];
+        // Three torches, two glowstone and a furnace that isn't lit
+        let section = test_fixtures::section_with_palette(0, palette, |x, y, z| match (x, y, z) {
+            (0..=2, 1, 0) => 1,
+            (5, 0..=1, 5) => 2,
+            (9, 0, 9) => 3,
+            _ => 0,
+        });
+        let mut analyzer = LightSourceAnalyzer::with_source(
+            test_fixtures::source(vec![test_fixtures::chunk(0, 0, vec![section])]),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        let mut counts: Vec<(&(String, u8), &u32)> = analyzer.counts.iter().collect();
+        counts.sort();
+        assert_eq!(
+            counts,
+            vec![
+                (&("minecraft:glowstone".to_string(), 15), &2),
+                (&("minecraft:torch".to_string(), 14), &3),
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Chunks of the McRegion format (Beta 1.3 to 1.1), stored in `.mcr` files.
+//!
+//! The region files themselves are laid out like `.mca` files, but chunks store all of their
+//! blocks in a single `Blocks` array of numeric block IDs, 128 blocks high, with a `Data` array
+//! of 4-bit values alongside it. These are converted to regular sections with the modern names
+//! of the blocks, so that they can be analyzed like any other chunk.
+
+use std::collections::HashMap;
+
+use nbt::CompoundTag;
+
+use crate::{
+    chunk_section::{ChunkSection, CHUNK_SIZE},
+    palette::Palette,
+};
+
+/// File extension of McRegion region files
+pub const MCREGION_EXTENSION: &str = "mcr";
+
+/// Height of the world, which the `Blocks` array always covers completely
+const WORLD_HEIGHT: usize = 128;
+
+const SECTION_COUNT: usize = WORLD_HEIGHT / CHUNK_SIZE;
+
+const BLOCK_COUNT: usize = CHUNK_SIZE * CHUNK_SIZE * WORLD_HEIGHT;
+
+/// Whether the chunk (its `Level` tag) stores its blocks in the McRegion format
+pub fn is_mcregion_chunk(level: &CompoundTag) -> bool {
+    level.get_i8_vec("Blocks").is_ok() && level.get_compound_tag_vec("Sections").is_err()
+}
+
+/// Converts the `Blocks` and `Data` arrays of a chunk's `Level` tag into sections.
+/// Sections that are entirely air are left out, like newer versions don't store them.
+/// Returns no sections if the arrays don't have one entry per block.
+pub fn sections_from_level(level: &CompoundTag, x: i32, z: i32) -> Vec<ChunkSection> {
+    let blocks = match level.get_i8_vec("Blocks") {
+        Ok(blocks) if blocks.len() == BLOCK_COUNT => blocks,
+        Ok(blocks) => {
+            eprintln!(
+                "Warning: chunk ({},{}) has {} Blocks entries instead of {}, skipping it",
+                x,
+                z,
+                blocks.len(),
+                BLOCK_COUNT
+            );
+            return Vec::new();
+        }
+        Err(_) => return Vec::new(),
+    };
+    // Missing or truncated `Data` only loses the block variants, not the blocks themselves
+    let data = level.get_i8_vec("Data").map(Vec::as_slice).unwrap_or(&[]);
+
+    (0..SECTION_COUNT)
+        .filter_map(|section_y| {
+            let mut states: Vec<String> = Vec::new();
+            let mut ids: HashMap<(u8, u8), usize> = HashMap::new();
+            let mut indices = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+
+            // Sections store blocks ordered by Y, then Z, then X,
+            // while `Blocks` is ordered by X, then Z, then Y
+            for y in 0..CHUNK_SIZE {
+                for local_z in 0..CHUNK_SIZE {
+                    for local_x in 0..CHUNK_SIZE {
+                        let index = (local_x * CHUNK_SIZE + local_z) * WORLD_HEIGHT
+                            + section_y * CHUNK_SIZE
+                            + y;
+
+                        let id = blocks[index] as u8;
+                        let value = data
+                            .get(index / 2)
+                            .map_or(0, |&byte| (byte as u8 >> (4 * (index % 2))) & 0xf);
+
+                        let next_id = states.len();
+                        let palette_id = *ids.entry((id, value)).or_insert_with(|| {
+                            states.push(legacy_block_name(id, value));
+                            next_id
+                        });
+                        indices.push(palette_id);
+                    }
+                }
+            }
+
+            if ids.keys().all(|&(id, _)| id == 0) {
+                return None;
+            }
+
+            Some(ChunkSection::from_blocks(
+                (x, section_y as i8, z),
+                Palette::from_states(states),
+                &indices,
+            ))
+        })
+        .collect()
+}
+
+/// Modern name of a block given its numeric ID and data value.
+/// The data value only matters for the few blocks whose variants are separate blocks today;
+/// IDs that didn't exist yet are named `legacy:<id>`.
+pub fn legacy_block_name(id: u8, data: u8) -> String {
+    const WOOD: [&str; 4] = ["oak", "spruce", "birch", "jungle"];
+    const COLORS: [&str; 16] = [
+        "white",
+        "orange",
+        "magenta",
+        "light_blue",
+        "yellow",
+        "lime",
+        "pink",
+        "gray",
+        "light_gray",
+        "cyan",
+        "purple",
+        "blue",
+        "brown",
+        "green",
+        "red",
+        "black",
+    ];
+
+    let name = match id {
+        0 => "air",
+        1 => "stone",
+        2 => "grass_block",
+        3 => "dirt",
+        4 => "cobblestone",
+        5 => "oak_planks",
+        6 => return format!("minecraft:{}_sapling", WOOD[(data & 3) as usize]),
+        7 => "bedrock",
+        8 | 9 => "water",
+        10 | 11 => "lava",
+        12 => "sand",
+        13 => "gravel",
+        14 => "gold_ore",
+        15 => "iron_ore",
+        16 => "coal_ore",
+        17 => return format!("minecraft:{}_log", WOOD[(data & 3) as usize]),
+        18 => return format!("minecraft:{}_leaves", WOOD[(data & 3) as usize]),
+        19 => "sponge",
+        20 => "glass",
+        21 => "lapis_ore",
+        22 => "lapis_block",
+        23 => "dispenser",
+        24 => "sandstone",
+        25 => "note_block",
+        26 => "red_bed",
+        27 => "powered_rail",
+        28 => "detector_rail",
+        29 => "sticky_piston",
+        30 => "cobweb",
+        31 => match data {
+            0 => "dead_bush",
+            2 => "fern",
+            _ => "grass",
+        },
+        32 => "dead_bush",
+        33 => "piston",
+        34 => "piston_head",
+        35 => return format!("minecraft:{}_wool", COLORS[data as usize]),
+        36 => "moving_piston",
+        37 => "dandelion",
+        38 => "poppy",
+        39 => "brown_mushroom",
+        40 => "red_mushroom",
+        41 => "gold_block",
+        42 => "iron_block",
+        43 | 44 => match data & 7 {
+            1 => "sandstone_slab",
+            2 => "petrified_oak_slab",
+            3 => "cobblestone_slab",
+            4 => "brick_slab",
+            5 => "stone_brick_slab",
+            _ => "smooth_stone_slab",
+        },
+        45 => "bricks",
+        46 => "tnt",
+        47 => "bookshelf",
+        48 => "mossy_cobblestone",
+        49 => "obsidian",
+        50 => "torch",
+        51 => "fire",
+        52 => "spawner",
+        53 => "oak_stairs",
+        54 => "chest",
+        55 => "redstone_wire",
+        56 => "diamond_ore",
+        57 => "diamond_block",
+        58 => "crafting_table",
+        59 => "wheat",
+        60 => "farmland",
+        61 | 62 => "furnace",
+        63 => "oak_sign",
+        64 => "oak_door",
+        65 => "ladder",
+        66 => "rail",
+        67 => "cobblestone_stairs",
+        68 => "oak_wall_sign",
+        69 => "lever",
+        70 => "stone_pressure_plate",
+        71 => "iron_door",
+        72 => "oak_pressure_plate",
+        73 | 74 => "redstone_ore",
+        75 | 76 => "redstone_torch",
+        77 => "stone_button",
+        78 => "snow",
+        79 => "ice",
+        80 => "snow_block",
+        81 => "cactus",
+        82 => "clay",
+        83 => "sugar_cane",
+        84 => "jukebox",
+        85 => "oak_fence",
+        86 => "carved_pumpkin",
+        87 => "netherrack",
+        88 => "soul_sand",
+        89 => "glowstone",
+        90 => "nether_portal",
+        91 => "jack_o_lantern",
+        92 => "cake",
+        93 | 94 => "repeater",
+        // The locked chest of April Fools' 2011
+        95 => "chest",
+        96 => "oak_trapdoor",
+        97 => match data {
+            1 => "infested_cobblestone",
+            2 => "infested_stone_bricks",
+            _ => "infested_stone",
+        },
+        98 => match data {
+            1 => "mossy_stone_bricks",
+            2 => "cracked_stone_bricks",
+            3 => "chiseled_stone_bricks",
+            _ => "stone_bricks",
+        },
+        99 => "brown_mushroom_block",
+        100 => "red_mushroom_block",
+        101 => "iron_bars",
+        102 => "glass_pane",
+        103 => "melon",
+        104 => "pumpkin_stem",
+        105 => "melon_stem",
+        106 => "vine",
+        107 => "oak_fence_gate",
+        108 => "brick_stairs",
+        109 => "stone_brick_stairs",
+        110 => "mycelium",
+        111 => "lily_pad",
+        112 => "nether_bricks",
+        113 => "nether_brick_fence",
+        114 => "nether_brick_stairs",
+        115 => "nether_wart",
+        116 => "enchanting_table",
+        117 => "brewing_stand",
+        118 => "cauldron",
+        119 => "end_portal",
+        120 => "end_portal_frame",
+        121 => "end_stone",
+        122 => "dragon_egg",
+        _ => return format!("legacy:{}", id),
+    };
+
+    format!("minecraft:{}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunk_loader::LoaderOptions,
+        composition_analyzer::{CompositionAnalyzer, CompositionOptions},
+        test_fixtures::{self, TempDir},
+    };
+
+    #[test]
+    fn mcregion_world() {
+        // Bedrock, stone up to Y 60 and a single block of red wool on top of it at (0, 61, 0)
+        let chunk = test_fixtures::mcregion_chunk(0, 0, |x, y, z| match y {
+            0 => (7, 0),
+            1..=60 => (1, 0),
+            61 if (x, z) == (0, 0) => (35, 14),
+            _ => (0, 0),
+        });
+        let dir = TempDir::new();
+        test_fixtures::write_region_file(
+            &dir.path().join(format!("r.0.0.{}", MCREGION_EXTENSION)),
+            vec![((0, 0), 2, test_fixtures::zlib(chunk))],
+        );
+
+        let mut analyzer = CompositionAnalyzer::new(
+            dir.as_str(),
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        // Only the four sections up to Y 63 have blocks other than air
+        assert_eq!(
+            analyzer.totals(),
+            vec![
+                ("minecraft:stone".to_string(), 60 * 256),
+                ("minecraft:air".to_string(), 3 * 256 - 1),
+                ("minecraft:bedrock".to_string(), 256),
+                ("minecraft:red_wool".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_block_names() {
+        assert_eq!(legacy_block_name(1, 0), "minecraft:stone");
+        assert_eq!(legacy_block_name(17, 2), "minecraft:birch_log");
+        assert_eq!(legacy_block_name(35, 14), "minecraft:red_wool");
+    }
+}
@@ -0,0 +1,250 @@
+//! Estimates how long it takes to dig out a scanned area, from the hardness of its blocks.
+//!
+//! Every block is assumed to be mined with the right diamond tool, without enchantments or
+//! effects, standing on the ground. Blocks break once their accumulated damage reaches 1, taking
+//! `hardness * 30 / 8` ticks rounded up, or no time at all if that's at most one tick.
+
+use std::collections::HashMap;
+
+use crate::{
+    block_id::{split_namespace, DEFAULT_NAMESPACE},
+    palette::is_air,
+};
+
+/// Mining speed of diamond tools on the blocks they're made for
+pub const DIAMOND_TOOL_SPEED: f64 = 8.0;
+
+/// Game ticks per second
+pub const TICKS_PER_SECOND: u64 = 20;
+
+/// Hardness of the most common natural blocks, and of the ores and building blocks found in
+/// larger numbers. Blocks that can't be mined by players, like bedrock and fluids, are
+/// in `UNBREAKABLE_BLOCKS` instead.
+const BLOCK_HARDNESS: &[(&str, f64)] = &[
+    ("grass", 0.0),
+    ("short_grass", 0.0),
+    ("tall_grass", 0.0),
+    ("fern", 0.0),
+    ("dead_bush", 0.0),
+    ("glow_lichen", 0.2),
+    ("vine", 0.2),
+    ("snow", 0.1),
+    ("oak_leaves", 0.2),
+    ("spruce_leaves", 0.2),
+    ("birch_leaves", 0.2),
+    ("jungle_leaves", 0.2),
+    ("acacia_leaves", 0.2),
+    ("dark_oak_leaves", 0.2),
+    ("mangrove_leaves", 0.2),
+    ("azalea_leaves", 0.2),
+    ("stone", 1.5),
+    ("granite", 1.5),
+    ("diorite", 1.5),
+    ("andesite", 1.5),
+    ("tuff", 1.5),
+    ("calcite", 0.75),
+    ("dripstone_block", 1.5),
+    ("deepslate", 3.0),
+    ("cobblestone", 2.0),
+    ("mossy_cobblestone", 2.0),
+    ("cobbled_deepslate", 3.5),
+    ("smooth_basalt", 1.25),
+    ("amethyst_block", 1.5),
+    ("budding_amethyst", 1.5),
+    ("dirt", 0.5),
+    ("coarse_dirt", 0.5),
+    ("rooted_dirt", 0.5),
+    ("grass_block", 0.6),
+    ("podzol", 0.5),
+    ("mycelium", 0.6),
+    ("mud", 0.5),
+    ("clay", 0.6),
+    ("moss_block", 0.1),
+    ("sand", 0.5),
+    ("red_sand", 0.5),
+    ("gravel", 0.6),
+    ("sandstone", 0.8),
+    ("red_sandstone", 0.8),
+    ("terracotta", 1.25),
+    ("ice", 0.5),
+    ("packed_ice", 0.5),
+    ("blue_ice", 2.8),
+    ("snow_block", 0.2),
+    ("powder_snow", 0.25),
+    ("coal_ore", 3.0),
+    ("iron_ore", 3.0),
+    ("copper_ore", 3.0),
+    ("gold_ore", 3.0),
+    ("redstone_ore", 3.0),
+    ("lapis_ore", 3.0),
+    ("diamond_ore", 3.0),
+    ("emerald_ore", 3.0),
+    ("deepslate_coal_ore", 4.5),
+    ("deepslate_iron_ore", 4.5),
+    ("deepslate_copper_ore", 4.5),
+    ("deepslate_gold_ore", 4.5),
+    ("deepslate_redstone_ore", 4.5),
+    ("deepslate_lapis_ore", 4.5),
+    ("deepslate_diamond_ore", 4.5),
+    ("deepslate_emerald_ore", 4.5),
+    ("raw_iron_block", 5.0),
+    ("raw_copper_block", 5.0),
+    ("netherrack", 0.4),
+    ("nether_gold_ore", 3.0),
+    ("nether_quartz_ore", 3.0),
+    ("ancient_debris", 30.0),
+    ("basalt", 1.25),
+    ("blackstone", 1.5),
+    ("magma_block", 0.5),
+    ("soul_sand", 0.5),
+    ("soul_soil", 0.5),
+    ("glowstone", 0.3),
+    ("crimson_nylium", 0.4),
+    ("warped_nylium", 0.4),
+    ("end_stone", 3.0),
+    ("obsidian", 50.0),
+    ("crying_obsidian", 50.0),
+    ("oak_log", 2.0),
+    ("spruce_log", 2.0),
+    ("birch_log", 2.0),
+    ("jungle_log", 2.0),
+    ("acacia_log", 2.0),
+    ("dark_oak_log", 2.0),
+    ("mangrove_log", 2.0),
+    ("oak_planks", 2.0),
+    ("stone_bricks", 1.5),
+    ("bricks", 2.0),
+    ("glass", 0.3),
+    ("sculk", 0.2),
+    ("reinforced_deepslate", 55.0),
+    ("spawner", 5.0),
+    ("chest", 2.5),
+];
+
+/// Blocks players can't mine in survival
+const UNBREAKABLE_BLOCKS: &[&str] = &[
+    "bedrock",
+    "barrier",
+    "light",
+    "water",
+    "lava",
+    "bubble_column",
+    "end_portal",
+    "end_portal_frame",
+    "end_gateway",
+    "nether_portal",
+    "command_block",
+    "chain_command_block",
+    "repeating_command_block",
+    "structure_block",
+    "jigsaw",
+    "moving_piston",
+];
+
+/// Hardness of a block, or `None` if it can't be mined or isn't in the table
+pub fn hardness(blockstate: &str) -> Option<f64> {
+    let (namespace, name) = split_namespace(blockstate);
+    if namespace != DEFAULT_NAMESPACE {
+        return None;
+    }
+
+    BLOCK_HARDNESS
+        .iter()
+        .find(|&&(block, _)| block == name)
+        .map(|&(_, hardness)| hardness)
+}
+
+/// Whether players can't mine the block in survival
+pub fn is_unbreakable(blockstate: &str) -> bool {
+    let (namespace, name) = split_namespace(blockstate);
+    namespace == DEFAULT_NAMESPACE && UNBREAKABLE_BLOCKS.contains(&name)
+}
+
+/// Ticks it takes to mine a block of this hardness with a diamond tool
+pub fn mining_ticks(hardness: f64) -> u64 {
+    let ticks = hardness * 30.0 / DIAMOND_TOOL_SPEED;
+    if ticks <= 1.0 {
+        0
+    } else {
+        ticks.ceil() as u64
+    }
+}
+
+/// Total time it takes to mine a set of blocks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MiningEstimate {
+    /// Ticks spent on every blockstate, as `(blockstate, blocks, ticks)`,
+    /// the most time consuming first
+    pub blocks: Vec<(String, u64, u64)>,
+
+    /// Blocks that can't be mined, which are left out of the estimate
+    pub unbreakable: u64,
+
+    /// Blocks of blockstates missing from the hardness table, which are left out of the estimate
+    pub unknown: HashMap<String, u64>,
+}
+
+impl MiningEstimate {
+    /// Weights every blockstate's count with the ticks it takes to mine it. Air is skipped.
+    pub fn from_totals(totals: &[(String, u64)]) -> Self {
+        let mut estimate = MiningEstimate::default();
+
+        for (blockstate, count) in totals {
+            if is_air(blockstate) {
+                continue;
+            }
+            if is_unbreakable(blockstate) {
+                estimate.unbreakable += count;
+                continue;
+            }
+
+            match hardness(blockstate) {
+                Some(hardness) => {
+                    let ticks = mining_ticks(hardness) * count;
+                    estimate.blocks.push((blockstate.clone(), *count, ticks));
+                }
+                None => *estimate.unknown.entry(blockstate.clone()).or_insert(0) += count,
+            }
+        }
+
+        estimate
+            .blocks
+            .sort_by(|(a_name, _, a), (b_name, _, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        estimate
+    }
+
+    pub fn total_ticks(&self) -> u64 {
+        self.blocks.iter().map(|&(_, _, ticks)| ticks).sum()
+    }
+}
+
+/// Formats a number of ticks as hours, minutes and seconds, e.g. `2h 05m 30s`
+pub fn format_duration(ticks: u64) -> String {
+    let seconds = ticks / TICKS_PER_SECOND;
+    format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_from_totals() {
+        let totals = vec![
+            ("minecraft:stone".to_string(), 1000),
+            ("minecraft:obsidian".to_string(), 10),
+            ("minecraft:bedrock".to_string(), 5),
+        ];
+        let estimate = MiningEstimate::from_totals(&totals);
+
+        // 6 ticks for every block of stone and 188 for every block of obsidian
+        assert_eq!(estimate.total_ticks(), 1000 * 6 + 10 * 188);
+        assert_eq!(estimate.unbreakable, 5);
+        assert!(estimate.unknown.is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+
+/// Writes one line of newline-delimited JSON with a chunk's coordinates and blockstate counts:
+/// `{"x":0,"z":-1,"counts":{"minecraft:stone":1234,...}}`
+pub fn write_chunk_counts<W: Write>(
+    out: &mut W,
+    (chunk_x, chunk_z): (i32, i32),
+    counts: &[(String, u32)],
+) -> io::Result<()> {
+    write!(out, "{{\"x\":{},\"z\":{},\"counts\":{{", chunk_x, chunk_z)?;
+
+    for (index, (blockstate, count)) in counts.iter().enumerate() {
+        if index > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{}:{}", json_string(blockstate), count)?;
+    }
+
+    writeln!(out, "}}}}")
+}
+
+/// Quotes and escapes a string for use in JSON
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn one_valid_object_per_line() {
+        let mut out = Vec::new();
+        write_chunk_counts(
+            &mut out,
+            (0, -1),
+            &[
+                ("minecraft:stone".to_string(), 4000),
+                ("minecraft:dirt".to_string(), 96),
+            ],
+        )
+        .unwrap();
+        // Names that need escaping, as some mods produce them
+        write_chunk_counts(
+            &mut out,
+            (5, 7),
+            &[("mod:\"quoted\"\\block\t".to_string(), 1)],
+        )
+        .unwrap();
+        write_chunk_counts(&mut out, (6, 7), &[]).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                json!({"x": 0, "z": -1, "counts": {"minecraft:stone": 4000, "minecraft:dirt": 96}}),
+                json!({"x": 5, "z": 7, "counts": {"mod:\"quoted\"\\block\t": 1}}),
+                json!({"x": 6, "z": 7, "counts": {}}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+/// Formats a number with `,` between groups of three digits, e.g. `-1,234,567`.
+/// The result is quoted so that it still is a single CSV field.
+pub fn human_number(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 + 3);
+
+    grouped.push('"');
+    if value < 0 {
+        grouped.push('-');
+    }
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped.push('"');
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_digits() {
+        assert_eq!(human_number(0), "\"0\"");
+        assert_eq!(human_number(999), "\"999\"");
+        assert_eq!(human_number(1000), "\"1,000\"");
+        assert_eq!(human_number(1_234_567), "\"1,234,567\"");
+        assert_eq!(human_number(-12_345), "\"-12,345\"");
+        assert_eq!(human_number(i64::MIN), "\"-9,223,372,036,854,775,808\"");
+    }
+}
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// Ore group name -> blockstates belonging to that ore
+pub type OreGroups = HashMap<String, Vec<String>>;
+
+const BUILTIN_ORE_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "coal",
+        &["minecraft:coal_ore", "minecraft:deepslate_coal_ore"],
+    ),
+    (
+        "iron",
+        &["minecraft:iron_ore", "minecraft:deepslate_iron_ore"],
+    ),
+    (
+        "copper",
+        &["minecraft:copper_ore", "minecraft:deepslate_copper_ore"],
+    ),
+    (
+        "gold",
+        &["minecraft:gold_ore", "minecraft:deepslate_gold_ore"],
+    ),
+    (
+        "redstone",
+        &["minecraft:redstone_ore", "minecraft:deepslate_redstone_ore"],
+    ),
+    (
+        "lapis",
+        &["minecraft:lapis_ore", "minecraft:deepslate_lapis_ore"],
+    ),
+    (
+        "emerald",
+        &["minecraft:emerald_ore", "minecraft:deepslate_emerald_ore"],
+    ),
+    (
+        "diamond",
+        &["minecraft:diamond_ore", "minecraft:deepslate_diamond_ore"],
+    ),
+    ("nether_gold", &["minecraft:nether_gold_ore"]),
+    ("quartz", &["minecraft:nether_quartz_ore"]),
+    ("ancient_debris", &["minecraft:ancient_debris"]),
+];
+
+pub fn builtin_ore_groups() -> OreGroups {
+    BUILTIN_ORE_GROUPS
+        .iter()
+        .map(|(name, ores)| {
+            (
+                name.to_string(),
+                ores.iter().map(|ore| ore.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Parses ore definitions in a small subset of TOML: one group per line,
+/// mapping its name to an array of blockstates, e.g.
+///
+/// ```toml
+/// # Create
+/// zinc = ["create:zinc_ore", "create:deepslate_zinc_ore"]
+/// ```
+///
+/// Arrays spanning multiple lines aren't supported.
+pub fn parse_ore_defs(contents: &str) -> Result<OreGroups, String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            parse_named_list(line).ok_or_else(|| format!("Invalid ore definition '{}'", line))
+        })
+        .collect()
+}
+
+/// Reads custom ore definitions from a file, on top of the built-in ore groups.
+/// Custom groups replace built-in groups of the same name.
+pub fn read_ore_defs(path: &Path) -> io::Result<OreGroups> {
+    let custom_groups = parse_ore_defs(&fs::read_to_string(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let mut ore_groups = builtin_ore_groups();
+    ore_groups.extend(custom_groups);
+
+    Ok(ore_groups)
+}
+
+/// Looks up the blockstates of an ore group, among the built-in groups and those defined in `ore_defs`
+pub fn resolve_ore_group(ore_defs: Option<&Path>, name: &str) -> Result<Vec<String>, String> {
+    let ore_groups = match ore_defs {
+        Some(path) => read_ore_defs(path)
+            .map_err(|err| format!("Could not read '{}': {}", path.display(), err))?,
+        None => builtin_ore_groups(),
+    };
+
+    ore_groups
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown ore group '{}'", name))
+}
+
+/// Parses a single `name = ["a", "b"]` line
+pub(crate) fn parse_named_list(line: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = line.splitn(2, '=');
+    let name = unquote(parts.next()?.trim());
+    let list = parts.next()?.trim();
+
+    let list = list.strip_prefix('[')?.strip_suffix(']')?;
+    let ores: Vec<String> = list
+        .split(',')
+        .map(|ore| unquote(ore.trim()))
+        .filter(|ore| !ore.is_empty())
+        .map(|ore| ore.to_string())
+        .collect();
+
+    if name.is_empty() || ores.is_empty() {
+        return None;
+    }
+
+    Some((name.to_string(), ores))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
@@ -0,0 +1,30 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Folder that all files generated by a run are written to
+#[derive(Debug, Clone)]
+pub struct OutputDir {
+    path: PathBuf,
+}
+
+impl OutputDir {
+    /// Creates the folder (and its parents) if it doesn't exist yet
+    pub fn create(path: &Path) -> io::Result<Self> {
+        fs::create_dir_all(path)?;
+
+        Ok(OutputDir {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of a file with the given name inside the output folder
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
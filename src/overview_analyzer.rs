@@ -0,0 +1,312 @@
+use std::{collections::HashSet, convert::TryFrom, path::Path};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    chunk::Chunk,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+    ore_defs::builtin_ore_groups,
+};
+
+const MISSING_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Metrics of the red, green and blue channel, unless configured otherwise
+pub const DEFAULT_OVERVIEW_METRICS: &str = "ores,diversity,inhabited";
+
+/// A number measured for every chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkMetric {
+    /// Number of blocks of any built-in ore
+    Ores,
+    /// Number of distinct blockstates
+    Diversity,
+    /// Ticks players spent near the chunk
+    InhabitedTime,
+    /// Game tick at which the chunk was last saved
+    LastUpdate,
+    /// Number of sections containing anything but air
+    Sections,
+    /// Number of blocks of this blockstate
+    Block(String),
+}
+
+impl ChunkMetric {
+    /// Any name with a namespace, e.g. `minecraft:diamond_ore`, counts that blockstate
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ores" => Some(ChunkMetric::Ores),
+            "diversity" => Some(ChunkMetric::Diversity),
+            "inhabited" => Some(ChunkMetric::InhabitedTime),
+            "last-update" => Some(ChunkMetric::LastUpdate),
+            "sections" => Some(ChunkMetric::Sections),
+            _ if name.contains(':') => Some(ChunkMetric::Block(name.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Parses the metrics of the red, green and blue channel, e.g. `ores,diversity,inhabited`
+    pub fn parse_channels(list: &str) -> Option<[ChunkMetric; 3]> {
+        let metrics: Vec<ChunkMetric> = list
+            .split(',')
+            .map(|name| ChunkMetric::from_name(name.trim()))
+            .collect::<Option<_>>()?;
+
+        <[ChunkMetric; 3]>::try_from(metrics).ok()
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            ChunkMetric::Ores => "Ores",
+            ChunkMetric::Diversity => "Distinct blockstates",
+            ChunkMetric::InhabitedTime => "Inhabited time",
+            ChunkMetric::LastUpdate => "Last update",
+            ChunkMetric::Sections => "Sections",
+            ChunkMetric::Block(blockstate) => blockstate,
+        }
+    }
+
+    fn measure(&self, chunk: &Chunk, ores: &HashSet<String>) -> u64 {
+        let count_blocks = |matches: &dyn Fn(&str) -> bool| {
+            chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .flat_map(|section| section.blocks())
+                .filter(|block| matches(&block.blockstate))
+                .count() as u64
+        };
+
+        match self {
+            ChunkMetric::Ores => count_blocks(&|blockstate: &str| ores.contains(blockstate)),
+            ChunkMetric::Diversity => chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .flat_map(|section| section.blocks().map(|block| block.blockstate))
+                .collect::<HashSet<String>>()
+                .len() as u64,
+            ChunkMetric::InhabitedTime => chunk.inhabited_time.max(0) as u64,
+            ChunkMetric::LastUpdate => chunk.last_update.max(0) as u64,
+            ChunkMetric::Sections => chunk.non_air_section_count() as u64,
+            ChunkMetric::Block(target) => {
+                count_blocks(&|blockstate: &str| blockstate == target.as_str())
+            }
+        }
+    }
+}
+
+/// Measures three metrics per chunk and combines them into one map, with each metric in one
+/// color channel. Every channel is scaled to its own maximum, so that e.g. a red chunk is high
+/// in the first metric and low in the other two.
+pub struct OverviewAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    area: Area,
+
+    /// Metrics of the red, green and blue channel
+    channels: [ChunkMetric; 3],
+
+    /// Blockstates counted by `ChunkMetric::Ores`
+    ores: HashSet<String>,
+
+    /// Chunk coordinates and the value of every channel, in the order they were scanned
+    values: Vec<((i32, i32), [u64; 3])>,
+}
+
+impl<'a> OverviewAnalyzer<'a> {
+    pub fn new(
+        path: &'a str,
+        area: Area,
+        channels: [ChunkMetric; 3],
+        loader_options: LoaderOptions,
+    ) -> Self {
+        OverviewAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            area,
+            channels,
+            loader_options,
+        )
+    }
+
+    /// Measures chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        channels: [ChunkMetric; 3],
+        loader_options: LoaderOptions,
+    ) -> Self {
+        OverviewAnalyzer {
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            area,
+            channels,
+            ores: builtin_ore_groups().into_values().flatten().collect(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let ores = &self.ores;
+            let values = [
+                self.channels[0].measure(chunk, ores),
+                self.channels[1].measure(chunk, ores),
+                self.channels[2].measure(chunk, ores),
+            ];
+            self.values.push(((chunk_x, chunk_z), values));
+        }
+    }
+
+    /// Color of every scanned chunk, with each channel scaled to the highest value of its metric
+    pub fn colors(&self) -> Vec<((i32, i32), [u8; 3])> {
+        colors(&self.values)
+    }
+
+    pub fn print_csv(&self) {
+        println!(
+            "X,Z,{},{},{}",
+            self.channels[0].name(),
+            self.channels[1].name(),
+            self.channels[2].name()
+        );
+        for ((x, z), [r, g, b]) in &self.values {
+            println!("{},{},{},{},{}", x, z, r, g, b);
+        }
+    }
+
+    /// Saves the map with one pixel per chunk. Chunks that weren't analyzed are black.
+    /// With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, colors) = (&self.area, self.colors());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, &colors, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, &colors, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+/// Scales every channel to its own maximum
+fn colors(values: &[((i32, i32), [u64; 3])]) -> Vec<((i32, i32), [u8; 3])> {
+    let mut max = [1u64; 3];
+    for (_, chunk_values) in values {
+        for (max, &value) in max.iter_mut().zip(chunk_values) {
+            *max = (*max).max(value);
+        }
+    }
+
+    values
+        .iter()
+        .map(|&(chunk, chunk_values)| {
+            let mut color = [0u8; 3];
+            for ((channel, value), max) in color.iter_mut().zip(&chunk_values).zip(&max) {
+                *channel = (*value as u128 * u8::MAX as u128 / *max as u128) as u8;
+            }
+            (chunk, color)
+        })
+        .collect()
+}
+
+/// Renders the part of the map with its top left corner at `(x, y)`
+fn render(
+    area: &Area,
+    colors: &[((i32, i32), [u8; 3])],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, MISSING_COLOR);
+    let map_height = area.chunk_width_z();
+
+    for &((chunk_x, chunk_z), color) in colors {
+        let (pixel_x, pixel_z) = area.get_positive_coords(chunk_x, chunk_z);
+        if pixel_z >= map_height {
+            continue;
+        }
+
+        let pixel_y = map_height - pixel_z - 1;
+        if (x..x + width).contains(&pixel_x) && (y..y + height).contains(&pixel_y) {
+            img.put_pixel(pixel_x - x, pixel_y - y, Rgb(color));
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn channel_colors() {
+        // A chunk made of diamond ore that nobody visited,
+        // and one made of stone that players spent a lot of time in
+        let chunks = [
+            (0, "minecraft:diamond_ore", 0),
+            (1, "minecraft:stone", 72000),
+        ]
+        .iter()
+        .map(|&(x, block, inhabited_time)| {
+            let mut chunk =
+                test_fixtures::chunk(x, 0, vec![test_fixtures::uniform_section(0, block)]);
+            chunk.insert_i64("InhabitedTime", inhabited_time);
+            chunk
+        })
+        .collect();
+
+        let area = Area::new(0, 2, 0, 1);
+        let channels = ChunkMetric::parse_channels("ores,diversity,inhabited").unwrap();
+        let mut analyzer = OverviewAnalyzer::with_source(
+            test_fixtures::source(chunks),
+            area,
+            channels,
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(area);
+
+        let colors = analyzer.colors();
+        // Both chunks have a single blockstate, so they're equally green
+        assert_eq!(colors[0], ((0, 0), [255, 255, 0]));
+        assert_eq!(colors[1], ((1, 0), [0, 255, 255]));
+    }
+}
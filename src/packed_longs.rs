@@ -0,0 +1,118 @@
+/// Unpacks `count` values of `bits` bits each from an array of longs, starting at the
+/// least significant bit of the first long.
+///
+/// Since 1.16 (`cross_long == false`) values never straddle two longs and the unused high
+/// bits of each long are padding. Before that, values are packed back to back and may
+/// continue in the next long.
+///
+/// If `data` is too short, only the values that are fully contained in it are returned.
+pub fn unpack(data: &[i64], bits: u32, count: usize, cross_long: bool) -> Vec<u32> {
+    assert!(bits > 0 && bits <= 32, "Unsupported value width {}", bits);
+
+    let mask = (1u64 << bits) - 1;
+    let mut result = Vec::with_capacity(count);
+
+    if cross_long {
+        for index in 0..count {
+            let bit_offset = index * bits as usize;
+            let long_index = bit_offset / 64;
+            let bit_in_long = (bit_offset % 64) as u32;
+            let end_long_index = (bit_offset + bits as usize - 1) / 64;
+
+            let low = match data.get(long_index) {
+                Some(&long) => long as u64 >> bit_in_long,
+                None => break,
+            };
+
+            let value = if end_long_index == long_index {
+                low
+            } else {
+                match data.get(end_long_index) {
+                    Some(&long) => low | (long as u64) << (64 - bit_in_long),
+                    None => break,
+                }
+            };
+
+            result.push((value & mask) as u32);
+        }
+    } else {
+        let values_per_long = (64 / bits) as usize;
+
+        'longs: for &long in data {
+            let long = long as u64;
+
+            for index in 0..values_per_long {
+                if result.len() >= count {
+                    break 'longs;
+                }
+
+                result.push((long >> (index as u32 * bits) & mask) as u32);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    /// Values that use every bit of their width somewhere
+    fn values(bits: u32, count: usize) -> Vec<u32> {
+        (0..count as u32)
+            .map(|index| index.wrapping_mul(2_654_435_761) >> (32 - bits))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_widths() {
+        for bits in [4, 5, 13] {
+            let values = values(bits, 4096);
+
+            let padded = test_fixtures::pack(&values, bits);
+            assert_eq!(unpack(&padded, bits, 4096, false), values, "{} bits", bits);
+
+            let straddling = test_fixtures::pack_straddling(&values, bits);
+            assert_eq!(
+                unpack(&straddling, bits, 4096, true),
+                values,
+                "{} bits",
+                bits
+            );
+        }
+    }
+
+    #[test]
+    fn value_straddling_two_longs() {
+        // The 13th 5-bit value takes the top 4 bits of the first long and the lowest bit of the next
+        let data = [(0xf_u64 << 60) as i64, 1];
+
+        assert_eq!(unpack(&data, 5, 13, true)[12], 31);
+        // Without straddling, only 12 values fit into the first long and the rest is padding
+        assert_eq!(unpack(&data, 5, 13, false)[12], 1);
+    }
+
+    #[test]
+    fn last_partial_long() {
+        // 12 5-bit values fit into a long, so the last of 86 longs only holds 4 of them
+        let values = values(5, 1024);
+        let padded = test_fixtures::pack(&values, 5);
+        assert_eq!(padded.len(), 86);
+
+        assert_eq!(unpack(&padded, 5, 1024, false), values);
+    }
+
+    #[test]
+    fn data_too_short() {
+        let values = values(13, 4096);
+
+        let padded = test_fixtures::pack(&values, 13);
+        assert_eq!(unpack(&padded[..10], 13, 4096, false), values[..40]);
+
+        // 10 longs hold 640 bits, and the 50th value would need bits up to 650
+        let straddling = test_fixtures::pack_straddling(&values, 13);
+        assert_eq!(unpack(&straddling[..10], 13, 4096, true), values[..49]);
+    }
+}
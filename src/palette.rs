@@ -1,20 +1,58 @@
-use std::{cmp::max, convert::TryInto};
+use std::{cmp::max, collections::BTreeMap, convert::TryInto};
 
-use nbt::CompoundTag;
+use nbt::{CompoundTag, Tag};
+
+/// Blockstate property name -> value, e.g. `instrument` -> `harp`
+pub type Properties = BTreeMap<String, String>;
+
+/// Options controlling how a section palette is read from NBT
+#[derive(Debug, Clone, Copy)]
+pub struct PaletteOptions {
+    /// Whether `minecraft:air` is inserted as index 0 when the palette is empty
+    /// or doesn't start with air, like vanilla does implicitly.
+    /// Some modded or non-vanilla formats don't follow this convention, in which
+    /// case this should be disabled so palette indices aren't shifted by one.
+    pub implicit_air: bool,
+
+    /// Decodes block states with this many bits per block instead of the width derived from
+    /// the palette length. Only meant for debugging suspected bit width or palette bugs,
+    /// a wrong width garbles the blocks or leaves palette indices out of range.
+    pub forced_bit_width: Option<u32>,
+}
+
+impl Default for PaletteOptions {
+    fn default() -> Self {
+        PaletteOptions {
+            implicit_air: true,
+            forced_bit_width: None,
+        }
+    }
+}
+
+impl PaletteOptions {
+    /// Bits per block to decode block states of a section with the given palette
+    pub fn bit_width(&self, palette: &Palette) -> u32 {
+        self.forced_bit_width
+            .unwrap_or_else(|| palette.get_elem_bit_size())
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Palette {
     elements: Vec<String>,
+    properties: Vec<Properties>,
 }
 
 impl Palette {
     // We can't reuse `add` from below because chunk palettes allow for duplicates
     // (because they differentiate between block states, which we ignore here)
-    pub fn from_nbt(nbt: Vec<&CompoundTag>) -> Self {
+    pub fn from_nbt(nbt: Vec<&CompoundTag>, options: PaletteOptions) -> Self {
         let mut elements = Vec::with_capacity(nbt.len());
+        let mut properties = Vec::with_capacity(nbt.len());
 
-        if nbt.is_empty() {
+        if options.implicit_air && nbt.is_empty() {
             elements.push("minecraft:air".to_string());
+            properties.push(Properties::new());
         }
 
         nbt.into_iter().enumerate().for_each(|(index, entry)| {
@@ -22,14 +60,29 @@ impl Palette {
 
             // Vanilla minecraft will implicitly have id 0 = minecraft:air
             // even if it's not specified
-            if index == 0 && blockstate != "minecraft:air" {
+            if options.implicit_air && index == 0 && blockstate != "minecraft:air" {
                 elements.push("minecraft:air".to_string());
+                properties.push(Properties::new());
             }
 
             elements.push(blockstate.to_string());
+            properties.push(parse_palette_properties(entry));
         });
 
-        Palette { elements }
+        Palette {
+            elements,
+            properties,
+        }
+    }
+
+    /// Palette of the given blockstates without any properties, in this order
+    pub fn from_states(states: Vec<String>) -> Self {
+        let properties = vec![Properties::new(); states.len()];
+
+        Palette {
+            elements: states,
+            properties,
+        }
     }
 
     // Calculate amount of bits for each palette item in the .mca format
@@ -38,9 +91,31 @@ impl Palette {
         max(4, f64::log2(palette_length.into()).ceil() as u32)
     }
 
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
     pub fn get_state(&self, id: usize) -> Option<&str> {
         self.elements.get(id).map(|s| s.as_str())
     }
+
+    pub fn get_properties(&self, id: usize) -> Option<&Properties> {
+        self.properties.get(id)
+    }
+}
+
+/// Stand-in for blocks whose palette index is out of range, e.g. because the section was mis-decoded
+pub const UNKNOWN_BLOCK: &str = "minecraft:unknown";
+
+/// All variants of air, which are treated as empty space
+pub const AIR_BLOCKS: &[&str] = &["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+
+pub fn is_air(blockstate: &str) -> bool {
+    AIR_BLOCKS.contains(&blockstate)
 }
 
 fn parse_palette_entry(palette_entry: &CompoundTag) -> &str {
@@ -48,3 +123,51 @@ fn parse_palette_entry(palette_entry: &CompoundTag) -> &str {
         .get_str("Name")
         .expect("Couldn't get field Name for palette entry")
 }
+
+fn parse_palette_properties(palette_entry: &CompoundTag) -> Properties {
+    let properties = if let Ok(properties) = palette_entry.get_compound_tag("Properties") {
+        properties
+    } else {
+        return Properties::new();
+    };
+
+    properties
+        .iter()
+        .filter_map(|(name, value)| match value {
+            Tag::String(value) => Some((name.clone(), value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn stone_and_dirt(implicit_air: bool) -> Palette {
+        let entries = test_fixtures::palette(&["minecraft:stone", "minecraft:dirt"]);
+        let options = PaletteOptions {
+            implicit_air,
+            ..PaletteOptions::default()
+        };
+        Palette::from_nbt(entries.iter().collect(), options)
+    }
+
+    #[test]
+    fn implicit_air_before_stone() {
+        let palette = stone_and_dirt(true);
+        assert_eq!(palette.len(), 3);
+        assert_eq!(palette.get_state(0), Some("minecraft:air"));
+        assert_eq!(palette.get_state(1), Some("minecraft:stone"));
+        assert_eq!(palette.get_state(2), Some("minecraft:dirt"));
+    }
+
+    #[test]
+    fn without_implicit_air() {
+        let palette = stone_and_dirt(false);
+        assert_eq!(palette.len(), 2);
+        assert_eq!(palette.get_state(0), Some("minecraft:stone"));
+        assert_eq!(palette.get_state(1), Some("minecraft:dirt"));
+    }
+}
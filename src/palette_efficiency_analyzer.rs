@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::{ChunkSection, CHUNK_SIZE},
+    palette::PaletteOptions,
+};
+
+const SECTION_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Palette statistics of all sections at one section Y
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaletteStats {
+    pub sections: u32,
+    /// Sections with at least one unused palette entry
+    pub wasteful_sections: u32,
+    /// Palette entries that no block refers to, summed over all sections
+    pub unused_entries: u64,
+    /// Bytes of block data that could be saved by dropping the unused entries,
+    /// since fewer entries may need fewer bits per block
+    pub wasted_bytes: u64,
+    /// Bytes of block data that could be saved by storing every section with the fewest bits
+    /// that tell its distinct blocks apart, ignoring the minimum of 4 bits the game uses
+    pub reoptimizable_bytes: u64,
+    /// Bytes of block data as currently stored, summed over all sections
+    pub stored_bytes: u64,
+    /// Bytes of block data if every palette were trimmed to its used entries, the way the game
+    /// would store the sections after optimizing the world
+    pub compacted_bytes: u64,
+}
+
+/// Bytes in a megabyte, for the storage-savings estimate
+const BYTES_PER_MB: f64 = 1_000_000.0;
+
+/// Finds palette entries that no block of their section refers to, which still take up space
+/// in the palette and may widen the bits stored per block
+pub struct PaletteEfficiencyAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// section Y -> statistics of the sections there
+    stats: HashMap<i8, PaletteStats>,
+
+    /// bits per block -> number of sections stored with that many
+    bit_widths: HashMap<u32, u32>,
+}
+
+impl<'a> PaletteEfficiencyAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        PaletteEfficiencyAnalyzer {
+            // Air inserted at the start of the palette isn't stored, so it mustn't count as unused
+            chunk_loader: ChunkLoader::new(
+                path,
+                None,
+                LoaderOptions {
+                    palette: PaletteOptions {
+                        implicit_air: false,
+                        ..loader_options.palette
+                    },
+                    skip_unused_tags: true,
+                    ..loader_options
+                },
+            ),
+            stats: HashMap::new(),
+            bit_widths: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for y in chunk.section_indices() {
+                let section = match chunk.get_section(y) {
+                    Some(section) => section,
+                    None => continue,
+                };
+
+                let stats = self.stats.entry(y).or_default();
+                stats.add_section(section);
+
+                *self
+                    .bit_widths
+                    .entry(stored_bits_per_block(section.palette_len()))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Statistics of every section Y that had sections, from bottom to top
+    pub fn stats(&self) -> Vec<(i8, PaletteStats)> {
+        let mut stats: Vec<(i8, PaletteStats)> =
+            self.stats.iter().map(|(&y, &stats)| (y, stats)).collect();
+        stats.sort_unstable_by_key(|&(y, _)| y);
+        stats
+    }
+
+    /// How many sections are stored with each number of bits per block, from narrowest to widest
+    pub fn bit_widths(&self) -> Vec<(u32, u32)> {
+        let mut bit_widths: Vec<(u32, u32)> = self
+            .bit_widths
+            .iter()
+            .map(|(&bits, &sections)| (bits, sections))
+            .collect();
+        bit_widths.sort_unstable();
+        bit_widths
+    }
+
+    /// Prints the size of the block data per section Y as stored and as it would be if every palette
+    /// were trimmed to its used entries, which is what optimizing the world in the game does
+    pub fn print_compaction(&self) {
+        println!("Section Y,Sections,Stored bytes,Compacted bytes");
+
+        let mut total = PaletteStats::default();
+        for (y, stats) in self.stats() {
+            println!(
+                "{:8},{:8},{:8},{:8}",
+                y, stats.sections, stats.stored_bytes, stats.compacted_bytes
+            );
+
+            total.sections += stats.sections;
+            total.stored_bytes += stats.stored_bytes;
+            total.compacted_bytes += stats.compacted_bytes;
+        }
+
+        println!(
+            "   Total,{:8},{:8},{:8}",
+            total.sections, total.stored_bytes, total.compacted_bytes
+        );
+
+        eprintln!(
+            "Block data would shrink from {:.2} MB to {:.2} MB",
+            total.stored_bytes as f64 / BYTES_PER_MB,
+            total.compacted_bytes as f64 / BYTES_PER_MB
+        );
+    }
+
+    pub fn print_csv(&self) {
+        println!("Section Y,Sections,Sections with unused entries,Unused entries,Wasted bytes,Reoptimizable bytes");
+
+        let mut total = PaletteStats::default();
+        for (y, stats) in self.stats() {
+            println!(
+                "{:8},{:8},{:8},{:8},{:8},{:8}",
+                y,
+                stats.sections,
+                stats.wasteful_sections,
+                stats.unused_entries,
+                stats.wasted_bytes,
+                stats.reoptimizable_bytes
+            );
+
+            total.sections += stats.sections;
+            total.wasteful_sections += stats.wasteful_sections;
+            total.unused_entries += stats.unused_entries;
+            total.wasted_bytes += stats.wasted_bytes;
+            total.reoptimizable_bytes += stats.reoptimizable_bytes;
+            total.stored_bytes += stats.stored_bytes;
+            total.compacted_bytes += stats.compacted_bytes;
+        }
+
+        println!(
+            "   Total,{:8},{:8},{:8},{:8},{:8}",
+            total.sections,
+            total.wasteful_sections,
+            total.unused_entries,
+            total.wasted_bytes,
+            total.reoptimizable_bytes
+        );
+        println!();
+
+        println!("Bits per block,Sections");
+        for (bits, sections) in self.bit_widths() {
+            println!("{:8},{:8}", bits, sections);
+        }
+
+        eprintln!(
+            "{:.2} MB could be saved by re-optimizing the block data",
+            total.reoptimizable_bytes as f64 / BYTES_PER_MB
+        );
+    }
+}
+
+impl PaletteStats {
+    /// Adds a section to the statistics
+    pub fn add_section(&mut self, section: &ChunkSection) {
+        let unused = section.unused_palette_entries().len();
+        let palette_len = section.palette_len();
+
+        let excess_bits =
+            stored_bits_per_block(palette_len) - minimal_bits_per_block(palette_len - unused);
+        self.reoptimizable_bytes += (excess_bits as usize * SECTION_VOLUME / 8) as u64;
+        self.stored_bytes += stored_bytes(palette_len);
+        self.compacted_bytes += stored_bytes(palette_len - unused);
+
+        self.sections += 1;
+        if unused > 0 {
+            self.wasteful_sections += 1;
+            self.unused_entries += unused as u64;
+
+            let saved_bits = bits_per_block(palette_len) - bits_per_block(palette_len - unused);
+            self.wasted_bytes += (saved_bits as usize * SECTION_VOLUME / 8) as u64;
+        }
+    }
+}
+
+/// Bits stored per block for a palette of this many entries since 1.18,
+/// where sections of a single blockstate don't store any
+fn stored_bits_per_block(palette_len: usize) -> u32 {
+    if palette_len <= 1 {
+        0
+    } else {
+        bits_per_block(palette_len)
+    }
+}
+
+/// Bytes of block data stored for a palette of this many entries since 1.16,
+/// where the values of a block don't span two longs
+fn stored_bytes(palette_len: usize) -> u64 {
+    let bits = stored_bits_per_block(palette_len) as usize;
+    if bits == 0 {
+        return 0;
+    }
+
+    let blocks_per_long = 64 / bits;
+    let longs = SECTION_VOLUME.div_ceil(blocks_per_long);
+    (longs * 8) as u64
+}
+
+/// Fewest bits per block that tell this many distinct blockstates apart
+fn minimal_bits_per_block(distinct_blocks: usize) -> u32 {
+    usize::BITS - distinct_blocks.saturating_sub(1).leading_zeros()
+}
+
+/// Bits stored per block for a palette of this many entries, like `Palette::get_elem_bit_size`
+fn bits_per_block(palette_len: usize) -> u32 {
+    let mut bits = 4;
+    while (1 << bits) < palette_len {
+        bits += 1;
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::Palette;
+
+    #[test]
+    fn unused_entries_and_compaction() {
+        // 200 palette entries need 8 bits per block, but only the first 3 are used,
+        // which 2 bits would tell apart
+        let states = (0..200)
+            .map(|id| format!("minecraft:block_{}", id))
+            .collect();
+        let indices: Vec<usize> = (0..4096).map(|index| index % 3).collect();
+        let section = ChunkSection::from_blocks((0, 0, 0), Palette::from_states(states), &indices);
+
+        let mut stats = PaletteStats::default();
+        stats.add_section(&section);
+
+        assert_eq!(stats.unused_entries, 197);
+        // 6 bits of each of the 4096 blocks
+        assert_eq!(stats.reoptimizable_bytes, 3072);
+        // 8 blocks per long as stored, 16 once the palette is trimmed to 4 bits per block
+        assert_eq!(stats.stored_bytes, 4096);
+        assert_eq!(stats.compacted_bytes, 2048);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use anvil_region::{error::ChunkReadError, provider::FolderRegionProvider};
+use nbt::{CompoundTag, Tag};
+
+use crate::chunk_loader::read_chunk_nbt;
+
+/// Tallies point-of-interest types (workstations, beds, portals, ...) from a dimension's `poi` folder
+pub struct PoiAnalyzer<'a> {
+    region_provider: FolderRegionProvider<'a>,
+    poi_folder: &'a str,
+
+    /// POI type -> count
+    poi_counts: HashMap<String, u32>,
+}
+
+impl<'a> PoiAnalyzer<'a> {
+    pub fn new(poi_folder: &'a str) -> Self {
+        PoiAnalyzer {
+            region_provider: FolderRegionProvider::new(poi_folder),
+            poi_folder,
+            poi_counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            match read_chunk_nbt(&self.region_provider, self.poi_folder, chunk_x, chunk_z) {
+                Ok(nbt) => self.count_poi_chunk(&nbt),
+                // Only chunks that ever contained a POI are stored
+                Err(ChunkReadError::ChunkNotFound { .. }) => {}
+                Err(err) => eprintln!(
+                    "Could not read POI data of chunk ({},{}): {:?}",
+                    chunk_x, chunk_z, err
+                ),
+            }
+        }
+    }
+
+    fn count_poi_chunk(&mut self, nbt: &CompoundTag) {
+        let sections = if let Ok(sections) = nbt.get_compound_tag("Sections") {
+            sections
+        } else {
+            return;
+        };
+
+        for (_, section) in sections.iter() {
+            let records = match section {
+                Tag::Compound(section) => {
+                    section.get_compound_tag_vec("Records").unwrap_or_default()
+                }
+                _ => continue,
+            };
+
+            for record in records {
+                if let Ok(poi_type) = record.get_str("type") {
+                    *self.poi_counts.entry(poi_type.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut poi_counts: Vec<(&String, &u32)> = self.poi_counts.iter().collect();
+        poi_counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("POI type,Count");
+        for (poi_type, count) in poi_counts {
+            println!("{},{:8}", poi_type, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, TempDir};
+
+    fn record(poi_type: &str, pos: [i32; 3]) -> CompoundTag {
+        let mut record = CompoundTag::new();
+        record.insert_str("type", poi_type);
+        record.insert_i32_vec("pos", pos.to_vec());
+        record.insert_i32("free_tickets", 1);
+        record
+    }
+
+    #[test]
+    fn workstations() {
+        // Two composters and a lectern in section 4, a bed in section 5
+        let mut section_4 = CompoundTag::new();
+        section_4.insert_compound_tag_vec(
+            "Records",
+            vec![
+                record("minecraft:farmer", [3, 70, 4]),
+                record("minecraft:farmer", [5, 70, 4]),
+                record("minecraft:librarian", [8, 71, 2]),
+            ],
+        );
+        let mut section_5 = CompoundTag::new();
+        section_5.insert_compound_tag_vec("Records", vec![record("minecraft:home", [1, 82, 1])]);
+        let mut sections = CompoundTag::new();
+        sections.insert_compound_tag("4", section_4);
+        sections.insert_compound_tag("5", section_5);
+        let mut poi_chunk = CompoundTag::new();
+        poi_chunk.insert_i32("DataVersion", test_fixtures::DATA_VERSION);
+        poi_chunk.insert_compound_tag("Sections", sections);
+
+        // Stored like chunks, compressed with zlib
+        let folder = TempDir::new();
+        test_fixtures::write_region_file(
+            &folder.path().join("r.0.0.mca"),
+            vec![((0, 0), 2, test_fixtures::zlib(poi_chunk))],
+        );
+
+        let mut analyzer = PoiAnalyzer::new(folder.as_str());
+        analyzer.analyze(vec![(0, 0), (1, 0)]);
+
+        assert_eq!(
+            analyzer.poi_counts,
+            HashMap::from([
+                ("minecraft:farmer".to_string(), 2),
+                ("minecraft:librarian".to_string(), 1),
+                ("minecraft:home".to_string(), 1),
+            ])
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fs, io, path::Path};
+
+use crate::{area::Area, chunk_section::CHUNK_SIZE};
+
+/// An irregular area such as a claim or plot, given by the block coordinates of its corners.
+/// Chunks are selected by the polygon's bounding box, and `contains` tells which block columns
+/// within them are actually inside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolygonArea {
+    /// Corners as block `(x, z)`, in order around the polygon
+    vertices: Vec<(i64, i64)>,
+}
+
+impl PolygonArea {
+    /// Needs at least three vertices
+    pub fn new(vertices: Vec<(i64, i64)>) -> Option<Self> {
+        if vertices.len() < 3 {
+            return None;
+        }
+
+        Some(PolygonArea { vertices })
+    }
+
+    /// Reads the corners of a polygon, one `X,Z` block coordinate pair per line in order around it.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        let vertices = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                parse_vertex(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("'{}' is not a valid vertex", line),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        PolygonArea::new(vertices).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "A polygon needs at least three vertices",
+            )
+        })
+    }
+
+    /// Whether the block column at `(x, z)` is inside the polygon, judged by the center of the column.
+    /// Uses the even-odd rule, so self-intersecting polygons have holes where they overlap.
+    pub fn contains(&self, x: i64, z: i64) -> bool {
+        let (px, pz) = (x as f64 + 0.5, z as f64 + 0.5);
+
+        let mut inside = false;
+        let mut prev = self.vertices[self.vertices.len() - 1];
+        for &vertex in &self.vertices {
+            let (x1, z1) = (prev.0 as f64, prev.1 as f64);
+            let (x2, z2) = (vertex.0 as f64, vertex.1 as f64);
+            if (z1 > pz) != (z2 > pz) && px < x1 + (pz - z1) / (z2 - z1) * (x2 - x1) {
+                inside = !inside;
+            }
+            prev = vertex;
+        }
+
+        inside
+    }
+
+    /// Smallest area of whole chunks containing the polygon
+    pub fn bounding_area(&self) -> Area {
+        let chunk = |coord: i64| coord.div_euclid(CHUNK_SIZE as i64) as i32;
+
+        let min_x = self.vertices.iter().map(|&(x, _)| chunk(x)).min().unwrap();
+        let max_x = self
+            .vertices
+            .iter()
+            .map(|&(x, _)| chunk(x) + 1)
+            .max()
+            .unwrap();
+        let min_z = self.vertices.iter().map(|&(_, z)| chunk(z)).min().unwrap();
+        let max_z = self
+            .vertices
+            .iter()
+            .map(|&(_, z)| chunk(z) + 1)
+            .max()
+            .unwrap();
+
+        Area::new(min_x, max_x, min_z, max_z)
+    }
+}
+
+impl IntoIterator for PolygonArea {
+    type Item = (i32, i32);
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    /// Iterates over the chunk coordinates of the polygon's bounding box
+    fn into_iter(self) -> Self::IntoIter {
+        self.bounding_area().into_iter()
+    }
+}
+
+fn parse_vertex(line: &str) -> Option<(i64, i64)> {
+    let mut coords = line.split(',').map(|part| part.trim().parse());
+
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(x)), Some(Ok(z)), None) => Some((x, z)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inside_columns(polygon: &PolygonArea, range: std::ops::Range<i64>) -> Vec<(i64, i64)> {
+        range
+            .clone()
+            .flat_map(|z| range.clone().map(move |x| (x, z)))
+            .filter(|&(x, z)| polygon.contains(x, z))
+            .collect()
+    }
+
+    #[test]
+    fn square_edges() {
+        // Columns are judged by their centers, so the columns just inside the corners count
+        // and a 4 by 4 square contains exactly 16 of them
+        let square = PolygonArea::new(vec![(0, 0), (4, 0), (4, 4), (0, 4)]).unwrap();
+
+        assert!(square.contains(0, 0));
+        assert!(square.contains(3, 3));
+        assert!(!square.contains(4, 0));
+        assert!(!square.contains(-1, 2));
+        assert!(!square.contains(2, 4));
+        assert_eq!(inside_columns(&square, -2..6).len(), 16);
+    }
+
+    #[test]
+    fn diagonal_edge() {
+        // The center of (1, 2) lies exactly on the diagonal and counts as outside
+        let triangle = PolygonArea::new(vec![(0, 0), (4, 0), (0, 4)]).unwrap();
+
+        assert!(triangle.contains(1, 1));
+        assert!(!triangle.contains(1, 2));
+        assert!(!triangle.contains(2, 2));
+        assert_eq!(inside_columns(&triangle, -2..6).len(), 6);
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // An L shape, missing the 4 by 4 square at its top right
+        let l_shape =
+            PolygonArea::new(vec![(0, 0), (8, 0), (8, 4), (4, 4), (4, 8), (0, 8)]).unwrap();
+
+        assert!(l_shape.contains(6, 2));
+        assert!(l_shape.contains(2, 6));
+        assert!(!l_shape.contains(6, 6));
+        assert!(!l_shape.contains(4, 4));
+        assert_eq!(inside_columns(&l_shape, -2..10).len(), 64 - 16);
+    }
+
+    #[test]
+    fn too_few_vertices() {
+        assert_eq!(PolygonArea::new(vec![(0, 0), (4, 0)]), None);
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::Path;
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+};
+
+const PRESENT_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+const ABSENT_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Finds the chunks that contain a blockstate at all, without counting it.
+/// Scanning a chunk stops at the first match, which makes this much faster than counting.
+pub struct PresenceAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    target: String,
+    area: Area,
+
+    /// Chunks containing the target, in the order they were scanned
+    present: Vec<(i32, i32)>,
+}
+
+impl<'a> PresenceAnalyzer<'a> {
+    pub fn new(path: &'a str, area: Area, target: String, loader_options: LoaderOptions) -> Self {
+        PresenceAnalyzer::with_source(RegionFolderSource::new(path), area, target, loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        target: String,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        PresenceAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            target,
+            area,
+            present: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let target = self.target.as_str();
+            let present = chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .any(|section| section.contains_blockstate(target));
+
+            if present {
+                self.present.push((chunk_x, chunk_z));
+            }
+        }
+    }
+
+    /// Stops at the first block with the target blockstate and returns its position,
+    /// or `None` if none of the chunks contains it
+    pub fn find_first<I: IntoIterator<Item = (i32, i32)>>(
+        &mut self,
+        chunks: I,
+    ) -> Option<(i64, i32, i64)> {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let target = self.target.as_str();
+            let found = chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .filter(|section| section.contains_blockstate(target))
+                .find_map(|section| section.blocks().find(|block| block.blockstate == target));
+
+            if let Some(block) = found {
+                self.present.push((chunk_x, chunk_z));
+                return Some(block.global_pos);
+            }
+        }
+
+        None
+    }
+
+    /// Prints the coordinates of every chunk containing the target
+    pub fn print_csv(&self) {
+        println!("X,Z");
+        for (x, z) in &self.present {
+            println!("{},{}", x, z);
+        }
+    }
+
+    /// Saves a map with one pixel per chunk, white where the target is present.
+    /// All chunks need to lie within the area the analyzer was created with.
+    /// With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, present) = (&self.area, self.present.as_slice());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, present, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, present, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+/// Renders the part of the map with its top left corner at `(x, y)`, white for the `present` chunks
+pub(crate) fn render(
+    area: &Area,
+    present: &[(i32, i32)],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, ABSENT_COLOR);
+    let map_height = area.chunk_width_z();
+
+    for &(chunk_x, chunk_z) in present {
+        let (pixel_x, pixel_z) = area.get_positive_coords(chunk_x, chunk_z);
+        if pixel_z >= map_height {
+            continue;
+        }
+
+        let pixel_y = map_height - pixel_z - 1;
+        if (x..x + width).contains(&pixel_x) && (y..y + height).contains(&pixel_y) {
+            img.put_pixel(pixel_x - x, pixel_y - y, PRESENT_COLOR);
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn chunk_with_one_match_is_present() {
+        let names = ["minecraft:netherrack", "minecraft:ancient_debris"];
+        let source = test_fixtures::source(vec![
+            // A single block of ancient debris
+            test_fixtures::chunk(
+                0,
+                0,
+                vec![test_fixtures::section(0, &names, |x, y, z| {
+                    ((x, y, z) == (3, 4, 5)) as usize
+                })],
+            ),
+            // Ancient debris in the palette, but not used by any block
+            test_fixtures::chunk(1, 0, vec![test_fixtures::section(0, &names, |_, _, _| 0)]),
+        ]);
+        let area = Area::new(0, 2, 0, 1);
+        let mut analyzer = PresenceAnalyzer::with_source(
+            source,
+            area,
+            "minecraft:ancient_debris".to_string(),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(area);
+
+        assert_eq!(analyzer.present, vec![(0, 0)]);
+
+        let img = render(&analyzer.area, &analyzer.present, (0, 0), (2, 1));
+        assert_eq!(*img.get_pixel(0, 0), PRESENT_COLOR);
+        assert_eq!(*img.get_pixel(1, 0), ABSENT_COLOR);
+    }
+
+    #[test]
+    fn find_first_stops_at_match() {
+        let names = ["minecraft:stone", "minecraft:emerald_ore"];
+        let chunks = (0..4)
+            .map(|x| {
+                // Emerald ore only in chunk 1, at Y 7
+                let section =
+                    test_fixtures::section(0, &names, move |_, y, _| (x == 1 && y == 7) as usize);
+                test_fixtures::chunk(x, 0, vec![section])
+            })
+            .collect();
+        let area = Area::new(0, 4, 0, 1);
+        let mut analyzer = PresenceAnalyzer::with_source(
+            test_fixtures::source(chunks),
+            area,
+            "minecraft:emerald_ore".to_string(),
+            LoaderOptions::default(),
+        );
+
+        assert_eq!(analyzer.find_first(area), Some((16, 7, 0)));
+        // Chunks after the match aren't loaded
+        assert_eq!(analyzer.chunk_loader.stats().misses, 2);
+
+        analyzer.target = "minecraft:diamond_ore".to_string();
+        assert_eq!(analyzer.find_first(area), None);
+        assert_eq!(analyzer.chunk_loader.stats().misses, 4);
+    }
+}
@@ -0,0 +1,39 @@
+/// Blocks of the deep dark biome and its ancient cities. Sculk spreads on its own in the deep dark,
+/// while catalysts are rare and reinforced deepslate only occurs in ancient cities.
+pub const DEEP_DARK_BLOCKS: &[&str] = &[
+    "minecraft:sculk",
+    "minecraft:sculk_vein",
+    "minecraft:sculk_sensor",
+    "minecraft:sculk_shrieker",
+    "minecraft:sculk_catalyst",
+    "minecraft:reinforced_deepslate",
+];
+
+/// A ready-made set of blocks for a common search, e.g. locating ancient cities.
+/// Every block of the set is counted and its location recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    DeepDark,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "deep-dark" => Some(Preset::DeepDark),
+            _ => None,
+        }
+    }
+
+    pub fn blocks(self) -> &'static [&'static str] {
+        match self {
+            Preset::DeepDark => DEEP_DARK_BLOCKS,
+        }
+    }
+
+    /// Heading of the block column in the output
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::DeepDark => "Deep dark block",
+        }
+    }
+}
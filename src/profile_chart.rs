@@ -0,0 +1,122 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::layers::Layers;
+
+/// Width of the chart, which is the width of a layer at 100%
+const CHART_WIDTH: u32 = 400;
+
+/// Height of a single layer in pixels
+pub const ROW_HEIGHT: u32 = 2;
+
+/// Colors of the most common blockstates, in order
+const SERIES_COLORS: &[Rgb<u8>] = &[
+    Rgb([31, 119, 180]),
+    Rgb([255, 127, 14]),
+    Rgb([44, 160, 44]),
+    Rgb([214, 39, 40]),
+    Rgb([148, 103, 189]),
+    Rgb([140, 86, 75]),
+    Rgb([227, 119, 194]),
+    Rgb([188, 189, 34]),
+    Rgb([23, 190, 207]),
+];
+
+/// Color for all blockstates that aren't shown individually
+pub const OTHER_COLOR: Rgb<u8> = Rgb([127, 127, 127]);
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Renders the share of every blockstate per layer as a stacked bar chart, with the highest layer at the top.
+/// Only the given blockstates are shown individually (at most one per color), all others are combined.
+/// Returns the chart along with the blockstates and their colors, in the order they're stacked.
+pub fn render_profile(
+    layers: &Layers,
+    blockstates: &[String],
+) -> (RgbImage, Vec<(String, Rgb<u8>)>) {
+    let series: Vec<(String, Rgb<u8>)> = blockstates
+        .iter()
+        .zip(SERIES_COLORS)
+        .map(|(blockstate, &color)| (blockstate.clone(), color))
+        .collect();
+
+    let layers: Vec<_> = layers.iter().collect();
+    let (min_y, max_y) = match (layers.first(), layers.last()) {
+        (Some(bottom), Some(top)) => (bottom.y, top.y),
+        _ => return (ImageBuffer::from_pixel(CHART_WIDTH, 1, BACKGROUND), series),
+    };
+
+    let height = (max_y - min_y + 1) as u32 * ROW_HEIGHT;
+    let mut img: RgbImage = ImageBuffer::from_pixel(CHART_WIDTH, height, BACKGROUND);
+
+    for layer in layers {
+        let total = layer.total();
+        if total == 0 {
+            continue;
+        }
+
+        let row = (max_y - layer.y) as u32 * ROW_HEIGHT;
+        let mut counted = 0;
+        let mut x = 0;
+
+        for (blockstate, color) in &series {
+            counted += layer.get_count(blockstate);
+            let end = (counted * CHART_WIDTH as u64 / total) as u32;
+
+            fill_row(&mut img, row, x..end, *color);
+            x = end;
+        }
+
+        fill_row(&mut img, row, x..CHART_WIDTH, OTHER_COLOR);
+    }
+
+    (img, series)
+}
+
+fn fill_row(img: &mut RgbImage, row: u32, columns: std::ops::Range<u32>, color: Rgb<u8>) {
+    for y in row..row + ROW_HEIGHT {
+        for x in columns.clone() {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossover_pixel() {
+        // Deepslate from Y -8 to -1, stone from Y 1 to 7 and half of each at Y 0
+        let mut layers = Layers::new();
+        for y in -8..8 {
+            for index in 0..10 {
+                let deepslate = y < 0 || (y == 0 && index < 5);
+                let blockstate = if deepslate {
+                    "minecraft:deepslate"
+                } else {
+                    "minecraft:stone"
+                };
+                layers.increment(blockstate, y);
+            }
+        }
+        let blockstates = vec![
+            "minecraft:stone".to_string(),
+            "minecraft:deepslate".to_string(),
+        ];
+
+        let (img, series) = render_profile(&layers, &blockstates);
+        let (stone, deepslate) = (series[0].1, series[1].1);
+        assert_eq!(img.dimensions(), (CHART_WIDTH, 16 * ROW_HEIGHT));
+
+        // Y 7 is the top row, so Y 0 starts 7 layers down
+        let crossover = 7 * ROW_HEIGHT;
+        for x in [0, CHART_WIDTH - 1] {
+            assert_eq!(*img.get_pixel(x, crossover - 1), stone);
+            assert_eq!(*img.get_pixel(x, crossover + ROW_HEIGHT), deepslate);
+        }
+        assert_eq!(*img.get_pixel(0, crossover), stone);
+        assert_eq!(*img.get_pixel(CHART_WIDTH / 2 - 1, crossover), stone);
+        assert_eq!(*img.get_pixel(CHART_WIDTH / 2, crossover), deepslate);
+        assert_eq!(*img.get_pixel(CHART_WIDTH - 1, crossover + 1), deepslate);
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Number of recent chunks the processing rate is averaged over
+const RATE_WINDOW: usize = 256;
+
+/// Minimum time between two printed progress lines
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tracks how many of a known number of chunks are done and estimates the remaining time.
+/// The rate is a rolling average over the last chunks, so that stretches of missing chunks
+/// (which are skipped almost instantly) only skew the estimate briefly.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    total: usize,
+    done: usize,
+
+    /// Times at which the most recent chunks were finished, oldest first
+    recent: VecDeque<Instant>,
+}
+
+impl Progress {
+    pub fn new(total: usize, start: Instant) -> Self {
+        let mut recent = VecDeque::with_capacity(RATE_WINDOW + 1);
+        recent.push_back(start);
+
+        Progress {
+            total,
+            done: 0,
+            recent,
+        }
+    }
+
+    /// Marks one more chunk as done at the given time
+    pub fn record(&mut self, now: Instant) {
+        self.done += 1;
+
+        self.recent.push_back(now);
+        if self.recent.len() > RATE_WINDOW + 1 {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Estimated time until all chunks are done, if there's enough data for an estimate
+    pub fn eta(&self) -> Option<Duration> {
+        let (first, last) = (self.recent.front()?, self.recent.back()?);
+        let samples = self.recent.len() as u32 - 1;
+
+        if samples == 0 {
+            return None;
+        }
+
+        let per_chunk = last.duration_since(*first) / samples;
+        let remaining = self.total.saturating_sub(self.done) as u32;
+
+        Some(per_chunk * remaining)
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} chunks", self.done, self.total)?;
+
+        if let Some(eta) = self.eta() {
+            let secs = eta.as_secs();
+            write!(f, ", ETA ")?;
+
+            if secs >= 3600 {
+                write!(f, "{}h{}m{}s", secs / 3600, secs % 3600 / 60, secs % 60)?;
+            } else if secs >= 60 {
+                write!(f, "{}m{}s", secs / 60, secs % 60)?;
+            } else {
+                write!(f, "{}s", secs)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Wraps a chunk iterator, printing the progress to stderr at most once per `REPORT_INTERVAL`
+/// if `report` is set. A chunk counts as done once the next one is requested.
+pub struct ProgressIter<I: Iterator> {
+    chunks: I,
+    progress: Progress,
+    report: bool,
+    started: bool,
+    last_report: Instant,
+
+    /// Chunks taking longer than this to process are logged
+    slow_chunk_threshold: Option<Duration>,
+    /// Chunk currently being processed and when it was handed out
+    current: Option<(I::Item, Instant)>,
+    /// Chunks that took longer than the threshold so far, with the time they took
+    slow_chunks: Vec<(I::Item, Duration)>,
+    /// Whether all chunks have been handed out
+    finished: bool,
+}
+
+impl<I: Iterator> ProgressIter<I> {
+    pub fn new(chunks: I, total: usize, report: bool) -> Self {
+        let now = Instant::now();
+
+        ProgressIter {
+            chunks,
+            progress: Progress::new(total, now),
+            report,
+            started: false,
+            last_report: now,
+            slow_chunk_threshold: None,
+            current: None,
+            slow_chunks: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Logs every chunk that takes longer than `threshold` to process, i.e. from the time it's
+    /// handed out until the next chunk is requested, to find chunks that slow down a scan
+    pub fn with_slow_chunk_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.slow_chunk_threshold = threshold;
+        self
+    }
+}
+
+impl<I> Iterator for ProgressIter<I>
+where
+    I: Iterator,
+    I::Item: Clone + fmt::Debug,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = Instant::now();
+
+        if let (Some(threshold), Some((chunk, started))) =
+            (self.slow_chunk_threshold, self.current.take())
+        {
+            let elapsed = now.duration_since(started);
+            if elapsed > threshold {
+                eprintln!("Slow chunk {:?}: {} ms", chunk, elapsed.as_millis());
+                self.slow_chunks.push((chunk, elapsed));
+            }
+        }
+
+        if self.started {
+            self.progress.record(now);
+        } else {
+            // Restart the clock so that setup before the first chunk isn't counted
+            self.progress = Progress::new(self.progress.total, now);
+            self.started = true;
+        }
+
+        let next = self.chunks.next();
+
+        if self.slow_chunk_threshold.is_some() {
+            self.current = next.clone().map(|chunk| (chunk, now));
+        }
+
+        if next.is_none() && !self.finished {
+            self.finished = true;
+            if let Some(threshold) = self.slow_chunk_threshold {
+                print_slow_chunk_summary(&self.slow_chunks, threshold);
+            }
+        }
+
+        if !self.report {
+            return next;
+        }
+
+        if next.is_none() || now.duration_since(self.last_report) >= REPORT_INTERVAL {
+            eprintln!("{}", self.progress);
+            self.last_report = now;
+        }
+
+        next
+    }
+}
+
+/// Sums up the slow chunks of a scan, since the lines about single chunks are easily missed in long ones
+fn print_slow_chunk_summary<T: fmt::Debug>(slow_chunks: &[(T, Duration)], threshold: Duration) {
+    match slow_chunks.iter().max_by_key(|(_, elapsed)| *elapsed) {
+        Some((chunk, elapsed)) => eprintln!(
+            "{} chunks took longer than {} ms, the slowest was {:?} with {} ms",
+            slow_chunks.len(),
+            threshold.as_millis(),
+            chunk,
+            elapsed.as_millis()
+        ),
+        None => eprintln!("No chunk took longer than {} ms", threshold.as_millis()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use anvil_region::error::ChunkReadError;
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::{
+        chunk_loader::LoaderOptions,
+        chunk_source::ChunkSource,
+        composition_analyzer::{CompositionAnalyzer, CompositionOptions},
+        test_fixtures::{self, MemoryChunkSource},
+    };
+
+    /// Takes a while to read one of its chunks
+    struct SlowSource {
+        chunks: MemoryChunkSource,
+        slow_chunk: (i32, i32),
+    }
+
+    impl ChunkSource for SlowSource {
+        fn read_chunk_nbt(
+            &self,
+            chunk_x: i32,
+            chunk_z: i32,
+            skip: &[&str],
+        ) -> Result<CompoundTag, ChunkReadError> {
+            if (chunk_x, chunk_z) == self.slow_chunk {
+                thread::sleep(Duration::from_millis(100));
+            }
+            self.chunks.read_chunk_nbt(chunk_x, chunk_z, skip)
+        }
+    }
+
+    #[test]
+    fn eta() {
+        let start = Instant::now();
+        let mut progress = Progress::new(1000, start);
+        assert_eq!(progress.eta(), None);
+        assert_eq!(progress.to_string(), "0/1000 chunks");
+
+        // 10 chunks taking a second each
+        for second in 1..=10 {
+            progress.record(start + Duration::from_secs(second));
+        }
+        assert_eq!(progress.eta(), Some(Duration::from_secs(990)));
+        assert_eq!(progress.to_string(), "10/1000 chunks, ETA 16m30s");
+
+        // A stretch of missing chunks, skipped within a second, speeds up the estimate
+        let skipped = start + Duration::from_secs(11);
+        for _ in 0..90 {
+            progress.record(skipped);
+        }
+        assert_eq!(progress.eta(), Some(Duration::from_secs(11) / 100 * 900));
+
+        // Once more than `RATE_WINDOW` further chunks are done, only their rate counts
+        let mut now = skipped;
+        for _ in 0..RATE_WINDOW {
+            now += Duration::from_secs(2);
+            progress.record(now);
+        }
+        assert_eq!(progress.eta(), Some(Duration::from_secs(2 * 644)));
+        assert_eq!(progress.to_string(), "356/1000 chunks, ETA 21m28s");
+    }
+
+    #[test]
+    fn slow_chunk_is_logged() {
+        let chunks = (0..3)
+            .map(|x| {
+                let section = test_fixtures::uniform_section(0, "minecraft:stone");
+                test_fixtures::chunk(x, 0, vec![section])
+            })
+            .collect();
+        let source = SlowSource {
+            chunks: test_fixtures::source(chunks),
+            slow_chunk: (1, 0),
+        };
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+
+        let positions = vec![(0, 0), (1, 0), (2, 0)];
+        let mut chunks = ProgressIter::new(positions.into_iter(), 3, false)
+            .with_slow_chunk_threshold(Some(Duration::from_millis(50)));
+        analyzer.analyze(&mut chunks);
+
+        let slow: Vec<(i32, i32)> = chunks.slow_chunks.iter().map(|(chunk, _)| *chunk).collect();
+        assert_eq!(slow, vec![(1, 0)]);
+        assert!(chunks.slow_chunks[0].1 >= Duration::from_millis(100));
+    }
+}
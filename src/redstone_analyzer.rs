@@ -0,0 +1,253 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    area::Area,
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    grid::draw_grid,
+    image_output::{save_image, save_tiles, ImageFormat},
+};
+
+const EMPTY_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Blocks that make up redstone circuits, which are counted if no custom list is given
+pub const REDSTONE_COMPONENTS: &[&str] = &[
+    "minecraft:redstone_wire",
+    "minecraft:repeater",
+    "minecraft:comparator",
+    "minecraft:observer",
+    "minecraft:piston",
+    "minecraft:sticky_piston",
+    "minecraft:piston_head",
+    "minecraft:moving_piston",
+    "minecraft:hopper",
+    "minecraft:dropper",
+    "minecraft:dispenser",
+    "minecraft:redstone_torch",
+    "minecraft:redstone_wall_torch",
+    "minecraft:redstone_block",
+    "minecraft:redstone_lamp",
+    "minecraft:lever",
+    "minecraft:daylight_detector",
+    "minecraft:target",
+    "minecraft:tripwire_hook",
+    "minecraft:note_block",
+    "minecraft:sculk_sensor",
+    "minecraft:calibrated_sculk_sensor",
+];
+
+/// Counts redstone components, in total and per chunk, to find dense circuitry that may cause lag
+pub struct RedstoneAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    area: Area,
+    components: HashSet<String>,
+
+    /// blockstate -> count
+    totals: HashMap<String, u32>,
+
+    /// Chunk coordinates and number of components, for chunks containing any
+    chunk_counts: Vec<((i32, i32), u32)>,
+}
+
+impl<'a> RedstoneAnalyzer<'a> {
+    pub fn new(
+        path: &'a str,
+        area: Area,
+        components: HashSet<String>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        RedstoneAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            area,
+            components,
+            loader_options,
+        )
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        components: HashSet<String>,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        RedstoneAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            area,
+            components,
+            totals: HashMap::new(),
+            chunk_counts: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let mut chunk_count = 0;
+            for section in chunk {
+                for block in section.blocks() {
+                    if self.components.contains(&block.blockstate) {
+                        *self.totals.entry(block.blockstate).or_insert(0) += 1;
+                        chunk_count += 1;
+                    }
+                }
+            }
+
+            if chunk_count > 0 {
+                self.chunk_counts.push(((chunk_x, chunk_z), chunk_count));
+            }
+        }
+    }
+
+    /// Prints the most common components first
+    pub fn print_csv(&self, id_form: IdForm) {
+        let mut totals: Vec<(&String, &u32)> = self.totals.iter().collect();
+        totals.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("Component,Count");
+        for (blockstate, count) in totals {
+            println!("{},{:8}", id_form.render(blockstate), count);
+        }
+    }
+
+    /// Saves a heatmap with one pixel per chunk, redder the more components it has relative to
+    /// the chunk with the most. With a tile size, the map is split into tiles as described in `save_tiles`,
+    /// otherwise gridlines are drawn every `grid` chunks if given, see `draw_grid`.
+    pub fn print_img(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        tile_size: Option<u32>,
+        grid: Option<u32>,
+    ) {
+        let size = (self.area.chunk_width_x(), self.area.chunk_width_z());
+        let (area, chunk_counts) = (&self.area, self.chunk_counts.as_slice());
+
+        match tile_size {
+            Some(tile_size) => {
+                save_tiles(
+                    Path::new(path),
+                    size,
+                    tile_size,
+                    format,
+                    |x, y, width, height| render(area, chunk_counts, (x, y), (width, height)),
+                )
+                .unwrap();
+            }
+            None => {
+                let mut img = render(area, chunk_counts, (0, 0), size);
+                if let Some(spacing) = grid {
+                    draw_grid(&mut img, area, spacing);
+                }
+                save_image(&img, path, format).unwrap();
+            }
+        }
+    }
+}
+
+/// Renders the part of the heatmap with its top left corner at `(x, y)`
+fn render(
+    area: &Area,
+    chunk_counts: &[((i32, i32), u32)],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::from_pixel(width, height, EMPTY_COLOR);
+    let map_height = area.chunk_width_z();
+    let max_count = chunk_counts
+        .iter()
+        .map(|&(_, count)| count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    for &((chunk_x, chunk_z), count) in chunk_counts {
+        let (pixel_x, pixel_z) = area.get_positive_coords(chunk_x, chunk_z);
+        if pixel_z >= map_height {
+            continue;
+        }
+
+        let pixel_y = map_height - pixel_z - 1;
+        if (x..x + width).contains(&pixel_x) && (y..y + height).contains(&pixel_y) {
+            let heat = (count as u64 * u8::MAX as u64 / max_count as u64) as u8;
+            img.put_pixel(pixel_x - x, pixel_y - y, Rgb([heat, 0, 0]));
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn hotspot() {
+        let names = [
+            "minecraft:stone",
+            "minecraft:hopper",
+            "minecraft:repeater",
+            "minecraft:lever",
+        ];
+        // A layer of hoppers below a layer of repeaters
+        let packed = test_fixtures::section(0, &names, |_, y, _| match y {
+            0 => 1,
+            1 => 2,
+            _ => 0,
+        });
+        // A single lever
+        let sparse = test_fixtures::section(
+            0,
+            &names,
+            |x, y, z| {
+                if (x, y, z) == (0, 0, 0) {
+                    3
+                } else {
+                    0
+                }
+            },
+        );
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(0, 0, vec![sparse]),
+            test_fixtures::chunk(
+                1,
+                0,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            ),
+            test_fixtures::chunk(1, 1, vec![packed]),
+        ]);
+
+        let components = REDSTONE_COMPONENTS
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let area = Area::new(0, 2, 0, 2);
+        let mut analyzer =
+            RedstoneAnalyzer::with_source(source, area, components, LoaderOptions::default());
+        analyzer.analyze(area);
+
+        assert_eq!(analyzer.totals["minecraft:hopper"], 256);
+        assert_eq!(analyzer.totals["minecraft:repeater"], 256);
+        assert_eq!(analyzer.totals["minecraft:lever"], 1);
+        assert_eq!(analyzer.chunk_counts, vec![((0, 0), 1), ((1, 1), 512)]);
+
+        // Chunk 1, 1 is at the top right, chunk 0, 0 at the bottom left
+        let img = render(&analyzer.area, &analyzer.chunk_counts, (0, 0), (2, 2));
+        assert_eq!(*img.get_pixel(1, 0), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(0, 1), Rgb([0, 0, 0]));
+    }
+}
@@ -0,0 +1,259 @@
+//! Reading region files out of `.tar.gz` archives of a region folder, e.g. world backups.
+//!
+//! The `.mca` files are unpacked into a temporary folder that the usual region readers work on.
+//! Only plain (ustar and GNU) tar entries are supported, other files in the archive are skipped.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process,
+};
+
+use flate2::read::GzDecoder;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Whether the path looks like a gzip-compressed tar archive
+pub fn is_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Region files of an archive unpacked into a temporary folder, which is removed again on drop
+#[derive(Debug)]
+pub struct RegionArchive {
+    folder: PathBuf,
+}
+
+impl RegionArchive {
+    /// Unpacks all `.mca` files of the archive into one folder, regardless of where they are
+    /// stored inside the archive
+    pub fn extract(archive_path: &Path) -> io::Result<Self> {
+        let folder = std::env::temp_dir().join(format!("mca-analyzer-{}", process::id()));
+        fs::create_dir_all(&folder)?;
+
+        let archive = RegionArchive { folder };
+        let mut reader = GzDecoder::new(File::open(archive_path)?);
+        archive.unpack(&mut reader)?;
+
+        Ok(archive)
+    }
+
+    /// The folder containing the unpacked region files
+    pub fn path(&self) -> &Path {
+        &self.folder
+    }
+
+    fn unpack(&self, reader: &mut impl Read) -> io::Result<()> {
+        let mut long_name = None;
+
+        loop {
+            let mut header = [0; BLOCK_SIZE];
+            if !read_block(reader, &mut header)? || header.iter().all(|&byte| byte == 0) {
+                return Ok(());
+            }
+
+            let size = parse_octal(&header[124..136])?;
+            let padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+            let mut entry = reader.by_ref().take(size);
+
+            match header[156] {
+                b'L' => {
+                    let mut name = Vec::new();
+                    entry.read_to_end(&mut name)?;
+                    long_name = Some(trim_nul(&name).to_vec());
+                }
+                b'0' | 0 => {
+                    let name = long_name.take().unwrap_or_else(|| entry_name(&header));
+                    let name = String::from_utf8_lossy(&name).into_owned();
+
+                    match region_file_name(&name) {
+                        Some(file_name) => {
+                            let mut file = File::create(self.folder.join(file_name))?;
+                            io::copy(&mut entry, &mut file)?;
+                        }
+                        None => {
+                            io::copy(&mut entry, &mut io::sink())?;
+                        }
+                    }
+                }
+                _ => {
+                    long_name = None;
+                    io::copy(&mut entry, &mut io::sink())?;
+                }
+            }
+
+            if entry.limit() > 0 {
+                return Err(invalid("archive ends in the middle of an entry"));
+            }
+            io::copy(&mut reader.by_ref().take(padding), &mut io::sink())?;
+        }
+    }
+}
+
+impl Drop for RegionArchive {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.folder);
+    }
+}
+
+/// Reads a whole block, returning false if the archive ended before it
+fn read_block(reader: &mut impl Read, block: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+
+    while read < block.len() {
+        match reader.read(&mut block[read..])? {
+            0 if read == 0 => return Ok(false),
+            0 => return Err(invalid("archive ends in the middle of a header")),
+            count => read += count,
+        }
+    }
+
+    Ok(true)
+}
+
+/// Name of an entry, including the ustar prefix if there is one
+fn entry_name(header: &[u8; BLOCK_SIZE]) -> Vec<u8> {
+    let name = trim_nul(&header[0..100]);
+    let prefix = if &header[257..262] == b"ustar" {
+        trim_nul(&header[345..500])
+    } else {
+        &[][..]
+    };
+
+    if prefix.is_empty() {
+        name.to_vec()
+    } else {
+        [prefix, &b"/"[..], name].concat()
+    }
+}
+
+/// File name of an entry if it's a region file, e.g. `r.0.-1.mca` for `world/region/r.0.-1.mca`.
+/// McRegion `.mcr` files count too.
+fn region_file_name(name: &str) -> Option<&str> {
+    let file_name = name.rsplit('/').next()?;
+
+    if file_name.starts_with("r.") && (file_name.ends_with(".mca") || file_name.ends_with(".mcr")) {
+        Some(file_name)
+    } else {
+        None
+    }
+}
+
+fn parse_octal(field: &[u8]) -> io::Result<u64> {
+    let digits = String::from_utf8_lossy(trim_nul(field));
+    let digits = digits.trim();
+
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("invalid entry size"))
+}
+
+fn trim_nul(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    &bytes[..end]
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::{
+        chunk_loader::LoaderOptions,
+        composition_analyzer::{CompositionAnalyzer, CompositionOptions},
+        test_fixtures::{self, TempDir},
+    };
+
+    /// Appends a file entry with a ustar header to a tar archive
+    fn append_entry(tar: &mut Vec<u8>, name: &str, contents: &[u8]) {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(contents);
+        tar.resize(tar.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+    }
+
+    fn totals(region_folder: &str) -> Vec<(String, u64)> {
+        let mut analyzer = CompositionAnalyzer::new(
+            region_folder,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0), (1, 0)]);
+        analyzer.totals()
+    }
+
+    #[test]
+    fn archive_like_extracted_folder() {
+        let extracted = TempDir::new();
+        let region_path = extracted.path().join("r.0.0.mca");
+        let chunks = (0..2)
+            .map(|x| {
+                let section = test_fixtures::section(
+                    0,
+                    &["minecraft:stone", "minecraft:iron_ore"],
+                    move |_, y, _| (y == x as usize) as usize,
+                );
+                (
+                    (x, 0),
+                    2,
+                    test_fixtures::zlib(test_fixtures::chunk(x, 0, vec![section])),
+                )
+            })
+            .collect();
+        test_fixtures::write_region_file(&region_path, chunks);
+
+        // The region file within a world folder, next to a file that isn't a region
+        let mut tar = Vec::new();
+        append_entry(&mut tar, "world/level.dat", b"not a region");
+        append_entry(
+            &mut tar,
+            "world/region/r.0.0.mca",
+            &fs::read(&region_path).unwrap(),
+        );
+        tar.extend_from_slice(&[0; 2 * BLOCK_SIZE]);
+
+        let archive_dir = TempDir::new();
+        let archive_path = archive_dir.path().join("world.tar.gz");
+        let mut encoder =
+            GzEncoder::new(File::create(&archive_path).unwrap(), Compression::default());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap();
+
+        assert!(is_archive(&archive_path));
+        let archive = RegionArchive::extract(&archive_path).unwrap();
+        let files: Vec<_> = fs::read_dir(archive.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+
+        let from_archive = totals(archive.path().to_str().unwrap());
+        assert_eq!(from_archive, totals(extracted.as_str()));
+        assert_eq!(
+            from_archive,
+            vec![
+                ("minecraft:stone".to_string(), 2 * 4096 - 2 * 256),
+                ("minecraft:iron_ore".to_string(), 2 * 256),
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::{fs, io, path::Path};
+
+use crate::area::{Area, REGION_SIZE};
+
+/// An explicit set of regions to analyze, as an alternative to a rectangular `Area`
+#[derive(Debug, Clone)]
+pub struct RegionList {
+    regions: Vec<(i32, i32)>,
+}
+
+impl RegionList {
+    /// Reads a list of regions, one per line, either as a region file name (`r.X.Z.mca`)
+    /// or as region coordinates (`X,Z`). Blank lines and lines starting with `#` are ignored.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        let regions = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                parse_region(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("'{}' is not a valid region", line),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(RegionList { regions })
+    }
+
+    /// Smallest area containing all listed regions
+    pub fn bounding_area(&self) -> Area {
+        let min_x = self.regions.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = self.regions.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+        let min_z = self.regions.iter().map(|&(_, z)| z).min().unwrap_or(0);
+        let max_z = self.regions.iter().map(|&(_, z)| z + 1).max().unwrap_or(0);
+
+        Area::new(
+            min_x * REGION_SIZE,
+            max_x * REGION_SIZE,
+            min_z * REGION_SIZE,
+            max_z * REGION_SIZE,
+        )
+    }
+}
+
+impl IntoIterator for RegionList {
+    type Item = (i32, i32);
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    /// Iterates over the chunk coordinates of all listed regions
+    fn into_iter(self) -> Self::IntoIter {
+        self.regions
+            .into_iter()
+            .flat_map(|(region_x, region_z)| {
+                Area::new(
+                    region_x * REGION_SIZE,
+                    (region_x + 1) * REGION_SIZE,
+                    region_z * REGION_SIZE,
+                    (region_z + 1) * REGION_SIZE,
+                )
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+fn parse_region(line: &str) -> Option<(i32, i32)> {
+    let parts: Vec<&str> = line.split('.').collect();
+
+    if let ["r", x, z, "mca"] | ["r", x, z, "mcr"] = parts.as_slice() {
+        return Some((x.parse().ok()?, z.parse().ok()?));
+    }
+
+    let mut coords = line.split(',').map(|part| part.trim().parse());
+
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(x)), Some(Ok(z)), None) => Some((x, z)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunk_loader::LoaderOptions,
+        composition_analyzer::{CompositionAnalyzer, CompositionOptions},
+        test_fixtures::{self, TempDir},
+    };
+
+    #[test]
+    fn only_listed_region() {
+        let folder = TempDir::new();
+        let file = folder.path().join("regions.txt");
+        fs::write(&file, "# From another tool\nr.1.-1.mca\n").unwrap();
+
+        let regions = RegionList::from_file(&file).unwrap();
+        let chunks: Vec<(i32, i32)> = regions.into_iter().collect();
+        assert_eq!(chunks.len(), (REGION_SIZE * REGION_SIZE) as usize);
+        assert!(chunks
+            .iter()
+            .all(|&(x, z)| (32..64).contains(&x) && (-32..0).contains(&z)));
+
+        // A chunk in the listed region and one in the region next to it
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(
+                40,
+                -10,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            ),
+            test_fixtures::chunk(
+                0,
+                -10,
+                vec![test_fixtures::uniform_section(0, "minecraft:dirt")],
+            ),
+        ]);
+        let mut analyzer = CompositionAnalyzer::with_source(
+            source,
+            LoaderOptions::default(),
+            CompositionOptions::default(),
+        );
+        analyzer.analyze(chunks);
+
+        assert_eq!(
+            analyzer.totals(),
+            vec![("minecraft:stone".to_string(), 4096)]
+        );
+    }
+}
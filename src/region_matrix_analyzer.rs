@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::{
+    area::{Area, REGION_SIZE},
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Counts one blockstate per region and prints the counts as a grid of regions,
+/// ready to be pasted into a spreadsheet for a heatmap
+pub struct RegionMatrixAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    area: Area,
+    target: String,
+
+    /// region coordinates -> count of the target, for regions with at least one chunk
+    counts: HashMap<(i32, i32), u64>,
+}
+
+impl<'a> RegionMatrixAnalyzer<'a> {
+    pub fn new(path: &'a str, area: Area, target: String, loader_options: LoaderOptions) -> Self {
+        RegionMatrixAnalyzer::with_source(
+            RegionFolderSource::new(path),
+            area,
+            target,
+            loader_options,
+        )
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        area: Area,
+        target: String,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        RegionMatrixAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            area,
+            target,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let target = self.target.as_str();
+            let count = chunk
+                .section_indices()
+                .into_iter()
+                .filter_map(|y| chunk.get_section(y))
+                .filter(|section| section.contains_blockstate(target))
+                .flat_map(|section| section.blocks())
+                .filter(|block| block.blockstate == target)
+                .count();
+
+            let region = (
+                chunk_x.div_euclid(REGION_SIZE),
+                chunk_z.div_euclid(REGION_SIZE),
+            );
+            *self.counts.entry(region).or_insert(0) += count as u64;
+        }
+    }
+
+    /// Prints one row per region Z and one column per region X, covering all regions the area touches.
+    /// Regions without any chunk are left blank, unlike regions without the target which are 0.
+    pub fn print_csv(&self) {
+        for line in self.csv_lines() {
+            println!("{}", line);
+        }
+    }
+
+    fn csv_lines(&self) -> Vec<String> {
+        let (region_xs, region_zs) = self.area.region_ranges();
+
+        let header: Vec<String> = region_xs.clone().map(|x| x.to_string()).collect();
+        let mut lines = vec![format!("Z\\X,{}", header.join(","))];
+
+        for z in region_zs {
+            let cells: Vec<String> = region_xs
+                .clone()
+                .map(|x| {
+                    self.counts
+                        .get(&(x, z))
+                        .map_or(String::new(), |count| count.to_string())
+                })
+                .collect();
+            lines.push(format!("{},{}", z, cells.join(",")));
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn two_region_matrix() {
+        // A row of diamond ore in region -1, none in region 0, and region 1 has no chunks
+        let ore = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:diamond_ore"],
+            |x, y, _| (x == 0 && y == 0) as usize,
+        );
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(-1, 0, vec![ore]),
+            test_fixtures::chunk(
+                0,
+                0,
+                vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+            ),
+        ]);
+        let area = Area::new(-32, 64, 0, 1);
+
+        let mut analyzer = RegionMatrixAnalyzer::with_source(
+            source,
+            area,
+            "minecraft:diamond_ore".to_string(),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(-1, 0), (0, 0), (32, 0)]);
+
+        assert_eq!(analyzer.csv_lines(), vec!["Z\\X,-1,0,1", "0,16,0,"]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fmt, ops::RangeInclusive, path::PathBuf};
+
+use crate::{area::Area, block_id::IdForm, chunk_loader::LoaderOptions, image_output::ImageFormat};
+
+/// Settings of a run after resolving all arguments and defaults,
+/// printed with `--print-config` so that logs show exactly what was run
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub mode: String,
+
+    /// Canonical path of the folder that is read
+    pub input_folder: PathBuf,
+    pub output: Option<PathBuf>,
+
+    pub area: Area,
+    pub chunk_count: usize,
+    pub y_range: Option<RangeInclusive<i32>>,
+    pub y_step: Option<i32>,
+
+    pub loader_options: LoaderOptions,
+    /// Number of chunks kept in memory per loader
+    pub cache_size: usize,
+
+    pub image_format: ImageFormat,
+    pub tile_size: Option<u32>,
+    pub id_form: IdForm,
+}
+
+impl fmt::Display for RunConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn optional<T: fmt::Debug>(value: &Option<T>) -> String {
+            value
+                .as_ref()
+                .map_or("-".to_string(), |value| format!("{:?}", value))
+        }
+
+        writeln!(f, "mode: {}", self.mode)?;
+        writeln!(f, "input folder: {}", self.input_folder.display())?;
+        writeln!(
+            f,
+            "output: {}",
+            optional(&self.output.as_ref().map(|path| path.display()))
+        )?;
+        writeln!(f, "area: {} ({} chunks)", self.area, self.chunk_count)?;
+        writeln!(f, "y range: {}", optional(&self.y_range))?;
+        writeln!(f, "y step: {}", optional(&self.y_step))?;
+        writeln!(
+            f,
+            "implicit air: {}",
+            self.loader_options.palette.implicit_air
+        )?;
+        writeln!(
+            f,
+            "forced bit width: {}",
+            optional(&self.loader_options.palette.forced_bit_width)
+        )?;
+        writeln!(
+            f,
+            "skip corrupt regions: {}",
+            self.loader_options.skip_corrupt_regions
+        )?;
+        writeln!(f, "full chunks only: {}", self.loader_options.full_only)?;
+        writeln!(
+            f,
+            "skipped sections: {:?}",
+            self.loader_options.skipped_sections
+        )?;
+        writeln!(f, "chunk cache size: {}", self.cache_size)?;
+        writeln!(f, "image format: {:?}", self.image_format)?;
+        writeln!(f, "tile size: {}", optional(&self.tile_size))?;
+        write!(f, "id form: {:?}", self.id_form)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::PaletteOptions;
+
+    #[test]
+    fn prints_given_values_and_defaults() {
+        // A few settings given on the command line, everything else left at its default
+        let config = RunConfig {
+            mode: "composition".to_string(),
+            input_folder: PathBuf::from("/worlds/survival/region"),
+            output: None,
+            area: Area::new(-2, 2, 0, 4),
+            chunk_count: 16,
+            y_range: Some(-64..=0),
+            y_step: None,
+            loader_options: LoaderOptions {
+                palette: PaletteOptions {
+                    forced_bit_width: Some(5),
+                    ..PaletteOptions::default()
+                },
+                full_only: true,
+                ..LoaderOptions::default()
+            },
+            cache_size: 32,
+            image_format: ImageFormat::default(),
+            tile_size: None,
+            id_form: IdForm::default(),
+        };
+
+        let printed = config.to_string();
+        let lines: Vec<&str> = printed.lines().collect();
+        assert!(lines.contains(&"mode: composition"));
+        assert!(lines.contains(&"input folder: /worlds/survival/region"));
+        assert!(lines.contains(&"output: -"));
+        assert!(lines.contains(&"area: chunks x -2..2, z 0..4 (16 chunks)"));
+        assert!(lines.contains(&"y range: -64..=0"));
+        assert!(lines.contains(&"y step: -"));
+        assert!(lines.contains(&"forced bit width: 5"));
+        assert!(lines.contains(&"full chunks only: true"));
+        assert!(lines.contains(&"skip corrupt regions: false"));
+        assert!(lines.contains(&"chunk cache size: 32"));
+        assert!(lines.contains(&"image format: Png"));
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+/// Scans with at least this many chunks need to be confirmed
+pub const LARGE_SCAN_CHUNKS: usize = 1_000_000;
+
+/// Ballpark throughput of a full scan, only used for the upfront estimate
+const ROUGH_CHUNKS_PER_SECOND: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanSize {
+    /// There's nothing to scan, e.g. because the area has zero width
+    Empty,
+    Normal,
+    /// Suspiciously many chunks, likely a mistake
+    Large,
+}
+
+impl ScanSize {
+    pub fn of(chunk_count: usize) -> Self {
+        if chunk_count == 0 {
+            ScanSize::Empty
+        } else if chunk_count >= LARGE_SCAN_CHUNKS {
+            ScanSize::Large
+        } else {
+            ScanSize::Normal
+        }
+    }
+}
+
+/// Very rough estimate of how long scanning this many chunks takes
+pub fn estimated_duration(chunk_count: usize) -> Duration {
+    Duration::from_secs(chunk_count as u64 / ROUGH_CHUNKS_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::Area;
+
+    #[test]
+    fn zero_width_area_is_empty() {
+        let area = Area::new(5, 5, 0, 100);
+        assert_eq!(ScanSize::of(area.into_iter().count()), ScanSize::Empty);
+    }
+
+    #[test]
+    fn huge_area_is_large() {
+        let area = Area::new(-1000, 1000, -1000, 1000);
+        let chunk_count = (area.chunk_width_x() * area.chunk_width_z()) as usize;
+        assert_eq!(ScanSize::of(chunk_count), ScanSize::Large);
+        assert_eq!(estimated_duration(chunk_count), Duration::from_secs(40_000));
+
+        // The default area of 256 by 256 chunks doesn't need confirmation
+        assert_eq!(ScanSize::of(256 * 256), ScanSize::Normal);
+        assert_eq!(ScanSize::of(LARGE_SCAN_CHUNKS - 1), ScanSize::Normal);
+    }
+}
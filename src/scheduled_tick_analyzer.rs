@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+/// Tallies pending scheduled block and fluid updates, as a proxy for active or unstable terrain
+pub struct ScheduledTickAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// block or fluid id -> number of scheduled ticks
+    tick_counts: HashMap<String, u32>,
+}
+
+impl<'a> ScheduledTickAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        ScheduledTickAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        ScheduledTickAnalyzer {
+            // Blocks aren't needed, so don't decode any sections
+            chunk_loader: ChunkLoader::with_source(source, Some(0..0), loader_options),
+            tick_counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            for block in &chunk.scheduled_ticks {
+                *self.tick_counts.entry(block.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut tick_counts: Vec<(&String, &u32)> = self.tick_counts.iter().collect();
+        tick_counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        println!("Block,Scheduled ticks");
+        for (block, count) in tick_counts {
+            println!("{},{:8}", block, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures;
+
+    fn tick(id: &str, (x, y, z): (i32, i32, i32)) -> CompoundTag {
+        let mut tick = CompoundTag::new();
+        tick.insert_str("i", id);
+        tick.insert_i32("x", x);
+        tick.insert_i32("y", y);
+        tick.insert_i32("z", z);
+        tick.insert_i32("t", 5);
+        tick.insert_i32("p", 0);
+        tick
+    }
+
+    #[test]
+    fn fluid_ticks() {
+        // Water flowing down next to a sand block about to fall, and a chunk without ticks
+        let mut active = test_fixtures::chunk(0, 0, Vec::new());
+        active.insert_compound_tag_vec(
+            "fluid_ticks",
+            vec![
+                tick("minecraft:flowing_water", (3, 64, 3)),
+                tick("minecraft:flowing_water", (3, 63, 3)),
+            ],
+        );
+        active.insert_compound_tag_vec("block_ticks", vec![tick("minecraft:sand", (4, 65, 3))]);
+        let source = test_fixtures::source(vec![active, test_fixtures::chunk(1, 0, Vec::new())]);
+
+        let mut analyzer = ScheduledTickAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0), (1, 0)]);
+
+        assert_eq!(
+            analyzer.tick_counts,
+            HashMap::from([
+                ("minecraft:flowing_water".to_string(), 2),
+                ("minecraft:sand".to_string(), 1),
+            ])
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_section::CHUNK_SIZE,
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+const SECTION_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// How full sections are at each section Y, as the share of their blocks that aren't air.
+/// A vertical profile of this shows which Y ranges contain terrain worth analyzing.
+pub struct SectionDensityAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// section Y -> (number of sections, sum of their densities)
+    densities: HashMap<i8, (u32, f64)>,
+}
+
+impl<'a> SectionDensityAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        SectionDensityAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        SectionDensityAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            densities: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for y in chunk.section_indices() {
+                let section = match chunk.get_section(y) {
+                    Some(section) => section,
+                    None => continue,
+                };
+
+                let (sections, density_sum) = self.densities.entry(y).or_insert((0, 0.0));
+                *sections += 1;
+                *density_sum += section.non_air_count() as f64 / SECTION_VOLUME as f64;
+            }
+        }
+    }
+
+    /// Mean density of the sections at a section Y, `None` if no chunk had a section there
+    pub fn mean_density(&self, section_y: i8) -> Option<f64> {
+        self.densities
+            .get(&section_y)
+            .map(|&(sections, density_sum)| density_sum / sections as f64)
+    }
+
+    pub fn print_csv(&self) {
+        let mut section_ys: Vec<&i8> = self.densities.keys().collect();
+        section_ys.sort_unstable();
+
+        println!("Section Y,Sections,Mean density");
+        for &y in section_ys {
+            let (sections, _) = self.densities[&y];
+            println!(
+                "{:8},{:8},{:.4}",
+                y,
+                sections,
+                self.mean_density(y).unwrap()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn solid_and_sky_sections() {
+        let source = test_fixtures::source(vec![test_fixtures::chunk(
+            0,
+            0,
+            vec![
+                test_fixtures::uniform_section(0, "minecraft:stone"),
+                test_fixtures::uniform_section(1, "minecraft:air"),
+            ],
+        )]);
+        let mut analyzer = SectionDensityAnalyzer::with_source(source, LoaderOptions::default());
+        analyzer.analyze(vec![(0, 0)]);
+
+        assert_eq!(analyzer.mean_density(0), Some(1.0));
+        assert_eq!(analyzer.mean_density(1), Some(0.0));
+        assert_eq!(analyzer.mean_density(2), None);
+    }
+}
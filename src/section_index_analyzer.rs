@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+};
+
+pub struct SectionIndexAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// section Y -> number of chunks that have a section at that Y
+    chunks_by_section: HashMap<i8, u32>,
+
+    /// number of non-air sections -> number of chunks with that many
+    chunks_by_section_count: HashMap<usize, u32>,
+
+    /// Present section indices of every chunk, only recorded if requested
+    section_indices_per_chunk: Option<Vec<(i32, i32, Vec<i8>)>>,
+}
+
+impl<'a> SectionIndexAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions, per_chunk: bool) -> Self {
+        SectionIndexAnalyzer::with_source(RegionFolderSource::new(path), loader_options, per_chunk)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(
+        source: S,
+        loader_options: LoaderOptions,
+        per_chunk: bool,
+    ) -> Self {
+        SectionIndexAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            chunks_by_section: HashMap::new(),
+            chunks_by_section_count: HashMap::new(),
+            section_indices_per_chunk: if per_chunk { Some(Vec::new()) } else { None },
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let (section_indices, non_air_section_count) =
+                match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                    Some(chunk) => (chunk.section_indices(), chunk.non_air_section_count()),
+                    None => continue,
+                };
+
+            *self
+                .chunks_by_section_count
+                .entry(non_air_section_count)
+                .or_insert(0) += 1;
+
+            for &y in &section_indices {
+                *self.chunks_by_section.entry(y).or_insert(0) += 1;
+            }
+
+            if let Some(per_chunk) = &mut self.section_indices_per_chunk {
+                per_chunk.push((chunk_x, chunk_z, section_indices));
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut chunks_by_section: Vec<(&i8, &u32)> = self.chunks_by_section.iter().collect();
+        chunks_by_section.sort_unstable();
+
+        println!("Section Y,Chunks");
+        for (y, chunks) in chunks_by_section {
+            println!("{:8},{:8}", y, chunks);
+        }
+
+        println!();
+
+        let mut chunks_by_section_count: Vec<(&usize, &u32)> =
+            self.chunks_by_section_count.iter().collect();
+        chunks_by_section_count.sort_unstable();
+
+        println!("Sections present,Chunks");
+        for (section_count, chunks) in chunks_by_section_count {
+            println!("{:8},{:8}", section_count, chunks);
+        }
+
+        if let Some(per_chunk) = &self.section_indices_per_chunk {
+            println!();
+
+            println!("Chunk X,Chunk Z,Section Y indices");
+            for (chunk_x, chunk_z, section_indices) in per_chunk {
+                let section_indices: Vec<String> =
+                    section_indices.iter().map(|y| y.to_string()).collect();
+                println!("{},{},{}", chunk_x, chunk_z, section_indices.join(" "));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn section_indices_including_negative() {
+        let sections = |ys: &[i8]| {
+            ys.iter()
+                .map(|&y| test_fixtures::uniform_section(y, "minecraft:stone"))
+                .collect()
+        };
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(0, 0, sections(&[-4, -1, 0, 3])),
+            test_fixtures::chunk(1, 0, sections(&[-4, 0])),
+        ]);
+        let mut analyzer =
+            SectionIndexAnalyzer::with_source(source, LoaderOptions::default(), true);
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+
+        assert_eq!(
+            analyzer.chunks_by_section,
+            HashMap::from([(-4, 2), (-1, 1), (0, 2), (3, 1)])
+        );
+        assert_eq!(
+            analyzer.section_indices_per_chunk.unwrap(),
+            vec![(0, 0, vec![-4, -1, 0, 3]), (1, 0, vec![-4, 0])]
+        );
+    }
+
+    #[test]
+    fn non_air_section_histogram() {
+        let section = test_fixtures::uniform_section;
+        let source = test_fixtures::source(vec![
+            test_fixtures::chunk(
+                0,
+                0,
+                vec![
+                    section(0, "minecraft:stone"),
+                    section(1, "minecraft:stone"),
+                    section(2, "minecraft:dirt"),
+                ],
+            ),
+            // Sections of only air don't count
+            test_fixtures::chunk(
+                1,
+                0,
+                vec![
+                    section(0, "minecraft:stone"),
+                    section(1, "minecraft:air"),
+                    section(2, "minecraft:cave_air"),
+                ],
+            ),
+            test_fixtures::chunk(2, 0, vec![section(0, "minecraft:grass_block")]),
+        ]);
+        let mut analyzer =
+            SectionIndexAnalyzer::with_source(source, LoaderOptions::default(), false);
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+
+        assert_eq!(
+            analyzer.chunks_by_section_count,
+            HashMap::from([(1, 2), (3, 1)])
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::{self, Read};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use nbt::{decode::TagDecodeError, CompoundTag, Tag};
+
+const END_TAG_ID: u8 = 0;
+const COMPOUND_TAG_ID: u8 = 10;
+
+/// Reads a compound tag like `nbt::decode::read_compound_tag`, except that tags with one of the
+/// given names (at any depth) are skipped over without ever being held in memory.
+/// This keeps peak memory low for chunks with huge lists of e.g. entities that aren't needed.
+pub fn read_compound_tag_skipping<R: Read>(
+    reader: &mut R,
+    skip: &[&str],
+) -> Result<CompoundTag, TagDecodeError> {
+    let tag_id = reader.read_u8()?;
+    let name = read_string(reader)?;
+
+    match read_tag(tag_id, reader, skip)? {
+        Tag::Compound(mut compound) => {
+            compound.name = Some(name);
+            Ok(compound)
+        }
+        actual_tag => Err(TagDecodeError::RootMustBeCompoundTag { actual_tag }),
+    }
+}
+
+fn read_tag<R: Read>(tag_id: u8, reader: &mut R, skip: &[&str]) -> Result<Tag, TagDecodeError> {
+    let tag = match tag_id {
+        1 => Tag::Byte(reader.read_i8()?),
+        2 => Tag::Short(reader.read_i16::<BigEndian>()?),
+        3 => Tag::Int(reader.read_i32::<BigEndian>()?),
+        4 => Tag::Long(reader.read_i64::<BigEndian>()?),
+        5 => Tag::Float(reader.read_f32::<BigEndian>()?),
+        6 => Tag::Double(reader.read_f64::<BigEndian>()?),
+        7 => {
+            let length = reader.read_u32::<BigEndian>()? as usize;
+            let mut value = vec![0; length];
+            reader.read_i8_into(&mut value)?;
+            Tag::ByteArray(value)
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let list_tags_id = reader.read_u8()?;
+            let length = reader.read_u32::<BigEndian>()?;
+
+            let value = (0..length)
+                .map(|_| read_tag(list_tags_id, reader, skip))
+                .collect::<Result<_, _>>()?;
+            Tag::List(value)
+        }
+        COMPOUND_TAG_ID => {
+            let mut compound = CompoundTag::new();
+
+            loop {
+                let tag_id = reader.read_u8()?;
+                if tag_id == END_TAG_ID {
+                    break;
+                }
+
+                let name = read_string(reader)?;
+
+                if skip.contains(&name.as_str()) {
+                    skip_tag(tag_id, reader)?;
+                } else {
+                    compound.insert(name, read_tag(tag_id, reader, skip)?);
+                }
+            }
+
+            Tag::Compound(compound)
+        }
+        11 => {
+            let length = reader.read_u32::<BigEndian>()? as usize;
+            let mut value = vec![0; length];
+            reader.read_i32_into::<BigEndian>(&mut value)?;
+            Tag::IntArray(value)
+        }
+        12 => {
+            let length = reader.read_u32::<BigEndian>()? as usize;
+            let mut value = vec![0; length];
+            reader.read_i64_into::<BigEndian>(&mut value)?;
+            Tag::LongArray(value)
+        }
+        tag_type_id => return Err(TagDecodeError::UnknownTagType { tag_type_id }),
+    };
+
+    Ok(tag)
+}
+
+/// Consumes the payload of a tag without decoding it
+fn skip_tag<R: Read>(tag_id: u8, reader: &mut R) -> Result<(), TagDecodeError> {
+    match tag_id {
+        1 => skip_bytes(reader, 1)?,
+        2 => skip_bytes(reader, 2)?,
+        3 | 5 => skip_bytes(reader, 4)?,
+        4 | 6 => skip_bytes(reader, 8)?,
+        7 => {
+            let length = reader.read_u32::<BigEndian>()? as u64;
+            skip_bytes(reader, length)?;
+        }
+        8 => {
+            let length = reader.read_u16::<BigEndian>()? as u64;
+            skip_bytes(reader, length)?;
+        }
+        9 => {
+            let list_tags_id = reader.read_u8()?;
+            let length = reader.read_u32::<BigEndian>()?;
+
+            for _ in 0..length {
+                skip_tag(list_tags_id, reader)?;
+            }
+        }
+        COMPOUND_TAG_ID => loop {
+            let tag_id = reader.read_u8()?;
+            if tag_id == END_TAG_ID {
+                break;
+            }
+
+            let name_length = reader.read_u16::<BigEndian>()? as u64;
+            skip_bytes(reader, name_length)?;
+            skip_tag(tag_id, reader)?;
+        },
+        11 => {
+            let length = reader.read_u32::<BigEndian>()? as u64;
+            skip_bytes(reader, length * 4)?;
+        }
+        12 => {
+            let length = reader.read_u32::<BigEndian>()? as u64;
+            skip_bytes(reader, length * 8)?;
+        }
+        tag_type_id => return Err(TagDecodeError::UnknownTagType { tag_type_id }),
+    }
+
+    Ok(())
+}
+
+fn skip_bytes<R: Read>(reader: &mut R, count: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(count), &mut io::sink())?;
+
+    if skipped < count {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, TagDecodeError> {
+    let length = reader.read_u16::<BigEndian>()?;
+    let mut buf = vec![0; length as usize];
+    reader.read_exact(&mut buf)?;
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunk::ChunkFormat, chunk_loader::UNUSED_CHUNK_TAGS, chunk_section::ChunkSection,
+        palette::PaletteOptions, test_fixtures,
+    };
+    use nbt::{decode::read_compound_tag, encode::write_compound_tag};
+    use std::io::Cursor;
+
+    /// Every section's position and blocks, in the order they're stored
+    fn sections(chunk: &CompoundTag) -> Vec<((i32, i8, i32), Vec<String>)> {
+        chunk
+            .get_compound_tag_vec("sections")
+            .unwrap()
+            .into_iter()
+            .map(|nbt| {
+                let section = ChunkSection::from_nbt(
+                    nbt,
+                    0,
+                    0,
+                    ChunkFormat::Flattened,
+                    PaletteOptions::default(),
+                )
+                .unwrap();
+                let blocks = section.blocks().map(|block| block.blockstate).collect();
+                (section.pos, blocks)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_sections_as_full_parse() {
+        let mut chunk = test_fixtures::chunk(
+            0,
+            0,
+            vec![
+                test_fixtures::section(
+                    -1,
+                    &["minecraft:deepslate", "minecraft:diamond_ore"],
+                    |x, y, z| (x + y + z) % 2,
+                ),
+                test_fixtures::section(
+                    0,
+                    &["minecraft:air", "minecraft:stone", "minecraft:chest"],
+                    |x, y, z| (x * z + y) % 3,
+                ),
+            ],
+        );
+
+        // Skipped tags of every type, both before and after the sections and nested in a list
+        let entities = (0..100)
+            .map(|index| {
+                let mut entity = CompoundTag::new();
+                entity.insert_str("id", "minecraft:item");
+                entity.insert_i32_vec("UUID", vec![index, 1, 2, 3]);
+                let mut passengers = CompoundTag::new();
+                passengers.insert_compound_tag_vec("Entities", vec![CompoundTag::new()]);
+                entity.insert_compound_tag("Passenger", passengers);
+                entity
+            })
+            .collect::<Vec<_>>();
+        chunk.insert_compound_tag_vec("Entities", entities);
+        let mut block_entity = CompoundTag::new();
+        block_entity.insert_str("id", "minecraft:chest");
+        block_entity.insert_i8_vec("Lock", vec![1, 2, 3]);
+        block_entity.insert_i64_vec("LootTableSeed", vec![4, 5]);
+        chunk.insert_compound_tag_vec("block_entities", vec![block_entity]);
+        let mut structures = CompoundTag::new();
+        structures.insert_f64("Weight", 0.5);
+        structures.insert_i16("Refs", 7);
+        chunk.insert_compound_tag("structures", structures);
+        chunk.insert_str("Status", "minecraft:full");
+
+        let mut bytes = Vec::new();
+        write_compound_tag(&mut bytes, chunk).unwrap();
+
+        let full = read_compound_tag(&mut Cursor::new(&bytes)).unwrap();
+        let selective =
+            read_compound_tag_skipping(&mut Cursor::new(&bytes), UNUSED_CHUNK_TAGS).unwrap();
+
+        assert_eq!(sections(&selective), sections(&full));
+        assert_eq!(sections(&full).len(), 2);
+        assert_eq!(selective.get_str("Status").unwrap(), "minecraft:full");
+        assert!(full.get_compound_tag_vec("Entities").is_ok());
+        for tag in UNUSED_CHUNK_TAGS {
+            assert!(!selective.contains_key(tag), "{} wasn't skipped", tag);
+        }
+    }
+}
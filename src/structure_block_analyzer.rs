@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    structures::StructureStart,
+};
+
+/// Counts the blocks within the pieces of structures (villages, strongholds, ...), by structure type
+pub struct StructureBlockAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// structure name -> blockstate -> count
+    counts: HashMap<String, HashMap<String, u64>>,
+}
+
+impl<'a> StructureBlockAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        StructureBlockAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        StructureBlockAnalyzer {
+            // Structures are among the tags skipped as unused
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: false,
+                    ..loader_options
+                },
+            ),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let mut structures: Vec<StructureStart> = chunk.structure_starts.clone();
+
+            for (name, (start_x, start_z)) in &chunk.structure_references {
+                if (*start_x, *start_z) == (chunk_x, chunk_z) {
+                    continue;
+                }
+
+                if let Some(start_chunk) = self.chunk_loader.get_or_load(*start_x, *start_z) {
+                    structures.extend(
+                        start_chunk
+                            .structure_starts
+                            .iter()
+                            .filter(|start| &start.name == name)
+                            .cloned(),
+                    );
+                }
+            }
+
+            if structures.is_empty() {
+                continue;
+            }
+
+            for block in chunk.into_iter().flatten() {
+                for structure in &structures {
+                    if structure.contains(block.global_pos) {
+                        *self
+                            .counts
+                            .entry(structure.name.clone())
+                            .or_default()
+                            .entry(block.blockstate.clone())
+                            .or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let mut structures: Vec<&String> = self.counts.keys().collect();
+        structures.sort_unstable();
+
+        println!("Structure,Blockstate,Count");
+        for structure in structures {
+            let mut counts: Vec<(&String, &u64)> = self.counts[structure].iter().collect();
+            counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+            for (blockstate, count) in counts {
+                println!("{},{},{:8}", structure, blockstate, count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn only_blocks_within_structures() {
+        // A village starting in chunk 0, 0 with a 1 block high piece reaching 4 blocks into the
+        // chunk next to it
+        let mut piece = CompoundTag::new();
+        piece.insert_i32_vec("BB", vec![12, 0, 0, 19, 0, 1]);
+        let mut village = CompoundTag::new();
+        village.insert_str("id", "minecraft:village_plains");
+        village.insert_compound_tag_vec("Children", vec![piece]);
+        let mut starts = CompoundTag::new();
+        starts.insert_compound_tag("minecraft:village_plains", village);
+        let mut start_structures = CompoundTag::new();
+        start_structures.insert_compound_tag("starts", starts);
+
+        let mut start_chunk = test_fixtures::chunk(
+            0,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:stone")],
+        );
+        start_chunk.insert_compound_tag("structures", start_structures);
+
+        // The chunk next to it only references the village
+        let mut references = CompoundTag::new();
+        references.insert_i64_vec("minecraft:village_plains", vec![0]);
+        let mut referencing_structures = CompoundTag::new();
+        referencing_structures.insert_compound_tag("References", references);
+
+        let mut referencing_chunk = test_fixtures::chunk(
+            1,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:cobblestone")],
+        );
+        referencing_chunk.insert_compound_tag("structures", referencing_structures);
+
+        // A chunk without structures
+        let other_chunk = test_fixtures::chunk(
+            2,
+            0,
+            vec![test_fixtures::uniform_section(0, "minecraft:dirt")],
+        );
+
+        let mut analyzer = StructureBlockAnalyzer::with_source(
+            test_fixtures::source(vec![start_chunk, referencing_chunk, other_chunk]),
+            LoaderOptions {
+                skip_unused_tags: true,
+                ..LoaderOptions::default()
+            },
+        );
+        analyzer.analyze(vec![(0, 0), (1, 0), (2, 0)]);
+
+        assert_eq!(analyzer.counts.len(), 1);
+        let village = &analyzer.counts["minecraft:village_plains"];
+        assert_eq!(village.len(), 2);
+        assert_eq!(village["minecraft:stone"], 4 * 2);
+        assert_eq!(village["minecraft:cobblestone"], 4 * 2);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    structures::StructureStart,
+};
+
+/// A single generated structure, identified by its name and the chunk it starts in
+pub type StructureInstance = (String, (i32, i32));
+
+/// Counts the blocks within the pieces of every single structure, e.g. how much cobblestone one
+/// village is made of. Unlike `StructureBlockAnalyzer`, structures of the same type aren't merged.
+pub struct StructureCompositionAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// structure instance -> blockstate -> count
+    counts: HashMap<StructureInstance, HashMap<String, u64>>,
+}
+
+impl<'a> StructureCompositionAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        StructureCompositionAnalyzer::with_source(RegionFolderSource::new(path), loader_options)
+    }
+
+    /// Scans chunks from any source, e.g. a `MemoryChunkSource` in tests
+    pub fn with_source<S: ChunkSource + 'a>(source: S, loader_options: LoaderOptions) -> Self {
+        StructureCompositionAnalyzer {
+            // Structures are among the tags skipped as unused
+            chunk_loader: ChunkLoader::with_source(
+                source,
+                None,
+                LoaderOptions {
+                    skip_unused_tags: false,
+                    ..loader_options
+                },
+            ),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            let mut structures: Vec<(StructureStart, (i32, i32))> = chunk
+                .structure_starts
+                .iter()
+                .map(|start| (start.clone(), (chunk_x, chunk_z)))
+                .collect();
+
+            for (name, (start_x, start_z)) in &chunk.structure_references {
+                if (*start_x, *start_z) == (chunk_x, chunk_z) {
+                    continue;
+                }
+
+                if let Some(start_chunk) = self.chunk_loader.get_or_load(*start_x, *start_z) {
+                    structures.extend(
+                        start_chunk
+                            .structure_starts
+                            .iter()
+                            .filter(|start| &start.name == name)
+                            .map(|start| (start.clone(), (*start_x, *start_z))),
+                    );
+                }
+            }
+
+            if structures.is_empty() {
+                continue;
+            }
+
+            for block in chunk.into_iter().flatten() {
+                for (structure, start_chunk) in &structures {
+                    if structure.contains(block.global_pos) {
+                        *self
+                            .counts
+                            .entry((structure.name.clone(), *start_chunk))
+                            .or_default()
+                            .entry(block.blockstate.clone())
+                            .or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blocks found so far within one structure, most common first
+    pub fn composition(&self, structure: &StructureInstance) -> Vec<(String, u64)> {
+        let mut counts: Vec<(String, u64)> = self
+            .counts
+            .get(structure)
+            .into_iter()
+            .flatten()
+            .map(|(blockstate, count)| (blockstate.clone(), *count))
+            .collect();
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        counts
+    }
+
+    pub fn print_csv(&self) {
+        let mut structures: Vec<&StructureInstance> = self.counts.keys().collect();
+        structures.sort_unstable();
+
+        println!("Structure,Start chunk X,Start chunk Z,Blockstate,Count");
+        for structure in structures {
+            let (name, (start_x, start_z)) = structure;
+            for (blockstate, count) in self.composition(structure) {
+                println!(
+                    "{},{},{},{},{:8}",
+                    name, start_x, start_z, blockstate, count
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::CompoundTag;
+
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn only_blocks_within_pieces() {
+        // Stone with a layer of cobblestone at Y 0
+        let section = test_fixtures::section(
+            0,
+            &["minecraft:stone", "minecraft:cobblestone"],
+            |_, y, _| (y == 0) as usize,
+        );
+
+        // A village with a single 4x2x4 piece on top of the cobblestone
+        let mut piece = CompoundTag::new();
+        piece.insert_i32_vec("BB", vec![0, 0, 0, 3, 1, 3]);
+        let mut village = CompoundTag::new();
+        village.insert_str("id", "minecraft:village_plains");
+        village.insert_compound_tag_vec("Children", vec![piece]);
+        let mut starts = CompoundTag::new();
+        starts.insert_compound_tag("minecraft:village_plains", village);
+        let mut structures = CompoundTag::new();
+        structures.insert_compound_tag("starts", starts);
+
+        let mut chunk = test_fixtures::chunk(0, 0, vec![section]);
+        chunk.insert_compound_tag("structures", structures);
+
+        let mut analyzer = StructureCompositionAnalyzer::with_source(
+            test_fixtures::source(vec![chunk]),
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(0, 0)]);
+
+        // Only the blocks within the piece, not the rest of the section
+        let village = ("minecraft:village_plains".to_string(), (0, 0));
+        assert_eq!(
+            analyzer.composition(&village),
+            vec![
+                ("minecraft:cobblestone".to_string(), 16),
+                ("minecraft:stone".to_string(), 16)
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use nbt::{CompoundTag, Tag};
+
+/// Inclusive box of block coordinates, as stored in structure `BB` tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: (i32, i32, i32),
+    pub max: (i32, i32, i32),
+}
+
+impl BoundingBox {
+    /// Reads a `[min_x, min_y, min_z, max_x, max_y, max_z]` int array
+    pub fn from_nbt(values: &[i32]) -> Option<Self> {
+        match *values {
+            [min_x, min_y, min_z, max_x, max_y, max_z] => Some(BoundingBox {
+                min: (min_x, min_y, min_z),
+                max: (max_x, max_y, max_z),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, (x, y, z): (i64, i32, i64)) -> bool {
+        (self.min.0 as i64..=self.max.0 as i64).contains(&x)
+            && (self.min.1..=self.max.1).contains(&y)
+            && (self.min.2 as i64..=self.max.2 as i64).contains(&z)
+    }
+}
+
+/// A structure that starts in a chunk, e.g. a village, with the boxes of its pieces
+#[derive(Debug, Clone)]
+pub struct StructureStart {
+    pub name: String,
+    pub pieces: Vec<BoundingBox>,
+}
+
+impl StructureStart {
+    pub fn contains(&self, pos: (i64, i32, i64)) -> bool {
+        self.pieces.iter().any(|piece| piece.contains(pos))
+    }
+}
+
+/// Reads the structures starting in a chunk from its `Structures` (or, since 1.18, `structures`) tag.
+/// Placeholder starts for structures that didn't generate are left out.
+pub fn parse_structure_starts(structures: &CompoundTag) -> Vec<StructureStart> {
+    let starts = match structures
+        .get_compound_tag("Starts")
+        .or_else(|_| structures.get_compound_tag("starts"))
+    {
+        Ok(starts) => starts,
+        Err(_) => return Vec::new(),
+    };
+
+    starts
+        .iter()
+        .filter_map(|(name, start)| match start {
+            Tag::Compound(start) if start.get_str("id").ok() != Some("INVALID") => {
+                Some((name, start))
+            }
+            _ => None,
+        })
+        .map(|(name, start)| {
+            let mut pieces: Vec<BoundingBox> = start
+                .get_compound_tag_vec("Children")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|piece| BoundingBox::from_nbt(piece.get_i32_vec("BB").ok()?))
+                .collect();
+
+            // Fall back to the box around the whole structure if there are no pieces
+            if pieces.is_empty() {
+                pieces.extend(
+                    start
+                        .get_i32_vec("BB")
+                        .ok()
+                        .and_then(|bb| BoundingBox::from_nbt(bb)),
+                );
+            }
+
+            StructureStart {
+                name: name.clone(),
+                pieces,
+            }
+        })
+        .collect()
+}
+
+/// Reads which structures reach into a chunk, as pairs of structure name and the chunk it starts in
+pub fn parse_structure_references(structures: &CompoundTag) -> Vec<(String, (i32, i32))> {
+    let references = match structures.get_compound_tag("References") {
+        Ok(references) => references,
+        Err(_) => return Vec::new(),
+    };
+
+    references
+        .iter()
+        .flat_map(|(name, chunks)| {
+            let chunks: &[i64] = match chunks {
+                Tag::LongArray(chunks) => chunks,
+                _ => &[],
+            };
+
+            // Chunk positions are packed with x in the lower and z in the upper 32 bits
+            chunks
+                .iter()
+                .map(move |&chunk| (name.clone(), (chunk as i32, (chunk >> 32) as i32)))
+        })
+        .collect()
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use crate::{
+    block_id::IdForm,
+    chunk_loader::{ChunkLoader, LoaderOptions},
+    chunk_source::{ChunkSource, RegionFolderSource},
+    presets::Preset,
+};
+
+/// Blocks that can only be placed in creative mode or with commands, which adventure maps rely on
+pub const TECHNICAL_BLOCKS: &[&str] = &[
+    "minecraft:command_block",
+    "minecraft:chain_command_block",
+    "minecraft:repeating_command_block",
+    "minecraft:structure_block",
+    "minecraft:structure_void",
+    "minecraft:jigsaw",
+    "minecraft:barrier",
+    "minecraft:light",
+];
+
+/// Counts technical blocks (command blocks, structure blocks, barriers, ...) and records where
+/// every single one of them is, for auditing maps. Works the same for the blocks of a `Preset`.
+pub struct TechnicalBlockAnalyzer<'a> {
+    chunk_loader: ChunkLoader<'a>,
+
+    /// Blockstates that are counted and located
+    blocks: HashSet<&'static str>,
+
+    /// What the blocks are called in the output headings
+    label: &'static str,
+
+    /// blockstate -> count
+    counts: HashMap<String, u32>,
+
+    /// Location of every technical block, in the order they were found
+    positions: Vec<((i64, i32, i64), String)>,
+}
+
+impl<'a> TechnicalBlockAnalyzer<'a> {
+    pub fn new(path: &'a str, loader_options: LoaderOptions) -> Self {
+        TechnicalBlockAnalyzer::with_blocks(
+            RegionFolderSource::new(path),
+            TECHNICAL_BLOCKS,
+            "Technical block",
+            loader_options,
+        )
+    }
+
+    /// Counts and locates the blocks of a preset instead of technical blocks
+    pub fn for_preset<S: ChunkSource + 'a>(
+        source: S,
+        preset: Preset,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        TechnicalBlockAnalyzer::with_blocks(source, preset.blocks(), preset.label(), loader_options)
+    }
+
+    fn with_blocks<S: ChunkSource + 'a>(
+        source: S,
+        blocks: &'static [&'static str],
+        label: &'static str,
+        loader_options: LoaderOptions,
+    ) -> Self {
+        TechnicalBlockAnalyzer {
+            chunk_loader: ChunkLoader::with_source(source, None, loader_options),
+            blocks: blocks.iter().copied().collect(),
+            label,
+            counts: HashMap::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn analyze<I: IntoIterator<Item = (i32, i32)>>(&mut self, chunks: I) {
+        for (chunk_x, chunk_z) in chunks {
+            let chunk = match self.chunk_loader.get_or_load(chunk_x, chunk_z) {
+                Some(chunk) => chunk.clone(),
+                None => continue,
+            };
+
+            eprintln!("Analyzing chunk ({},{})", chunk_x, chunk_z);
+
+            for block in chunk.into_iter().flatten() {
+                if !self.blocks.contains(block.blockstate.as_str()) {
+                    continue;
+                }
+
+                *self.counts.entry(block.blockstate.clone()).or_insert(0) += 1;
+                self.positions.push((block.global_pos, block.blockstate));
+            }
+        }
+    }
+
+    pub fn print_csv(&self, id_form: IdForm) {
+        self.write_csv(&mut io::stdout().lock(), id_form).unwrap();
+    }
+
+    /// Writes the counts per block, followed by the coordinates of every block in the order they
+    /// were found
+    pub fn write_csv<W: Write>(&self, out: &mut W, id_form: IdForm) -> io::Result<()> {
+        let mut counts: Vec<(&String, &u32)> = self.counts.iter().collect();
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        writeln!(out, "{},Count", self.label)?;
+        for (blockstate, count) in counts {
+            writeln!(out, "{},{:8}", id_form.render(blockstate), count)?;
+        }
+
+        writeln!(out)?;
+
+        writeln!(out, "X,Y,Z,{}", self.label)?;
+        for ((x, y, z), blockstate) in &self.positions {
+            writeln!(out, "{},{},{},{}", x, y, z, id_form.render(blockstate))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn preset_positions() {
+        // A 1.19 chunk whose lowest section is deepslate with a sculk shrieker in the corner
+        let section = test_fixtures::section(
+            -4,
+            &["minecraft:deepslate", "minecraft:sculk_shrieker"],
+            |x, y, z| (x + y + z == 0) as usize,
+        );
+        let mut chunk = test_fixtures::chunk(2, -1, vec![section]);
+        chunk.insert_i32("DataVersion", 3120);
+
+        let mut analyzer = TechnicalBlockAnalyzer::for_preset(
+            test_fixtures::source(vec![chunk]),
+            Preset::DeepDark,
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(2, -1)]);
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv, IdForm::Full).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Deep dark block,Count\n\
+             minecraft:sculk_shrieker,       1\n\
+             \n\
+             X,Y,Z,Deep dark block\n\
+             32,-64,-16,minecraft:sculk_shrieker\n"
+        );
+    }
+
+    #[test]
+    fn two_command_blocks() {
+        let section = test_fixtures::section(
+            3,
+            &["minecraft:stone", "minecraft:command_block"],
+            |x, y, z| matches!((x, y, z), (1, 2, 3) | (15, 10, 0)) as usize,
+        );
+        let mut analyzer = TechnicalBlockAnalyzer::with_blocks(
+            test_fixtures::source(vec![test_fixtures::chunk(-1, 0, vec![section])]),
+            TECHNICAL_BLOCKS,
+            "Technical block",
+            LoaderOptions::default(),
+        );
+        analyzer.analyze(vec![(-1, 0)]);
+
+        let mut csv = Vec::new();
+        analyzer.write_csv(&mut csv, IdForm::Short).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Technical block,Count\n\
+             command_block,       2\n\
+             \n\
+             X,Y,Z,Technical block\n\
+             -15,50,3,command_block\n\
+             -1,58,0,command_block\n"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Chunks built in memory for unit tests, so that a test only spells out what's special about
+//! its chunks. Everything is written the way 1.18 stores it unless noted otherwise.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anvil_region::{error::ChunkReadError, position::RegionChunkPosition};
+use flate2::{write::ZlibEncoder, Compression};
+use nbt::{encode::write_compound_tag, CompoundTag};
+
+use crate::{area::REGION_SIZE, chunk_section::CHUNK_SIZE, chunk_source::ChunkSource};
+
+/// DataVersion of 1.18.2
+pub const DATA_VERSION: i32 = 2975;
+
+const BLOCKS_PER_SECTION: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Packs values the way 1.16 and later store them: as many as fit into each long, starting at
+/// the least significant bit, with the remaining high bits left as padding
+pub fn pack(values: &[u32], bits: u32) -> Vec<i64> {
+    let values_per_long = (64 / bits) as usize;
+    values
+        .chunks(values_per_long)
+        .map(|values| {
+            values
+                .iter()
+                .enumerate()
+                .fold(0u64, |long, (index, &value)| {
+                    long | (value as u64) << (index as u32 * bits)
+                }) as i64
+        })
+        .collect()
+}
+
+/// Packs values the way versions before 1.16 store them, back to back across longs
+pub fn pack_straddling(values: &[u32], bits: u32) -> Vec<i64> {
+    let mut data = vec![0u64; (values.len() * bits as usize).div_ceil(64)];
+    for (index, &value) in values.iter().enumerate() {
+        let bit_offset = index * bits as usize;
+        let (long_index, bit_in_long) = (bit_offset / 64, (bit_offset % 64) as u32);
+        data[long_index] |= (value as u64) << bit_in_long;
+        if bit_in_long + bits > 64 {
+            data[long_index + 1] |= (value as u64) >> (64 - bit_in_long);
+        }
+    }
+    data.into_iter().map(|long| long as i64).collect()
+}
+
+/// Bits per palette index, at least 4 like the game writes them
+fn bits_for(palette_len: usize) -> u32 {
+    let bits = usize::BITS - palette_len.saturating_sub(1).leading_zeros();
+    bits.max(4)
+}
+
+pub fn palette(names: &[&str]) -> Vec<CompoundTag> {
+    names
+        .iter()
+        .map(|name| {
+            let mut entry = CompoundTag::new();
+            entry.insert_str("Name", name);
+            entry
+        })
+        .collect()
+}
+
+/// A palette entry with block properties, e.g. `("waterlogged", "true")`
+pub fn palette_entry(name: &str, properties: &[(&str, &str)]) -> CompoundTag {
+    let mut entry = CompoundTag::new();
+    entry.insert_str("Name", name);
+    if !properties.is_empty() {
+        let mut tag = CompoundTag::new();
+        for (key, value) in properties {
+            tag.insert_str(key, value);
+        }
+        entry.insert_compound_tag("Properties", tag);
+    }
+    entry
+}
+
+/// A section whose block at each chunk X, Y and Z within the section is the palette entry
+/// returned by `block`
+pub fn section<F>(y: i8, names: &[&str], block: F) -> CompoundTag
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    section_with_palette(y, palette(names), block)
+}
+
+pub fn section_with_palette<F>(y: i8, palette: Vec<CompoundTag>, block: F) -> CompoundTag
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    let mut block_states = CompoundTag::new();
+    block_states.insert_i64_vec("data", block_data(palette.len(), block));
+    block_states.insert_compound_tag_vec("palette", palette);
+
+    let mut section = CompoundTag::new();
+    section.insert_i8("Y", y);
+    section.insert_compound_tag("block_states", block_states);
+    section
+}
+
+/// A section as stored before 1.16, with the palette and data next to the Y index and the
+/// block states packed across longs. Vanilla palettes of that time start with air.
+pub fn legacy_section<F>(y: i8, names: &[&str], block: F) -> CompoundTag
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    let mut section = CompoundTag::new();
+    section.insert_i8("Y", y);
+    section.insert_i64_vec(
+        "BlockStates",
+        pack_straddling(&block_indices(block), bits_for(names.len())),
+    );
+    section.insert_compound_tag_vec("Palette", palette(names));
+    section
+}
+
+/// Palette indices of all blocks of a section, packed the way 1.16 and later store them
+fn block_data<F>(palette_len: usize, block: F) -> Vec<i64>
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    pack(&block_indices(block), bits_for(palette_len))
+}
+
+fn block_indices<F>(block: F) -> Vec<u32>
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    (0..BLOCKS_PER_SECTION)
+        .map(|index| {
+            let (x, z, y) = (
+                index % CHUNK_SIZE,
+                index / CHUNK_SIZE % CHUNK_SIZE,
+                index / (CHUNK_SIZE * CHUNK_SIZE),
+            );
+            block(x, y, z) as u32
+        })
+        .collect()
+}
+
+/// A section of a single block, which only stores its palette
+pub fn uniform_section(y: i8, name: &str) -> CompoundTag {
+    let mut block_states = CompoundTag::new();
+    block_states.insert_compound_tag_vec("palette", palette(&[name]));
+
+    let mut section = CompoundTag::new();
+    section.insert_i8("Y", y);
+    section.insert_compound_tag("block_states", block_states);
+    section
+}
+
+/// Adds biomes to a section, `cell` returning the palette entry of each 4x4x4 cell by its X, Y
+/// and Z within the section
+pub fn with_biomes<F>(mut section: CompoundTag, names: &[&str], cell: F) -> CompoundTag
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    let cells: Vec<u32> = (0..64)
+        .map(|index| cell(index % 4, index / 16, index / 4 % 4) as u32)
+        .collect();
+    let bits = usize::BITS - names.len().saturating_sub(1).leading_zeros();
+
+    let mut biomes = CompoundTag::new();
+    biomes.insert_str_vec("palette", names.to_vec());
+    if bits > 0 {
+        biomes.insert_i64_vec("data", pack(&cells, bits));
+    }
+    section.insert_compound_tag("biomes", biomes);
+    section
+}
+
+pub fn chunk(x: i32, z: i32, sections: Vec<CompoundTag>) -> CompoundTag {
+    let mut chunk = CompoundTag::new();
+    chunk.insert_i32("DataVersion", DATA_VERSION);
+    chunk.insert_i32("xPos", x);
+    chunk.insert_i32("zPos", z);
+    chunk.insert_compound_tag_vec("sections", sections);
+    chunk
+}
+
+/// A chunk as stored before 1.18, with everything within a `Level` tag
+pub fn legacy_chunk(x: i32, z: i32, data_version: i32, sections: Vec<CompoundTag>) -> CompoundTag {
+    let mut level = CompoundTag::new();
+    level.insert_i32("xPos", x);
+    level.insert_i32("zPos", z);
+    level.insert_compound_tag_vec("Sections", sections);
+
+    let mut chunk = CompoundTag::new();
+    chunk.insert_i32("DataVersion", data_version);
+    chunk.insert_compound_tag("Level", level);
+    chunk
+}
+
+/// A chunk as stored by McRegion, with numeric block IDs and data values given as `(id, data)`
+/// for every `(x, y, z)` within its 128 blocks of height
+pub fn mcregion_chunk<F>(x: i32, z: i32, block: F) -> CompoundTag
+where
+    F: Fn(usize, usize, usize) -> (u8, u8),
+{
+    let mut blocks = vec![0i8; 16 * 16 * 128];
+    let mut data = vec![0i8; 16 * 16 * 128 / 2];
+    for (index, id) in blocks.iter_mut().enumerate() {
+        // Ordered by X, then Z, then Y
+        let (block_x, block_z, block_y) = (index / (16 * 128), index / 128 % 16, index % 128);
+        let (block_id, value) = block(block_x, block_y, block_z);
+        *id = block_id as i8;
+        data[index / 2] |= ((value & 0xf) << (4 * (index % 2))) as i8;
+    }
+
+    let mut level = CompoundTag::new();
+    level.insert_i32("xPos", x);
+    level.insert_i32("zPos", z);
+    level.insert_i8_vec("Blocks", blocks);
+    level.insert_i8_vec("Data", data);
+
+    let mut chunk = CompoundTag::new();
+    chunk.insert_compound_tag("Level", level);
+    chunk
+}
+
+/// The chunk X and Z a chunk says it's at, for both the 1.18 and the older layout
+fn position(chunk: &CompoundTag) -> (i32, i32) {
+    let level = chunk.get_compound_tag("Level").unwrap_or(chunk);
+    (
+        level.get_i32("xPos").unwrap(),
+        level.get_i32("zPos").unwrap(),
+    )
+}
+
+/// Chunks built in memory, to run analyzers on hand-made chunks without any region files.
+/// Tags to skip are ignored since the chunks are in memory already.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryChunkSource {
+    chunks: HashMap<(i32, i32), CompoundTag>,
+}
+
+impl MemoryChunkSource {
+    pub fn new() -> Self {
+        MemoryChunkSource::default()
+    }
+
+    pub fn insert(&mut self, chunk_x: i32, chunk_z: i32, nbt: CompoundTag) {
+        self.chunks.insert((chunk_x, chunk_z), nbt);
+    }
+}
+
+impl ChunkSource for MemoryChunkSource {
+    fn read_chunk_nbt(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        _skip: &[&str],
+    ) -> Result<CompoundTag, ChunkReadError> {
+        self.chunks
+            .get(&(chunk_x, chunk_z))
+            .cloned()
+            .ok_or(ChunkReadError::ChunkNotFound {
+                position: RegionChunkPosition::from_chunk_position(chunk_x, chunk_z),
+            })
+    }
+}
+
+/// A source with each of the chunks at the position it says it's at
+pub fn source(chunks: Vec<CompoundTag>) -> MemoryChunkSource {
+    let mut source = MemoryChunkSource::new();
+    for chunk in chunks {
+        let (x, z) = position(&chunk);
+        source.insert(x, z, chunk);
+    }
+    source
+}
+
+/// A source with a single chunk at 0, 0 made of one section at Y 0 filled with one block
+pub fn uniform_source(name: &str) -> MemoryChunkSource {
+    source(vec![chunk(0, 0, vec![uniform_section(0, name)])])
+}
+
+/// A folder of its own for a test, removed again when dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "mca-analyzer-test-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path as a string, like region folders are passed to analyzers
+    pub fn as_str(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A chunk encoded and compressed with zlib, the way the game stores it
+pub fn zlib(chunk: CompoundTag) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    write_compound_tag(&mut encoder, chunk).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// A chunk's NBT compressed with zstd, as some third-party tools store chunks
+#[cfg(feature = "zstd")]
+pub fn zstd(chunk: CompoundTag) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_compound_tag(&mut data, chunk).unwrap();
+    zstd::encode_all(data.as_slice(), 0).unwrap()
+}
+
+/// Writes a region file of the chunks at the given positions within the region, each with its
+/// compression type and the data that follows it
+pub fn write_region_file(path: &Path, chunks: Vec<((i32, i32), u8, Vec<u8>)>) {
+    const SECTOR: usize = 4096;
+
+    let mut locations = vec![0u8; SECTOR];
+    let mut data = Vec::new();
+    for ((x, z), compression_scheme, payload) in chunks {
+        let sector = 2 + data.len() / SECTOR;
+        let index = 4 * (x.rem_euclid(REGION_SIZE) + z.rem_euclid(REGION_SIZE) * REGION_SIZE);
+
+        data.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        data.push(compression_scheme);
+        data.extend_from_slice(&payload);
+        data.resize(data.len().div_ceil(SECTOR) * SECTOR, 0);
+
+        let sectors = data.len() / SECTOR + 2 - sector;
+        let location = (sector as u32) << 8 | sectors as u32;
+        locations[index as usize..index as usize + 4].copy_from_slice(&location.to_be_bytes());
+    }
+
+    let mut file = fs::File::create(path).unwrap();
+    file.write_all(&locations).unwrap();
+    // Timestamps
+    file.write_all(&[0; SECTOR]).unwrap();
+    file.write_all(&data).unwrap();
+}
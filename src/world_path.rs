@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+
+use crate::dimension::Dimension;
+
+/// Resolves a folder that lives next to the `region` folder of a dimension, such as `poi` or `entities`.
+/// Accepts either the region folder itself or the dimension folder containing it.
+pub fn sibling_folder(region_folder: &Path, name: &str) -> PathBuf {
+    if region_folder.join("region").is_dir() {
+        return region_folder.join(name);
+    }
+
+    region_folder
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(name)
+}
+
+/// Region folders of all vanilla dimensions that exist in the given world folder
+pub fn dimension_region_folders(world_folder: &Path) -> Vec<(Dimension, PathBuf)> {
+    Dimension::ALL
+        .iter()
+        .map(|&dimension| (dimension, dimension.region_path(world_folder)))
+        .filter(|(_, folder)| folder.is_dir())
+        .collect()
+}